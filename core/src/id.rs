@@ -0,0 +1,117 @@
+/// `id` is an 11 characters video id, surrounding whitespace is ignored
+pub fn validate_id(id: &str) -> bool {
+    let id = id.trim();
+    id.len() == 11 && id.bytes().all(is_id_byte)
+}
+
+/// Video id of an id or a `http(s)` URL. `?v=` URLs are only accepted from `valid_query_domains`,
+/// path URLs (`youtu.be/`, `/shorts/`, `/embed/`, `/v/`...) from every `youtube.com` and `youtu.be` host
+///
+/// # Example
+/// ```
+/// use rusty_ytdl_core::parse_video_id;
+///
+/// assert_eq!(
+///     parse_video_id("https://youtu.be/FZ8BxMU3BYc", &["youtube.com"]),
+///     Some("FZ8BxMU3BYc")
+/// );
+/// ```
+pub fn parse_video_id<'a, S: AsRef<str>>(
+    url_or_id: &'a str,
+    valid_query_domains: &[S],
+) -> Option<&'a str> {
+    let url = url_or_id.trim();
+
+    if validate_id(url) {
+        return Some(url);
+    }
+
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))?;
+    let rest = rest.split('#').next().unwrap_or("");
+    let (rest, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let (authority, path) = match rest.find('/') {
+        Some(index) => rest.split_at(index),
+        None => (rest, ""),
+    };
+
+    let host = authority.rsplit('@').next().unwrap_or("");
+    let host = match host.strip_prefix('[') {
+        Some(ipv6) => ipv6.split(']').next().unwrap_or(""),
+        None => host.split(':').next().unwrap_or(""),
+    };
+    if host.is_empty() {
+        return None;
+    }
+
+    // The last `v` wins like in `url::Url::query_pairs`
+    let query_id = query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .filter(|(key, _)| *key == "v")
+        .map(|(_, value)| value)
+        .last();
+
+    let id = match query_id {
+        Some(id) => {
+            let valid = valid_query_domains
+                .iter()
+                .any(|domain| domain.as_ref().eq_ignore_ascii_case(host));
+            if !valid {
+                return None;
+            }
+            id
+        }
+        None => path_id(host, path)?,
+    };
+
+    // Ids longer than 11 characters are cut like `youtube.com` does
+    let id = id.get(..11).unwrap_or(id);
+    validate_id(id).then_some(id)
+}
+
+/// Id from the path of `youtu.be/{id}`, `youtube.com/{v,e,embed,shorts}/{id}` and `youtube.com/{a}/{b}/.../{id}`
+fn path_id<'a>(host: &str, path: &'a str) -> Option<&'a str> {
+    let mut segments = path.split('/').skip(1);
+
+    let segment = if is_domain(host, "youtu.be") {
+        segments.next()?
+    } else if is_domain(host, "youtube.com") || is_domain(host, "youtube-nocookie.com") {
+        match segments.next()? {
+            "v" | "e" | "embed" | "shorts" => segments.next()?,
+            _ => segments
+                .enumerate()
+                .filter(|(index, segment)| *index >= 1 && !segment.is_empty())
+                .map(|(_, segment)| segment)
+                .last()?,
+        }
+    } else {
+        return None;
+    };
+
+    let end = segment
+        .bytes()
+        .position(|x| !is_id_byte(x))
+        .unwrap_or(segment.len());
+    Some(&segment[..end]).filter(|x| !x.is_empty())
+}
+
+/// `host` is `domain` or a subdomain of it
+fn is_domain(host: &str, domain: &str) -> bool {
+    let split = match host.len().checked_sub(domain.len()) {
+        Some(split) if host.is_char_boundary(split) => split,
+        _ => return false,
+    };
+    let (prefix, suffix) = host.split_at(split);
+
+    suffix.eq_ignore_ascii_case(domain)
+        && prefix
+            .bytes()
+            .last()
+            .map_or(true, |x| !x.is_ascii_alphanumeric() && x != b'_')
+}
+
+fn is_id_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_'
+}
@@ -0,0 +1,15 @@
+//! Parsers of [rusty_ytdl](https://docs.rs/rusty_ytdl) without I/O. The crate is `no_std` with `alloc` and has no
+//! dependencies, `rusty_ytdl` re-exports it as `rusty_ytdl::rusty_ytdl_core`
+#![no_std]
+
+extern crate alloc;
+
+mod id;
+mod number;
+mod sort;
+mod storyboard;
+
+pub use id::{parse_video_id, validate_id};
+pub use number::{parse_abbreviated_number, parse_exact_count, parse_leading_int, time_to_ms};
+pub use sort::{compare_formats, rank_format, FormatFacts, FormatSortKey};
+pub use storyboard::{parse_storyboard_spec, StoryboardLevel};
@@ -0,0 +1,80 @@
+use alloc::string::String;
+
+/// Milliseconds of `[[hh:]mm:]ss` durations, unparsable parts count as `0`
+pub fn time_to_ms(duration: &str) -> usize {
+    duration
+        .split(':')
+        .rev()
+        .enumerate()
+        .map(|(i, part)| part.parse::<usize>().unwrap_or(0) * 60usize.pow(i as u32))
+        .sum::<usize>()
+        * 1000
+}
+
+/// Numbers like `1,2M`, `3.4K` or `12,345` of view and like counts, `0` if there is no number
+pub fn parse_abbreviated_number(text: &str) -> usize {
+    if let Some(count) = parse_exact_count(text) {
+        return count as usize;
+    }
+
+    let text = text
+        .chars()
+        .filter(|x| *x != ' ')
+        .map(|x| if x == ',' { '.' } else { x })
+        .collect::<String>();
+
+    let start = match text.find(|x: char| x.is_ascii_digit() || x == '.') {
+        Some(start) => start,
+        None => return 0,
+    };
+    let rest = &text[start..];
+    let end = rest
+        .find(|x: char| !x.is_ascii_digit() && x != '.')
+        .unwrap_or(rest.len());
+
+    let mut number = rest[..end].parse::<f64>().unwrap_or(0f64);
+    match rest[end..].chars().next() {
+        Some('B') => number *= 1000000000f64,
+        Some('M') => number *= 1000000f64,
+        Some('K') => number *= 1000f64,
+        _ => {}
+    }
+
+    // `f64::round` needs std
+    (number + 0.5) as usize
+}
+
+/// Counts written out like `12,345 subscribers`, [`None`] for abbreviated counts like `1.2M` or texts without digits
+pub fn parse_exact_count(text: &str) -> Option<u64> {
+    let start = text.find(|x: char| x.is_ascii_digit())?;
+    let rest = &text[start..];
+    let end = rest
+        .find(|x: char| !x.is_ascii_digit() && x != ',' && x != '.')
+        .unwrap_or(rest.len());
+
+    let suffix = rest[end..].trim_start_matches([' ', '\u{a0}']);
+    if suffix.starts_with(['K', 'M', 'B']) {
+        return None;
+    }
+
+    rest[..end]
+        .chars()
+        .filter(|x| x.is_ascii_digit())
+        .collect::<String>()
+        .parse()
+        .ok()
+}
+
+/// Leading integer of texts like `1080p60`, surrounding whitespace is ignored
+pub fn parse_leading_int(text: &str) -> Option<i64> {
+    let text = text.trim_start();
+    let digits = text.strip_prefix(['-', '+']).unwrap_or(text);
+    let end = digits
+        .find(|x: char| !x.is_ascii_digit())
+        .unwrap_or(digits.len());
+
+    if end == 0 {
+        return None;
+    }
+    text[..text.len() - digits.len() + end].parse().ok()
+}
@@ -0,0 +1,129 @@
+use core::cmp::Ordering;
+use core::fmt;
+
+use crate::number::parse_leading_int;
+
+/// Keys of [`compare_formats`], every key ranks higher values first
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FormatSortKey {
+    /// HLS, then DASH MPD, then progressive formats
+    Protocol,
+    /// Formats with both video and audio
+    AudioVideo,
+    /// Formats with video
+    HasVideo,
+    /// Formats with known content length
+    ContentLength,
+    /// Resolution from quality label (`1080p60` => 1080), height if there is no label
+    Resolution,
+    /// Frames per second
+    Fps,
+    /// Overall bitrate
+    Bitrate,
+    /// Audio bitrate
+    AudioBitrate,
+    /// Position in the video codec preferences
+    VideoCodec,
+    /// Position in the audio codec preferences
+    AudioCodec,
+    /// Formats without dynamic range compression
+    NoDrc,
+}
+
+impl fmt::Display for FormatSortKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// Fields of a format used by [`compare_formats`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct FormatFacts<'a> {
+    pub itag: u64,
+    pub is_hls: bool,
+    pub is_dash_mpd: bool,
+    pub has_video: bool,
+    pub has_audio: bool,
+    pub content_length: Option<u64>,
+    pub quality_label: Option<&'a str>,
+    pub height: Option<u64>,
+    pub fps: Option<u64>,
+    pub bitrate: u64,
+    pub audio_bitrate: Option<u64>,
+    /// Comma separated codecs of the mime type
+    pub codecs: Option<&'a str>,
+    pub is_drc: bool,
+}
+
+/// Compare formats by `keys` in order, [`Ordering::Less`] means `a` is better than `b`.
+/// Formats equal by every key are ordered by ascending itag.
+/// Codec preferences are most preferred first and matched case-insensitively as prefix of `codecs`
+pub fn compare_formats<S: AsRef<str>>(
+    keys: &[FormatSortKey],
+    video_codecs: &[S],
+    audio_codecs: &[S],
+    a: &FormatFacts,
+    b: &FormatFacts,
+) -> Ordering {
+    keys.iter()
+        .map(|key| {
+            rank_format(*key, video_codecs, audio_codecs, b).cmp(&rank_format(
+                *key,
+                video_codecs,
+                audio_codecs,
+                a,
+            ))
+        })
+        .find(|x| x.is_ne())
+        .unwrap_or_else(|| a.itag.cmp(&b.itag))
+}
+
+/// Rank of `format` by `key`, higher is better
+pub fn rank_format<S: AsRef<str>>(
+    key: FormatSortKey,
+    video_codecs: &[S],
+    audio_codecs: &[S],
+    format: &FormatFacts,
+) -> i64 {
+    match key {
+        FormatSortKey::Protocol => {
+            if format.is_hls {
+                2
+            } else if format.is_dash_mpd {
+                1
+            } else {
+                0
+            }
+        }
+        FormatSortKey::AudioVideo => (format.has_video && format.has_audio) as i64,
+        FormatSortKey::HasVideo => format.has_video as i64,
+        FormatSortKey::ContentLength => format.content_length.map_or(0, |x| (x > 0) as i64),
+        FormatSortKey::Resolution => format
+            .quality_label
+            .and_then(parse_leading_int)
+            .or(format.height.map(|x| x as i64))
+            .unwrap_or(0),
+        FormatSortKey::Fps => format.fps.unwrap_or(0) as i64,
+        FormatSortKey::Bitrate => format.bitrate as i64,
+        FormatSortKey::AudioBitrate => format.audio_bitrate.unwrap_or(0) as i64,
+        FormatSortKey::VideoCodec => codec_rank(video_codecs, format.codecs),
+        FormatSortKey::AudioCodec => codec_rank(audio_codecs, format.codecs),
+        FormatSortKey::NoDrc => !format.is_drc as i64,
+    }
+}
+
+/// Higher is more preferred, `-1` if codec is unknown or not in preferences
+fn codec_rank<S: AsRef<str>>(preferences: &[S], codecs: Option<&str>) -> i64 {
+    let codecs = codecs.unwrap_or("").to_lowercase();
+
+    preferences
+        .iter()
+        .position(|x| {
+            let preference = x.as_ref().to_lowercase();
+            codecs
+                .split(',')
+                .any(|codec| codec.trim().starts_with(&preference))
+        })
+        .map(|x| (preferences.len() - x) as i64)
+        .unwrap_or(-1)
+}
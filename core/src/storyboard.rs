@@ -0,0 +1,90 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+/// One level of a storyboard spec, `$M` of `template_url` is the sheet index
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct StoryboardLevel {
+    pub template_url: String,
+    pub thumbnail_width: i32,
+    pub thumbnail_height: i32,
+    pub thumbnail_count: i32,
+    /// Milliseconds between thumbnails
+    pub interval: i32,
+    pub columns: i32,
+    pub rows: i32,
+    /// Thumbnail sheets of the level
+    pub storyboard_count: i32,
+}
+
+/// Levels of `playerStoryboardSpecRenderer.spec`: a base URL, then
+/// `width#height#count#columns#rows#interval#name#sigh` for each level separated by `|`
+pub fn parse_storyboard_spec(spec: &str) -> Vec<StoryboardLevel> {
+    let mut parts = spec.split('|');
+
+    let base = parts.next().unwrap_or("");
+    let base = if base.starts_with("https://") || base.starts_with("http://") {
+        base
+    } else {
+        "https://i.ytimg.com/"
+    };
+    let separator = if base.contains('?') { '&' } else { '?' };
+
+    parts
+        .enumerate()
+        .map(|(i, part)| {
+            let mut fields = part.split('#');
+            let mut field = || fields.next().unwrap_or("0");
+            let number = |x: &str| x.parse::<i32>().unwrap_or(0);
+
+            let thumbnail_width = number(field());
+            let thumbnail_height = number(field());
+            let thumbnail_count = number(field());
+            let columns = number(field());
+            let rows = number(field());
+            let interval = number(field());
+            let name = field();
+            let sigh = field();
+
+            let mut template_url = String::with_capacity(base.len() + sigh.len() + 6);
+            template_url.push_str(base);
+            template_url.push(separator);
+            template_url.push_str("sigh=");
+            encode_query_value(sigh, &mut template_url);
+
+            StoryboardLevel {
+                template_url: template_url
+                    .replace("$L", &i.to_string())
+                    .replace("$N", name),
+                thumbnail_width,
+                thumbnail_height,
+                thumbnail_count,
+                interval,
+                columns,
+                rows,
+                storyboard_count: columns
+                    .checked_mul(rows)
+                    .and_then(|x| thumbnail_count.checked_div(x))
+                    .unwrap_or(0),
+            }
+        })
+        .collect()
+}
+
+/// `application/x-www-form-urlencoded` encoding of a query value
+fn encode_query_value(value: &str, out: &mut String) {
+    const HEX: &[u8; 16] = b"0123456789ABCDEF";
+
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'*' | b'-' | b'.' | b'_' => {
+                out.push(byte as char)
+            }
+            b' ' => out.push('+'),
+            _ => {
+                out.push('%');
+                out.push(HEX[(byte >> 4) as usize] as char);
+                out.push(HEX[(byte & 0xf) as usize] as char);
+            }
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use rusty_ytdl_core::{
+    compare_formats, parse_abbreviated_number, parse_exact_count, parse_storyboard_spec,
+    parse_video_id, time_to_ms, FormatFacts, FormatSortKey,
+};
+
+#[test]
+fn parsers() {
+    let domains = ["youtube.com", "www.youtube.com", "music.youtube.com"];
+
+    for url in [
+        "FZ8BxMU3BYc",
+        "https://www.youtube.com/watch?v=FZ8BxMU3BYc",
+        "https://music.youtube.com/watch?v=FZ8BxMU3BYc&feature=share",
+        "https://youtu.be/FZ8BxMU3BYc?t=10",
+        "https://www.youtube.com/shorts/FZ8BxMU3BYc",
+        "https://www.youtube-nocookie.com/embed/FZ8BxMU3BYc",
+    ] {
+        assert_eq!(parse_video_id(url, &domains), Some("FZ8BxMU3BYc"), "{url}");
+    }
+    assert_eq!(
+        parse_video_id("https://example.com/watch?v=FZ8BxMU3BYc", &domains),
+        None
+    );
+    assert_eq!(
+        parse_video_id("https://notyoutu.be/FZ8BxMU3BYc", &domains),
+        None
+    );
+    assert_eq!(parse_video_id("FZ8BxU3BYc", &domains), None);
+
+    assert_eq!(parse_abbreviated_number("1,2M views"), 1200000);
+    assert_eq!(parse_abbreviated_number("3.4K"), 3400);
+    assert_eq!(parse_abbreviated_number("No views"), 0);
+    assert_eq!(parse_abbreviated_number("3.1B subscribers"), 3100000000);
+    assert_eq!(parse_abbreviated_number("12,345 views"), 12345);
+    assert_eq!(parse_exact_count("12,345 subscribers"), Some(12345));
+    assert_eq!(parse_exact_count("1.2M subscribers"), None);
+    assert_eq!(time_to_ms("1:02:03"), 3723000);
+
+    let levels = parse_storyboard_spec(
+        "https://i.ytimg.com/sb/FZ8BxMU3BYc/storyboard3_L$L/$N.jpg?sqp=abc|48#27#100#10#10#0#default#rs$A|80#45#100#10#0#2000#M$M#rs$B",
+    );
+    assert_eq!(levels.len(), 2);
+    assert_eq!(
+        levels[0].template_url,
+        "https://i.ytimg.com/sb/FZ8BxMU3BYc/storyboard3_L0/default.jpg?sqp=abc&sigh=rs%24A"
+    );
+    assert_eq!(levels[0].storyboard_count, 1);
+    assert_eq!((levels[1].interval, levels[1].storyboard_count), (2000, 0));
+
+    let hd = FormatFacts {
+        itag: 137,
+        has_video: true,
+        quality_label: Some("1080p"),
+        ..Default::default()
+    };
+    let sd = FormatFacts {
+        itag: 18,
+        has_video: true,
+        has_audio: true,
+        height: Some(360),
+        ..Default::default()
+    };
+    let no_codecs: [&str; 0] = [];
+    let keys = [FormatSortKey::Resolution];
+    assert!(compare_formats(&keys, &no_codecs, &no_codecs, &hd, &sd).is_lt());
+    let keys = [FormatSortKey::AudioVideo, FormatSortKey::Resolution];
+    assert!(compare_formats(&keys, &no_codecs, &no_codecs, &hd, &sd).is_gt());
+}
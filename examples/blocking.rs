@@ -2,8 +2,21 @@ fn main() {
     #[cfg(feature = "blocking")]
     {
         use rusty_ytdl::blocking::*;
+        use rusty_ytdl::stream::DownloadProgress;
+        use rusty_ytdl::DownloadOptions;
+
         let video_url = "https://www.youtube.com/watch?v=FZ8BxMU3BYc";
 
-        let _video = Video::new(video_url).unwrap();
+        let video = Video::new(video_url).unwrap();
+
+        let options = DownloadOptions {
+            on_progress: Some(std::sync::Arc::new(|progress: &DownloadProgress| {
+                println!("{:.1?}%", progress.percentage());
+            })),
+            ..Default::default()
+        };
+
+        video.download_with_options("video.mp4", options).unwrap();
+        video.download_audio("audio.webm").unwrap();
     }
 }
@@ -0,0 +1,156 @@
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::structs::VideoError;
+
+/// Record of downloaded videos, consulted before downloading and appended on success
+pub trait DownloadArchive: Send + Sync {
+    /// Video has been downloaded before or not
+    fn contains(&self, video_id: &str) -> Result<bool, VideoError>;
+
+    /// Record downloaded video
+    fn insert(&self, video_id: &str) -> Result<(), VideoError>;
+}
+
+/// yt-dlp compatible `--download-archive` text file with `youtube <video id>` lines
+pub struct TextFileArchive {
+    ids: Mutex<HashSet<String>>,
+    /// File and if its last line lacks a newline
+    file: Mutex<(std::fs::File, bool)>,
+}
+
+impl TextFileArchive {
+    /// Open archive file, created if it doesn't exist
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, VideoError> {
+        let content = match std::fs::read_to_string(path.as_ref()) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(VideoError::ArchiveError(e.to_string())),
+        };
+
+        let ids = content
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("youtube "))
+            .map(|id| id.trim().to_string())
+            .collect();
+
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path.as_ref())
+            .map_err(|e| VideoError::ArchiveError(e.to_string()))?;
+
+        let missing_newline = !content.is_empty() && !content.ends_with('\n');
+
+        Ok(Self {
+            ids: Mutex::new(ids),
+            file: Mutex::new((file, missing_newline)),
+        })
+    }
+}
+
+impl DownloadArchive for TextFileArchive {
+    fn contains(&self, video_id: &str) -> Result<bool, VideoError> {
+        let ids = self
+            .ids
+            .lock()
+            .map_err(|e| VideoError::ArchiveError(e.to_string()))?;
+
+        Ok(ids.contains(video_id))
+    }
+
+    fn insert(&self, video_id: &str) -> Result<(), VideoError> {
+        let mut ids = self
+            .ids
+            .lock()
+            .map_err(|e| VideoError::ArchiveError(e.to_string()))?;
+
+        if ids.contains(video_id) {
+            return Ok(());
+        }
+
+        let mut file = self
+            .file
+            .lock()
+            .map_err(|e| VideoError::ArchiveError(e.to_string()))?;
+        let (file, missing_newline) = &mut *file;
+
+        // Id is recorded only once written, a failed write is retried by the next insert
+        let line = format!(
+            "{}youtube {video_id}\n",
+            if *missing_newline { "\n" } else { "" }
+        );
+        file.write_all(line.as_bytes())
+            .map_err(|e| VideoError::ArchiveError(e.to_string()))?;
+        *missing_newline = false;
+        ids.insert(video_id.to_string());
+
+        Ok(())
+    }
+}
+
+/// SQLite archive, suitable for large archives shared by many processes
+#[cfg(feature = "sqlite")]
+pub struct SqliteArchive {
+    connection: Mutex<rusqlite::Connection>,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteArchive {
+    /// Open archive database, `archive` table is created if it doesn't exist
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, VideoError> {
+        let connection = rusqlite::Connection::open(path)
+            .map_err(|e| VideoError::ArchiveError(e.to_string()))?;
+
+        connection
+            .execute(
+                "CREATE TABLE IF NOT EXISTS archive (
+                    extractor TEXT NOT NULL,
+                    video_id TEXT NOT NULL,
+                    downloaded_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+                    PRIMARY KEY (extractor, video_id)
+                )",
+                (),
+            )
+            .map_err(|e| VideoError::ArchiveError(e.to_string()))?;
+
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl DownloadArchive for SqliteArchive {
+    fn contains(&self, video_id: &str) -> Result<bool, VideoError> {
+        let connection = self
+            .connection
+            .lock()
+            .map_err(|e| VideoError::ArchiveError(e.to_string()))?;
+
+        connection
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM archive WHERE extractor = 'youtube' AND video_id = ?1)",
+                [video_id],
+                |row| row.get(0),
+            )
+            .map_err(|e| VideoError::ArchiveError(e.to_string()))
+    }
+
+    fn insert(&self, video_id: &str) -> Result<(), VideoError> {
+        let connection = self
+            .connection
+            .lock()
+            .map_err(|e| VideoError::ArchiveError(e.to_string()))?;
+
+        connection
+            .execute(
+                "INSERT OR IGNORE INTO archive (extractor, video_id) VALUES ('youtube', ?1)",
+                [video_id],
+            )
+            .map(|_| ())
+            .map_err(|e| VideoError::ArchiveError(e.to_string()))
+    }
+}
@@ -0,0 +1,471 @@
+use std::{path::Path, process::exit, time::Duration};
+
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
+use colored::Colorize;
+use indicatif::{ProgressBar, ProgressState, ProgressStyle};
+use rusty_ytdl::search::{
+    Playlist, PlaylistSearchOptions, SearchOptions, SearchResult, SearchType, YouTube,
+};
+use rusty_ytdl::{
+    choose_format, render_output_template, SanitizeProfile, Video, VideoOptions, VideoQuality,
+    VideoSearchOptions,
+};
+
+const DEFAULT_OUTPUT_TEMPLATE: &str = "{id}.{ext}";
+
+#[tokio::main]
+async fn main() {
+    let cmd = Command::new("rusty-ytdl")
+        .about("A CLI for rusty_ytdl")
+        .version(env!("CARGO_PKG_VERSION"))
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .author("Mithronn")
+        .subcommand(
+            Command::new("info")
+                .about("Print video details and available formats")
+                .arg(id_or_url_arg())
+                .arg(
+                    Arg::new("dump-json")
+                        .long("dump-json")
+                        .help("Print video info as a single JSON line (info.json schema)")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("search")
+                .about("Search videos, playlists or channels")
+                .arg(
+                    Arg::new("query")
+                        .help("Search query")
+                        .num_args(1..)
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("limit")
+                        .long("limit")
+                        .short('l')
+                        .help("Maximum result count")
+                        .value_parser(value_parser!(u64))
+                        .default_value("10"),
+                )
+                .arg(
+                    Arg::new("type")
+                        .long("type")
+                        .short('t')
+                        .help("Result type")
+                        .value_parser(["video", "playlist", "channel", "all"])
+                        .default_value("video"),
+                ),
+        )
+        .subcommand(
+            Command::new("playlist")
+                .about("Dump videos of the playlist")
+                .arg(id_or_url_arg())
+                .arg(
+                    Arg::new("limit")
+                        .long("limit")
+                        .short('l')
+                        .help("Maximum video count")
+                        .value_parser(value_parser!(u64))
+                        .default_value("100"),
+                )
+                .arg(
+                    Arg::new("all")
+                        .long("all")
+                        .help("Fetch all videos and ignore limit")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .help("Print playlist as JSON")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("download")
+                .about("Download the video to spesific folder")
+                .arg(id_or_url_arg())
+                .arg(
+                    Arg::new("path")
+                        .long("path")
+                        .short('p')
+                        .help("Location folder to download")
+                        .num_args(1)
+                        .default_value("."),
+                )
+                .arg(
+                    Arg::new("output")
+                        .long("output")
+                        .short('o')
+                        .help("Output file name template. Available keys: {id}, {title}, {author}, {itag}, {quality}, {ext}")
+                        .num_args(1)
+                        .default_value(DEFAULT_OUTPUT_TEMPLATE),
+                )
+                .arg(
+                    Arg::new("quality")
+                        .long("quality")
+                        .short('q')
+                        .help("Format quality")
+                        .value_parser([
+                            "highest",
+                            "lowest",
+                            "highest-audio",
+                            "lowest-audio",
+                            "highest-video",
+                            "lowest-video",
+                        ])
+                        .default_value("highest"),
+                )
+                .arg(
+                    Arg::new("filter")
+                        .long("filter")
+                        .short('f')
+                        .help("Format filter")
+                        .value_parser(["video-audio", "video", "audio"])
+                        .default_value("video-audio"),
+                )
+                .arg(
+                    Arg::new("audio-language")
+                        .long("audio-language")
+                        .help("Audio track language of multi-audio videos (en, es-419...)"),
+                )
+                .arg(
+                    Arg::new("itag")
+                        .long("itag")
+                        .help("Download the format with given itag instead of quality and filter")
+                        .value_parser(value_parser!(u64)),
+                ),
+        );
+
+    let matches = cmd.get_matches();
+    match matches.subcommand() {
+        Some(("info", info_matches)) => info(info_matches).await,
+        Some(("search", search_matches)) => search(search_matches).await,
+        Some(("playlist", playlist_matches)) => playlist(playlist_matches).await,
+        Some(("download", download_matches)) => download(download_matches).await,
+        None => unreachable!(),
+        _ => unreachable!(),
+    }
+}
+
+fn id_or_url_arg() -> Arg {
+    Arg::new("id-or-url")
+        .short('i')
+        .long("id")
+        .help("Video ID or URL")
+        .num_args(1)
+        .required(true)
+}
+
+async fn info(matches: &ArgMatches) {
+    let id_or_url = matches.get_one::<String>("id-or-url").expect("id-or-url");
+
+    let video = unwrap_or_exit(Video::new(id_or_url));
+    let video_info = unwrap_or_exit(video.get_info().await);
+
+    if matches.get_flag("dump-json") {
+        println!("{}", video_info.to_info_json());
+        exit(0);
+    }
+
+    let details = &video_info.video_details;
+
+    println!("{} {}", "Title:".white().bold(), details.title.cyan());
+    println!(
+        "{} {}",
+        "Author:".white().bold(),
+        details
+            .author
+            .as_ref()
+            .map(|x| x.name.as_str())
+            .unwrap_or(details.owner_channel_name.as_str())
+    );
+    println!("{} {}s", "Duration:".white().bold(), details.length_seconds);
+    println!("{} {}", "Views:".white().bold(), details.view_count);
+    println!("{} {}\n", "URL:".white().bold(), details.video_url);
+
+    println!(
+        "{:<6} {:<28} {:<10} {:<10} {:>12}",
+        "itag".bold(),
+        "mime type".bold(),
+        "quality".bold(),
+        "bitrate".bold(),
+        "size".bold()
+    );
+    for format in video_info.formats.iter() {
+        println!(
+            "{:<6} {:<28} {:<10} {:<10} {:>12}",
+            format.itag,
+            format.mime_type.split(';').next().unwrap_or(""),
+            format
+                .quality_label
+                .as_deref()
+                .or(format.audio_quality.as_deref())
+                .unwrap_or("-"),
+            format.bitrate,
+            format.content_length.as_deref().unwrap_or("-"),
+        );
+    }
+    exit(0);
+}
+
+async fn search(matches: &ArgMatches) {
+    let query: Vec<_> = matches
+        .get_many::<String>("query")
+        .expect("query")
+        .map(|s| s.as_str())
+        .collect();
+
+    let search_type = match matches.get_one::<String>("type").map(|s| s.as_str()) {
+        Some("playlist") => SearchType::Playlist,
+        Some("channel") => SearchType::Channel,
+        Some("all") => SearchType::All,
+        _ => SearchType::Video,
+    };
+
+    let options = SearchOptions {
+        limit: *matches.get_one::<u64>("limit").expect("limit"),
+        search_type,
+        ..Default::default()
+    };
+
+    let youtube = unwrap_or_exit(YouTube::new());
+    let results = unwrap_or_exit(youtube.search(query.join(" "), Some(&options)).await);
+
+    for result in results.iter() {
+        match result {
+            SearchResult::Video(video) => println!(
+                "{} {} {} ({})",
+                "[video]".blue(),
+                video.title.cyan(),
+                video.channel.name.white(),
+                video.url.underline()
+            ),
+            SearchResult::Playlist(playlist) => println!(
+                "{} {} {} ({})",
+                "[playlist]".magenta(),
+                playlist.name.cyan(),
+                playlist.channel.name.white(),
+                playlist.url.underline()
+            ),
+            SearchResult::Channel(channel) => println!(
+                "{} {} ({})",
+                "[channel]".yellow(),
+                channel.name.cyan(),
+                channel.url.underline()
+            ),
+            SearchResult::Short(short) => println!(
+                "{} {} ({})",
+                "[short]".red(),
+                short.title.cyan(),
+                short.url.underline()
+            ),
+            SearchResult::Movie(movie) => println!(
+                "{} {} {} ({})",
+                "[movie]".green(),
+                movie.title.cyan(),
+                movie.channel.name.white(),
+                movie.url.underline()
+            ),
+        }
+    }
+    exit(0);
+}
+
+async fn playlist(matches: &ArgMatches) {
+    let id_or_url = matches.get_one::<String>("id-or-url").expect("id-or-url");
+
+    let options = PlaylistSearchOptions {
+        limit: *matches.get_one::<u64>("limit").expect("limit"),
+        fetch_all: matches.get_flag("all"),
+        ..Default::default()
+    };
+
+    let playlist = unwrap_or_exit(Playlist::get(id_or_url, Some(&options)).await);
+
+    if matches.get_flag("json") {
+        print_json(&playlist);
+        exit(0);
+    }
+
+    println!(
+        "{} {} ({} videos)\n",
+        playlist.name.cyan().bold(),
+        playlist.channel.name.white(),
+        playlist.videos.len()
+    );
+    for (index, video) in playlist.videos.iter().enumerate() {
+        println!(
+            "{:>4}. {} [{}] ({})",
+            index + 1,
+            video.title,
+            video.duration_raw,
+            video.url.underline()
+        );
+    }
+    exit(0);
+}
+
+async fn download(matches: &ArgMatches) {
+    let id_or_url = matches.get_one::<String>("id-or-url").expect("id-or-url");
+
+    let path_values = Path::new(matches.get_one::<String>("path").expect("path"));
+
+    if !path_values.exists() {
+        print_error("Folder path not found!");
+        exit(1);
+    } else if !path_values.is_dir() {
+        print_error("Output path must be folder!");
+        exit(1);
+    }
+
+    let quality = match matches.get_one::<String>("quality").map(|s| s.as_str()) {
+        Some("lowest") => VideoQuality::Lowest,
+        Some("highest-audio") => VideoQuality::HighestAudio,
+        Some("lowest-audio") => VideoQuality::LowestAudio,
+        Some("highest-video") => VideoQuality::HighestVideo,
+        Some("lowest-video") => VideoQuality::LowestVideo,
+        _ => VideoQuality::Highest,
+    };
+
+    let filter = match matches.get_one::<String>("filter").map(|s| s.as_str()) {
+        Some("video") => VideoSearchOptions::Video,
+        Some("audio") => VideoSearchOptions::Audio,
+        _ => VideoSearchOptions::VideoAudio,
+    };
+
+    let download_options = VideoOptions {
+        quality,
+        filter,
+        audio_language: matches.get_one::<String>("audio-language").cloned(),
+        ..Default::default()
+    };
+
+    let video = unwrap_or_exit(Video::new_with_options(id_or_url, download_options.clone()));
+    let video_info = unwrap_or_exit(video.get_info().await);
+
+    let format = match matches.get_one::<u64>("itag") {
+        Some(itag) => {
+            let format = video_info.formats.iter().find(|x| x.itag == *itag);
+
+            if format.is_none() {
+                print_error(format!("Format with itag {itag} not found!"));
+                exit(1);
+            }
+
+            format.unwrap().clone()
+        }
+        None => unwrap_or_exit(choose_format(&video_info.formats, &download_options)),
+    };
+
+    let template = matches.get_one::<String>("output").expect("output");
+    let file_path = path_values.join(render_output_template(
+        template,
+        &video_info,
+        &format,
+        sanitize_profile(),
+    ));
+
+    let stream = unwrap_or_exit(video.stream_with_format(&format).await);
+    let video_size = stream.content_length();
+
+    let pb = ProgressBar::new(video_size as u64);
+
+    pb.set_style(ProgressStyle::with_template("{msg}\n\n{spinner:.blue} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({eta})")
+        .unwrap()
+        .with_key("eta", |state: &ProgressState, w: &mut dyn std::fmt::Write| write!(w, "{:.1}s", state.eta().as_secs_f64()).unwrap())
+        .progress_chars("█░░")
+        .tick_chars("⠋⠙⠹⠸⠼⠴⠦⠧⠇⠏"));
+
+    pb.set_message(format!(
+        "{} {}",
+        video_info.video_details.title.cyan(),
+        "is downloading...".white().bold(),
+    ));
+    pb.enable_steady_tick(Duration::from_millis(100));
+
+    // Write to .part file and rename when completed
+    let mut part_path = file_path.clone().into_os_string();
+    part_path.push(".part");
+
+    let file = std::fs::File::create(&part_path);
+    if let Err(err) = file {
+        pb.finish_and_clear();
+        print_error(err.to_string());
+        exit(1);
+    }
+    let mut file = file.unwrap();
+
+    let mut downloaded = 0_u64;
+
+    loop {
+        let bytes = match stream.chunk().await {
+            Ok(Some(bytes)) => bytes,
+            Ok(None) => break,
+            Err(err) => {
+                pb.finish_and_clear();
+                let _ = std::fs::remove_file(&part_path);
+                print_error(err.to_string());
+                exit(1);
+            }
+        };
+
+        use std::io::Write;
+
+        if let Err(err) = file.write_all(&bytes) {
+            pb.finish_and_clear();
+            let _ = std::fs::remove_file(&part_path);
+            print_error(err.to_string());
+            exit(1);
+        }
+
+        downloaded += bytes.len() as u64;
+        pb.set_position(std::cmp::min(downloaded, video_size as u64));
+    }
+
+    pb.finish_and_clear();
+
+    drop(file);
+    unwrap_or_exit(std::fs::rename(&part_path, &file_path));
+
+    // Display successfuly download message than exit with success
+    println!(
+        "\n{} {}\n",
+        "Video successfully downloaded to".white().bold(),
+        file_path.to_string_lossy().green().underline()
+    );
+    exit(0);
+}
+
+fn sanitize_profile() -> SanitizeProfile {
+    if cfg!(windows) {
+        SanitizeProfile::Windows
+    } else {
+        SanitizeProfile::Unix
+    }
+}
+
+fn unwrap_or_exit<T, E: std::fmt::Display>(result: Result<T, E>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(err) => {
+            print_error(err.to_string());
+            exit(1);
+        }
+    }
+}
+
+fn print_json(value: &impl serde::Serialize) {
+    println!("{}", unwrap_or_exit(serde_json::to_string_pretty(value)));
+}
+
+fn print_error(msg: impl Into<String>) {
+    println!(
+        "{} {}\n\nFor more information, try '{}'.",
+        "error:".bold().red(),
+        msg.into(),
+        "--help".white().bold(),
+    );
+}
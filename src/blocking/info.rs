@@ -1,7 +1,15 @@
+use std::sync::{mpsc, Arc, Mutex};
+
+use bytes::Bytes;
+
 use crate::block_async;
-use crate::structs::{VideoError, VideoInfo, VideoOptions};
-use crate::utils::choose_format;
-use crate::Video as AsyncVideo;
+use crate::stream::DownloadProgress;
+use crate::structs::{
+    DownloadOptions, DownloadResult, RangeObject, VideoError, VideoFormat, VideoInfo, VideoOptions,
+    VideoQuality, VideoSearchOptions,
+};
+use crate::utils::{choose_format, finalize_url};
+use crate::{TaskGroup, Video as AsyncVideo};
 
 use super::stream::{LiveStream, LiveStreamOptions, NonLiveStream, NonLiveStreamOptions, Stream};
 
@@ -49,7 +57,7 @@ impl Video {
     ///     }
     /// ```
     pub fn stream(&self) -> Result<Box<dyn Stream + Send + Sync>, VideoError> {
-        let client = self.0.get_client();
+        let client = self.0.get_stream_client();
 
         let options = self.0.get_options();
 
@@ -57,17 +65,14 @@ impl Video {
         let format = choose_format(&info.formats, &options)
             .map_err(|_op| VideoError::VideoSourceNotFound)?;
 
-        let link = format.url;
-
-        if link.is_empty() {
-            return Err(VideoError::VideoSourceNotFound);
-        }
+        let link = finalize_url(&format, &options.request_options.stream_session)?;
 
         // Only check for HLS formats for live streams
         if format.is_hls {
             let stream = LiveStream::new(LiveStreamOptions {
                 client: Some(client.clone()),
                 stream_url: link,
+                on_progress: options.download_options.on_progress.clone(),
             });
 
             if stream.is_err() {
@@ -86,25 +91,13 @@ impl Video {
         let start = 0;
         let end = start + dl_chunk_size;
 
-        let mut content_length = format
+        // Unknown length is taken from the first ranged response
+        let content_length = format
             .content_length
             .unwrap_or("0".to_string())
             .parse::<u64>()
             .unwrap_or(0);
 
-        // Get content length from source url if content_length is 0
-        if content_length == 0 {
-            let content_length_response = block_async!(client.get(&link).send())
-                .map_err(VideoError::ReqwestMiddleware)?
-                .content_length();
-
-            if content_length_response.is_none() {
-                return Err(VideoError::VideoNotFound);
-            }
-
-            content_length = content_length_response.unwrap();
-        }
-
         let stream = NonLiveStream::new(NonLiveStreamOptions {
             client: Some(client.clone()),
             link,
@@ -112,6 +105,15 @@ impl Video {
             dl_chunk_size,
             start,
             end,
+            prefetch_chunks: match options.request_options.throttled_mode {
+                Some(_) => 0,
+                None => options.download_options.prefetch_chunks,
+            },
+            on_progress: options.download_options.on_progress.clone(),
+            init_range: format.init_range.as_ref().and_then(RangeObject::bounds),
+            index_range: format.index_range.as_ref().and_then(RangeObject::bounds),
+            content_type: Some(format.mime_type),
+            tasks: options.download_options.tasks.clone(),
         });
 
         if stream.is_err() {
@@ -121,9 +123,117 @@ impl Video {
         Ok(Box::new(stream.unwrap()))
     }
 
-    /// Download video directly to the file
-    pub fn download<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), VideoError> {
-        Ok(block_async!(self.0.download(path))?)
+    /// Download video directly to the file, with the [`DownloadOptions`] of the video.
+    /// [`DownloadOptions::on_progress`] is called on the calling thread
+    pub fn download<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+    ) -> Result<DownloadResult, VideoError> {
+        self.download_with(path.as_ref(), self.0.get_options())
+    }
+
+    /// Download video directly to the file with the given [`DownloadOptions`].
+    /// [`DownloadOptions::on_progress`] is called on the calling thread, so GUI state can be updated from it
+    /// # Example
+    /// ```ignore
+    ///     let video = Video::new("FZ8BxMU3BYc").unwrap();
+    ///
+    ///     let options = DownloadOptions {
+    ///         on_progress: Some(Arc::new(|progress: &DownloadProgress| {
+    ///             println!("{:?}%", progress.percentage());
+    ///         })),
+    ///         ..Default::default()
+    ///     };
+    ///
+    ///     video.download_with_options("video.mp4", options).unwrap();
+    /// ```
+    pub fn download_with_options<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+        options: DownloadOptions,
+    ) -> Result<DownloadResult, VideoError> {
+        let mut video_options = self.0.get_options();
+        video_options.download_options = options;
+
+        self.download_with(path.as_ref(), video_options)
+    }
+
+    /// Download the highest quality audio only format to the file, with the [`DownloadOptions`] of the video
+    pub fn download_audio<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+    ) -> Result<DownloadResult, VideoError> {
+        let mut video_options = self.0.get_options();
+        video_options.quality = VideoQuality::HighestAudio;
+        video_options.filter = VideoSearchOptions::Audio;
+
+        self.download_with(path.as_ref(), video_options)
+    }
+
+    /// Run the download on the runtime and forward its progress to the calling thread
+    fn download_with(
+        &self,
+        path: &std::path::Path,
+        mut options: VideoOptions,
+    ) -> Result<DownloadResult, VideoError> {
+        enum Event {
+            Progress(DownloadProgress),
+            Done(Result<DownloadResult, VideoError>),
+        }
+
+        let (sender, receiver) = mpsc::channel();
+
+        let on_progress = options.download_options.on_progress.take();
+        if on_progress.is_some() {
+            // `mpsc::Sender` is not `Sync` on older compilers
+            let sender = Mutex::new(sender.clone());
+            options.download_options.on_progress = Some(Arc::new(move |progress| {
+                let _ = sender
+                    .lock()
+                    .unwrap()
+                    .send(Event::Progress(progress.clone()));
+            }));
+        }
+
+        let tasks = options
+            .download_options
+            .tasks
+            .clone()
+            .unwrap_or_else(TaskGroup::shared);
+        let video = self.0.with_options(options);
+        let path = path.to_path_buf();
+        tasks.spawn_on(&super::TOKIO_RT, async move {
+            let result = video.download(path).await;
+            let _ = sender.send(Event::Done(result));
+        });
+
+        for event in receiver {
+            match event {
+                Event::Progress(progress) => {
+                    if let Some(on_progress) = &on_progress {
+                        on_progress(&progress);
+                    }
+                }
+                Event::Done(result) => return result,
+            }
+        }
+
+        // Sender dropped without a result, the task was cancelled or panicked
+        Err(VideoError::Cancelled)
+    }
+
+    /// Download the given [`VideoFormat`] into memory, up to `max_size` bytes
+    pub fn download_to_vec(
+        &self,
+        format: &VideoFormat,
+        max_size: usize,
+    ) -> Result<Vec<u8>, VideoError> {
+        Ok(block_async!(self.0.download_to_vec(format, max_size))?)
+    }
+
+    /// Download video chosen with [`VideoOptions`] into memory, up to `max_size` bytes
+    pub fn download_to_bytes(&self, max_size: usize) -> Result<Bytes, VideoError> {
+        Ok(block_async!(self.0.download_to_bytes(max_size))?)
     }
 
     /// Get video URL
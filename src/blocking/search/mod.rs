@@ -1,6 +1,7 @@
 mod youtube;
 
 pub use youtube::{
-    Channel, EmbedOptions, Playlist, PlaylistSearchOptions, RequestOptions, SearchOptions,
-    SearchResult, SearchType, Video, YouTube,
+    Channel, EmbedOptions, Movie, Playlist, PlaylistSearchOptions, RequestOptions, SearchDuration,
+    SearchFeature, SearchFilters, SearchOptions, SearchResult, SearchType, Short, SortBy,
+    UploadDate, Video, YouTube,
 };
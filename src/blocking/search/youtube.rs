@@ -1,6 +1,7 @@
 pub use crate::search::{
-    Channel, EmbedOptions, PlaylistSearchOptions, RequestOptions, SearchOptions, SearchResult,
-    SearchType, Video,
+    Channel, EmbedOptions, Movie, PlaylistSearchOptions, RequestOptions, SearchDuration,
+    SearchFeature, SearchFilters, SearchOptions, SearchResult, SearchType, Short, SortBy,
+    UploadDate, Video,
 };
 use crate::search::{Playlist as AsyncPlaylist, YouTube as AsyncYouTube};
 use crate::{block_async, VideoError};
@@ -145,6 +146,14 @@ impl Playlist {
         self
     }
 
+    /// Restore a [`Playlist`] saved by [`save_state`](AsyncPlaylist::save_state)
+    pub fn resume(
+        state: &str,
+        request_options: Option<&RequestOptions>,
+    ) -> Result<Self, VideoError> {
+        Ok(Self(AsyncPlaylist::resume(state, request_options)?))
+    }
+
     pub fn is_playlist(url_or_id: impl Into<String>) -> bool {
         AsyncPlaylist::is_playlist(url_or_id)
     }
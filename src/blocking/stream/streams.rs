@@ -1,13 +1,22 @@
+use bytes::Bytes;
+
 pub use crate::stream::{LiveStreamOptions, NonLiveStreamOptions};
 
-use crate::stream::{LiveStream as AsyncLiveStream, NonLiveStream as AsyncNonLiveStream};
+use crate::stream::{
+    ContainerType, LiveStream as AsyncLiveStream, NonLiveStream as AsyncNonLiveStream,
+};
 use crate::{block_async, VideoError};
 
 pub trait Stream {
-    /// Stream a chunk of the [`u8`] bytes
+    /// Stream a chunk of the [`u8`] bytes as [`Bytes`], without copying the response buffer
     ///
     /// When the bytes has been exhausted, this will return `None`.
-    fn chunk(&self) -> Result<Option<Vec<u8>>, VideoError>;
+    fn chunk(&self) -> Result<Option<Bytes>, VideoError>;
+
+    /// Same as [`Stream::chunk`] but copies the bytes into an owned [`Vec<u8>`]
+    fn chunk_vec(&self) -> Result<Option<Vec<u8>>, VideoError> {
+        Ok(self.chunk()?.map(|chunk| chunk.to_vec()))
+    }
 
     /// Content length of the stream
     ///
@@ -15,6 +24,32 @@ pub trait Stream {
     fn content_length(&self) -> usize {
         0
     }
+
+    /// MIME type of the streamed bytes, [`None`] if unknown
+    fn content_type(&self) -> Option<&str> {
+        None
+    }
+
+    /// `Content-Type` header of the first media response, may disagree with [`Stream::content_type`]
+    fn response_content_type(&self) -> Option<&str> {
+        None
+    }
+
+    /// Container detected from the first received chunk, [`None`] before it or if not recognized
+    fn sniffed_container(&self) -> Option<ContainerType> {
+        None
+    }
+
+    /// Reposition the stream, the next [`Stream::chunk`] returns bytes starting at `byte_offset`
+    fn seek(&self, _byte_offset: u64) -> Result<(), VideoError> {
+        Err(VideoError::SeekNotSupported)
+    }
+
+    /// Seek to the start of the fragment containing `time` and return its byte offset.
+    /// Needs the index range of an `mp4` or `webm` format
+    fn seek_time(&self, _time: std::time::Duration) -> Result<u64, VideoError> {
+        Err(VideoError::SeekNotSupported)
+    }
 }
 
 pub struct NonLiveStream(AsyncNonLiveStream);
@@ -26,7 +61,7 @@ impl NonLiveStream {
 }
 
 impl Stream for NonLiveStream {
-    fn chunk(&self) -> Result<Option<Vec<u8>>, VideoError> {
+    fn chunk(&self) -> Result<Option<Bytes>, VideoError> {
         use crate::stream::Stream;
         Ok(block_async!(self.0.chunk())?)
     }
@@ -34,6 +69,31 @@ impl Stream for NonLiveStream {
     fn content_length(&self) -> usize {
         self.0.content_length() as usize
     }
+
+    fn content_type(&self) -> Option<&str> {
+        use crate::stream::Stream;
+        self.0.content_type()
+    }
+
+    fn response_content_type(&self) -> Option<&str> {
+        use crate::stream::Stream;
+        self.0.response_content_type()
+    }
+
+    fn sniffed_container(&self) -> Option<ContainerType> {
+        use crate::stream::Stream;
+        self.0.sniffed_container()
+    }
+
+    fn seek(&self, byte_offset: u64) -> Result<(), VideoError> {
+        use crate::stream::Stream;
+        Ok(block_async!(self.0.seek(byte_offset))?)
+    }
+
+    fn seek_time(&self, time: std::time::Duration) -> Result<u64, VideoError> {
+        use crate::stream::Stream;
+        Ok(block_async!(self.0.seek_time(time))?)
+    }
 }
 
 impl std::ops::Deref for NonLiveStream {
@@ -59,10 +119,25 @@ impl LiveStream {
 }
 
 impl Stream for LiveStream {
-    fn chunk(&self) -> Result<Option<Vec<u8>>, VideoError> {
+    fn chunk(&self) -> Result<Option<Bytes>, VideoError> {
         use crate::stream::Stream;
         Ok(block_async!(self.0.chunk())?)
     }
+
+    fn content_type(&self) -> Option<&str> {
+        use crate::stream::Stream;
+        self.0.content_type()
+    }
+
+    fn response_content_type(&self) -> Option<&str> {
+        use crate::stream::Stream;
+        self.0.response_content_type()
+    }
+
+    fn sniffed_container(&self) -> Option<ContainerType> {
+        use crate::stream::Stream;
+        self.0.sniffed_container()
+    }
 }
 
 impl std::ops::Deref for LiveStream {
@@ -0,0 +1,203 @@
+use std::time::Duration;
+
+use crate::structs::{CaptionTrack, VideoError};
+use crate::Video;
+
+/// Caption file formats served by Youtube timedtext API
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, derive_more::Display)]
+pub enum CaptionFormat {
+    /// WebVTT
+    #[default]
+    #[display(fmt = "vtt")]
+    Vtt,
+    /// Timed Text Markup Language
+    #[display(fmt = "ttml")]
+    Ttml,
+    /// Youtube timed text XML
+    #[display(fmt = "srv3")]
+    Srv3,
+    /// Youtube timed text JSON
+    #[display(fmt = "json3")]
+    Json3,
+}
+
+impl CaptionFormat {
+    /// File extension of the format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Vtt => "vtt",
+            Self::Ttml => "ttml",
+            Self::Srv3 => "xml",
+            Self::Json3 => "json",
+        }
+    }
+}
+
+impl CaptionTrack {
+    /// Timedtext URL of the track in the given format
+    pub fn url(&self, format: CaptionFormat) -> String {
+        format!("{}&fmt={}", self.base_url, format)
+    }
+}
+
+impl Video {
+    /// Download the caption track in the given format
+    /// # Example
+    /// ```ignore
+    ///     let video_info = video.get_info().await.unwrap();
+    ///
+    ///     let track = video_info.caption_tracks.iter().find(|x| x.language_code == "en").unwrap();
+    ///
+    ///     let vtt = video.download_caption(track, CaptionFormat::Vtt).await.unwrap();
+    /// ```
+    pub async fn download_caption(
+        &self,
+        track: &CaptionTrack,
+        format: CaptionFormat,
+    ) -> Result<String, VideoError> {
+        let response = self
+            .get_client()
+            .get(track.url(format))
+            .send()
+            .await
+            .map_err(VideoError::ReqwestMiddleware)?;
+
+        response.text().await.map_err(VideoError::Reqwest)
+    }
+
+    /// Download the transcript of the caption track in `language_code`, manually created tracks are preferred
+    pub async fn transcript(&self, language_code: &str) -> Result<Transcript, VideoError> {
+        let info = self.get_basic_info().await?;
+        let track = select_caption_tracks(&info.caption_tracks, &[language_code.to_string()])
+            .into_iter()
+            .next()
+            .cloned()
+            .ok_or_else(|| VideoError::CaptionTrackNotFound(language_code.to_string()))?;
+
+        let json3 = self.download_caption(&track, CaptionFormat::Json3).await?;
+
+        Transcript::from_json3(&track.language_code, &json3)
+    }
+
+    /// Fetch the transcript in `language_code` and [search](Transcript::search) it for `query`
+    /// # Example
+    /// ```ignore
+    ///     for segment in video.find_in_transcript("never gonna", "en").await.unwrap() {
+    ///         println!("{:?} {}", segment.start, segment.text);
+    ///     }
+    /// ```
+    pub async fn find_in_transcript(
+        &self,
+        query: &str,
+        language_code: &str,
+    ) -> Result<Vec<TranscriptSegment>, VideoError> {
+        let transcript = self.transcript(language_code).await?;
+
+        Ok(transcript.search(query).into_iter().cloned().collect())
+    }
+}
+
+/// Timed text of a caption track
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct Transcript {
+    pub language_code: String,
+    pub segments: Vec<TranscriptSegment>,
+}
+
+/// Text shown from `start` for `duration`
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct TranscriptSegment {
+    pub start: Duration,
+    pub duration: Duration,
+    pub text: String,
+}
+
+impl Transcript {
+    /// Parse a [`CaptionFormat::Json3`] caption file
+    pub fn from_json3(language_code: &str, json3: &str) -> Result<Self, VideoError> {
+        let value: serde_json::Value = serde_json::from_str(json3)?;
+
+        let segments = value
+            .get("events")
+            .and_then(|x| x.as_array())
+            .ok_or(VideoError::BodyCannotParsed)?
+            .iter()
+            .filter_map(|event| {
+                let millis = |key: &str| event.get(key).and_then(|x| x.as_u64()).unwrap_or(0);
+
+                let text = event
+                    .get("segs")?
+                    .as_array()?
+                    .iter()
+                    .filter_map(|x| x.get("utf8").and_then(|x| x.as_str()))
+                    .collect::<String>();
+                let text = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+
+                (!text.is_empty()).then(|| TranscriptSegment {
+                    start: Duration::from_millis(millis("tStartMs")),
+                    duration: Duration::from_millis(millis("dDurationMs")),
+                    text,
+                })
+            })
+            .collect();
+
+        Ok(Self {
+            language_code: language_code.to_string(),
+            segments,
+        })
+    }
+
+    /// Segments where a case-insensitive match of `query` starts. Phrases may continue into the next segments,
+    /// so phrases split by the caption timing are found too
+    pub fn search(&self, query: &str) -> Vec<&TranscriptSegment> {
+        let query = query.split_whitespace().collect::<Vec<&str>>().join(" ");
+        if query.is_empty() {
+            return vec![];
+        }
+        let query = query.to_lowercase();
+
+        // Segments joined with spaces, with the start offset of every segment
+        let mut text = String::new();
+        let mut offsets = Vec::with_capacity(self.segments.len());
+        for segment in &self.segments {
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            offsets.push(text.len());
+            text.push_str(&segment.text.to_lowercase());
+        }
+
+        let mut matches: Vec<&TranscriptSegment> = vec![];
+        for (position, _) in text.match_indices(&query) {
+            let index = offsets
+                .partition_point(|x| *x <= position)
+                .saturating_sub(1);
+            let segment = &self.segments[index];
+
+            if matches.last().map_or(true, |x| !std::ptr::eq(*x, segment)) {
+                matches.push(segment);
+            }
+        }
+
+        matches
+    }
+}
+
+/// Pick caption tracks for the given language codes, preferring manually created tracks over auto-generated ones
+pub fn select_caption_tracks<'a>(
+    tracks: &'a [CaptionTrack],
+    language_codes: &[String],
+) -> Vec<&'a CaptionTrack> {
+    language_codes
+        .iter()
+        .filter_map(|language_code| {
+            let mut matching = tracks
+                .iter()
+                .filter(|x| &x.language_code == language_code)
+                .collect::<Vec<&CaptionTrack>>();
+
+            matching.sort_by_key(|x| x.is_auto_generated());
+            matching.first().copied()
+        })
+        .collect()
+}
@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use reqwest::{Request, Response};
+use reqwest_middleware::{Middleware, Next};
+use task_local_extensions::Extensions;
+
+use crate::constants::{CIRCUIT_BREAKER_COOLDOWN, CIRCUIT_BREAKER_THRESHOLD};
+
+static SHARED: Lazy<CircuitBreaker> = Lazy::new(|| CircuitBreaker::new(CIRCUIT_BREAKER_THRESHOLD));
+
+/// Consecutive failure tracking of media hosts. After `threshold` consecutive failures (connection errors,
+/// timeouts or `5xx`) a host is skipped for a cooldown and requests switch to a mirror host from the `mn`
+/// parameter of the URL, see [`VideoFormat::fallback_urls`](crate::VideoFormat::fallback_urls).
+/// Requests without mirrors are passed through
+///
+/// # Example
+/// ```ignore
+///     let video_options = VideoOptions {
+///         request_options: RequestOptions {
+///             circuit_breaker: Some(CircuitBreaker::new(5)),
+///             ..Default::default()
+///         },
+///         ..Default::default()
+///     };
+/// ```
+#[derive(Clone, derivative::Derivative)]
+#[derivative(Debug)]
+pub struct CircuitBreaker {
+    #[derivative(Debug = "ignore")]
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    threshold: u32,
+    cooldown: Mutex<Duration>,
+    hosts: Mutex<HashMap<String, HostState>>,
+}
+
+#[derive(Default)]
+struct HostState {
+    failures: u32,
+    open_until: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    /// Open the circuit of a host after `threshold` consecutive failures
+    pub fn new(threshold: u32) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                threshold: threshold.max(1),
+                cooldown: Mutex::new(CIRCUIT_BREAKER_COOLDOWN),
+                hosts: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+
+    /// Breaker shared by every client without [`RequestOptions::circuit_breaker`](crate::RequestOptions::circuit_breaker)
+    pub fn shared() -> Self {
+        SHARED.clone()
+    }
+
+    /// Change how long an open host is skipped. Default is [`CIRCUIT_BREAKER_COOLDOWN`]
+    pub fn with_cooldown(self, cooldown: Duration) -> Self {
+        *self.inner.cooldown.lock().unwrap() = cooldown;
+        self
+    }
+
+    /// Host is skipped while a mirror is available
+    pub fn is_open(&self, host: &str) -> bool {
+        self.inner
+            .hosts
+            .lock()
+            .unwrap()
+            .get(host)
+            .and_then(|x| x.open_until)
+            .map_or(false, |until| until > Instant::now())
+    }
+
+    /// Record a failed request and return whether the circuit of the host is open.
+    /// Hosts tried again after their cooldown open at the first failure
+    pub fn record_failure(&self, host: &str) -> bool {
+        let cooldown = *self.inner.cooldown.lock().unwrap();
+        let mut hosts = self.inner.hosts.lock().unwrap();
+        let state = hosts.entry(host.to_string()).or_default();
+
+        state.failures += 1;
+        if state.failures >= self.inner.threshold {
+            state.open_until = Some(Instant::now() + cooldown);
+        }
+
+        state
+            .open_until
+            .map_or(false, |until| until > Instant::now())
+    }
+
+    /// Record a successful request, the host starts over with no failures
+    pub fn record_success(&self, host: &str) {
+        self.inner.hosts.lock().unwrap().remove(host);
+    }
+}
+
+/// Mirrors of a media URL, `rr{fvip}---{sn}` hosts of every other server name of the `mn` parameter.
+/// The current `rrN` prefix is kept without `fvip`
+pub(crate) fn mirror_urls(url: &url::Url) -> Vec<url::Url> {
+    let host = match url.host_str() {
+        Some(host) => host,
+        None => return vec![],
+    };
+
+    let (prefix, rest) = match host.split_once("---") {
+        Some(split) => split,
+        None => return vec![],
+    };
+    let (server, domain) = rest.split_once('.').unwrap_or((rest, ""));
+
+    let param = |name: &str| {
+        url.query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.to_string())
+    };
+
+    let servers = param("mn").unwrap_or_default();
+    let prefix = param("fvip")
+        .filter(|x| !x.is_empty() && x.bytes().all(|x| x.is_ascii_digit()))
+        .map(|x| format!("rr{x}"))
+        .unwrap_or_else(|| prefix.to_string());
+
+    servers
+        .split(',')
+        .filter(|x| !x.is_empty() && *x != server)
+        .filter_map(|x| {
+            let mut mirror = url.clone();
+            let host = if domain.is_empty() {
+                format!("{prefix}---{x}")
+            } else {
+                format!("{prefix}---{x}.{domain}")
+            };
+
+            mirror.set_host(Some(&host)).ok()?;
+            Some(mirror)
+        })
+        .collect()
+}
+
+#[async_trait::async_trait]
+impl Middleware for CircuitBreaker {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        let mut urls = mirror_urls(req.url());
+        if urls.is_empty() {
+            return next.run(req, extensions).await;
+        }
+        urls.insert(0, req.url().clone());
+
+        // Every host open, the original one is tried
+        let start = urls
+            .iter()
+            .position(|x| !self.is_open(x.host_str().unwrap_or("")))
+            .unwrap_or(0);
+
+        for (index, url) in urls.iter().enumerate().skip(start) {
+            let host = url.host_str().unwrap_or("");
+            if index > start && self.is_open(host) {
+                continue;
+            }
+
+            // Streaming bodies cannot be sent again
+            let mut request = match req.try_clone() {
+                Some(request) => request,
+                None => return next.run(req, extensions).await,
+            };
+            *request.url_mut() = url.clone();
+
+            let result = next.clone().run(request, extensions).await;
+
+            let failed = match &result {
+                Ok(response) => response.status().is_server_error(),
+                Err(reqwest_middleware::Error::Reqwest(err)) => {
+                    err.is_timeout() || err.is_connect()
+                }
+                Err(_) => false,
+            };
+
+            if !failed {
+                self.record_success(host);
+                return result;
+            }
+
+            // Closed circuits return the failure to the retry policy
+            if !self.record_failure(host) || index + 1 == urls.len() {
+                return result;
+            }
+        }
+
+        next.run(req, extensions).await
+    }
+}
@@ -0,0 +1,456 @@
+//! Import and export yt-dlp `info.json` documents
+//!
+//! Only the fields which have a counterpart in [`VideoInfo`] / [`VideoFormat`] are mapped,
+//! everything else is dropped on import and left out on export.
+
+use crate::constants::BASE_URL;
+use crate::structs::{
+    Author, Badge, Chapter, Embed, Thumbnail, VideoDetails, VideoError, VideoFormat, VideoInfo,
+    ViewCount,
+};
+use crate::utils::{get_default_audio_language, has_multiple_audio_tracks};
+
+/// Parse a yt-dlp `info.json` string into [`VideoInfo`]
+pub fn from_info_json_str(json: &str) -> Result<VideoInfo, VideoError> {
+    let value: serde_json::Value = serde_json::from_str(json)?;
+
+    from_info_json(&value)
+}
+
+/// Convert a yt-dlp `info.json` document into [`VideoInfo`]
+///
+/// Formats without a numeric itag `format_id` (e.g. storyboards) are skipped
+pub fn from_info_json(value: &serde_json::Value) -> Result<VideoInfo, VideoError> {
+    let id = str_field(value, "id");
+
+    if id.is_empty() {
+        return Err(VideoError::BodyCannotParsed);
+    }
+
+    let is_live = value
+        .get("is_live")
+        .and_then(|x| x.as_bool())
+        .unwrap_or(false);
+
+    let formats = value
+        .get("formats")
+        .and_then(|x| x.as_array())
+        .map(|x| {
+            x.iter()
+                .filter_map(|format| format_from_ytdlp(format, is_live))
+                .collect::<Vec<VideoFormat>>()
+        })
+        .unwrap_or_default();
+
+    let channel_id = first_str_field(value, &["channel_id", "uploader_id"]);
+    let channel_name = first_str_field(value, &["channel", "uploader"]);
+    let channel_url = first_str_field(value, &["channel_url", "uploader_url"]);
+
+    let author = if channel_id.is_empty() && channel_name.is_empty() {
+        None
+    } else {
+        let verified = value
+            .get("channel_is_verified")
+            .and_then(|x| x.as_bool())
+            .unwrap_or(false);
+
+        Some(Author {
+            id: channel_id.clone(),
+            name: channel_name.clone(),
+            user: str_field(value, "uploader_id"),
+            channel_url: channel_url.clone(),
+            external_channel_url: channel_url,
+            user_url: str_field(value, "uploader_url"),
+            thumbnails: vec![],
+            verified,
+            badge: if verified {
+                Badge::Verified
+            } else {
+                Badge::None
+            },
+            subscriber_count: u64_field(value, "channel_follower_count").unwrap_or(0),
+            subscriber_count_text: String::new(),
+            subscriber_count_exact: None,
+        })
+    };
+
+    let thumbnails = value
+        .get("thumbnails")
+        .and_then(|x| x.as_array())
+        .map(|x| {
+            x.iter()
+                .map(|thumbnail| Thumbnail {
+                    width: u64_field(thumbnail, "width").unwrap_or(0),
+                    height: u64_field(thumbnail, "height").unwrap_or(0),
+                    url: str_field(thumbnail, "url"),
+                })
+                .collect::<Vec<Thumbnail>>()
+        })
+        .unwrap_or_default();
+
+    let chapters = value
+        .get("chapters")
+        .and_then(|x| x.as_array())
+        .map(|x| {
+            x.iter()
+                .map(|chapter| Chapter {
+                    title: str_field(chapter, "title"),
+                    start_time: u64_field(chapter, "start_time").unwrap_or(0) as i32,
+                })
+                .collect::<Vec<Chapter>>()
+        })
+        .unwrap_or_default();
+
+    let string_array = |key: &str| {
+        value
+            .get(key)
+            .and_then(|x| x.as_array())
+            .map(|x| {
+                x.iter()
+                    .filter_map(|x| x.as_str().map(|x| x.to_string()))
+                    .collect::<Vec<String>>()
+            })
+            .unwrap_or_default()
+    };
+
+    // yt-dlp dates are YYYYMMDD, Youtube uses YYYY-MM-DD
+    let upload_date = str_field(value, "upload_date");
+    let upload_date = if upload_date.len() == 8 && upload_date.bytes().all(|x| x.is_ascii_digit()) {
+        format!(
+            "{}-{}-{}",
+            &upload_date[0..4],
+            &upload_date[4..6],
+            &upload_date[6..8]
+        )
+    } else {
+        upload_date
+    };
+
+    let video_url = str_field(value, "webpage_url");
+
+    let video_details = VideoDetails {
+        author,
+        likes: u64_field(value, "like_count").unwrap_or(0) as i32,
+        dislikes: 0,
+        age_restricted: u64_field(value, "age_limit").unwrap_or(0) >= 18,
+        video_url: if video_url.is_empty() {
+            format!("{BASE_URL}{id}")
+        } else {
+            video_url
+        },
+        storyboards: vec![],
+        chapters,
+        embed: Embed {
+            flash_secure_url: "".to_string(),
+            flash_url: "".to_string(),
+            iframe_url: format!("https://www.youtube.com/embed/{id}"),
+            height: 0,
+            width: 0,
+        },
+        title: str_field(value, "title"),
+        description: str_field(value, "description"),
+        length_seconds: u64_field(value, "duration").unwrap_or(0).to_string(),
+        duration: std::time::Duration::from_secs(u64_field(value, "duration").unwrap_or(0)),
+        owner_profile_url: str_field(value, "uploader_url"),
+        external_channel_id: channel_id.clone(),
+        is_family_safe: u64_field(value, "age_limit").unwrap_or(0) == 0,
+        available_countries: vec![],
+        is_embeddable: value.get("playable_in_embed").and_then(|x| x.as_bool()) != Some(false),
+        is_unlisted: value.get("availability").and_then(|x| x.as_str()) == Some("unlisted"),
+        has_ypc_metadata: false,
+        view_count: u64_field(value, "view_count").unwrap_or(0).to_string(),
+        views: match u64_field(value, "concurrent_view_count") {
+            Some(viewers) if is_live => ViewCount::Watching(viewers),
+            _ => ViewCount::Views(u64_field(value, "view_count").unwrap_or(0)),
+        },
+        category: string_array("categories")
+            .first()
+            .cloned()
+            .unwrap_or_default(),
+        publish_date: upload_date.clone(),
+        owner_channel_name: channel_name,
+        upload_date,
+        video_id: id,
+        keywords: string_array("tags"),
+        hashtags: vec![],
+        channel_id,
+        is_owner_viewing: false,
+        is_crawlable: true,
+        allow_ratings: true,
+        is_private: value.get("availability").and_then(|x| x.as_str()) == Some("private"),
+        is_unplugged_corpus: false,
+        is_live_content: is_live || value.get("was_live").and_then(|x| x.as_bool()) == Some(true),
+        thumbnails,
+        default_audio_language: get_default_audio_language(&formats),
+        has_multiple_audio_tracks: has_multiple_audio_tracks(&formats),
+        has_paid_promotion: false,
+        rich_metadata: vec![],
+    };
+
+    Ok(VideoInfo {
+        dash_manifest_url: None,
+        hls_manifest_url: None,
+        formats,
+        related_videos: vec![],
+        video_details,
+        caption_tracks: vec![],
+        extraction_report: Default::default(),
+        extraction_client: None,
+        products: vec![],
+        ticket_events: vec![],
+        polls: vec![],
+        raw_sections: Default::default(),
+    })
+}
+
+/// Export [`VideoInfo`] in yt-dlp `info.json` shape: the fields of [`VideoInfo::to_info_json`] under their yt-dlp
+/// names, with the yt-dlp only keys added
+pub fn to_info_json(info: &VideoInfo) -> serde_json::Value {
+    let details = &info.video_details;
+    let exported = info.to_info_json();
+    let channel = &exported["channel"];
+    let is_live = info.formats.iter().any(|x| x.is_live);
+
+    let chapters = exported["chapters"].as_array().cloned().unwrap_or_default();
+    let end_times = chapters
+        .iter()
+        .skip(1)
+        .map(|x| x["start_time"].clone())
+        // Live streams have no duration
+        .chain(std::iter::once(
+            exported["duration"].as_u64().filter(|x| *x > 0).into(),
+        ));
+    let chapters: Vec<serde_json::Value> = chapters
+        .iter()
+        .zip(end_times)
+        .map(|(chapter, end_time)| {
+            serde_json::json!({
+                "title": chapter["title"],
+                "start_time": chapter["start_time"],
+                "end_time": end_time,
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "id": exported["id"],
+        "title": exported["title"],
+        "fulltitle": exported["title"],
+        "description": exported["description"],
+        "webpage_url": exported["url"],
+        "original_url": exported["url"],
+        "duration": exported["duration"],
+        "view_count": exported["view_count"],
+        "like_count": details.likes,
+        "channel": channel["name"],
+        "channel_id": channel["id"],
+        "channel_url": channel["url"],
+        "uploader": channel["name"],
+        "uploader_url": details.owner_profile_url,
+        "upload_date": details.upload_date.replace('-', ""),
+        "categories": [exported["category"]],
+        "tags": exported["keywords"],
+        "age_limit": if details.age_restricted { 18 } else { 0 },
+        "is_live": is_live,
+        "was_live": details.is_live_content && !is_live,
+        "thumbnails": exported["thumbnails"],
+        "chapters": chapters,
+        "formats": info.formats.iter().map(format_to_ytdlp).collect::<Vec<serde_json::Value>>(),
+        "extractor": "youtube",
+        "extractor_key": "Youtube",
+    })
+}
+
+/// Convert a yt-dlp format object into [`VideoFormat`]
+pub fn format_from_ytdlp(value: &serde_json::Value, is_live: bool) -> Option<VideoFormat> {
+    // format_id can have suffixes like "251-drc"
+    let format_id = str_field(value, "format_id");
+    let itag = format_id
+        .split('-')
+        .next()
+        .and_then(|x| x.parse::<u64>().ok())?;
+    let is_drc = format_id.split('-').skip(1).any(|x| x == "drc");
+
+    let codec = |key: &str| {
+        value
+            .get(key)
+            .and_then(|x| x.as_str())
+            .filter(|x| *x != "none")
+            .map(|x| x.to_string())
+    };
+
+    let video_codec = codec("vcodec");
+    let audio_codec = codec("acodec");
+
+    let ext = str_field(value, "ext");
+    let container = match ext.as_str() {
+        "m4a" => "mp4".to_string(),
+        "weba" => "webm".to_string(),
+        _ => ext,
+    };
+
+    let codecs = [video_codec.clone(), audio_codec.clone()]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    let kind = if video_codec.is_some() {
+        "video"
+    } else {
+        "audio"
+    };
+
+    let protocol = str_field(value, "protocol");
+
+    // yt-dlp bitrates are in kbit/s
+    let kbps = |key: &str| {
+        value
+            .get(key)
+            .and_then(|x| x.as_f64())
+            .map(|x| (x * 1000.0) as u64)
+    };
+
+    let format_note = str_field(value, "format_note");
+
+    let audio_track_id = value
+        .get("language")
+        .and_then(|x| x.as_str())
+        .filter(|_| audio_codec.is_some())
+        .map(|x| x.to_string());
+
+    // yt-dlp ranks the original audio track with language_preference 10
+    let is_default_audio = audio_codec.is_some()
+        && value
+            .get("language_preference")
+            .and_then(|x| x.as_i64())
+            .map(|x| x >= 10)
+            .unwrap_or(true);
+
+    Some(VideoFormat {
+        itag,
+        mime_type: format!(r#"{kind}/{container}; codecs="{codecs}""#),
+        bitrate: kbps("tbr").unwrap_or(0),
+        width: u64_field(value, "width"),
+        height: u64_field(value, "height"),
+        init_range: None,
+        index_range: None,
+        last_modified: None,
+        content_length: u64_field(value, "filesize").map(|x| x.to_string()),
+        quality: None,
+        fps: u64_field(value, "fps"),
+        quality_label: if video_codec.is_some() && !format_note.is_empty() {
+            Some(format_note.clone())
+        } else {
+            None
+        },
+        projection_type: None,
+        average_bitrate: kbps("tbr"),
+        high_replication: None,
+        audio_quality: if video_codec.is_none() && !format_note.is_empty() {
+            Some(format!("AUDIO_QUALITY_{}", format_note.to_uppercase()))
+        } else {
+            None
+        },
+        color_info: None,
+        approx_duration_ms: None,
+        audio_sample_rate: u64_field(value, "asr").map(|x| x.to_string()),
+        audio_channels: u64_field(value, "audio_channels").map(|x| x as u8),
+        audio_bitrate: value.get("abr").and_then(|x| x.as_f64()).map(|x| x as u64),
+        loudness_db: None,
+        url: str_field(value, "url"),
+        has_video: video_codec.is_some(),
+        has_audio: audio_codec.is_some(),
+        container: Some(container),
+        codecs: Some(codecs),
+        video_codec,
+        audio_codec,
+        is_live,
+        is_hls: protocol.starts_with("m3u8"),
+        is_dash_mpd: protocol == "http_dash_segments",
+        audio_track_id,
+        audio_track_name: None,
+        is_default_audio,
+        is_drc,
+    })
+}
+
+/// Convert [`VideoFormat`] into a yt-dlp format object, built like [`to_info_json`] on the format object of
+/// [`VideoInfo::to_info_json`]
+pub fn format_to_ytdlp(format: &VideoFormat) -> serde_json::Value {
+    let exported = format.to_info_json();
+
+    let protocol = if format.is_hls {
+        "m3u8_native"
+    } else if format.is_dash_mpd {
+        "http_dash_segments"
+    } else {
+        "https"
+    };
+
+    let container = format.container.clone().unwrap_or_default();
+    let ext = if !format.has_video && container == "mp4" {
+        "m4a".to_string()
+    } else {
+        container
+    };
+
+    let format_note = if format.has_video {
+        format.quality_label.clone()
+    } else {
+        format
+            .audio_quality
+            .as_ref()
+            .map(|x| x.trim_start_matches("AUDIO_QUALITY_").to_lowercase())
+    };
+
+    serde_json::json!({
+        "format_id": if format.is_drc {
+            format!("{}-drc", format.itag)
+        } else {
+            format.itag.to_string()
+        },
+        "format_note": format_note,
+        "url": format.url,
+        "ext": ext,
+        "protocol": protocol,
+        "vcodec": format.video_codec.clone().unwrap_or("none".to_string()),
+        "acodec": format.audio_codec.clone().unwrap_or("none".to_string()),
+        "width": exported["width"],
+        "height": exported["height"],
+        "fps": exported["fps"],
+        "tbr": format.bitrate as f64 / 1000.0,
+        "abr": exported["audio_bitrate"],
+        "asr": exported["audio_sample_rate"],
+        "audio_channels": exported["audio_channels"],
+        "filesize": exported["content_length"],
+        "language": format.audio_language(),
+        "language_preference": if format.audio_track_id.is_some() && format.is_default_audio {
+            10
+        } else {
+            -1
+        },
+    })
+}
+
+fn str_field(value: &serde_json::Value, key: &str) -> String {
+    value
+        .get(key)
+        .and_then(|x| x.as_str())
+        .unwrap_or("")
+        .to_string()
+}
+
+fn first_str_field(value: &serde_json::Value, keys: &[&str]) -> String {
+    keys.iter()
+        .map(|key| str_field(value, key))
+        .find(|x| !x.is_empty())
+        .unwrap_or_default()
+}
+
+/// yt-dlp numbers can be floats (duration, fps...)
+fn u64_field(value: &serde_json::Value, key: &str) -> Option<u64> {
+    value
+        .get(key)
+        .and_then(|x| x.as_u64().or_else(|| x.as_f64().map(|x| x as u64)))
+}
@@ -0,0 +1,126 @@
+use std::future::Future;
+
+use crate::structs::{ErrorContext, VideoError};
+
+tokio::task_local! {
+    static REQUEST_ID: RequestId;
+}
+
+/// Id shared by the log events and the error of one logical operation, like an info fetch or a download job,
+/// across all of its requests and retries
+#[derive(Clone, Debug, PartialEq, Eq, Hash, derive_more::Display)]
+pub struct RequestId(String);
+
+impl RequestId {
+    /// Random 16 digit hex id
+    pub fn new() -> Self {
+        Self(format!("{:016x}", rand::random::<u64>()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for RequestId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Id of the operation running in the current task, also inside callbacks like
+/// [`DownloadOptions::on_progress`](crate::DownloadOptions::on_progress) and in the background tasks it spawns
+/// (prefetching, playlist downloads). Streams returned to the caller are read outside of their operation
+pub fn current_request_id() -> Option<RequestId> {
+    REQUEST_ID.try_with(|x| x.clone()).ok()
+}
+
+/// Id of log lines, `-` outside of an operation
+pub(crate) fn log_label() -> String {
+    current_request_id()
+        .map(|x| x.0)
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// `task` running with the id of the current operation, for tasks spawned by it
+pub(crate) fn in_current_operation<F: Future>(task: F) -> impl Future<Output = F::Output> {
+    let request_id = current_request_id();
+
+    async move {
+        match request_id {
+            Some(request_id) => REQUEST_ID.scope(request_id, task).await,
+            None => task.await,
+        }
+    }
+}
+
+/// Run `operation` with a new [`RequestId`], the id of a failure is stored in `last_error`.
+/// Operations inside another one keep the outer id. The error itself is returned unchanged
+#[cfg_attr(not(feature = "log"), allow(unused_variables))]
+pub(crate) async fn correlate<T>(
+    name: &str,
+    video_id: &str,
+    last_error: &std::sync::Mutex<Option<ErrorContext>>,
+    operation: impl Future<Output = Result<T, VideoError>>,
+) -> Result<T, VideoError> {
+    if current_request_id().is_some() {
+        return operation.await;
+    }
+
+    let request_id = RequestId::new();
+
+    #[cfg(feature = "log")]
+    log::debug!("[{request_id}] {name} {video_id}");
+
+    let result = REQUEST_ID.scope(request_id.clone(), operation).await;
+
+    #[cfg(feature = "log")]
+    match &result {
+        Ok(_) => log::debug!("[{request_id}] {name} finished"),
+        Err(err) => log::debug!("[{request_id}] {name} failed: {err}"),
+    }
+
+    if result.is_err() {
+        *last_error.lock().unwrap() = Some(ErrorContext {
+            request_id: Some(request_id),
+        });
+    }
+
+    result
+}
+
+/// Debug log of every request attempt with the id of its operation, query strings are left out as they carry signatures
+#[cfg(feature = "log")]
+pub(crate) struct RequestLog;
+
+#[cfg(feature = "log")]
+#[async_trait::async_trait]
+impl reqwest_middleware::Middleware for RequestLog {
+    async fn handle(
+        &self,
+        req: reqwest::Request,
+        extensions: &mut task_local_extensions::Extensions,
+        next: reqwest_middleware::Next<'_>,
+    ) -> reqwest_middleware::Result<reqwest::Response> {
+        let method = req.method().clone();
+        let url = format!("{}{}", req.url().host_str().unwrap_or(""), req.url().path());
+        let started = std::time::Instant::now();
+
+        let result = next.run(req, extensions).await;
+
+        let elapsed = started.elapsed().as_millis();
+        match &result {
+            Ok(response) => log::debug!(
+                "[{}] {method} {url} {} ({elapsed} ms)",
+                log_label(),
+                response.status()
+            ),
+            Err(err) => log::debug!(
+                "[{}] {method} {url} failed: {err} ({elapsed} ms)",
+                log_label()
+            ),
+        }
+
+        result
+    }
+}
@@ -0,0 +1,318 @@
+use std::time::{Duration, Instant};
+
+use crate::structs::{VideoError, VideoOptions};
+use crate::utils::{finalize_url, parse_video_formats};
+use crate::Video;
+
+/// Checks of [`diagnose`], run in this order
+#[derive(Clone, Copy, Debug, PartialEq, Eq, derive_more::Display)]
+pub enum DiagnosticCheck {
+    /// `youtube.com` answers
+    #[display(fmt = "reachability")]
+    Reachability,
+    /// Watch page is fetched and its player response parsed
+    #[display(fmt = "watch page")]
+    WatchPage,
+    /// Decipher functions are extracted from the player script
+    #[display(fmt = "player")]
+    Player,
+    /// A sample format URL answers
+    #[display(fmt = "format url")]
+    FormatUrl,
+}
+
+/// Most likely cause of a failed [`diagnose`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, derive_more::Display)]
+pub enum DiagnosticVerdict {
+    /// Every check passed
+    #[display(fmt = "healthy")]
+    Healthy,
+    /// `youtube.com` cannot be reached, check the network or proxy
+    #[display(fmt = "unreachable")]
+    Unreachable,
+    /// YouTube blocks the IP or the client (captcha, `429`, sign in to confirm you're not a bot)
+    #[display(fmt = "blocked")]
+    Blocked,
+    /// The video itself cannot be played (private, removed, upcoming)
+    #[display(fmt = "video unavailable")]
+    Unavailable,
+    /// YouTube answered but the crate failed to parse or decipher it, worth reporting as a bug
+    #[display(fmt = "broken")]
+    Broken,
+}
+
+/// Outcome of one [`DiagnosticCheck`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiagnosticStep {
+    pub check: DiagnosticCheck,
+    pub passed: bool,
+    /// Status, error or what was found
+    pub detail: String,
+    pub latency: Duration,
+}
+
+/// Result of [`diagnose`], checks stop at the first failure
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiagnosticReport {
+    pub video_id: String,
+    pub steps: Vec<DiagnosticStep>,
+    pub verdict: DiagnosticVerdict,
+}
+
+impl std::fmt::Display for DiagnosticReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "rusty_ytdl {} diagnose {}",
+            env!("CARGO_PKG_VERSION"),
+            self.video_id
+        )?;
+
+        for step in &self.steps {
+            writeln!(
+                f,
+                "[{}] {} ({} ms): {}",
+                if step.passed { "ok" } else { "failed" },
+                step.check,
+                step.latency.as_millis(),
+                step.detail
+            )?;
+        }
+
+        write!(f, "verdict: {}", self.verdict)
+    }
+}
+
+impl DiagnosticReport {
+    fn pass(&mut self, check: DiagnosticCheck, started: Instant, detail: impl Into<String>) {
+        self.steps.push(DiagnosticStep {
+            check,
+            passed: true,
+            detail: detail.into(),
+            latency: started.elapsed(),
+        });
+    }
+
+    fn fail(
+        mut self,
+        check: DiagnosticCheck,
+        started: Instant,
+        detail: impl Into<String>,
+        verdict: DiagnosticVerdict,
+    ) -> Self {
+        self.steps.push(DiagnosticStep {
+            check,
+            passed: false,
+            detail: detail.into(),
+            latency: started.elapsed(),
+        });
+        self.verdict = verdict;
+        self
+    }
+}
+
+/// Verdict of a failed watch page or player request
+fn error_verdict(err: &VideoError) -> DiagnosticVerdict {
+    match err {
+        VideoError::BotDetection { .. } | VideoError::ConsentRequired(_) => {
+            DiagnosticVerdict::Blocked
+        }
+        VideoError::ReqwestMiddleware(_) | VideoError::Reqwest(_) => DiagnosticVerdict::Unreachable,
+        _ => DiagnosticVerdict::Broken,
+    }
+}
+
+/// Check step by step whether `video_id` can be downloaded, to tell IP blocks from crate breakage.
+/// Print the report in bug reports
+///
+/// # Example
+/// ```ignore
+///     let report = rusty_ytdl::diagnose("FZ8BxMU3BYc").await.unwrap();
+///
+///     println!("{report}");
+/// ```
+pub async fn diagnose(url_or_id: &str) -> Result<DiagnosticReport, VideoError> {
+    diagnose_with_options(url_or_id, VideoOptions::default()).await
+}
+
+/// [`diagnose`] with the proxies, cookies and extractor of `options`
+pub async fn diagnose_with_options(
+    url_or_id: &str,
+    options: VideoOptions,
+) -> Result<DiagnosticReport, VideoError> {
+    Ok(Video::new_with_options(url_or_id, options)?
+        .diagnose()
+        .await)
+}
+
+impl Video {
+    /// See [`diagnose`]
+    pub async fn diagnose(&self) -> DiagnosticReport {
+        let client = self.get_client();
+        let options = self.get_options();
+        let config = &options.request_options.config;
+        let extractor = self.extractor();
+
+        let mut report = DiagnosticReport {
+            video_id: self.get_video_id(),
+            steps: vec![],
+            verdict: DiagnosticVerdict::Healthy,
+        };
+
+        let started = Instant::now();
+        match client.get(config.url("/generate_204")).send().await {
+            Ok(response) if response.status().is_success() => {
+                report.pass(
+                    DiagnosticCheck::Reachability,
+                    started,
+                    response.status().to_string(),
+                );
+            }
+            Ok(response) => {
+                let verdict = match response.status().as_u16() {
+                    403 | 429 => DiagnosticVerdict::Blocked,
+                    _ => DiagnosticVerdict::Unreachable,
+                };
+                return report.fail(
+                    DiagnosticCheck::Reachability,
+                    started,
+                    response.status().to_string(),
+                    verdict,
+                );
+            }
+            Err(err) => {
+                return report.fail(
+                    DiagnosticCheck::Reachability,
+                    started,
+                    err.to_string(),
+                    DiagnosticVerdict::Unreachable,
+                );
+            }
+        }
+
+        let started = Instant::now();
+        let info = match extractor.fetch_info(client, config, &report.video_id).await {
+            Ok(info) => info,
+            Err(err) => {
+                let verdict = error_verdict(&err);
+                return report.fail(
+                    DiagnosticCheck::WatchPage,
+                    started,
+                    err.to_string(),
+                    verdict,
+                );
+            }
+        };
+
+        let playability = info.player_response.get("playabilityStatus");
+        let status = playability
+            .and_then(|x| x.get("status"))
+            .and_then(|x| x.as_str())
+            .unwrap_or("")
+            .to_string();
+        let reason = playability
+            .and_then(|x| x.get("reason"))
+            .and_then(|x| x.as_str())
+            .unwrap_or("")
+            .to_string();
+
+        match status.as_str() {
+            "OK" => report.pass(DiagnosticCheck::WatchPage, started, status),
+            "" => {
+                return report.fail(
+                    DiagnosticCheck::WatchPage,
+                    started,
+                    "No playability status",
+                    DiagnosticVerdict::Broken,
+                );
+            }
+            _ => {
+                let verdict = if reason.to_lowercase().contains("bot") {
+                    DiagnosticVerdict::Blocked
+                } else {
+                    DiagnosticVerdict::Unavailable
+                };
+                return report.fail(
+                    DiagnosticCheck::WatchPage,
+                    started,
+                    format!("{status}: {reason}"),
+                    verdict,
+                );
+            }
+        }
+
+        let started = Instant::now();
+        let functions = match extractor.decipher(client, config, &info).await {
+            Ok(functions) => functions,
+            Err(err) => {
+                let verdict = error_verdict(&err);
+                return report.fail(DiagnosticCheck::Player, started, err.to_string(), verdict);
+            }
+        };
+        let detail = match info.html5player.as_ref() {
+            Some(html5player) => format!(
+                "{} functions from {html5player}",
+                functions.iter().filter(|x| !x.1.is_empty()).count()
+            ),
+            None => "No player script needed".to_string(),
+        };
+        report.pass(DiagnosticCheck::Player, started, detail);
+
+        let started = Instant::now();
+        let formats = parse_video_formats(&info.player_response, functions).unwrap_or_default();
+        let format = match formats.iter().find(|x| !x.url.is_empty() && !x.is_hls) {
+            Some(format) => format,
+            None => {
+                return report.fail(
+                    DiagnosticCheck::FormatUrl,
+                    started,
+                    format!("No format URL in {} formats", formats.len()),
+                    DiagnosticVerdict::Broken,
+                );
+            }
+        };
+
+        let url = match finalize_url(format, &self.get_options().request_options.stream_session) {
+            Ok(url) => url,
+            Err(err) => {
+                let verdict = error_verdict(&err);
+                return report.fail(
+                    DiagnosticCheck::FormatUrl,
+                    started,
+                    err.to_string(),
+                    verdict,
+                );
+            }
+        };
+
+        let response = self
+            .get_stream_client()
+            .get(url)
+            .header(reqwest::header::RANGE, "bytes=0-0")
+            .send()
+            .await;
+
+        match response {
+            Ok(response) if response.status().is_success() => {
+                let detail = format!("itag {}: {}", format.itag, response.status());
+                report.pass(DiagnosticCheck::FormatUrl, started, detail);
+                report
+            }
+            Ok(response) => {
+                // `403` of media hosts mostly means a wrong n-transform, `429` a blocked IP
+                let verdict = match response.status().as_u16() {
+                    429 => DiagnosticVerdict::Blocked,
+                    _ => DiagnosticVerdict::Broken,
+                };
+                let detail = format!("itag {}: {}", format.itag, response.status());
+                report.fail(DiagnosticCheck::FormatUrl, started, detail, verdict)
+            }
+            Err(err) => report.fail(
+                DiagnosticCheck::FormatUrl,
+                started,
+                err.to_string(),
+                DiagnosticVerdict::Unreachable,
+            ),
+        }
+    }
+}
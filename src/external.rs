@@ -0,0 +1,350 @@
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use tokio::io::AsyncReadExt;
+use tokio::process;
+
+use crate::stream::ProgressTracker;
+use crate::structs::{DownloadResult, RequestOptions, VideoError, VideoFormat};
+use crate::utils::ChecksumHasher;
+use crate::Video;
+
+/// Program doing the byte transfer of [`Video::download_external`], extraction and format selection stay in this crate
+#[derive(Clone, PartialEq, Debug)]
+pub enum ExternalDownloader {
+    /// Segmented download with `aria2c`, progress is read from its JSON-RPC interface
+    Aria2(Aria2Options),
+}
+
+impl Default for ExternalDownloader {
+    fn default() -> Self {
+        Self::Aria2(Aria2Options::default())
+    }
+}
+
+/// Options of [`ExternalDownloader::Aria2`]
+#[derive(Clone, PartialEq, Debug)]
+pub struct Aria2Options {
+    /// `aria2c` executable, looked up in `PATH` by default
+    pub binary: PathBuf,
+    /// Connections per download (`--max-connection-per-server` and `--split`)
+    pub connections: u16,
+    /// Smallest segment downloaded by one connection (`--min-split-size`), like `"1M"`
+    pub min_split_size: String,
+    /// RPC port, a free port is picked if `None`
+    pub rpc_port: Option<u16>,
+    /// Interval of the progress polls
+    pub poll_interval: Duration,
+    /// Additional `aria2c` arguments, added after the generated ones
+    pub args: Vec<String>,
+}
+
+impl Default for Aria2Options {
+    fn default() -> Self {
+        Self {
+            binary: PathBuf::from("aria2c"),
+            connections: 16,
+            min_split_size: "1M".to_string(),
+            rpc_port: None,
+            poll_interval: Duration::from_millis(500),
+            args: vec![],
+        }
+    }
+}
+
+impl ExternalDownloader {
+    /// Arguments downloading `format` into `path` with the headers and cookies of `request_options`,
+    /// without the RPC arguments. Proxies of [`RequestOptions`] can't be passed on, add them to the extra arguments
+    pub fn args(
+        &self,
+        format: &VideoFormat,
+        request_options: &RequestOptions,
+        path: &Path,
+    ) -> Result<Vec<String>, VideoError> {
+        let Self::Aria2(options) = self;
+
+        if format.is_hls {
+            return Err(VideoError::DownloadError(
+                "aria2c can't download HLS formats".to_string(),
+            ));
+        }
+
+        let request = format.as_http_request(request_options)?;
+
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let file_name = path.file_name().ok_or_else(|| {
+            VideoError::DownloadError(format!("{} is not a file", path.display()))
+        })?;
+
+        let mut args = vec![
+            format!("--dir={}", dir.display()),
+            format!("--out={}", file_name.to_string_lossy()),
+            format!("--max-connection-per-server={}", options.connections.max(1)),
+            format!("--split={}", options.connections.max(1)),
+            format!("--min-split-size={}", options.min_split_size),
+            "--allow-overwrite=true".to_string(),
+            "--auto-file-renaming=false".to_string(),
+            "--file-allocation=none".to_string(),
+        ];
+
+        for (name, value) in request.headers() {
+            let value = value.to_str().unwrap_or("");
+
+            // aria2 would send its own User-Agent next to a `--header` one
+            if name == hyper::header::USER_AGENT {
+                args.push(format!("--user-agent={value}"));
+            } else {
+                args.push(format!("--header={name}: {value}"));
+            }
+        }
+
+        args.extend(options.args.iter().cloned());
+        args.push(request.uri().to_string());
+
+        Ok(args)
+    }
+}
+
+impl Video {
+    /// Choose a format like [`Video::download`] and let an external program download it into `path`.
+    /// [`DownloadOptions::on_progress`](crate::DownloadOptions::on_progress) and
+    /// [`DownloadOptions::checksum`](crate::DownloadOptions::checksum) are supported
+    /// # Example
+    /// ```ignore
+    ///     let downloader = ExternalDownloader::Aria2(Aria2Options {
+    ///         connections: 8,
+    ///         ..Default::default()
+    ///     });
+    ///
+    ///     video.download_external("video.mp4", &downloader).await.unwrap();
+    /// ```
+    pub async fn download_external<P: AsRef<Path>>(
+        &self,
+        path: P,
+        downloader: &ExternalDownloader,
+    ) -> Result<DownloadResult, VideoError> {
+        crate::correlation::correlate(
+            "download_external",
+            self.get_video_id().as_str(),
+            &self.last_error,
+            async {
+                let info = self.get_info().await?;
+                let format = self.choose_working_format(&info).await?;
+
+                let options = self.get_options();
+                let args = downloader.args(&format, &options.request_options, path.as_ref())?;
+
+                let ExternalDownloader::Aria2(aria2) = downloader;
+                let total = format.content_length.as_ref().and_then(|x| x.parse().ok());
+                let mut tracker = options
+                    .download_options
+                    .on_progress
+                    .clone()
+                    .map(|callback| ProgressTracker::new(callback, total));
+
+                let size = run_aria2(aria2, args, &mut tracker).await?;
+
+                let checksum = match options.download_options.checksum {
+                    Some(algorithm) => Some(file_checksum(
+                        path.as_ref(),
+                        ChecksumHasher::new(algorithm),
+                    )?),
+                    None => None,
+                };
+
+                Ok(DownloadResult {
+                    path: path.as_ref().to_path_buf(),
+                    size,
+                    checksum,
+                    live_report: None,
+                    parts: vec![],
+                })
+            },
+        )
+        .await
+    }
+}
+
+/// Run `aria2c` with RPC enabled, poll its status until the download is done and return the downloaded bytes.
+/// aria2 keeps running in RPC mode, so it is shut down over RPC once the download is finished
+async fn run_aria2(
+    options: &Aria2Options,
+    args: Vec<String>,
+    tracker: &mut Option<ProgressTracker>,
+) -> Result<u64, VideoError> {
+    let port = match options.rpc_port {
+        Some(port) => port,
+        None => free_port()?,
+    };
+    let secret = format!("{:016x}", rand::random::<u64>());
+    let gid = format!("{:016x}", rand::random::<u64>());
+
+    let mut child = process::Command::new(&options.binary)
+        .arg("--enable-rpc=true")
+        .arg(format!("--rpc-listen-port={port}"))
+        .arg(format!("--rpc-secret={secret}"))
+        .arg(format!("--gid={gid}"))
+        .arg("--console-log-level=error")
+        .arg("--summary-interval=0")
+        .arg("--show-console-readout=false")
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| VideoError::ChildProcessError(e.to_string()))?;
+
+    let rpc = Aria2Rpc {
+        client: reqwest::Client::new(),
+        url: format!("http://127.0.0.1:{port}/jsonrpc"),
+        secret,
+    };
+    let mut downloaded = 0u64;
+
+    loop {
+        tokio::time::sleep(options.poll_interval).await;
+
+        if let Some(status) = child
+            .try_wait()
+            .map_err(|e| VideoError::ChildProcessError(e.to_string()))?
+        {
+            let mut stderr = String::new();
+            if let Some(mut pipe) = child.stderr.take() {
+                let _ = pipe.read_to_string(&mut stderr).await;
+            }
+
+            return Err(VideoError::ChildProcessError(format!(
+                "aria2c exited with {status}: {}",
+                stderr.trim()
+            )));
+        }
+
+        // RPC server is not up yet
+        let status = match rpc.tell_status(&gid).await {
+            Ok(status) => status,
+            Err(_) => continue,
+        };
+
+        let completed = status_number(&status, "completedLength");
+        if let Some(tracker) = tracker.as_mut() {
+            if completed > downloaded {
+                tracker.record(completed - downloaded);
+            }
+        }
+        downloaded = downloaded.max(completed);
+
+        match status.get("status").and_then(|x| x.as_str()) {
+            Some("complete") => break,
+            Some("error") | Some("removed") => {
+                let _ = rpc.call("aria2.forceShutdown", vec![]).await;
+                let _ = child.wait().await;
+
+                return Err(VideoError::DownloadError(format!(
+                    "aria2c failed: {}",
+                    status
+                        .get("errorMessage")
+                        .and_then(|x| x.as_str())
+                        .unwrap_or("unknown error")
+                )));
+            }
+            _ => {}
+        }
+    }
+
+    let _ = rpc.call("aria2.shutdown", vec![]).await;
+    let _ = child.wait().await;
+
+    Ok(downloaded)
+}
+
+struct Aria2Rpc {
+    client: reqwest::Client,
+    url: String,
+    secret: String,
+}
+
+impl Aria2Rpc {
+    async fn call(&self, method: &str, params: Vec<Value>) -> Result<Value, VideoError> {
+        let mut all_params = vec![json!(format!("token:{}", self.secret))];
+        all_params.extend(params);
+
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": "rusty_ytdl",
+            "method": method,
+            "params": all_params,
+        });
+
+        let response: Value = self
+            .client
+            .post(&self.url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(VideoError::Reqwest)?
+            .json()
+            .await
+            .map_err(VideoError::Reqwest)?;
+
+        if let Some(error) = response.get("error") {
+            return Err(VideoError::DownloadError(format!(
+                "aria2 RPC error: {error}"
+            )));
+        }
+
+        Ok(response.get("result").cloned().unwrap_or(Value::Null))
+    }
+
+    async fn tell_status(&self, gid: &str) -> Result<Value, VideoError> {
+        self.call(
+            "aria2.tellStatus",
+            vec![
+                json!(gid),
+                json!(["status", "completedLength", "totalLength", "errorMessage"]),
+            ],
+        )
+        .await
+    }
+}
+
+/// aria2 sends numbers as strings
+fn status_number(status: &Value, key: &str) -> u64 {
+    status
+        .get(key)
+        .and_then(|x| x.as_str())
+        .and_then(|x| x.parse().ok())
+        .unwrap_or(0)
+}
+
+fn free_port() -> Result<u16, VideoError> {
+    std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|x| x.local_addr())
+        .map(|x| x.port())
+        .map_err(|e| VideoError::ChildProcessError(e.to_string()))
+}
+
+fn file_checksum(path: &Path, mut hasher: ChecksumHasher) -> Result<String, VideoError> {
+    use std::io::Read;
+
+    let mut file =
+        std::fs::File::open(path).map_err(|e| VideoError::DownloadError(e.to_string()))?;
+    let mut buf = vec![0u8; 64 * 1024];
+
+    loop {
+        let n = file
+            .read(&mut buf)
+            .map_err(|e| VideoError::DownloadError(e.to_string()))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+
+    Ok(hasher.finalize())
+}
@@ -0,0 +1,263 @@
+#[cfg(feature = "extract")]
+use scraper::{Html, Selector};
+
+use crate::structs::{Config, InnertubeClient, InnertubeConfig, VideoError, VideoFormat};
+use crate::utils::{
+    get_current_html5player, get_functions, get_html, get_html5player, get_player_functions,
+    innertube_config, parse_video_formats, web_headers,
+};
+
+/// Responses a backend got for one video, parsed by [`Video`](crate::Video) into [`VideoInfo`](crate::VideoInfo)
+#[derive(Clone, Debug, Default)]
+pub struct ExtractedInfo {
+    /// `ytInitialPlayerResponse` or an equivalent player response
+    pub player_response: serde_json::Value,
+    /// `ytInitialData` or an equivalent next response, [`serde_json::Value::Null`] if the backend has none
+    pub initial_response: serde_json::Value,
+    /// Path of the player script, [`None`] if the formats carry plain URLs
+    pub html5player: Option<String>,
+}
+
+/// Backend of the info layer. [`WebExtractor`] scrapes the watch page, other backends (InnerTube clients,
+/// Invidious, test fixtures) are set with [`VideoOptions::extractor`](crate::VideoOptions::extractor)
+///
+/// # Example
+/// ```ignore
+///     struct FixtureExtractor(ExtractedInfo);
+///
+///     #[async_trait::async_trait]
+///     impl Extractor for FixtureExtractor {
+///         async fn fetch_info(&self, _: &ClientWithMiddleware, _: &Config, _: &str) -> Result<ExtractedInfo, VideoError> {
+///             Ok(self.0.clone())
+///         }
+///
+///         async fn decipher(&self, _: &ClientWithMiddleware, _: &Config, _: &ExtractedInfo) -> Result<Vec<(String, String)>, VideoError> {
+///             Ok(vec![])
+///         }
+///     }
+/// ```
+#[async_trait::async_trait]
+pub trait Extractor: Send + Sync {
+    /// Fetch the player response and initial data of `video_id`
+    async fn fetch_info(
+        &self,
+        client: &reqwest_middleware::ClientWithMiddleware,
+        config: &Config,
+        video_id: &str,
+    ) -> Result<ExtractedInfo, VideoError>;
+
+    /// Decipher and n-transform functions applied to the format URLs, empty if no deciphering is needed
+    async fn decipher(
+        &self,
+        client: &reqwest_middleware::ClientWithMiddleware,
+        config: &Config,
+        info: &ExtractedInfo,
+    ) -> Result<Vec<(String, String)>, VideoError>;
+
+    /// Formats of the player response with download URLs. Default deciphers the `streamingData` formats
+    async fn fetch_formats(
+        &self,
+        client: &reqwest_middleware::ClientWithMiddleware,
+        config: &Config,
+        info: &ExtractedInfo,
+    ) -> Result<Vec<VideoFormat>, VideoError> {
+        let functions = self.decipher(client, config, info).await?;
+
+        Ok(parse_video_formats(&info.player_response, functions).unwrap_or_default())
+    }
+
+    /// Innertube client the backend requests as, recorded in [`VideoInfo::extraction_client`](crate::VideoInfo::extraction_client)
+    fn client(&self) -> Option<InnertubeClient> {
+        None
+    }
+}
+
+/// Default [`Extractor`], scrapes the watch page and deciphers with its player script.
+/// Needs the `extract` feature, without it every request fails with [`VideoError::ExtractorUnavailable`]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WebExtractor;
+
+#[cfg(not(feature = "extract"))]
+#[async_trait::async_trait]
+impl Extractor for WebExtractor {
+    async fn fetch_info(
+        &self,
+        _client: &reqwest_middleware::ClientWithMiddleware,
+        _config: &Config,
+        _video_id: &str,
+    ) -> Result<ExtractedInfo, VideoError> {
+        Err(VideoError::ExtractorUnavailable)
+    }
+
+    async fn decipher(
+        &self,
+        _client: &reqwest_middleware::ClientWithMiddleware,
+        _config: &Config,
+        _info: &ExtractedInfo,
+    ) -> Result<Vec<(String, String)>, VideoError> {
+        Err(VideoError::ExtractorUnavailable)
+    }
+
+    fn client(&self) -> Option<InnertubeClient> {
+        Some(InnertubeClient::Web)
+    }
+}
+
+#[cfg(feature = "extract")]
+#[async_trait::async_trait]
+impl Extractor for WebExtractor {
+    async fn fetch_info(
+        &self,
+        client: &reqwest_middleware::ClientWithMiddleware,
+        config: &Config,
+        video_id: &str,
+    ) -> Result<ExtractedInfo, VideoError> {
+        let url = url::Url::parse_with_params(&config.watch_url(video_id), &[("hl", "en")])?;
+
+        let response = get_html(client, url.as_str(), Some(&web_headers())).await?;
+
+        let document = Html::parse_document(&response);
+        let scripts_selector = Selector::parse("script").unwrap();
+        let mut player_response_string = document
+            .select(&scripts_selector)
+            .filter(|x| x.inner_html().contains("var ytInitialPlayerResponse ="))
+            .map(|x| x.inner_html().replace("var ytInitialPlayerResponse =", ""))
+            .next()
+            .unwrap_or(String::from(""))
+            .trim()
+            .to_string();
+        let mut initial_response_string = document
+            .select(&scripts_selector)
+            .filter(|x| x.inner_html().contains("var ytInitialData ="))
+            .map(|x| x.inner_html().replace("var ytInitialData =", ""))
+            .next()
+            .unwrap_or(String::from(""))
+            .trim()
+            .to_string();
+
+        // remove json objects' last element (;)
+        player_response_string.pop();
+        initial_response_string.pop();
+
+        let player_response: serde_json::Value = serde_json::from_str(&player_response_string)?;
+        let initial_response: serde_json::Value = serde_json::from_str(&initial_response_string)?;
+
+        Ok(ExtractedInfo {
+            player_response,
+            initial_response,
+            html5player: get_html5player(response.as_str()),
+        })
+    }
+
+    async fn decipher(
+        &self,
+        client: &reqwest_middleware::ClientWithMiddleware,
+        config: &Config,
+        info: &ExtractedInfo,
+    ) -> Result<Vec<(String, String)>, VideoError> {
+        match info.html5player.as_ref() {
+            Some(html5player) => get_functions(html5player, client, config).await,
+            None => Ok(vec![]),
+        }
+    }
+
+    fn client(&self) -> Option<InnertubeClient> {
+        Some(InnertubeClient::Web)
+    }
+}
+
+/// [`Extractor`] requesting the innertube `player` API instead of scraping the watch page. The request carries the
+/// `signatureTimestamp` of the current player script, so ciphered URLs are deciphered with that same player.
+/// [`ExtractedInfo::initial_response`] is [`serde_json::Value::Null`], related videos and other watch page data are missing
+///
+/// # Example
+/// ```ignore
+///     let video_options = VideoOptions {
+///         extractor: Some(std::sync::Arc::new(InnertubeExtractor::new(InnertubeClient::Ios))),
+///         ..Default::default()
+///     };
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct InnertubeExtractor {
+    /// Client of the requests, `WEB` by default
+    pub client: InnertubeClient,
+}
+
+impl InnertubeExtractor {
+    pub fn new(client: InnertubeClient) -> Self {
+        Self { client }
+    }
+
+    /// Body of the innertube `player` request of `video_id`
+    pub fn player_request(
+        &self,
+        innertube: &InnertubeConfig,
+        video_id: &str,
+        signature_timestamp: Option<u64>,
+    ) -> serde_json::Value {
+        let mut playback_context = serde_json::json!({
+            "html5Preference": "HTML5_PREF_WANTS",
+        });
+
+        if let Some(signature_timestamp) = signature_timestamp {
+            playback_context["signatureTimestamp"] = signature_timestamp.into();
+        }
+
+        serde_json::json!({
+            "context": innertube.client_context(self.client),
+            "videoId": video_id,
+            "playbackContext": {
+                "contentPlaybackContext": playback_context,
+            },
+            "contentCheckOk": true,
+            "racyCheckOk": true,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl Extractor for InnertubeExtractor {
+    async fn fetch_info(
+        &self,
+        client: &reqwest_middleware::ClientWithMiddleware,
+        config: &Config,
+        video_id: &str,
+    ) -> Result<ExtractedInfo, VideoError> {
+        let html5player = get_current_html5player(client, config).await?;
+        let player = get_player_functions(&html5player, client, config).await?;
+        let innertube = innertube_config(client, config).await;
+
+        let player_response: serde_json::Value = client
+            .post(config.url(&format!("/youtubei/v1/player?key={}", innertube.api_key)))
+            .headers(self.client.header_profile().headers())
+            .json(&self.player_request(&innertube, video_id, player.signature_timestamp))
+            .send()
+            .await
+            .map_err(VideoError::ReqwestMiddleware)?
+            .json()
+            .await
+            .map_err(VideoError::Reqwest)?;
+
+        Ok(ExtractedInfo {
+            player_response,
+            initial_response: serde_json::Value::Null,
+            html5player: Some(html5player),
+        })
+    }
+
+    async fn decipher(
+        &self,
+        client: &reqwest_middleware::ClientWithMiddleware,
+        config: &Config,
+        info: &ExtractedInfo,
+    ) -> Result<Vec<(String, String)>, VideoError> {
+        match info.html5player.as_ref() {
+            Some(html5player) => get_functions(html5player, client, config).await,
+            None => Ok(vec![]),
+        }
+    }
+
+    fn client(&self) -> Option<InnertubeClient> {
+        Some(self.client)
+    }
+}
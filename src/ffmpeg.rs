@@ -0,0 +1,1346 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use tokio::process;
+
+use crate::postprocess::{PostProcessContext, PostProcessor};
+use crate::structs::{
+    Chapter, FormatId, SanitizeProfile, Thumbnail, VideoError, VideoFormat, VideoInfo,
+    VideoOptions, VideoQuality, VideoSearchOptions,
+};
+use crate::utils::{chapters_from_description, choose_format, render_output_template_with};
+use crate::Video;
+
+/// Output container of [`Video::download_muxed`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, derive_more::Display)]
+pub enum MuxContainer {
+    /// Matroska, accepts every Youtube codec
+    #[default]
+    #[display(fmt = "mkv")]
+    Mkv,
+    /// MPEG-4, only `mp4` video and audio formats are chosen
+    #[display(fmt = "mp4")]
+    Mp4,
+    /// WebM, only `webm` video and audio formats are chosen
+    #[display(fmt = "webm")]
+    Webm,
+}
+
+impl MuxContainer {
+    /// File extension of the container
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Mkv => "mkv",
+            Self::Mp4 => "mp4",
+            Self::Webm => "webm",
+        }
+    }
+
+    /// `ffmpeg` muxer writing the container
+    pub fn muxer(&self) -> &'static str {
+        match self {
+            Self::Mkv => "matroska",
+            Self::Mp4 => "mp4",
+            Self::Webm => "webm",
+        }
+    }
+
+    /// Soft subtitle codec supported by the container
+    pub fn subtitle_codec(&self) -> &'static str {
+        match self {
+            Self::Mkv => "srt",
+            Self::Mp4 => "mov_text",
+            Self::Webm => "webvtt",
+        }
+    }
+
+    fn accepts(&self, format: &VideoFormat) -> bool {
+        match self {
+            Self::Mkv => true,
+            Self::Mp4 => format.container.as_deref() == Some("mp4"),
+            Self::Webm => format.container.as_deref() == Some("webm"),
+        }
+    }
+}
+
+/// Options of [`Video::download_muxed`]
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct MuxOptions {
+    /// Output container
+    pub container: MuxContainer,
+    /// Video only format to mux, highest video format accepted by the container is chosen if `None`
+    pub video_format: Option<VideoFormat>,
+    /// Audio only format to mux, highest audio format accepted by the container is chosen if `None`
+    pub audio_format: Option<VideoFormat>,
+    /// Caption language codes to embed as soft subtitles, manually created tracks are preferred over auto-generated ones
+    #[cfg(feature = "captions")]
+    pub subtitle_languages: Vec<String>,
+    /// Embed video chapters as MP4 chapter atoms / Matroska chapters
+    pub embed_chapters: bool,
+    /// Embed the biggest thumbnail as MP4 cover art / Matroska attachment, ignored for WebM
+    pub embed_thumbnail: bool,
+    /// Discovery of the `ffmpeg` binary
+    pub ffmpeg: FFmpegLocator,
+}
+
+impl MuxOptions {
+    /// Mux the formats of a stored video + audio [`FormatId`] like `"137+140"`
+    pub fn with_format_id(mut self, info: &VideoInfo, id: &FormatId) -> Result<Self, VideoError> {
+        let formats = info.formats_by_id(id)?;
+
+        match formats.as_slice() {
+            [video, audio] => {
+                self.video_format = Some((*video).clone());
+                self.audio_format = Some((*audio).clone());
+                Ok(self)
+            }
+            _ => Err(VideoError::InvalidFormatId(format!(
+                "{id} is not a video + audio id"
+            ))),
+        }
+    }
+}
+
+impl Video {
+    /// Download video and audio formats separately and mux them into a single file with `ffmpeg`
+    /// # Example
+    /// ```ignore
+    ///     let options = MuxOptions {
+    ///         container: MuxContainer::Mkv,
+    ///         subtitle_languages: vec!["en".to_string()],
+    ///         ..Default::default()
+    ///     };
+    ///
+    ///     video.download_muxed("video.mkv", &options).await.unwrap();
+    /// ```
+    pub async fn download_muxed<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: &MuxOptions,
+    ) -> Result<(), VideoError> {
+        // Fail before downloading anything if muxing is not possible
+        let ffmpeg = options.ffmpeg.locate().await?;
+        ffmpeg.require_muxer(options.container.muxer())?;
+
+        #[cfg(feature = "captions")]
+        if !options.subtitle_languages.is_empty() {
+            ffmpeg.require_encoder(options.container.subtitle_codec())?;
+        }
+
+        let info = self.get_info().await?;
+
+        let video_format = match &options.video_format {
+            Some(format) => format.clone(),
+            None => choose_mux_format(&info.formats, options.container, self.get_options(), true)?,
+        };
+        let audio_format = match &options.audio_format {
+            Some(format) => format.clone(),
+            None => choose_mux_format(&info.formats, options.container, self.get_options(), false)?,
+        };
+
+        let video_id = info.video_details.video_id.clone();
+        let mut inputs: Vec<PathBuf> = vec![];
+        let mut attachments: Vec<PathBuf> = vec![];
+
+        let result = async {
+            for format in [&video_format, &audio_format] {
+                let input = temp_path(
+                    &video_id,
+                    &format.itag.to_string(),
+                    format.container.as_deref().unwrap_or("bin"),
+                );
+                inputs.push(input.clone());
+
+                let mut file = tokio::fs::File::create(&input)
+                    .await
+                    .map_err(|e| VideoError::DownloadError(e.to_string()))?;
+                self.download_format_to_writer(format, &mut file).await?;
+            }
+
+            // Soft subtitle inputs as (path, language code, title)
+            #[allow(unused_mut)]
+            let mut subtitles: Vec<(PathBuf, String, String)> = vec![];
+
+            #[cfg(feature = "captions")]
+            for track in crate::captions::select_caption_tracks(
+                &info.caption_tracks,
+                &options.subtitle_languages,
+            ) {
+                let caption = self
+                    .download_caption(track, crate::captions::CaptionFormat::Vtt)
+                    .await?;
+
+                let input = temp_path(&video_id, &track.language_code, "vtt");
+                inputs.push(input.clone());
+
+                tokio::fs::write(&input, caption)
+                    .await
+                    .map_err(|e| VideoError::DownloadError(e.to_string()))?;
+
+                subtitles.push((input, track.language_code.clone(), track.name.clone()));
+            }
+
+            let mut chapters_index = None;
+
+            if options.embed_chapters && !info.video_details.chapters.is_empty() {
+                let input = temp_path(&video_id, "metadata", "txt");
+                chapters_index = Some(inputs.len());
+                inputs.push(input.clone());
+
+                tokio::fs::write(&input, ffmetadata(&info))
+                    .await
+                    .map_err(|e| VideoError::DownloadError(e.to_string()))?;
+            }
+
+            let mut cover_index = None;
+
+            let thumbnail = if options.embed_thumbnail && options.container != MuxContainer::Webm {
+                choose_thumbnail(&info, options.container)
+            } else {
+                None
+            };
+
+            if let Some(thumbnail) = thumbnail {
+                let extension = thumbnail_extension(&thumbnail.url);
+                let input = temp_path(&video_id, "thumbnail", extension);
+                download_thumbnail(self, &thumbnail, &input).await?;
+
+                // MP4 takes cover as an extra video stream, Matroska as an attachment
+                if options.container == MuxContainer::Mp4 {
+                    cover_index = Some(inputs.len());
+                    inputs.push(input);
+                } else {
+                    attachments.push(input);
+                }
+            }
+
+            let mut cmd = ffmpeg.command();
+            cmd.arg("-y").arg("-loglevel").arg("error");
+
+            for input in inputs.iter() {
+                cmd.arg("-i").arg(input);
+            }
+
+            cmd.arg("-map")
+                .arg("0:v:0")
+                .arg("-map")
+                .arg("1:a:0")
+                .arg("-c:v")
+                .arg("copy")
+                .arg("-c:a")
+                .arg("copy");
+
+            for (index, (_, language_code, title)) in subtitles.iter().enumerate() {
+                cmd.arg("-map")
+                    .arg(format!("{}:s:0", index + 2))
+                    .arg(format!("-metadata:s:s:{index}"))
+                    .arg(format!("language={language_code}"))
+                    .arg(format!("-metadata:s:s:{index}"))
+                    .arg(format!("title={title}"));
+            }
+
+            if !subtitles.is_empty() {
+                cmd.arg("-c:s").arg(options.container.subtitle_codec());
+            }
+
+            if let Some(chapters_index) = chapters_index {
+                cmd.arg("-map_chapters").arg(chapters_index.to_string());
+            }
+
+            if let Some(cover_index) = cover_index {
+                cmd.arg("-map")
+                    .arg(format!("{cover_index}:v:0"))
+                    .arg("-disposition:v:1")
+                    .arg("attached_pic");
+            }
+
+            for attachment in attachments.iter() {
+                let extension = attachment
+                    .extension()
+                    .and_then(|x| x.to_str())
+                    .unwrap_or("jpg");
+
+                cmd.arg("-attach")
+                    .arg(attachment)
+                    .arg("-metadata:s:t")
+                    .arg(format!("mimetype={}", thumbnail_mime_type(extension)))
+                    .arg("-metadata:s:t")
+                    .arg(format!("filename=cover.{extension}"));
+            }
+
+            cmd.arg(path.as_ref());
+            run(cmd).await
+        }
+        .await;
+
+        // Remove intermediate files even if muxing failed
+        for input in inputs.iter().chain(attachments.iter()) {
+            let _ = tokio::fs::remove_file(input).await;
+        }
+
+        result
+    }
+}
+
+/// [`PostProcessor`] muxing the download into a container, video only downloads get the highest audio format
+/// accepted by the container
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Mux {
+    pub container: MuxContainer,
+    pub ffmpeg: FFmpegLocator,
+}
+
+#[async_trait::async_trait]
+impl PostProcessor for Mux {
+    fn name(&self) -> &str {
+        "mux"
+    }
+
+    async fn process(&self, context: &mut PostProcessContext<'_>) -> Result<(), VideoError> {
+        let ffmpeg = self.ffmpeg.locate().await?;
+        ffmpeg.require_muxer(self.container.muxer())?;
+
+        let audio = if context.format.has_video && !context.format.has_audio {
+            let format = choose_mux_format(
+                &context.info.formats,
+                self.container,
+                context.video.get_options(),
+                false,
+            )?;
+            let path = temp_path(
+                &context.info.video_details.video_id,
+                &format.itag.to_string(),
+                format.container.as_deref().unwrap_or("bin"),
+            );
+
+            context
+                .video
+                .download_format_to_file(&format, &path)
+                .await?;
+            Some(path)
+        } else {
+            None
+        };
+
+        let output = context.path.with_extension(self.container.extension());
+        let target = if output == context.path {
+            rewrite_path(&output)
+        } else {
+            output.clone()
+        };
+
+        let mut cmd = ffmpeg.command();
+        cmd.arg("-y")
+            .arg("-loglevel")
+            .arg("error")
+            .arg("-i")
+            .arg(&context.path);
+
+        match &audio {
+            Some(audio) => {
+                cmd.arg("-i")
+                    .arg(audio)
+                    .arg("-map")
+                    .arg("0:v:0")
+                    .arg("-map")
+                    .arg("1:a:0");
+            }
+            None => {
+                cmd.arg("-map").arg("0");
+            }
+        }
+
+        cmd.arg("-c").arg("copy").arg(&target);
+        let result = run(cmd).await;
+
+        if let Some(audio) = audio {
+            let _ = tokio::fs::remove_file(audio).await;
+        }
+        result?;
+
+        replace(&context.path, &target, &output).await?;
+        context.path = output;
+
+        Ok(())
+    }
+}
+
+/// [`PostProcessor`] writing title, artist, date, description and URL tags
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct TagMetadata {
+    pub ffmpeg: FFmpegLocator,
+}
+
+#[async_trait::async_trait]
+impl PostProcessor for TagMetadata {
+    fn name(&self) -> &str {
+        "tag"
+    }
+
+    async fn process(&self, context: &mut PostProcessContext<'_>) -> Result<(), VideoError> {
+        let ffmpeg = self.ffmpeg.locate().await?;
+        let details = &context.info.video_details;
+        let target = rewrite_path(&context.path);
+
+        let mut tags = vec![
+            ("title", details.title.clone()),
+            ("date", details.publish_date.clone()),
+            ("description", details.description.clone()),
+            ("comment", details.video_url.clone()),
+        ];
+        if let Some(author) = &details.author {
+            tags.push(("artist", author.name.clone()));
+        }
+
+        let mut cmd = ffmpeg.command();
+        cmd.arg("-y")
+            .arg("-loglevel")
+            .arg("error")
+            .arg("-i")
+            .arg(&context.path)
+            .arg("-map")
+            .arg("0")
+            .arg("-c")
+            .arg("copy");
+
+        for (key, value) in tags.iter().filter(|(_, value)| !value.is_empty()) {
+            cmd.arg("-metadata").arg(format!("{key}={value}"));
+        }
+
+        cmd.arg(&target);
+        run(cmd).await?;
+
+        replace(&context.path, &target, &context.path).await
+    }
+}
+
+/// [`PostProcessor`] embedding the biggest thumbnail as MP4 cover art / Matroska attachment,
+/// skipped for other containers
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct EmbedThumbnail {
+    pub ffmpeg: FFmpegLocator,
+}
+
+#[async_trait::async_trait]
+impl PostProcessor for EmbedThumbnail {
+    fn name(&self) -> &str {
+        "embed_thumbnail"
+    }
+
+    fn applies(&self, context: &PostProcessContext<'_>) -> bool {
+        matches!(
+            container_of(&context.path),
+            Some(MuxContainer::Mp4) | Some(MuxContainer::Mkv)
+        )
+    }
+
+    async fn process(&self, context: &mut PostProcessContext<'_>) -> Result<(), VideoError> {
+        let container = container_of(&context.path).unwrap_or_default();
+        let thumbnail = match choose_thumbnail(context.info, container) {
+            Some(thumbnail) => thumbnail,
+            None => return Ok(()),
+        };
+
+        let ffmpeg = self.ffmpeg.locate().await?;
+        let extension = thumbnail_extension(&thumbnail.url);
+        let cover = temp_path(&context.info.video_details.video_id, "thumbnail", extension);
+        download_thumbnail(context.video, &thumbnail, &cover).await?;
+
+        let target = rewrite_path(&context.path);
+        let mut cmd = ffmpeg.command();
+        cmd.arg("-y")
+            .arg("-loglevel")
+            .arg("error")
+            .arg("-i")
+            .arg(&context.path);
+
+        // MP4 takes cover as an extra video stream, Matroska as an attachment
+        if container == MuxContainer::Mp4 {
+            let cover_stream = usize::from(context.format.has_video);
+            cmd.arg("-i")
+                .arg(&cover)
+                .arg("-map")
+                .arg("0")
+                .arg("-map")
+                .arg("1")
+                .arg("-c")
+                .arg("copy")
+                .arg(format!("-disposition:v:{cover_stream}"))
+                .arg("attached_pic");
+        } else {
+            cmd.arg("-map")
+                .arg("0")
+                .arg("-c")
+                .arg("copy")
+                .arg("-attach")
+                .arg(&cover)
+                .arg("-metadata:s:t")
+                .arg(format!("mimetype={}", thumbnail_mime_type(extension)))
+                .arg("-metadata:s:t")
+                .arg(format!("filename=cover.{extension}"));
+        }
+
+        cmd.arg(&target);
+        let result = run(cmd).await;
+        let _ = tokio::fs::remove_file(&cover).await;
+        result?;
+
+        replace(&context.path, &target, &context.path).await
+    }
+}
+
+/// Loudness normalization of [`NormalizeLoudness`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum LoudnessMode {
+    /// Two-pass EBU R128 `loudnorm` filter, audio is re-encoded
+    #[default]
+    EbuR128,
+    /// Measure once and write ReplayGain 2.0 track gain and peak tags, audio is copied
+    ReplayGain,
+}
+
+/// [`PostProcessor`] normalizing loudness with `ffmpeg`
+#[derive(Clone, PartialEq, Debug)]
+pub struct NormalizeLoudness {
+    pub mode: LoudnessMode,
+    /// Integrated loudness target in LUFS of [`LoudnessMode::EbuR128`], ReplayGain uses its -18 LUFS reference
+    pub target_lufs: f64,
+    /// Maximum true peak in dBTP
+    pub true_peak: f64,
+    /// Loudness range target in LU
+    pub loudness_range: f64,
+    /// Bitrate of the re-encoded audio
+    pub audio_bitrate: String,
+    pub ffmpeg: FFmpegLocator,
+}
+
+impl Default for NormalizeLoudness {
+    fn default() -> Self {
+        Self {
+            mode: LoudnessMode::EbuR128,
+            target_lufs: -16.0,
+            true_peak: -1.5,
+            loudness_range: 11.0,
+            audio_bitrate: "192k".to_string(),
+            ffmpeg: FFmpegLocator::default(),
+        }
+    }
+}
+
+/// ReplayGain 2.0 reference loudness in LUFS
+const REPLAYGAIN_REFERENCE_LUFS: f64 = -18.0;
+
+/// First pass output of the `loudnorm` filter
+struct Loudness {
+    integrated: String,
+    true_peak: String,
+    range: String,
+    threshold: String,
+    target_offset: String,
+}
+
+#[async_trait::async_trait]
+impl PostProcessor for NormalizeLoudness {
+    fn name(&self) -> &str {
+        "normalize_loudness"
+    }
+
+    async fn process(&self, context: &mut PostProcessContext<'_>) -> Result<(), VideoError> {
+        let ffmpeg = self.ffmpeg.locate().await?;
+        let extension = context
+            .path
+            .extension()
+            .and_then(|x| x.to_str())
+            .unwrap_or("")
+            .to_string();
+
+        let encoder = match extension.as_str() {
+            "webm" | "opus" | "ogg" => "libopus",
+            "mp3" => "libmp3lame",
+            _ => "aac",
+        };
+        if self.mode == LoudnessMode::EbuR128 {
+            ffmpeg.require_encoder(encoder)?;
+        }
+
+        let loudness = self.measure(&ffmpeg, &context.path).await?;
+        context.report(0.5);
+
+        let target = rewrite_path(&context.path);
+        let mut cmd = ffmpeg.command();
+        cmd.arg("-y")
+            .arg("-loglevel")
+            .arg("error")
+            .arg("-i")
+            .arg(&context.path)
+            .arg("-map")
+            .arg("0");
+
+        match self.mode {
+            LoudnessMode::EbuR128 => {
+                // loudnorm resamples to 192 kHz, Opus only takes 48 kHz
+                let sample_rate = match encoder {
+                    "libopus" => "48000".to_string(),
+                    _ => context
+                        .format
+                        .audio_sample_rate
+                        .clone()
+                        .unwrap_or_else(|| "48000".to_string()),
+                };
+
+                cmd.arg("-af")
+                    .arg(format!(
+                        "loudnorm=I={}:TP={}:LRA={}:measured_I={}:measured_TP={}:measured_LRA={}:measured_thresh={}:offset={}:linear=true",
+                        self.target_lufs,
+                        self.true_peak,
+                        self.loudness_range,
+                        loudness.integrated,
+                        loudness.true_peak,
+                        loudness.range,
+                        loudness.threshold,
+                        loudness.target_offset
+                    ))
+                    .arg("-c")
+                    .arg("copy")
+                    .arg("-c:a")
+                    .arg(encoder)
+                    .arg("-b:a")
+                    .arg(&self.audio_bitrate)
+                    .arg("-ar")
+                    .arg(sample_rate);
+            }
+            LoudnessMode::ReplayGain => {
+                let (gain, peak) = replaygain(&loudness)?;
+
+                cmd.arg("-c")
+                    .arg("copy")
+                    .arg("-metadata")
+                    .arg(format!("REPLAYGAIN_TRACK_GAIN={gain:.2} dB"))
+                    .arg("-metadata")
+                    .arg(format!("REPLAYGAIN_TRACK_PEAK={peak:.6}"));
+
+                // MP4 drops unknown tags otherwise
+                if matches!(extension.as_str(), "mp4" | "m4a") {
+                    cmd.arg("-movflags").arg("use_metadata_tags");
+                }
+            }
+        }
+
+        cmd.arg(&target);
+        run(cmd).await?;
+
+        replace(&context.path, &target, &context.path).await
+    }
+}
+
+impl NormalizeLoudness {
+    async fn measure(&self, ffmpeg: &FFmpeg, path: &Path) -> Result<Loudness, VideoError> {
+        let output = ffmpeg
+            .command()
+            .arg("-hide_banner")
+            .arg("-nostats")
+            .arg("-i")
+            .arg(path)
+            .arg("-map")
+            .arg("0:a:0")
+            .arg("-af")
+            .arg(format!(
+                "loudnorm=I={}:TP={}:LRA={}:print_format=json",
+                self.target_lufs, self.true_peak, self.loudness_range
+            ))
+            .arg("-f")
+            .arg("null")
+            .arg("-")
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .output()
+            .await
+            .map_err(|e| VideoError::ChildProcessError(e.to_string()))?;
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !output.status.success() {
+            return Err(VideoError::ChildProcessError(stderr.trim().to_string()));
+        }
+
+        // Measurement is the last JSON object of the log
+        let json: serde_json::Value = stderr
+            .rfind('{')
+            .and_then(|start| serde_json::from_str(&stderr[start..]).ok())
+            .ok_or_else(|| {
+                VideoError::ChildProcessError("loudnorm printed no measurement".to_string())
+            })?;
+        let get = |key: &str| {
+            json.get(key)
+                .and_then(|x| x.as_str())
+                .unwrap_or("")
+                .to_string()
+        };
+
+        Ok(Loudness {
+            integrated: get("input_i"),
+            true_peak: get("input_tp"),
+            range: get("input_lra"),
+            threshold: get("input_thresh"),
+            target_offset: get("target_offset"),
+        })
+    }
+}
+
+/// Track gain in dB and linear peak
+fn replaygain(loudness: &Loudness) -> Result<(f64, f64), VideoError> {
+    let parse = |value: &str| {
+        value
+            .parse::<f64>()
+            .ok()
+            .filter(|x| x.is_finite())
+            .ok_or_else(|| VideoError::ChildProcessError(format!("invalid loudness {value}")))
+    };
+
+    // Silence measures -inf
+    let integrated = parse(&loudness.integrated)?;
+    let true_peak = parse(&loudness.true_peak)?;
+
+    Ok((
+        REPLAYGAIN_REFERENCE_LUFS - integrated,
+        10f64.powf(true_peak / 20.0),
+    ))
+}
+
+/// [`PostProcessor`] splitting the file into one part per chapter, from the chapter markers or the
+/// timestamps of the description. Parts are written next to the file
+#[derive(Clone, PartialEq, Debug)]
+pub struct SplitChapters {
+    /// [`render_output_template`](crate::render_output_template) of the part file names, with `{chapter}`,
+    /// `{chapter_number}` and `{chapter_start}` (seconds) keys. `{ext}` is the extension of the file
+    pub output_template: String,
+    pub sanitize_profile: SanitizeProfile,
+    /// Remove the file after splitting, the first part becomes [`PostProcessContext::path`]
+    pub remove_original: bool,
+    pub ffmpeg: FFmpegLocator,
+}
+
+impl Default for SplitChapters {
+    fn default() -> Self {
+        Self {
+            output_template: "{chapter_number} - {chapter}.{ext}".to_string(),
+            sanitize_profile: SanitizeProfile::default(),
+            remove_original: false,
+            ffmpeg: FFmpegLocator::default(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl PostProcessor for SplitChapters {
+    fn name(&self) -> &str {
+        "split_chapters"
+    }
+
+    fn applies(&self, context: &PostProcessContext<'_>) -> bool {
+        video_chapters(context.info).len() > 1
+    }
+
+    async fn process(&self, context: &mut PostProcessContext<'_>) -> Result<(), VideoError> {
+        let ffmpeg = self.ffmpeg.locate().await?;
+        let chapters = video_chapters(context.info);
+
+        let dir = match context.path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let extension = context
+            .path
+            .extension()
+            .map(|x| x.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let width = chapters.len().to_string().len().max(2);
+
+        let mut parts = vec![];
+
+        for (index, chapter) in chapters.iter().enumerate() {
+            let file_name = render_output_template_with(
+                &self.output_template,
+                context.info,
+                context.format,
+                self.sanitize_profile,
+                &[
+                    ("chapter", chapter.title.clone()),
+                    ("chapter_number", format!("{:0width$}", index + 1)),
+                    ("chapter_start", chapter.start_time.to_string()),
+                    ("ext", extension.clone()),
+                ],
+            );
+            let output = dir.join(file_name);
+
+            if output == context.path {
+                return Err(VideoError::DownloadError(format!(
+                    "part {} would overwrite the download",
+                    output.display()
+                )));
+            }
+
+            let mut cmd = ffmpeg.command();
+            cmd.arg("-y")
+                .arg("-loglevel")
+                .arg("error")
+                .arg("-ss")
+                .arg(chapter.start_time.to_string())
+                .arg("-i")
+                .arg(&context.path);
+
+            if let Some(next) = chapters.get(index + 1) {
+                cmd.arg("-t")
+                    .arg((next.start_time - chapter.start_time).to_string());
+            }
+
+            cmd.arg("-map")
+                .arg("0")
+                .arg("-map_chapters")
+                .arg("-1")
+                .arg("-c")
+                .arg("copy")
+                .arg(&output);
+            run(cmd).await?;
+
+            parts.push(output);
+            context.report((index + 1) as f64 / chapters.len() as f64);
+        }
+
+        if let (true, Some(first)) = (self.remove_original, parts.first()) {
+            tokio::fs::remove_file(&context.path)
+                .await
+                .map_err(|e| VideoError::DownloadError(e.to_string()))?;
+            context.path = first.clone();
+        }
+        context.parts.extend(parts);
+
+        Ok(())
+    }
+}
+
+/// Chapter markers, or chapters of the description timestamps if the video has none
+fn video_chapters(info: &VideoInfo) -> Vec<Chapter> {
+    let details = &info.video_details;
+
+    if details.chapters.is_empty() {
+        chapters_from_description(&details.description)
+    } else {
+        details.chapters.clone()
+    }
+}
+
+fn container_of(path: &Path) -> Option<MuxContainer> {
+    match path.extension()?.to_str()? {
+        "mkv" | "mka" => Some(MuxContainer::Mkv),
+        "mp4" | "m4a" => Some(MuxContainer::Mp4),
+        "webm" => Some(MuxContainer::Webm),
+        _ => None,
+    }
+}
+
+/// Output of steps rewriting a file in place, `video.mp4` is written to `video.tmp.mp4` to keep the muxer
+fn rewrite_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{stem}.tmp.{}", extension.to_string_lossy()),
+        None => format!("{stem}.tmp"),
+    };
+
+    path.with_file_name(name)
+}
+
+/// Remove `input` and move the written file `target` to `output`
+async fn replace(input: &Path, target: &Path, output: &Path) -> Result<(), VideoError> {
+    if input != output {
+        let _ = tokio::fs::remove_file(input).await;
+    }
+    if target != output {
+        tokio::fs::rename(target, output)
+            .await
+            .map_err(|e| VideoError::DownloadError(e.to_string()))?;
+    }
+
+    Ok(())
+}
+
+async fn run(mut cmd: process::Command) -> Result<(), VideoError> {
+    let output = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| VideoError::ChildProcessError(e.to_string()))?;
+
+    if !output.status.success() {
+        return Err(VideoError::ChildProcessError(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+async fn download_thumbnail(
+    video: &Video,
+    thumbnail: &Thumbnail,
+    path: &Path,
+) -> Result<(), VideoError> {
+    let body = video
+        .get_client()
+        .get(&thumbnail.url)
+        .send()
+        .await
+        .map_err(VideoError::ReqwestMiddleware)?
+        .bytes()
+        .await
+        .map_err(VideoError::Reqwest)?;
+
+    tokio::fs::write(path, body)
+        .await
+        .map_err(|e| VideoError::DownloadError(e.to_string()))
+}
+
+fn choose_mux_format(
+    formats: &[VideoFormat],
+    container: MuxContainer,
+    options: VideoOptions,
+    video: bool,
+) -> Result<VideoFormat, VideoError> {
+    let options = if video {
+        VideoOptions {
+            quality: VideoQuality::HighestVideo,
+            filter: VideoSearchOptions::Video,
+            ..options
+        }
+    } else {
+        VideoOptions {
+            quality: VideoQuality::HighestAudio,
+            filter: VideoSearchOptions::Audio,
+            ..options
+        }
+    };
+
+    let formats = formats
+        .iter()
+        .filter(|x| container.accepts(x))
+        .cloned()
+        .collect::<Vec<VideoFormat>>();
+
+    choose_format(&formats, &options)
+}
+
+/// Biggest thumbnail, MP4 covers can't be WebP
+fn choose_thumbnail(info: &VideoInfo, container: MuxContainer) -> Option<Thumbnail> {
+    info.video_details
+        .thumbnails
+        .iter()
+        .filter(|x| container != MuxContainer::Mp4 || thumbnail_extension(&x.url) != "webp")
+        .max_by_key(|x| x.width * x.height)
+        .cloned()
+}
+
+fn thumbnail_extension(url: &str) -> &'static str {
+    let path = url.split('?').next().unwrap_or("");
+
+    if path.ends_with(".webp") {
+        "webp"
+    } else if path.ends_with(".png") {
+        "png"
+    } else {
+        "jpg"
+    }
+}
+
+fn thumbnail_mime_type(extension: &str) -> &'static str {
+    match extension {
+        "webp" => "image/webp",
+        "png" => "image/png",
+        _ => "image/jpeg",
+    }
+}
+
+/// Unique per call, concurrent downloads of the same video don't share temporary files
+fn temp_path(video_id: &str, suffix: &str, extension: &str) -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "rusty_ytdl-{video_id}-{}-{:08x}-{suffix}.{extension}",
+        std::process::id(),
+        rand::random::<u32>()
+    ))
+}
+
+/// Chapters in ffmpeg metadata file format
+fn ffmetadata(info: &VideoInfo) -> String {
+    let escape = |value: &str| {
+        let mut escaped = String::new();
+        for c in value.chars() {
+            if matches!(c, '=' | ';' | '#' | '\\' | '\n') {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+        }
+        escaped
+    };
+
+    let chapters = &info.video_details.chapters;
+    let duration = info.video_details.duration.as_secs() as i64;
+
+    let mut metadata = String::from(";FFMETADATA1\n");
+
+    for (index, chapter) in chapters.iter().enumerate() {
+        let start = chapter.start_time as i64;
+        let end = chapters
+            .get(index + 1)
+            .map(|x| x.start_time as i64)
+            .unwrap_or(duration)
+            .max(start);
+
+        metadata.push_str(&format!(
+            "[CHAPTER]\nTIMEBASE=1/1000\nSTART={}\nEND={}\ntitle={}\n",
+            start * 1000,
+            end * 1000,
+            escape(&chapter.title)
+        ));
+    }
+
+    metadata
+}
+
+/// Env var with the path of the `ffmpeg` binary, checked before `PATH`
+pub const FFMPEG_ENV_VAR: &str = "RUSTY_YTDL_FFMPEG";
+
+/// Release of the static builds downloaded by [`FFmpegLocator::sidecar_dir`]
+#[cfg(feature = "ffmpeg-download")]
+const FFMPEG_SIDECAR_BASE_URL: &str =
+    "https://github.com/eugeneware/ffmpeg-static/releases/download/b6.0";
+
+static PROBED: Lazy<Mutex<HashMap<PathBuf, FFmpeg>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Finds the `ffmpeg` binary in order: [`FFmpegLocator::path`], [`FFMPEG_ENV_VAR`], `PATH`,
+/// then a sidecar build with the `ffmpeg-download` feature
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct FFmpegLocator {
+    /// Binary to use, skips the discovery
+    pub path: Option<PathBuf>,
+    /// Directory of a static `ffmpeg` build, downloaded on first use if `ffmpeg` isn't found elsewhere
+    #[cfg(feature = "ffmpeg-download")]
+    pub sidecar_dir: Option<PathBuf>,
+    /// Gzipped binary of the sidecar build, build of the current platform from `eugeneware/ffmpeg-static` if `None`
+    #[cfg(feature = "ffmpeg-download")]
+    pub sidecar_url: Option<String>,
+}
+
+/// Located `ffmpeg` binary and its capabilities
+#[derive(Clone, PartialEq, Debug)]
+pub struct FFmpeg {
+    pub path: PathBuf,
+    /// Version from `ffmpeg -version`, like `6.0`
+    pub version: String,
+    /// Output formats from `ffmpeg -muxers`, like `matroska`
+    pub muxers: HashSet<String>,
+    /// Encoders from `ffmpeg -encoders`, like `mov_text`
+    pub encoders: HashSet<String>,
+}
+
+impl FFmpegLocator {
+    /// Find and probe `ffmpeg`, probe results are cached per binary
+    pub async fn locate(&self) -> Result<FFmpeg, VideoError> {
+        let path = self.find().await?;
+
+        if let Some(ffmpeg) = PROBED.lock().unwrap().get(&path) {
+            return Ok(ffmpeg.clone());
+        }
+
+        let ffmpeg = FFmpeg::probe(path).await?;
+        PROBED
+            .lock()
+            .unwrap()
+            .insert(ffmpeg.path.clone(), ffmpeg.clone());
+
+        Ok(ffmpeg)
+    }
+
+    async fn find(&self) -> Result<PathBuf, VideoError> {
+        if let Some(path) = &self.path {
+            return existing(path, "FFmpegLocator::path");
+        }
+
+        if let Some(path) = std::env::var_os(FFMPEG_ENV_VAR) {
+            return existing(Path::new(&path), FFMPEG_ENV_VAR);
+        }
+
+        let binary = format!("ffmpeg{}", std::env::consts::EXE_SUFFIX);
+
+        if let Some(path) = std::env::var_os("PATH")
+            .iter()
+            .flat_map(std::env::split_paths)
+            .map(|dir| dir.join(&binary))
+            .find(|path| path.is_file())
+        {
+            return Ok(path);
+        }
+
+        #[cfg(feature = "ffmpeg-download")]
+        if let Some(dir) = &self.sidecar_dir {
+            let path = dir.join(&binary);
+            if !path.is_file() {
+                self.download_sidecar(&path).await?;
+            }
+            return Ok(path);
+        }
+
+        Err(VideoError::FFmpegNotFound(format!(
+            "not in {FFMPEG_ENV_VAR} or PATH, install ffmpeg or set {FFMPEG_ENV_VAR} to its path"
+        )))
+    }
+
+    #[cfg(feature = "ffmpeg-download")]
+    async fn download_sidecar(&self, path: &Path) -> Result<(), VideoError> {
+        use std::io::Read;
+
+        let url = match &self.sidecar_url {
+            Some(url) => url.clone(),
+            None => sidecar_url().ok_or_else(|| {
+                VideoError::FFmpegNotFound(format!(
+                    "no sidecar build for {}-{}, set FFmpegLocator::sidecar_url",
+                    std::env::consts::OS,
+                    std::env::consts::ARCH
+                ))
+            })?,
+        };
+
+        let response = reqwest::get(&url)
+            .await
+            .and_then(|x| x.error_for_status())
+            .map_err(|e| VideoError::FFmpegNotFound(format!("sidecar download failed: {e}")))?;
+        let compressed = response
+            .bytes()
+            .await
+            .map_err(|e| VideoError::FFmpegNotFound(format!("sidecar download failed: {e}")))?;
+
+        let mut binary = vec![];
+        flate2::read::GzDecoder::new(&compressed[..])
+            .read_to_end(&mut binary)
+            .map_err(|e| {
+                VideoError::FFmpegNotFound(format!("sidecar {url} is not gzipped: {e}"))
+            })?;
+
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir)
+                .await
+                .map_err(|e| VideoError::DownloadError(e.to_string()))?;
+        }
+
+        // Renamed into place, so an interrupted download isn't taken as the binary
+        let mut part_path = path.as_os_str().to_owned();
+        part_path.push(".part");
+        tokio::fs::write(&part_path, binary)
+            .await
+            .map_err(|e| VideoError::DownloadError(e.to_string()))?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            tokio::fs::set_permissions(&part_path, std::fs::Permissions::from_mode(0o755))
+                .await
+                .map_err(|e| VideoError::DownloadError(e.to_string()))?;
+        }
+
+        tokio::fs::rename(&part_path, path)
+            .await
+            .map_err(|e| VideoError::DownloadError(e.to_string()))
+    }
+}
+
+impl FFmpeg {
+    async fn probe(path: PathBuf) -> Result<Self, VideoError> {
+        let version = run_ffmpeg(&path, "-version").await?;
+        let version = version
+            .lines()
+            .next()
+            .and_then(|x| x.strip_prefix("ffmpeg version "))
+            .and_then(|x| x.split_whitespace().next())
+            .ok_or_else(|| VideoError::FFmpegNotFound(format!("{} is not ffmpeg", path.display())))?
+            .to_string();
+
+        let muxers = capability_list(&run_ffmpeg(&path, "-muxers").await?);
+        let encoders = capability_list(&run_ffmpeg(&path, "-encoders").await?);
+
+        Ok(Self {
+            path,
+            version,
+            muxers,
+            encoders,
+        })
+    }
+
+    /// Fail with [`VideoError::FFmpegUnsupported`] if `ffmpeg` can't write the `muxer` format
+    pub fn require_muxer(&self, muxer: &str) -> Result<(), VideoError> {
+        if self.muxers.contains(muxer) {
+            return Ok(());
+        }
+
+        Err(VideoError::FFmpegUnsupported(
+            self.path.display().to_string(),
+            format!("the {muxer} muxer"),
+        ))
+    }
+
+    /// Fail with [`VideoError::FFmpegUnsupported`] if `ffmpeg` has no `encoder`
+    pub fn require_encoder(&self, encoder: &str) -> Result<(), VideoError> {
+        if self.encoders.contains(encoder) {
+            return Ok(());
+        }
+
+        Err(VideoError::FFmpegUnsupported(
+            self.path.display().to_string(),
+            format!("the {encoder} encoder"),
+        ))
+    }
+
+    /// Command running this binary
+    pub fn command(&self) -> process::Command {
+        process::Command::new(&self.path)
+    }
+}
+
+fn existing(path: &Path, source: &str) -> Result<PathBuf, VideoError> {
+    if path.is_file() {
+        return Ok(path.to_path_buf());
+    }
+
+    Err(VideoError::FFmpegNotFound(format!(
+        "{} from {source} doesn't exist",
+        path.display()
+    )))
+}
+
+async fn run_ffmpeg(path: &Path, arg: &str) -> Result<String, VideoError> {
+    let output = process::Command::new(path)
+        .arg("-hide_banner")
+        .arg(arg)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| VideoError::FFmpegNotFound(format!("{} can't run: {e}", path.display())))?;
+
+    if !output.status.success() {
+        return Err(VideoError::FFmpegNotFound(format!(
+            "{} {arg} exited with {}",
+            path.display(),
+            output.status
+        )));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Names of `ffmpeg -muxers` / `ffmpeg -encoders` entries, listed after the ` --` separator as `<flags> <names> <description>`
+fn capability_list(output: &str) -> HashSet<String> {
+    output
+        .lines()
+        .skip_while(|x| !x.trim_start().starts_with("--"))
+        .skip(1)
+        .filter_map(|x| x.split_whitespace().nth(1))
+        .flat_map(|x| x.split(','))
+        .map(|x| x.to_string())
+        .collect()
+}
+
+#[cfg(feature = "ffmpeg-download")]
+fn sidecar_url() -> Option<String> {
+    let platform = match std::env::consts::OS {
+        "linux" => "linux",
+        "macos" => "darwin",
+        "windows" => "win32",
+        _ => return None,
+    };
+    let arch = match std::env::consts::ARCH {
+        "x86_64" => "x64",
+        "aarch64" => "arm64",
+        _ => return None,
+    };
+
+    Some(format!(
+        "{FFMPEG_SIDECAR_BASE_URL}/ffmpeg-{platform}-{arch}.gz"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ffmetadata() {
+        let mut info = VideoInfo::default();
+        info.video_details.duration = std::time::Duration::from_secs(90);
+        info.video_details.chapters = vec![
+            Chapter {
+                title: "Intro; a=b #1".to_string(),
+                start_time: 0,
+            },
+            Chapter {
+                title: "Back\\slash\nnewline".to_string(),
+                start_time: 30,
+            },
+        ];
+
+        // Last chapter runs to the end of the video
+        assert_eq!(
+            ffmetadata(&info),
+            ";FFMETADATA1\n\
+            [CHAPTER]\nTIMEBASE=1/1000\nSTART=0\nEND=30000\ntitle=Intro\\; a\\=b \\#1\n\
+            [CHAPTER]\nTIMEBASE=1/1000\nSTART=30000\nEND=90000\ntitle=Back\\\\slash\\\nnewline\n"
+        );
+    }
+
+    #[test]
+    fn test_choose_thumbnail() {
+        let thumbnail = |url: &str, width: u64| Thumbnail {
+            url: url.to_string(),
+            width,
+            height: width / 2,
+        };
+
+        let mut info = VideoInfo::default();
+        info.video_details.thumbnails = vec![
+            thumbnail("https://i.ytimg.com/vi/a/default.jpg", 120),
+            thumbnail("https://i.ytimg.com/vi_webp/a/maxresdefault.webp?v=1", 1280),
+            thumbnail("https://i.ytimg.com/vi/a/hqdefault.jpg?sqp=1", 480),
+        ];
+
+        // MP4 covers can't be WebP
+        assert_eq!(
+            choose_thumbnail(&info, MuxContainer::Mp4).unwrap().width,
+            480
+        );
+        assert_eq!(
+            choose_thumbnail(&info, MuxContainer::Mkv).unwrap().width,
+            1280
+        );
+        assert_eq!(
+            thumbnail_extension("https://i.ytimg.com/vi/a/hqdefault.jpg?sqp=1.webp"),
+            "jpg"
+        );
+
+        info.video_details.thumbnails.clear();
+        assert_eq!(choose_thumbnail(&info, MuxContainer::Mkv), None);
+    }
+
+    #[test]
+    fn test_temp_path() {
+        assert_ne!(
+            temp_path("FZ8BxMU3BYc", "140", "m4a"),
+            temp_path("FZ8BxMU3BYc", "140", "m4a")
+        );
+    }
+}
@@ -0,0 +1,266 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use reqwest::header::HeaderMap;
+use reqwest::{Request, Response, StatusCode};
+use reqwest_middleware::{Middleware, Next};
+use task_local_extensions::Extensions;
+
+use crate::structs::{ProxyOverride, RequestOptions, VideoError};
+use crate::utils::{build_client_with_proxy, override_headers};
+
+/// Cookies, visitor data, user agent and proxy used together as one client identity
+///
+/// # Example
+/// ```ignore
+///     let identity = Identity {
+///         cookies: Some("VISITOR_INFO1_LIVE=abc; PREF=hl=en".to_string()),
+///         visitor_data: Some("CgtBQkNERUZHSElKSyiA".to_string()),
+///         user_agent: Some("Mozilla/5.0 (X11; Linux x86_64; rv:109.0) Gecko/20100101 Firefox/115.0".to_string()),
+///         proxy: Some(reqwest::Proxy::all("http://10.0.0.1:8080").unwrap()),
+///     };
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Identity {
+    /// Initial cookies, the cookie jar of the identity keeps cookies set by responses
+    pub cookies: Option<String>,
+    /// Sent as `X-Goog-Visitor-Id`
+    pub visitor_data: Option<String>,
+    /// Overrides the `User-Agent` of the [`HeaderProfile`](crate::HeaderProfile) and [`RequestOptions::headers`]
+    pub user_agent: Option<String>,
+    /// Overrides [`RequestOptions::proxy`]
+    pub proxy: Option<reqwest::Proxy>,
+}
+
+/// When [`IdentityPool`] switches to another identity
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, derive_more::Display)]
+pub enum RotationStrategy {
+    /// Every request uses the next identity
+    PerRequest,
+    /// Keep the identity until a request is answered with `429`, then retry with the next one
+    #[default]
+    OnRateLimit,
+    /// Every [`Video`](crate::Video) keeps one identity chosen from its id, other clients behave as [`RotationStrategy::OnRateLimit`]
+    StickyPerVideo,
+}
+
+/// Set of [`Identity`] rotated by every client built with [`RequestOptions::identity_pool`]
+#[derive(Clone, derivative::Derivative)]
+#[derivative(Debug)]
+pub struct IdentityPool {
+    #[derivative(Debug(format_with = "fmt_identities"))]
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    identities: Vec<(Identity, Arc<reqwest::cookie::Jar>)>,
+    strategy: RotationStrategy,
+    cursor: AtomicUsize,
+}
+
+fn fmt_identities(inner: &Arc<Inner>, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    f.debug_struct("IdentityPool")
+        .field("identities", &inner.identities.len())
+        .field("strategy", &inner.strategy)
+        .finish()
+}
+
+impl IdentityPool {
+    /// Create pool, initial cookies of every identity are loaded into its own cookie jar
+    pub fn new(identities: Vec<Identity>, strategy: RotationStrategy) -> Self {
+        let host = "https://youtube.com".parse::<url::Url>().unwrap();
+
+        let identities = identities
+            .into_iter()
+            .map(|identity| {
+                let jar = reqwest::cookie::Jar::default();
+                if let Some(cookies) = identity.cookies.as_ref() {
+                    jar.add_cookie_str(cookies, &host);
+                }
+
+                (identity, Arc::new(jar))
+            })
+            .collect();
+
+        Self {
+            inner: Arc::new(Inner {
+                identities,
+                strategy,
+                cursor: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    /// Number of identities in the pool
+    pub fn len(&self) -> usize {
+        self.inner.identities.len()
+    }
+
+    /// Pool has no identities
+    pub fn is_empty(&self) -> bool {
+        self.inner.identities.is_empty()
+    }
+
+    /// Rotation strategy of the pool
+    pub fn strategy(&self) -> RotationStrategy {
+        self.inner.strategy
+    }
+
+    /// Identities of the pool in rotation order
+    pub fn identities(&self) -> Vec<Identity> {
+        self.inner.identities.iter().map(|x| x.0.clone()).collect()
+    }
+
+    /// Index of the identity used for the next request, `video_id` pins the identity with [`RotationStrategy::StickyPerVideo`]
+    pub fn select(&self, video_id: Option<&str>) -> usize {
+        let len = self.len().max(1);
+
+        match (self.inner.strategy, video_id) {
+            (RotationStrategy::PerRequest, _) => {
+                self.inner.cursor.fetch_add(1, Ordering::Relaxed) % len
+            }
+            (RotationStrategy::StickyPerVideo, Some(video_id)) => {
+                let mut hasher = DefaultHasher::new();
+                video_id.hash(&mut hasher);
+                hasher.finish() as usize % len
+            }
+            _ => self.inner.cursor.load(Ordering::Relaxed) % len,
+        }
+    }
+
+    /// Move away from identity `index` after it has been rate limited
+    pub fn report_rate_limited(&self, index: usize) {
+        let len = self.len().max(1);
+
+        let _ = self
+            .inner
+            .cursor
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |x| {
+                (x % len == index).then_some((index + 1) % len)
+            });
+    }
+
+    /// One client per identity, built from `request_options` with the identity applied, and the identity headers
+    /// set on its requests
+    fn build_clients(
+        &self,
+        request_options: &RequestOptions,
+        proxy_override: Option<&ProxyOverride>,
+    ) -> Result<Vec<(reqwest::Client, HeaderMap)>, VideoError> {
+        self.inner
+            .identities
+            .iter()
+            .map(|(identity, jar)| {
+                let mut options = request_options.clone();
+                options.cookies = None;
+
+                let mut headers = HeaderMap::new();
+
+                if let Some(user_agent) = identity.user_agent.as_ref() {
+                    headers.insert(
+                        reqwest::header::USER_AGENT,
+                        user_agent
+                            .parse()
+                            .map_err(|_| VideoError::InvalidIdentity(user_agent.clone()))?,
+                    );
+                }
+
+                if let Some(visitor_data) = identity.visitor_data.as_ref() {
+                    headers.insert(
+                        "X-Goog-Visitor-Id",
+                        visitor_data
+                            .parse()
+                            .map_err(|_| VideoError::InvalidIdentity(visitor_data.clone()))?,
+                    );
+                }
+
+                let identity_proxy = identity.proxy.clone().map(ProxyOverride::Proxy);
+
+                let client =
+                    build_client_with_proxy(&options, proxy_override.or(identity_proxy.as_ref()))?
+                        .cookie_provider(jar.clone())
+                        .build()
+                        .map_err(VideoError::Reqwest)?;
+
+                Ok((client, headers))
+            })
+            .collect()
+    }
+}
+
+/// Terminal middleware sending requests with the client of the selected identity
+pub(crate) struct IdentityRotation {
+    pool: IdentityPool,
+    clients: Vec<(reqwest::Client, HeaderMap)>,
+    pinned: Option<usize>,
+}
+
+impl IdentityRotation {
+    pub(crate) fn new(
+        pool: &IdentityPool,
+        request_options: &RequestOptions,
+        proxy_override: Option<&ProxyOverride>,
+        video_id: Option<&str>,
+    ) -> Result<Self, VideoError> {
+        let pinned = match (pool.strategy(), video_id) {
+            (RotationStrategy::StickyPerVideo, Some(_)) => Some(pool.select(video_id)),
+            _ => None,
+        };
+
+        Ok(Self {
+            pool: pool.clone(),
+            clients: pool.build_clients(request_options, proxy_override)?,
+            pinned,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl Middleware for IdentityRotation {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        if self.clients.is_empty() {
+            return next.run(req, extensions).await;
+        }
+
+        let mut attempts = self.clients.len();
+
+        loop {
+            let index = self.pinned.unwrap_or_else(|| self.pool.select(None));
+            let (client, headers) = &self.clients[index % self.clients.len()];
+
+            let retry = match (self.pinned, self.pool.strategy()) {
+                (None, RotationStrategy::OnRateLimit | RotationStrategy::StickyPerVideo) => {
+                    attempts > 1
+                }
+                _ => false,
+            };
+
+            // Streaming bodies cannot be sent again
+            let mut request = match req.try_clone() {
+                Some(request) if retry => request,
+                _ => {
+                    let mut req = req;
+                    override_headers(req.headers_mut(), headers);
+                    return client.execute(req).await.map_err(Into::into);
+                }
+            };
+            override_headers(request.headers_mut(), headers);
+
+            let response = client.execute(request).await?;
+
+            if response.status() != StatusCode::TOO_MANY_REQUESTS {
+                return Ok(response);
+            }
+
+            self.pool.report_rate_limited(index);
+            attempts -= 1;
+        }
+    }
+}
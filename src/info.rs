@@ -1,60 +1,57 @@
 use std::collections::HashMap;
-use std::sync::Arc;
 
-use scraper::{Html, Selector};
+use bytes::{Bytes, BytesMut};
 use xml_oxide::{sax::parser::Parser, sax::Event};
 
-use crate::constants::{BASE_URL, FORMATS};
-use crate::info_extras::{get_media, get_related_videos};
-use crate::stream::{LiveStream, LiveStreamOptions, NonLiveStream, NonLiveStreamOptions, Stream};
-use crate::structs::{VideoError, VideoFormat, VideoInfo, VideoOptions};
+use crate::constants::{DISK_SPACE_MARGIN, FORMATS};
+use crate::correlation::{correlate, current_request_id, log_label};
+use crate::extractor::{ExtractedInfo, Extractor, InnertubeExtractor, WebExtractor};
+use crate::info_extras::{
+    get_caption_tracks, get_extraction_report, get_media, get_polls, get_products,
+    get_raw_sections, get_related_videos, get_ticket_events,
+};
+use crate::stream::{
+    fragment_range, fragments, AudioFrames, LiveStream, LiveStreamOptions, NonLiveStream,
+    NonLiveStreamOptions, Stream, ThrottledStream,
+};
+use crate::structs::{
+    Config, DownloadResult, ErrorContext, FormatFallback, FormatFilter, InnertubeClient,
+    ProxyOverride, RangeObject, RequestOptions, SanitizeProfile, VideoError, VideoFormat,
+    VideoInfo, VideoOptions,
+};
+use crate::transcode::TranscodeStream;
 
 use crate::utils::{
-    add_format_meta, choose_format, clean_video_details, get_functions, get_html, get_html5player,
-    get_random_v6_ip, get_video_id, is_not_yet_broadcasted, is_play_error, is_private_video,
-    is_rental, parse_video_formats, sort_formats,
+    add_format_meta, build_client_with_proxy, choose_format, clean_video_details, finalize_url,
+    get_default_audio_language, get_html, get_scheduled_start_time, get_video_id_with_domains,
+    has_multiple_audio_tracks, is_not_yet_broadcasted, is_play_error, is_private_video, is_rental,
+    lacks_streaming_data, sanitize_filename, sort_formats, with_request_middlewares,
+    ChecksumHasher, PartFile,
 };
 
+/// Video to get info or download. `Send + Sync` and cheap to clone: options are shared behind an [`Arc`](std::sync::Arc),
+/// clients are reference counted and player functions and innertube values are cached process-wide behind locks,
+/// so one [`Video`] can be cloned into many tasks
 #[derive(Clone, derive_more::Display, derivative::Derivative)]
 #[display(fmt = "Video({video_id})")]
 #[derivative(Debug, PartialEq, Eq)]
 pub struct Video {
     video_id: String,
-    options: VideoOptions,
+    options: std::sync::Arc<VideoOptions>,
     #[derivative(PartialEq = "ignore")]
     client: reqwest_middleware::ClientWithMiddleware,
+    /// Client of media downloads, same as `client` if [`RequestOptions::stream_proxy`](crate::RequestOptions::stream_proxy) is not set
+    #[derivative(PartialEq = "ignore")]
+    stream_client: reqwest_middleware::ClientWithMiddleware,
+    /// Shared by clones
+    #[derivative(PartialEq = "ignore")]
+    pub(crate) last_error: std::sync::Arc<std::sync::Mutex<Option<ErrorContext>>>,
 }
 
 impl Video {
     /// Crate [`Video`] struct to get info or download with default [`VideoOptions`]
     pub fn new(url_or_id: impl Into<String>) -> Result<Self, VideoError> {
-        let id = get_video_id(&url_or_id.into());
-
-        if id.is_none() {
-            return Err(VideoError::VideoNotFound);
-        }
-
-        let client = reqwest::Client::builder()
-            .build()
-            .map_err(VideoError::Reqwest)?;
-
-        let retry_policy = reqwest_retry::policies::ExponentialBackoff::builder()
-            .retry_bounds(
-                std::time::Duration::from_millis(500),
-                std::time::Duration::from_millis(10000),
-            )
-            .build_with_max_retries(3);
-        let client = reqwest_middleware::ClientBuilder::new(client)
-            .with(reqwest_retry::RetryTransientMiddleware::new_with_policy(
-                retry_policy,
-            ))
-            .build();
-
-        Ok(Self {
-            video_id: id.unwrap(),
-            options: VideoOptions::default(),
-            client,
-        })
+        Self::new_with_options(url_or_id, VideoOptions::default())
     }
 
     /// Crate [`Video`] struct to get info or download with custom [`VideoOptions`]
@@ -62,118 +59,170 @@ impl Video {
         url_or_id: impl Into<String>,
         options: VideoOptions,
     ) -> Result<Self, VideoError> {
-        let id = get_video_id(&url_or_id.into());
+        let id = get_video_id_with_domains(
+            &url_or_id.into(),
+            &options.request_options.config.valid_query_domains,
+        );
 
         if id.is_none() {
             return Err(VideoError::VideoNotFound);
         }
 
-        let mut client = reqwest::Client::builder();
-
-        if options.request_options.proxy.is_some() {
-            client = client.proxy(options.request_options.proxy.as_ref().unwrap().clone());
-        }
-
-        if options.request_options.ipv6_block.is_some() {
-            let ipv6 = get_random_v6_ip(options.request_options.ipv6_block.as_ref().unwrap())?;
-            client = client.local_address(ipv6);
-        }
-
-        if options.request_options.cookies.is_some() {
-            let cookie = options.request_options.cookies.as_ref().unwrap();
-            let host = "https://youtube.com".parse::<url::Url>().unwrap();
-
-            let jar = reqwest::cookie::Jar::default();
-            jar.add_cookie_str(cookie.as_str(), &host);
-
-            client = client.cookie_provider(Arc::new(jar));
-        }
-
-        let client = client.build().map_err(VideoError::Reqwest)?;
-
-        let retry_policy = reqwest_retry::policies::ExponentialBackoff::builder()
-            .retry_bounds(
-                std::time::Duration::from_millis(500),
-                std::time::Duration::from_millis(10000),
-            )
-            .build_with_max_retries(3);
-        let client = reqwest_middleware::ClientBuilder::new(client)
-            .with(reqwest_retry::RetryTransientMiddleware::new_with_policy(
-                retry_policy,
-            ))
-            .build();
+        let video_id = id.unwrap();
+        let request_options = &options.request_options;
+        let client = build_video_client(
+            &video_id,
+            request_options,
+            request_options.info_proxy.as_ref(),
+        )?;
+        let stream_client = match request_options.stream_proxy.as_ref() {
+            Some(stream_proxy) => {
+                build_video_client(&video_id, request_options, Some(stream_proxy))?
+            }
+            None => client.clone(),
+        };
 
         Ok(Self {
-            video_id: id.unwrap(),
-            options,
+            video_id,
+            options: std::sync::Arc::new(options),
             client,
+            stream_client,
+            last_error: Default::default(),
         })
     }
 
+    /// Scheduled start of an upcoming live stream or premiere, [`None`] if the video is not upcoming.
+    /// Streams waiting for their host without a schedule return the current time
+    pub async fn scheduled_start(&self) -> Result<Option<std::time::SystemTime>, VideoError> {
+        let config = &self.options.request_options.config;
+        let extracted = self
+            .extractor()
+            .fetch_info(&self.client, config, &self.video_id)
+            .await?;
+
+        if !is_not_yet_broadcasted(&extracted.player_response) {
+            return Ok(None);
+        }
+
+        Ok(Some(
+            get_scheduled_start_time(&extracted.player_response)
+                .map(|x| std::time::UNIX_EPOCH + std::time::Duration::from_secs(x))
+                .unwrap_or_else(std::time::SystemTime::now),
+        ))
+    }
+
     /// Try to get basic information about video
     /// - `HLS` and `DashMPD` formats excluded!
     pub async fn get_basic_info(&self) -> Result<VideoInfo, VideoError> {
+        correlate(
+            "get_basic_info",
+            &self.video_id,
+            &self.last_error,
+            self.fetch_basic_info(),
+        )
+        .await
+    }
+
+    async fn fetch_basic_info(&self) -> Result<VideoInfo, VideoError> {
         let client = &self.client;
+        let config = &self.options.request_options.config;
+        let extractor = self.extractor();
+
+        let fallback = match self.options.client_fallback.as_ref() {
+            Some(fallback) if !fallback.clients.is_empty() => fallback,
+            _ => {
+                let extracted = extractor.fetch_info(client, config, &self.video_id).await?;
+                return self.build_info(extractor.as_ref(), extracted).await;
+            }
+        };
 
-        let url_parsed =
-            url::Url::parse_with_params(self.get_video_url().as_str(), &[("hl", "en")]);
-        if url_parsed.is_err() {
-            return Err(VideoError::URLParseError(url_parsed.err().unwrap()));
+        let mut extractors = vec![extractor];
+        extractors.extend(fallback.clients.iter().map(|client| {
+            std::sync::Arc::new(InnertubeExtractor::new(*client)) as std::sync::Arc<dyn Extractor>
+        }));
+
+        let last = extractors.len() - 1;
+        for (i, extractor) in extractors.iter().enumerate() {
+            let extracted = extractor.fetch_info(client, config, &self.video_id).await?;
+
+            if i < last
+                && fallback.on_missing_streaming_data
+                && lacks_streaming_data(&extracted.player_response)
+            {
+                self.log_client_fallback(
+                    extractor.as_ref(),
+                    "no streamingData",
+                    fallback.clients[i],
+                );
+                continue;
+            }
+
+            let info = self.build_info(extractor.as_ref(), extracted).await?;
+            if i == last || !fallback.on_forbidden_format {
+                return Ok(info);
+            }
+
+            let format = match choose_format(&info.formats, &self.options) {
+                Ok(format) => format,
+                Err(_) => return Ok(info),
+            };
+            match self.probe_format(&format).await {
+                Err(err @ VideoError::FormatRequestFailed(_)) => self.log_client_fallback(
+                    extractor.as_ref(),
+                    &err.to_string(),
+                    fallback.clients[i],
+                ),
+                _ => return Ok(info),
+            }
         }
 
-        let response = get_html(client, url_parsed.unwrap().as_str(), None).await?;
-
-        let (player_response, initial_response): (serde_json::Value, serde_json::Value) = {
-            let document = Html::parse_document(&response);
-            let scripts_selector = Selector::parse("script").unwrap();
-            let mut player_response_string = document
-                .select(&scripts_selector)
-                .filter(|x| x.inner_html().contains("var ytInitialPlayerResponse ="))
-                .map(|x| x.inner_html().replace("var ytInitialPlayerResponse =", ""))
-                .next()
-                .unwrap_or(String::from(""))
-                .trim()
-                .to_string();
-            let mut initial_response_string = document
-                .select(&scripts_selector)
-                .filter(|x| x.inner_html().contains("var ytInitialData ="))
-                .map(|x| x.inner_html().replace("var ytInitialData =", ""))
-                .next()
-                .unwrap_or(String::from(""))
-                .trim()
-                .to_string();
-
-            // remove json objects' last element (;)
-            player_response_string.pop();
-            initial_response_string.pop();
-
-            let player_response: serde_json::Value =
-                serde_json::from_str(&player_response_string).unwrap();
-            let initial_response: serde_json::Value =
-                serde_json::from_str(&initial_response_string).unwrap();
-
-            (player_response, initial_response)
-        };
+        unreachable!("the last extractor returns")
+    }
 
-        if is_play_error(&player_response, ["ERROR"].to_vec()) {
+    #[cfg_attr(not(feature = "log"), allow(unused_variables))]
+    fn log_client_fallback(&self, extractor: &dyn Extractor, reason: &str, next: InnertubeClient) {
+        #[cfg(feature = "log")]
+        log::warn!(
+            "[{}] extraction of {} with {} failed ({reason}), retrying with {next}",
+            log_label(),
+            self.video_id,
+            extractor
+                .client()
+                .map(|x| x.to_string())
+                .unwrap_or_else(|| "custom extractor".to_string()),
+        );
+    }
+
+    /// [`VideoInfo`] of the responses `extractor` fetched
+    async fn build_info(
+        &self,
+        extractor: &dyn Extractor,
+        extracted: ExtractedInfo,
+    ) -> Result<VideoInfo, VideoError> {
+        let client = &self.client;
+        let config = &self.options.request_options.config;
+        let player_response = &extracted.player_response;
+        let initial_response = &extracted.initial_response;
+
+        if is_play_error(player_response, ["ERROR"].to_vec()) {
             return Err(VideoError::VideoNotFound);
         }
 
-        if is_private_video(&player_response) {
+        if is_private_video(player_response) {
             return Err(VideoError::VideoIsPrivate);
         }
 
         if player_response.get("streamingData").is_none()
-            || is_rental(&player_response)
-            || is_not_yet_broadcasted(&player_response)
+            || is_rental(player_response)
+            || is_not_yet_broadcasted(player_response)
         {
             return Err(VideoError::VideoSourceNotFound);
         }
 
-        let video_details = clean_video_details(
-            &initial_response,
-            &player_response,
-            get_media(&initial_response).unwrap(),
+        let mut video_details = clean_video_details(
+            initial_response,
+            player_response,
+            get_media(initial_response),
             self.video_id.clone(),
         );
 
@@ -189,22 +238,40 @@ impl Video {
             .and_then(|x| x.as_str())
             .map(|x| x.to_string());
 
+        let formats = extractor.fetch_formats(client, config, &extracted).await?;
+
+        video_details.default_audio_language = get_default_audio_language(&formats);
+        video_details.has_multiple_audio_tracks = has_multiple_audio_tracks(&formats);
+
         Ok(VideoInfo {
             dash_manifest_url,
             hls_manifest_url,
-            formats: parse_video_formats(
-                &player_response,
-                get_functions(get_html5player(response.as_str()).unwrap(), client).await?,
-            )
-            .unwrap_or(vec![]),
-            related_videos: get_related_videos(&initial_response).unwrap_or(vec![]),
+            formats,
+            related_videos: get_related_videos(initial_response).unwrap_or(vec![]),
             video_details,
+            caption_tracks: get_caption_tracks(player_response).unwrap_or(vec![]),
+            extraction_report: get_extraction_report(initial_response, player_response),
+            extraction_client: extractor.client(),
+            products: get_products(initial_response),
+            ticket_events: get_ticket_events(initial_response),
+            polls: get_polls(player_response),
+            raw_sections: get_raw_sections(initial_response),
         })
     }
 
     /// Try to get full information about video
     /// - `HLS` and `DashMPD` formats included!
     pub async fn get_info(&self) -> Result<VideoInfo, VideoError> {
+        correlate(
+            "get_info",
+            &self.video_id,
+            &self.last_error,
+            self.fetch_info(),
+        )
+        .await
+    }
+
+    async fn fetch_info(&self) -> Result<VideoInfo, VideoError> {
         let client = &self.client;
 
         let mut info = self.get_basic_info().await?;
@@ -243,7 +310,8 @@ impl Video {
 
         if has_manifest && info.hls_manifest_url.is_some() {
             let url = info.hls_manifest_url.as_ref().expect("IMPOSSIBLE");
-            let unformated_formats = get_m3u8(url, client).await;
+            let unformated_formats =
+                get_m3u8(url, client, &self.options.request_options.config).await;
 
             // Skip if error occured
             if unformated_formats.is_ok() {
@@ -322,23 +390,163 @@ impl Video {
     ///     }
     /// ```
     pub async fn stream(&self) -> Result<Box<dyn Stream + Send + Sync>, VideoError> {
-        let client = &self.client;
+        correlate("stream", &self.video_id, &self.last_error, async {
+            let info = self.get_info().await?;
+            let format = self.choose_working_format(&info).await?;
 
-        let info = self.get_info().await?;
-        let format = choose_format(&info.formats, &self.options)
+            self.stream_with_format(&format).await
+        })
+        .await
+    }
+
+    /// [`choose_format`] with [`DownloadOptions::format_fallback`](crate::DownloadOptions::format_fallback):
+    /// formats whose URL fails are skipped for the next best one
+    pub(crate) async fn choose_working_format(
+        &self,
+        info: &VideoInfo,
+    ) -> Result<VideoFormat, VideoError> {
+        let mut format = choose_format(&info.formats, &self.options)
             .map_err(|_op| VideoError::VideoSourceNotFound)?;
 
-        let link = format.url;
+        let download_options = &self.options.download_options;
+        if !download_options.format_fallback {
+            return Ok(format);
+        }
 
-        if link.is_empty() {
-            return Err(VideoError::VideoSourceNotFound);
+        let mut formats = info.formats.clone();
+
+        loop {
+            let error = match self.probe_format(&format).await {
+                Ok(()) => return Ok(format),
+                Err(err) => err,
+            };
+
+            // Audio tracks of multi-audio videos share their itag
+            formats.retain(|x| x.url != format.url);
+            let fallback = match choose_format(&formats, &self.options) {
+                Ok(fallback) => fallback,
+                Err(_) => return Err(error),
+            };
+
+            let event = FormatFallback {
+                failed_itag: format.itag,
+                fallback_itag: fallback.itag,
+                error: error.to_string(),
+                request_id: current_request_id(),
+            };
+
+            #[cfg(feature = "log")]
+            log::warn!(
+                "[{}] itag {} of {} failed ({}), falling back to itag {}",
+                log_label(),
+                event.failed_itag,
+                self.video_id,
+                event.error,
+                event.fallback_itag
+            );
+
+            if let Some(on_format_fallback) = download_options.on_format_fallback.as_ref() {
+                on_format_fallback(&event);
+            }
+
+            format = fallback;
+        }
+    }
+
+    /// Request the first byte of a format, HLS playlists are not checked
+    async fn probe_format(&self, format: &VideoFormat) -> Result<(), VideoError> {
+        if format.is_hls {
+            return Ok(());
+        }
+
+        let response = self
+            .stream_client
+            .get(finalize_url(
+                format,
+                &self.options.request_options.stream_session,
+            )?)
+            .header(reqwest::header::RANGE, "bytes=0-0")
+            .send()
+            .await?;
+
+        let status = response.status();
+        if status.is_success() || status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+            Ok(())
+        } else {
+            Err(VideoError::FormatRequestFailed(format!(
+                "itag {} answered with {status}",
+                format.itag
+            )))
         }
+    }
+
+    /// Try to get [`Stream`] for the given [`VideoFormat`] instead of choosing one with [`VideoOptions`]
+    /// # Example
+    /// ```ignore
+    ///     let video_info = video.get_info().await.unwrap();
+    ///
+    ///     let format = video_info.formats.iter().find(|x| x.itag == 251).unwrap();
+    ///
+    ///     let stream = video.stream_with_format(format).await.unwrap();
+    /// ```
+    pub async fn stream_with_format(
+        &self,
+        format: &VideoFormat,
+    ) -> Result<Box<dyn Stream + Send + Sync>, VideoError> {
+        self.stream_with_format_from(format, 0).await
+    }
+
+    /// Opus packets of the best `webm` Opus audio format, ready for voice connections without ffmpeg
+    /// # Example
+    /// ```ignore
+    ///     let mut frames = video.audio_frames().await.unwrap();
+    ///
+    ///     while let Some(packet) = frames.next().await.unwrap() {
+    ///           println!("{:?} {} bytes", packet.timestamp, packet.data.len());
+    ///     }
+    /// ```
+    pub async fn audio_frames(&self) -> Result<AudioFrames, VideoError> {
+        let info = self.get_info().await?;
+        let filter = FormatFilter::audio()
+            .and(FormatFilter::container("webm"))
+            .and(FormatFilter::codec("opus"));
+
+        let format = info
+            .best_format(&filter)
+            .ok_or(VideoError::VideoSourceNotFound)?;
+
+        Ok(AudioFrames::new(self.stream_with_format(format).await?))
+    }
+
+    /// [`Video::stream_with_format`] starting at byte `offset`, ignored for live streams
+    async fn stream_with_format_from(
+        &self,
+        format: &VideoFormat,
+        offset: u64,
+    ) -> Result<Box<dyn Stream + Send + Sync>, VideoError> {
+        let stream = self.open_stream(format, offset).await?;
+
+        Ok(match self.options.download_options.bandwidth.as_ref() {
+            Some(budget) => Box::new(ThrottledStream::new(stream, budget.join())),
+            None => stream,
+        })
+    }
+
+    async fn open_stream(
+        &self,
+        format: &VideoFormat,
+        offset: u64,
+    ) -> Result<Box<dyn Stream + Send + Sync>, VideoError> {
+        let client = &self.stream_client;
+
+        let link = finalize_url(format, &self.options.request_options.stream_session)?;
 
         // Only check for HLS formats for live streams
         if format.is_hls {
             let stream = LiveStream::new(LiveStreamOptions {
                 client: Some(client.clone()),
                 stream_url: link,
+                on_progress: self.options.download_options.on_progress.clone(),
             });
 
             if stream.is_err() {
@@ -354,31 +562,17 @@ impl Video {
             1024 * 1024 * 10_u64 // -> Default is 10MB to avoid Youtube throttle (Bigger than this value can be throttle by Youtube)
         };
 
-        let start = 0;
+        let start = offset;
         let end = start + dl_chunk_size;
 
-        let mut content_length = format
+        // Unknown length is taken from the first ranged response, so the first byte needs one request only
+        let content_length = format
             .content_length
+            .clone()
             .unwrap_or("0".to_string())
             .parse::<u64>()
             .unwrap_or(0);
 
-        // Get content length from source url if content_length is 0
-        if content_length == 0 {
-            let content_length_response = client
-                .get(&link)
-                .send()
-                .await
-                .map_err(VideoError::ReqwestMiddleware)?
-                .content_length();
-
-            if content_length_response.is_none() {
-                return Err(VideoError::VideoNotFound);
-            }
-
-            content_length = content_length_response.unwrap();
-        }
-
         let stream = NonLiveStream::new(NonLiveStreamOptions {
             client: Some(client.clone()),
             link,
@@ -386,6 +580,16 @@ impl Video {
             dl_chunk_size,
             start,
             end,
+            // Prefetching sends requests next to the consumed one
+            prefetch_chunks: match self.options.request_options.throttled_mode {
+                Some(_) => 0,
+                None => self.options.download_options.prefetch_chunks,
+            },
+            on_progress: self.options.download_options.on_progress.clone(),
+            init_range: format.init_range.as_ref().and_then(RangeObject::bounds),
+            index_range: format.index_range.as_ref().and_then(RangeObject::bounds),
+            content_type: Some(format.mime_type.clone()),
+            tasks: self.options.download_options.tasks.clone(),
         });
 
         if stream.is_err() {
@@ -396,24 +600,468 @@ impl Video {
     }
 
     /// Download video directly to the file
-    pub async fn download<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), VideoError> {
+    ///
+    /// Video is written to `<path>.part` and renamed to `path` when the download is completed
+    pub async fn download<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+    ) -> Result<DownloadResult, VideoError> {
+        correlate("download", &self.video_id, &self.last_error, async {
+            let info = self.get_info().await?;
+            let format = self.choose_working_format(&info).await?;
+
+            let result = self.download_format_to_file(&format, path.as_ref()).await?;
+            self.post_process(&info, &format, result).await
+        })
+        .await
+    }
+
+    /// Download video into `dir` as `{title}.{ext}`, file name is sanitized with the given [`SanitizeProfile`]
+    /// # Example
+    /// ```ignore
+    ///     let result = video.download_to_dir("videos", SanitizeProfile::Windows).await.unwrap();
+    ///
+    ///     println!("{}", result.path.display());
+    /// ```
+    pub async fn download_to_dir<P: AsRef<std::path::Path>>(
+        &self,
+        dir: P,
+        profile: SanitizeProfile,
+    ) -> Result<DownloadResult, VideoError> {
+        correlate("download_to_dir", &self.video_id, &self.last_error, async {
+            let info = self.get_info().await?;
+            let format = self.choose_working_format(&info).await?;
+
+            let file_name = sanitize_filename(
+                &format!("{}.{}", info.video_details.title, file_extension(&format)),
+                profile,
+            );
+            let path = dir.as_ref().join(file_name);
+
+            let result = self.download_format_to_file(&format, &path).await?;
+            self.post_process(&info, &format, result).await
+        })
+        .await
+    }
+
+    /// Download a clip of `duration` from `offset` for previews, without downloading the whole video.
+    /// Only the fragments covering the clip are requested, so the clip starts at the keyframe before `offset` and may
+    /// run up to a fragment longer. Formats are chosen from the `mp4` and `webm` formats with an index, which are
+    /// video only or audio only, set [`VideoOptions::filter`] accordingly
+    /// # Example
+    /// ```ignore
+    ///     let video_options = VideoOptions {
+    ///         filter: VideoSearchOptions::Video,
+    ///         ..Default::default()
+    ///     };
+    ///     let video = Video::new_with_options(url, video_options).unwrap();
+    ///
+    ///     // 10 seconds from 1:30
+    ///     video
+    ///         .download_preview("preview.mp4", Duration::from_secs(10), Duration::from_secs(90))
+    ///         .await
+    ///         .unwrap();
+    /// ```
+    pub async fn download_preview<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+        duration: std::time::Duration,
+        offset: std::time::Duration,
+    ) -> Result<DownloadResult, VideoError> {
         use std::io::Write;
-        let stream = self.stream().await.unwrap();
 
-        let mut file =
-            std::fs::File::create(path).map_err(|e| VideoError::DownloadError(e.to_string()))?;
+        correlate(
+            "download_preview",
+            &self.video_id,
+            &self.last_error,
+            async {
+                let info = self.get_info().await?;
+                let formats: Vec<VideoFormat> = info
+                    .formats
+                    .iter()
+                    .filter(|x| !x.is_hls && x.init_range.is_some() && x.index_range.is_some())
+                    .cloned()
+                    .collect();
+                let format = choose_format(&formats, &self.options)
+                    .map_err(|_op| VideoError::VideoSourceNotFound)?;
+
+                let (init_range, index_range) = match (
+                    format.init_range.as_ref().and_then(RangeObject::bounds),
+                    format.index_range.as_ref().and_then(RangeObject::bounds),
+                ) {
+                    (Some(init_range), Some(index_range)) => (init_range, index_range),
+                    _ => return Err(VideoError::SeekNotSupported),
+                };
+
+                let init = self.fetch_format_range(&format, 0, init_range.1).await?;
+                let index = self
+                    .fetch_format_range(&format, index_range.0, index_range.1)
+                    .await?;
+
+                let (start, end) = fragments(&init, &index, index_range.0)
+                    .and_then(|x| fragment_range(&x, offset, offset + duration))
+                    .ok_or(VideoError::SeekNotSupported)?;
+
+                let mut hasher = self
+                    .options
+                    .download_options
+                    .checksum
+                    .map(ChecksumHasher::new);
+                let mut file = std::fs::File::create(path.as_ref())
+                    .map_err(|e| VideoError::DownloadError(e.to_string()))?;
+                let mut write = |bytes: &[u8]| {
+                    if let Some(hasher) = hasher.as_mut() {
+                        hasher.update(bytes);
+                    }
+                    file.write_all(bytes)
+                        .map_err(|e| VideoError::DownloadError(e.to_string()))
+                };
+
+                write(&init)?;
+                let mut size = init.len() as u64;
 
-        while let Some(chunk) = stream.chunk().await.unwrap() {
-            file.write_all(&chunk)
-                .map_err(|e| VideoError::DownloadError(e.to_string()))?;
+                let stream = self.stream_with_format_from(&format, start).await?;
+                let mut remaining = end.map(|end| end - start);
+
+                while let Some(chunk) = stream.chunk().await? {
+                    let length = remaining.map_or(chunk.len(), |x| (x as usize).min(chunk.len()));
+                    write(&chunk[..length])?;
+                    size += length as u64;
+
+                    if let Some(remaining) = remaining.as_mut() {
+                        *remaining -= length as u64;
+                        if *remaining == 0 {
+                            break;
+                        }
+                    }
+                }
+
+                Ok(DownloadResult {
+                    path: path.as_ref().to_path_buf(),
+                    size,
+                    checksum: hasher.map(|x| x.finalize()),
+                    live_report: None,
+                    parts: vec![],
+                })
+            },
+        )
+        .await
+    }
+
+    /// Bytes `start..=end` of a format
+    async fn fetch_format_range(
+        &self,
+        format: &VideoFormat,
+        start: u64,
+        end: u64,
+    ) -> Result<Bytes, VideoError> {
+        let response = self
+            .stream_client
+            .get(finalize_url(
+                format,
+                &self.options.request_options.stream_session,
+            )?)
+            .header(reqwest::header::RANGE, format!("bytes={start}-{end}"))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(VideoError::FormatRequestFailed(format!(
+                "Range {start}-{end} of itag {} answered with {}",
+                format.itag,
+                response.status()
+            )));
+        }
+
+        response.bytes().await.map_err(VideoError::Reqwest)
+    }
+
+    /// Run [`DownloadOptions::post_process`](crate::DownloadOptions::post_process) on a finished download
+    pub(crate) async fn post_process(
+        &self,
+        info: &VideoInfo,
+        format: &VideoFormat,
+        result: DownloadResult,
+    ) -> Result<DownloadResult, VideoError> {
+        let chain = match &self.options.download_options.post_process {
+            Some(chain) => chain,
+            None => return Ok(result),
+        };
+
+        chain.run(self, info, format, result).await
+    }
+
+    /// Download format into `<path>.part` and rename it to `path` on success.
+    /// With [`DownloadOptions::keep_part_files`](crate::DownloadOptions::keep_part_files) the part file
+    /// is kept on failure and download continues from its end
+    pub(crate) async fn download_format_to_file(
+        &self,
+        format: &VideoFormat,
+        path: &std::path::Path,
+    ) -> Result<DownloadResult, VideoError> {
+        self.download_format_to_file_until(format, path, None).await
+    }
+
+    /// [`Video::download_format_to_file`] stopping at the first chunk after `deadline`
+    pub(crate) async fn download_format_to_file_until(
+        &self,
+        format: &VideoFormat,
+        path: &std::path::Path,
+        deadline: Option<std::time::Instant>,
+    ) -> Result<DownloadResult, VideoError> {
+        use std::io::Read;
+
+        let keep_part_files = self.options.download_options.keep_part_files;
+        let mut hasher = self
+            .options
+            .download_options
+            .checksum
+            .map(ChecksumHasher::new);
+
+        let mut part_path = path.as_os_str().to_owned();
+        part_path.push(".part");
+        let part_path = std::path::PathBuf::from(part_path);
+
+        // Live streams and transcoded downloads can't be resumed
+        let transcoding = self.options.download_options.transcoder.is_some();
+        let part_file = PartFile::open(
+            &part_path,
+            keep_part_files && !format.is_hls && !transcoding,
+        )?;
+        let offset = part_file.contiguous();
+
+        // Transcoded bytes don't have the length of the format
+        let mut allocated = !self.options.download_options.preallocate || transcoding;
+
+        let result = async {
+            // Resumed bytes are hashed from the part file
+            if let (Some(hasher), true) = (hasher.as_mut(), offset > 0) {
+                let mut file = std::fs::File::open(&part_path)
+                    .map_err(|e| VideoError::DownloadError(e.to_string()))?
+                    .take(offset);
+                let mut buf = vec![0u8; 64 * 1024];
+
+                loop {
+                    let n = file
+                        .read(&mut buf)
+                        .map_err(|e| VideoError::DownloadError(e.to_string()))?;
+                    if n == 0 {
+                        break;
+                    }
+                    hasher.update(&buf[..n]);
+                }
+            }
+
+            let stream = self.stream_with_format_from(format, offset).await?;
+
+            // Fail before writing anything if remaining bytes don't fit
+            let dir = match path.parent() {
+                Some(dir) if !dir.as_os_str().is_empty() => dir,
+                _ => std::path::Path::new("."),
+            };
+            let check_space = |remaining: u64| {
+                if let Ok(available) = fs2::available_space(dir) {
+                    let required = remaining + DISK_SPACE_MARGIN;
+
+                    if available < required {
+                        return Err(VideoError::InsufficientDiskSpace(required, available));
+                    }
+                }
+                Ok(())
+            };
+
+            let mut space_checked = stream.content_length() > 0;
+            if space_checked {
+                check_space((stream.content_length() as u64).saturating_sub(offset))?;
+            }
+
+            let stream = self.transcoded(format, stream);
+
+            let mut size = offset;
+
+            while let Some(chunk) = stream.chunk().await? {
+                // Length may be known only after the first response
+                if !space_checked && stream.content_length() > 0 {
+                    check_space((stream.content_length() as u64).saturating_sub(size))?;
+                    space_checked = true;
+                }
+                if !allocated && stream.content_length() > 0 {
+                    part_file.allocate(stream.content_length() as u64)?;
+                    allocated = true;
+                }
+
+                part_file.write_at(size, &chunk)?;
+
+                if let Some(hasher) = hasher.as_mut() {
+                    hasher.update(&chunk);
+                }
+                size += chunk.len() as u64;
+
+                if deadline.map_or(false, |x| std::time::Instant::now() >= x) {
+                    break;
+                }
+            }
+
+            part_file.finish(size)?;
+
+            Ok((size, stream.live_report()))
+        }
+        .await;
+
+        if result.is_err() && keep_part_files {
+            let _ = part_file.keep();
         }
+        // Closed before it is renamed or removed
+        drop(part_file);
+
+        let (size, live_report) = match result {
+            Ok(result) => result,
+            Err(err) => {
+                if !keep_part_files {
+                    let _ = std::fs::remove_file(&part_path);
+                }
+                return Err(err);
+            }
+        };
+
+        std::fs::rename(&part_path, path).map_err(|e| VideoError::DownloadError(e.to_string()))?;
 
-        Ok(())
+        Ok(DownloadResult {
+            path: path.to_path_buf(),
+            size,
+            checksum: hasher.map(|x| x.finalize()),
+            live_report,
+            parts: vec![],
+        })
+    }
+
+    /// Download the given [`VideoFormat`] directly into `writer` (sockets, child process stdin, uploaders...)
+    /// and return written bytes count
+    /// # Example
+    /// ```ignore
+    ///     let video_info = video.get_info().await.unwrap();
+    ///     let format = choose_format(&video_info.formats, &video_options).unwrap();
+    ///
+    ///     let mut ffmpeg = tokio::process::Command::new("ffmpeg")
+    ///         .args(["-i", "-", "output.mp3"])
+    ///         .stdin(std::process::Stdio::piped())
+    ///         .spawn()
+    ///         .unwrap();
+    ///
+    ///     let mut stdin = ffmpeg.stdin.take().unwrap();
+    ///     video.download_format_to_writer(&format, &mut stdin).await.unwrap();
+    /// ```
+    pub async fn download_format_to_writer(
+        &self,
+        format: &VideoFormat,
+        writer: &mut (dyn tokio::io::AsyncWrite + Unpin + Send),
+    ) -> Result<u64, VideoError> {
+        correlate(
+            "download_format_to_writer",
+            &self.video_id,
+            &self.last_error,
+            async {
+                let stream = self.transcoded(format, self.stream_with_format(format).await?);
+
+                stream.write_to(writer).await
+            },
+        )
+        .await
+    }
+
+    /// Download the given [`VideoFormat`] into memory
+    ///
+    /// Fails with [`VideoError::DownloadSizeLimitExceeded`] if the content is bigger than `max_size` bytes
+    /// # Example
+    /// ```ignore
+    ///     let video_info = video.get_info().await.unwrap();
+    ///     let format = choose_format(&video_info.formats, &video_options).unwrap();
+    ///
+    ///     // Up to 5MB
+    ///     let buf = video.download_to_vec(&format, 5 * 1024 * 1024).await.unwrap();
+    /// ```
+    pub async fn download_to_vec(
+        &self,
+        format: &VideoFormat,
+        max_size: usize,
+    ) -> Result<Vec<u8>, VideoError> {
+        correlate("download_to_vec", &self.video_id, &self.last_error, async {
+            let stream = self.transcoded(format, self.stream_with_format(format).await?);
+
+            Ok(collect_stream(stream, max_size).await?.to_vec())
+        })
+        .await
+    }
+
+    /// Download video chosen with [`VideoOptions`] into memory
+    ///
+    /// Fails with [`VideoError::DownloadSizeLimitExceeded`] if the content is bigger than `max_size` bytes
+    pub async fn download_to_bytes(&self, max_size: usize) -> Result<Bytes, VideoError> {
+        correlate(
+            "download_to_bytes",
+            &self.video_id,
+            &self.last_error,
+            async {
+                let stream = self.download_stream().await?;
+
+                Ok(collect_stream(stream, max_size).await?.freeze())
+            },
+        )
+        .await
+    }
+
+    /// Download video chosen with [`VideoOptions`] directly into the given [`StorageSink`](crate::storage::StorageSink)
+    /// and return written bytes count
+    #[cfg(feature = "object-store")]
+    pub async fn download_to_sink(
+        &self,
+        sink: &(dyn crate::storage::StorageSink + Send + Sync),
+    ) -> Result<u64, VideoError> {
+        correlate(
+            "download_to_sink",
+            &self.video_id,
+            &self.last_error,
+            async {
+                let stream = self.download_stream().await?;
+
+                sink.write_stream(stream.as_ref()).await
+            },
+        )
+        .await
+    }
+
+    /// [`Video::stream`] with [`DownloadOptions::transcoder`](crate::DownloadOptions::transcoder) applied
+    async fn download_stream(&self) -> Result<Box<dyn Stream + Send + Sync>, VideoError> {
+        let info = self.get_info().await?;
+        let format = self.choose_working_format(&info).await?;
+
+        Ok(self.transcoded(&format, self.stream_with_format(&format).await?))
+    }
+
+    /// Wrap `stream` with [`DownloadOptions::transcoder`](crate::DownloadOptions::transcoder) if it is set.
+    /// HLS formats are remuxed into MP4 with the `remux` feature
+    pub(crate) fn transcoded(
+        &self,
+        format: &VideoFormat,
+        stream: Box<dyn Stream + Send + Sync>,
+    ) -> Box<dyn Stream + Send + Sync> {
+        match self.options.download_options.transcoder.as_ref() {
+            Some(transcoder) => Box::new(TranscodeStream::new(stream, transcoder(format))),
+            #[cfg(feature = "remux")]
+            None if format.is_hls => Box::new(TranscodeStream::new(
+                stream,
+                Box::new(crate::remux::TsRemuxer::new()),
+            )),
+            None => stream,
+        }
     }
 
     /// Get video URL
     pub fn get_video_url(&self) -> String {
-        format!("{}{}", BASE_URL, &self.video_id)
+        self.options
+            .request_options
+            .config
+            .watch_url(&self.video_id)
     }
 
     /// Get video id
@@ -426,19 +1074,80 @@ impl Video {
         &self.client
     }
 
+    #[allow(dead_code)]
+    pub(crate) fn get_stream_client(&self) -> &reqwest_middleware::ClientWithMiddleware {
+        &self.stream_client
+    }
+
+    /// [`VideoOptions::extractor`] or [`WebExtractor`]
+    pub(crate) fn extractor(&self) -> std::sync::Arc<dyn Extractor> {
+        self.options
+            .extractor
+            .clone()
+            .unwrap_or_else(|| std::sync::Arc::new(WebExtractor))
+    }
+
     #[allow(dead_code)]
     pub(crate) fn get_options(&self) -> VideoOptions {
-        self.options.clone()
+        (*self.options).clone()
+    }
+
+    /// Same video and clients with other options, `request_options` must not change
+    pub(crate) fn with_options(&self, options: VideoOptions) -> Self {
+        Self {
+            video_id: self.video_id.clone(),
+            options: std::sync::Arc::new(options),
+            client: self.client.clone(),
+            stream_client: self.stream_client.clone(),
+            last_error: self.last_error.clone(),
+        }
+    }
+
+    /// Context of the last failed operation (`get_info`, `stream`, `download`...) of this video or its clones,
+    /// errors are returned unchanged
+    /// # Example
+    /// ```ignore
+    ///     if let Err(err) = video.get_info().await {
+    ///         let request_id = video.last_error_context().and_then(|x| x.request_id);
+    ///         eprintln!("{err} (request {request_id:?})");
+    ///     }
+    /// ```
+    pub fn last_error_context(&self) -> Option<ErrorContext> {
+        self.last_error.lock().unwrap().clone()
     }
 }
 
+/// Client with transient error retries and the middlewares of `request_options`
+fn build_video_client(
+    video_id: &str,
+    request_options: &RequestOptions,
+    proxy_override: Option<&ProxyOverride>,
+) -> Result<reqwest_middleware::ClientWithMiddleware, VideoError> {
+    let client = build_client_with_proxy(request_options, proxy_override)?
+        .build()
+        .map_err(VideoError::Reqwest)?;
+
+    let retry_policy = reqwest_retry::policies::ExponentialBackoff::builder()
+        .retry_bounds(
+            std::time::Duration::from_millis(500),
+            std::time::Duration::from_millis(10000),
+        )
+        .build_with_max_retries(3);
+    let client = reqwest_middleware::ClientBuilder::new(client).with(
+        reqwest_retry::RetryTransientMiddleware::new_with_policy(retry_policy),
+    );
+
+    Ok(with_request_middlewares(client, request_options, proxy_override, Some(video_id))?.build())
+}
+
 #[allow(dead_code)]
 async fn get_dash_manifest(
     url: &str,
     client: &reqwest_middleware::ClientWithMiddleware,
+    config: &Config,
 ) -> Result<Vec<serde_json::Value>, VideoError> {
-    let base_url = url::Url::parse(BASE_URL).expect("BASE_URL corrapt");
-    let base_url_host = base_url.host_str().expect("BASE_URL host corrapt");
+    let base_url = url::Url::parse(&config.base_url)?;
+    let base_url_host = base_url.host_str().unwrap_or_default();
 
     let url = url::Url::parse(url)
         .and_then(|mut x| {
@@ -553,9 +1262,10 @@ async fn get_dash_manifest(
 async fn get_m3u8(
     url: &str,
     client: &reqwest_middleware::ClientWithMiddleware,
+    config: &Config,
 ) -> Result<Vec<(String, String)>, VideoError> {
-    let base_url = url::Url::parse(BASE_URL).expect("BASE_URL corrapt");
-    let base_url_host = base_url.host_str().expect("BASE_URL host corrapt");
+    let base_url = url::Url::parse(&config.base_url)?;
+    let base_url_host = base_url.host_str().unwrap_or_default();
 
     let url = url::Url::parse(url)
         .and_then(|mut x| {
@@ -593,3 +1303,35 @@ async fn get_m3u8(
 
     Ok(itag_and_url)
 }
+
+/// Extension of files downloaded from `format`, remuxed HLS formats are MP4 files
+pub(crate) fn file_extension(format: &VideoFormat) -> &str {
+    match format.container.as_deref() {
+        _ if cfg!(feature = "remux") && format.is_hls => "mp4",
+        Some(container) => container,
+        None => "mp4",
+    }
+}
+
+async fn collect_stream(
+    stream: Box<dyn Stream + Send + Sync>,
+    max_size: usize,
+) -> Result<BytesMut, VideoError> {
+    let content_length = stream.content_length();
+
+    if content_length > max_size {
+        return Err(VideoError::DownloadSizeLimitExceeded(max_size));
+    }
+
+    let mut buf = BytesMut::with_capacity(content_length);
+
+    while let Some(chunk) = stream.chunk().await? {
+        if buf.len() + chunk.len() > max_size {
+            return Err(VideoError::DownloadSizeLimitExceeded(max_size));
+        }
+
+        buf.extend_from_slice(&chunk);
+    }
+
+    Ok(buf)
+}
@@ -1,9 +1,16 @@
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-
-use crate::constants::BASE_URL;
-use crate::structs::{Author, Chapter, RelatedVideo, StoryBoard, Thumbnail};
-use crate::utils::{get_text, is_verified, parse_abbreviated_number, time_to_ms};
+use std::sync::Arc;
+
+use crate::constants::{BASE_URL, ORIGIN};
+use crate::structs::{
+    Author, Badge, CaptionTrack, Chapter, ExtractionReport, Poll, PollChoice, Product, RawSections,
+    RelatedVideo, RichMetadata, StoryBoard, Thumbnail, TicketEvent,
+};
+use crate::utils::{
+    get_badge, get_text, is_verified, parse_abbreviated_number, parse_approximate_date, time_to_ms,
+};
+use rusty_ytdl_core::parse_exact_count;
 
 pub fn get_related_videos(info: &serde_json::Value) -> Option<Vec<RelatedVideo>> {
     let mut rvs_params: Vec<&str> = vec![];
@@ -192,6 +199,15 @@ pub fn parse_related_video(
 
     let view_count_regex = Regex::new(r",").unwrap();
 
+    let published = if details.contains_key("publishedTimeText") {
+        get_text(&details["publishedTimeText"])
+            .as_str()
+            .unwrap_or("")
+            .to_string()
+    } else {
+        String::from("")
+    };
+
     let video = RelatedVideo {
         id: details
             .get("videoId")
@@ -220,14 +236,8 @@ pub fn parse_related_video(
         } else {
             String::from("")
         },
-        published: if details.contains_key("publishedTimeText") {
-            get_text(&details["publishedTimeText"])
-                .as_str()
-                .unwrap_or("")
-                .to_string()
-        } else {
-            String::from("")
-        },
+        published_date: parse_approximate_date(&published, std::time::SystemTime::now()),
+        published,
         author: if !browse_end_point.is_null() {
             Some(Author {
                 id: channel_id.as_str().unwrap_or("").to_string(),
@@ -316,7 +326,13 @@ pub fn parse_related_video(
                 } else {
                     false
                 },
+                badge: details
+                    .get("ownerBadges")
+                    .map(get_badge)
+                    .unwrap_or_default(),
                 subscriber_count: 0,
+                subscriber_count_text: String::new(),
+                subscriber_count_exact: None,
             })
         } else {
             None
@@ -385,234 +401,312 @@ pub fn parse_related_video(
     Some(video)
 }
 
-pub fn get_media(info: &serde_json::Value) -> Option<serde_json::Value> {
-    let empty_serde_array = serde_json::json!([]);
-    let empty_serde_object_array = vec![serde_json::json!({})];
-    let empty_serde_object = serde_json::json!({});
-
-    let results = info
-        .as_object()
-        .and_then(|x| x.get("contents"))
-        .and_then(|x| x.get("twoColumnWatchNextResults"))
-        .and_then(|x| x.get("results"))
-        .and_then(|x| x.get("results"))
-        .and_then(|x| x.get("contents"))
-        .unwrap_or(&empty_serde_array)
+/// Sections of the watch page results, with the contents of item sections
+fn get_watch_sections(info: &serde_json::Value) -> Vec<&serde_json::Value> {
+    let contents = info["contents"]["twoColumnWatchNextResults"]["results"]["results"]["contents"]
         .as_array()
-        .unwrap_or(&empty_serde_object_array);
+        .map(|x| x.as_slice())
+        .unwrap_or_default();
 
-    let result_option = results
+    // Shelves and the comments header are nested in item sections
+    let items = contents
         .iter()
-        .find(|x| x.get("videoSecondaryInfoRenderer").is_some());
-
-    let json_result = if let Some(result) = result_option {
-        let metadata_rows = if result.get("metadataRowContainer").is_some() {
-            result
-                .get("metadataRowContainer")
-                .and_then(|x| x.get("metadataRowContainerRenderer"))
-                .and_then(|x| x.get("rows"))
-                .unwrap_or(&empty_serde_object)
-        } else if result.get("videoSecondaryInfoRenderer").is_some()
-            && result
-                .get("videoSecondaryInfoRenderer")
-                .and_then(|x| x.get("metadataRowContainer"))
-                .is_some()
-        {
-            result
-                .get("videoSecondaryInfoRenderer")
-                .and_then(|x| x.get("metadataRowContainer"))
-                .and_then(|x| x.get("metadataRowContainerRenderer"))
-                .and_then(|x| x.get("rows"))
-                .unwrap_or(&empty_serde_object)
-        } else {
-            &empty_serde_object
-        }
-        .as_array()
-        .unwrap_or(&empty_serde_object_array);
-
-        let mut return_object = serde_json::json!({});
+        .filter_map(|x| x["itemSectionRenderer"]["contents"].as_array())
+        .flatten();
 
-        for row in metadata_rows {
-            // println!("{}", serde_json::to_string_pretty(row).unwrap());
-            if row.get("metadataRowRenderer").is_some() {
-                let title = get_text(
-                    row.get("metadataRowRenderer")
-                        .and_then(|x| x.get("title"))
-                        .unwrap_or(&empty_serde_object),
-                )
-                .as_str()
-                .unwrap_or("title");
-                let contents = row
-                    .get("metadataRowRenderer")
-                    .and_then(|x| x.get("contents"))
-                    .and_then(|x| x.as_array())
-                    .unwrap_or(&empty_serde_object_array)
-                    .get(0)
-                    .unwrap_or(&empty_serde_object);
-
-                let runs = contents.get("runs");
-
-                let mut title_url = "";
-
-                if runs.is_some()
-                    && runs.unwrap_or(&empty_serde_object).is_array()
-                    && runs
-                        .unwrap_or(&empty_serde_object)
-                        .as_array()
-                        .and_then(|x| x.get(0))
-                        .and_then(|x| x.get("navigationEndpoint"))
-                        .is_some()
-                {
-                    title_url = runs
-                        .unwrap_or(&empty_serde_array)
-                        .as_array()
-                        .unwrap_or(&empty_serde_object_array)
-                        .get(0)
-                        .and_then(|x| x.get("navigationEndpoint"))
-                        .and_then(|x| x.get("commandMetadata"))
-                        .and_then(|x| x.get("webCommandMetadata"))
-                        .and_then(|x| x.get("url"))
-                        .and_then(|x| x.as_str())
-                        .unwrap_or("");
-                }
+    contents.iter().chain(items).collect()
+}
 
-                let mut category = "";
-                let mut category_url = "";
+/// [`RawSections`] of the watch page initial data
+pub fn get_raw_sections(info: &serde_json::Value) -> RawSections {
+    let sections = get_watch_sections(info);
 
-                if title == "song" {
-                    category = "Music";
-                    category_url = "https://music.youtube.com/"
-                }
+    let find = |renderer: &str| {
+        sections
+            .iter()
+            .find_map(|x| x.get(renderer))
+            .map(|x| Arc::new(x.clone()))
+    };
 
-                let data = format!(
-                    r#"
-                "{title}": {title_content},
-                "{title}_url": {title_url},
-                "category: {category},
-                "category_url": {category_url},
-                "#,
-                    title = title,
-                    title_content = get_text(contents).as_str().unwrap_or(""),
-                    title_url = title_url,
-                    category = category,
-                    category_url = category_url,
-                );
-
-                return_object =
-                    serde_json::from_str(data.as_str()).unwrap_or(serde_json::json!({}));
-            } else if row.get("richMetadataRowRenderer").is_some() {
-                let contents = row
-                    .get("richMetadataRowRenderer")
-                    .and_then(|x| x.get("contents"))
-                    .and_then(|x| x.as_array())
-                    .unwrap_or(&empty_serde_object_array);
-
-                let box_art = contents.iter().filter(|x| {
-                    x.get("richMetadataRenderer")
-                        .and_then(|c| c.get("style"))
-                        .and_then(|c| c.as_str())
-                        .unwrap_or("")
-                        == "RICH_METADATA_RENDERER_STYLE_BOX_ART"
-                });
+    RawSections {
+        primary_info: find("videoPrimaryInfoRenderer"),
+        secondary_info: find("videoSecondaryInfoRenderer"),
+        merch_shelf: find("merchandiseShelfRenderer"),
+        comments_teaser: find("commentsEntryPointHeaderRenderer"),
+    }
+}
 
-                let mut media_year = "";
-                let mut media_type = "type";
-                let mut media_type_title = "";
-                let mut media_type_url = "";
-                let mut media_thumbnails = &empty_serde_array;
+/// First `urlEndpoint` url in a command, products link out through a few wrapper commands
+fn find_url_endpoint(value: &serde_json::Value) -> Option<&str> {
+    match value {
+        serde_json::Value::Object(x) => x
+            .get("urlEndpoint")
+            .and_then(|x| x["url"].as_str())
+            .or_else(|| x.values().find_map(find_url_endpoint)),
+        serde_json::Value::Array(x) => x.iter().find_map(find_url_endpoint),
+        _ => None,
+    }
+}
 
-                for box_art_value in box_art {
-                    let meta = box_art_value
-                        .get("richMetadataRenderer")
-                        .unwrap_or(&empty_serde_object);
+/// [`Product`]s of the merch shelf and the product list panel, without duplicates
+pub fn get_products(info: &serde_json::Value) -> Vec<Product> {
+    let shelf_items = get_watch_sections(info)
+        .into_iter()
+        .filter_map(|x| x["merchandiseShelfRenderer"]["items"].as_array())
+        .flatten()
+        .map(|x| &x["merchandiseItemRenderer"]);
 
-                    media_year = get_text(meta.get("subtitle").unwrap_or(&empty_serde_object))
-                        .as_str()
-                        .unwrap_or("");
+    let panels = info["engagementPanels"]
+        .as_array()
+        .map(|x| x.as_slice())
+        .unwrap_or_default();
+    let list_items = panels
+        .iter()
+        .filter_map(|x| {
+            x["engagementPanelSectionListRenderer"]["content"]["productListRenderer"]["contents"]
+                .as_array()
+        })
+        .flatten()
+        .map(|x| &x["productListItemRenderer"]);
+
+    let mut products: Vec<Product> = vec![];
+    for item in shelf_items.chain(list_items) {
+        let title = match text(&item["title"]) {
+            Some(title) => title,
+            None => continue,
+        };
+
+        let product = Product {
+            title,
+            price: text(&item["price"]).unwrap_or_default(),
+            vendor: text(&item["vendorName"]).or_else(|| text(&item["merchantName"])),
+            url: find_url_endpoint(item).unwrap_or("").to_string(),
+            thumbnail: item["thumbnail"]["thumbnails"]
+                .as_array()
+                .and_then(|x| x.last())
+                .and_then(|x| serde_json::from_value(x.clone()).ok()),
+        };
 
-                    media_type = get_text(meta.get("callToAction").unwrap_or(&empty_serde_object))
-                        .as_str()
-                        .unwrap_or("type")
-                        .split(' ')
-                        .collect::<Vec<&str>>()
-                        .get(1)
-                        .unwrap_or(&"type");
+        if !products
+            .iter()
+            .any(|x| x.title == product.title && x.url == product.url)
+        {
+            products.push(product);
+        }
+    }
 
-                    media_type_title = get_text(meta.get("title").unwrap_or(&empty_serde_object))
-                        .as_str()
-                        .unwrap_or("");
+    products
+}
 
-                    media_type_url = meta
-                        .get("endpoint")
-                        .and_then(|x| x.get("commandMetadata"))
-                        .and_then(|x| x.get("webCommandMetadata"))
-                        .and_then(|x| x.get("url"))
-                        .and_then(|x| x.as_str())
-                        .unwrap_or("");
-                    media_thumbnails = meta
-                        .get("thumbnail")
-                        .and_then(|x| x.get("thumbnails"))
-                        .unwrap_or(&empty_serde_array);
-                }
+/// [`TicketEvent`]s of the ticket shelf below music videos
+pub fn get_ticket_events(info: &serde_json::Value) -> Vec<TicketEvent> {
+    let first_text =
+        |item: &serde_json::Value, keys: &[&str]| keys.iter().find_map(|x| text(&item[x]));
+
+    get_watch_sections(info)
+        .into_iter()
+        .map(|x| &x["ticketShelfRenderer"])
+        .filter_map(|x| x["tickets"].as_array().or_else(|| x["items"].as_array()))
+        .flatten()
+        .map(|x| &x["ticketEventRenderer"])
+        .filter_map(|item| {
+            Some(TicketEvent {
+                date: first_text(item, &["date", "eventDate"])?,
+                venue: first_text(item, &["venueName", "venue"]).unwrap_or_default(),
+                location: first_text(item, &["location", "city"]),
+                vendor: first_text(item, &["vendorName", "ticketVendor"]),
+                url: find_url_endpoint(item).unwrap_or("").to_string(),
+            })
+        })
+        .collect()
+}
 
-                let topic = contents.iter().filter(|x| {
-                    x.get("richMetadataRenderer")
-                        .and_then(|x| x.get("style"))
-                        .and_then(|x| x.as_str())
-                        .unwrap_or("")
-                        == "RICH_METADATA_RENDERER_STYLE_TOPIC"
+/// Poll of a `pollRenderer`, or of a community post or info card wrapping one.
+/// Votes are only sent for finished polls or after voting
+pub fn parse_poll(value: &serde_json::Value) -> Option<Poll> {
+    let renderer = [
+        value,
+        &value["pollRenderer"],
+        &value["backstageAttachment"]["pollRenderer"],
+        &value["backstagePostRenderer"]["backstageAttachment"]["pollRenderer"],
+        &value["cardRenderer"]["content"]["pollRenderer"],
+    ]
+    .into_iter()
+    .find(|x| x["choices"].is_array())?;
+
+    let choices = renderer["choices"]
+        .as_array()
+        .map(|x| x.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|choice| {
+            let percentage = choice["voteRatio"]
+                .as_f64()
+                .map(|x| (x * 100.0) as f32)
+                .or_else(|| {
+                    text(&choice["votePercentage"])
+                        .and_then(|x| x.trim_end_matches('%').trim().parse().ok())
                 });
 
-                let mut category = "";
-                let mut category_url = "";
+            Some(PollChoice {
+                text: text(&choice["text"])?,
+                votes: choice["numVotes"]
+                    .as_u64()
+                    .or_else(|| choice["numVotes"].as_str().and_then(|x| x.parse().ok())),
+                percentage,
+            })
+        })
+        .collect();
 
-                for topic_value in topic {
-                    let meta = topic_value
-                        .get("richMetadataRenderer")
-                        .unwrap_or(&empty_serde_object);
+    Some(Poll {
+        question: text(&renderer["question"]),
+        choices,
+        total_votes: text(&renderer["totalVotes"]).map(|x| parse_abbreviated_number(&x) as u64),
+    })
+}
 
-                    category = get_text(meta.get("title").unwrap_or(&empty_serde_object))
-                        .as_str()
-                        .unwrap_or("");
+/// Polls of the info cards
+pub fn get_polls(player_response: &serde_json::Value) -> Vec<Poll> {
+    player_response["cards"]["cardCollectionRenderer"]["cards"]
+        .as_array()
+        .map(|x| x.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(parse_poll)
+        .collect()
+}
 
-                    category_url = meta
-                        .get("endpoint")
-                        .and_then(|x| x.get("commandMetadata"))
-                        .and_then(|x| x.get("webCommandMetadata"))
-                        .and_then(|x| x.get("url"))
-                        .and_then(|x| x.as_str())
-                        .unwrap_or("");
-                }
+/// Rows below the description, like the linked game, song or topic
+pub(crate) fn get_metadata_rows(info: &serde_json::Value) -> Vec<&serde_json::Value> {
+    let contents = info["contents"]["twoColumnWatchNextResults"]["results"]["results"]["contents"]
+        .as_array()
+        .map(|x| x.as_slice())
+        .unwrap_or_default();
 
-                let data = format!(
-                    r#"
-                    "year": {media_year},
-                    "{media_type}": {media_type_title},
-                    "{media_type}_url": {media_type_url},
-                    "thumbnails: {media_thumbnails},
-                    "category: {category},
-                    "category_url": {category_url},
-                    "#,
-                    media_year = media_year,
-                    media_type = media_type,
-                    media_type_title = media_type_title,
-                    media_type_url = media_type_url,
-                    media_thumbnails = media_thumbnails,
-                    category = category,
-                    category_url = category_url,
-                );
-
-                return_object =
-                    serde_json::from_str(data.as_str()).unwrap_or(serde_json::json!({}));
+    contents
+        .iter()
+        .filter_map(|x| {
+            x["videoSecondaryInfoRenderer"]["metadataRowContainer"]["metadataRowContainerRenderer"]
+                ["rows"]
+                .as_array()
+                .or_else(|| {
+                    x["metadataRowContainer"]["metadataRowContainerRenderer"]["rows"].as_array()
+                })
+        })
+        .flatten()
+        .collect()
+}
+
+/// Urls of the links in a metadata row
+pub(crate) fn get_metadata_row_urls(row: &serde_json::Value) -> Vec<&str> {
+    let contents = row["metadataRowRenderer"]["contents"]
+        .as_array()
+        .map(|x| x.as_slice())
+        .unwrap_or_default();
+
+    contents
+        .iter()
+        .filter_map(|x| x["runs"].as_array())
+        .flatten()
+        .flat_map(|x| {
+            [
+                x["navigationEndpoint"]["commandMetadata"]["webCommandMetadata"]["url"].as_str(),
+                x["navigationEndpoint"]["urlEndpoint"]["url"].as_str(),
+            ]
+        })
+        .flatten()
+        .collect()
+}
+
+fn endpoint_url(value: &serde_json::Value) -> Option<String> {
+    value["commandMetadata"]["webCommandMetadata"]["url"]
+        .as_str()
+        .filter(|x| !x.is_empty())
+        .map(|x| {
+            if x.starts_with('/') {
+                format!("{ORIGIN}{x}")
+            } else {
+                x.to_string()
+            }
+        })
+}
+
+/// Text of runs, simple texts and plain strings
+fn text(value: &serde_json::Value) -> Option<String> {
+    value
+        .as_str()
+        .or_else(|| get_text(value).as_str())
+        .map(|x| x.trim())
+        .filter(|x| !x.is_empty())
+        .map(|x| x.to_string())
+}
+
+/// [`RichMetadata`] of the rows below the description
+pub fn get_media(info: &serde_json::Value) -> Vec<RichMetadata> {
+    let mut media = vec![];
+    let (mut song, mut artist, mut album, mut song_url) = (None, None, None, None);
+
+    for row in get_metadata_rows(info) {
+        if let Some(renderer) = row.get("metadataRowRenderer") {
+            let content = &renderer["contents"][0];
+
+            match text(&renderer["title"])
+                .map(|x| x.to_lowercase())
+                .as_deref()
+            {
+                Some("song") => {
+                    song = text(content);
+                    song_url = endpoint_url(&content["runs"][0]["navigationEndpoint"]);
+                }
+                Some("artist") => artist = text(content),
+                Some("album") => album = text(content),
+                _ => {}
+            }
+        } else if let Some(contents) = row["richMetadataRowRenderer"]["contents"].as_array() {
+            for meta in contents.iter().map(|x| &x["richMetadataRenderer"]) {
+                let title = match text(&meta["title"]) {
+                    Some(title) => title,
+                    None => continue,
+                };
+                let url = endpoint_url(&meta["endpoint"]);
+
+                match meta["style"].as_str() {
+                    Some("RICH_METADATA_RENDERER_STYLE_BOX_ART") => {
+                        media.push(RichMetadata::Game {
+                            title,
+                            year: text(&meta["subtitle"]).and_then(|x| x.parse().ok()),
+                            url,
+                            thumbnails: meta["thumbnail"]["thumbnails"]
+                                .as_array()
+                                .map(|x| x.as_slice())
+                                .unwrap_or_default()
+                                .iter()
+                                .filter_map(|x| serde_json::from_value(x.clone()).ok())
+                                .collect(),
+                        })
+                    }
+                    Some("RICH_METADATA_RENDERER_STYLE_TOPIC") => {
+                        media.push(RichMetadata::Topic { title, url })
+                    }
+                    _ => {}
+                }
             }
         }
+    }
 
-        Some(return_object)
-    } else {
-        Some(serde_json::json!({}))
-    };
+    if let Some(song) = song {
+        media.insert(
+            0,
+            RichMetadata::Music {
+                song,
+                artist,
+                album,
+                url: song_url,
+            },
+        );
+    }
 
-    json_result
+    media
 }
 
 pub fn get_author(
@@ -710,17 +804,12 @@ pub fn get_author(
                 .to_string(),
         })
         .collect::<Vec<Thumbnail>>();
-    let zero_viewer = serde_json::json!("0");
-    let subscriber_count = parse_abbreviated_number(
-        get_text(
-            video_ownder_renderer
-                .get("subscriberCountText")
-                .unwrap_or(&zero_viewer),
-        )
-        .as_str()
-        .unwrap_or("0"),
-    );
-    let verified = is_verified(
+    let subscriber_count_text = video_ownder_renderer
+        .get("subscriberCountText")
+        .map(|x| get_text(x).as_str().unwrap_or("").to_string())
+        .unwrap_or_default();
+    let subscriber_count_exact = parse_exact_count(&subscriber_count_text);
+    let badge = get_badge(
         video_ownder_renderer
             .get("badges")
             .unwrap_or(&serde_empty_object),
@@ -828,8 +917,12 @@ pub fn get_author(
             String::from("")
         },
         thumbnails,
-        verified,
-        subscriber_count: subscriber_count as i32,
+        verified: badge != Badge::None,
+        badge,
+        subscriber_count: subscriber_count_exact
+            .unwrap_or(parse_abbreviated_number(&subscriber_count_text) as u64),
+        subscriber_count_text,
+        subscriber_count_exact,
     })
 }
 
@@ -967,62 +1060,56 @@ pub fn get_dislikes(info: &serde_json::Value) -> i32 {
     count_final.parse::<i32>().unwrap_or(0i32)
 }
 
+/// People watching a running live stream, from the `watching now` count below the title
+pub fn get_live_viewers(info: &serde_json::Value) -> Option<u64> {
+    get_watch_sections(info)
+        .into_iter()
+        .map(|x| &x["videoPrimaryInfoRenderer"]["viewCount"]["videoViewCountRenderer"])
+        .find(|x| x.is_object())
+        .filter(|renderer| {
+            renderer["isLive"].as_bool().unwrap_or(false)
+                || text(&renderer["viewCount"]).map_or(false, |x| x.contains("watching"))
+        })
+        .and_then(|renderer| text(&renderer["viewCount"]))
+        .map(|x| x.chars().filter(|x| x.is_ascii_digit()).collect::<String>())
+        .and_then(|x| x.parse().ok())
+}
+
+/// Hashtags linked above the title, like `#music`, in page order without duplicates
+pub fn get_hashtags(info: &serde_json::Value) -> Vec<String> {
+    let contents = info["contents"]["twoColumnWatchNextResults"]["results"]["results"]["contents"]
+        .as_array()
+        .map(|x| x.as_slice())
+        .unwrap_or_default();
+
+    let runs = contents
+        .iter()
+        .filter_map(|x| x["videoPrimaryInfoRenderer"]["superTitleLink"]["runs"].as_array())
+        .flatten();
+
+    let mut hashtags: Vec<String> = vec![];
+    for text in runs.filter_map(|x| x["text"].as_str()).map(|x| x.trim()) {
+        if text.len() > 1 && text.starts_with('#') && !hashtags.iter().any(|x| x == text) {
+            hashtags.push(text.to_string());
+        }
+    }
+
+    hashtags
+}
+
 pub fn get_storyboards(info: &serde_json::Value) -> Option<Vec<StoryBoard>> {
-    let parts = info
+    let spec = info
         .get("storyboards")
         .and_then(|x| x.get("playerStoryboardSpecRenderer"))
         .and_then(|x| x.get("spec"))
         .and_then(|x| x.as_str());
 
-    if parts.is_none() {
-        return Some(vec![]);
-    };
-
-    let mut parts = parts.unwrap_or("").split('|').collect::<Vec<&str>>();
-
-    let mut url = url::Url::parse(parts.remove(0))
-        .unwrap_or(url::Url::parse("https://i.ytimg.com/").unwrap());
     Some(
-        parts
-            .iter()
-            .enumerate()
-            .map(|(i, part)| {
-                let part_split_vec = part.split('#').collect::<Vec<&str>>();
-                let thumbnail_width = part_split_vec.first().unwrap_or(&"0");
-                let thumbnail_height = part_split_vec.get(1).unwrap_or(&"0");
-                let thumbnail_count = part_split_vec.get(2).unwrap_or(&"0");
-                let columns = part_split_vec.get(3).unwrap_or(&"0");
-                let rows = part_split_vec.get(4).unwrap_or(&"0");
-                let interval = part_split_vec.get(5).unwrap_or(&"0");
-                let name_replacement = part_split_vec.get(6).unwrap_or(&"0");
-                let sigh = part_split_vec.get(7).unwrap_or(&"0");
-
-                url.query_pairs_mut().append_pair("sigh", sigh);
-
-                let thumbnail_count_parsed = thumbnail_count.parse::<i32>().unwrap_or(0i32);
-                let columns_parsed = columns.parse::<i32>().unwrap_or(0i32);
-                let rows_parsed = rows.parse::<i32>().unwrap_or(0i32);
-
-                let storyboard_count_ceiled =
-                    thumbnail_count_parsed / (columns_parsed * rows_parsed);
-
-                let template_url = url
-                    .as_str()
-                    .replace("$L", i.to_string().as_str())
-                    .replace("$N", name_replacement);
-
-                StoryBoard {
-                    template_url,
-                    thumbnail_width: thumbnail_width.parse::<i32>().unwrap_or(0i32),
-                    thumbnail_height: thumbnail_height.parse::<i32>().unwrap_or(0i32),
-                    thumbnail_count: thumbnail_count_parsed,
-                    interval: interval.parse::<i32>().unwrap_or(0i32),
-                    columns: columns_parsed,
-                    rows: rows_parsed,
-                    storyboard_count: storyboard_count_ceiled,
-                }
-            })
-            .collect::<Vec<StoryBoard>>(),
+        spec.map(rusty_ytdl_core::parse_storyboard_spec)
+            .unwrap_or_default()
+            .into_iter()
+            .map(StoryBoard::from)
+            .collect(),
     )
 }
 
@@ -1094,3 +1181,101 @@ pub fn get_chapters(info: &serde_json::Value) -> Option<Vec<Chapter>> {
             .collect::<Vec<Chapter>>(),
     )
 }
+
+pub fn get_caption_tracks(player_response: &serde_json::Value) -> Option<Vec<CaptionTrack>> {
+    let empty_serde_vec: Vec<serde_json::Value> = vec![];
+
+    let caption_tracks = player_response
+        .get("captions")
+        .and_then(|x| x.get("playerCaptionsTracklistRenderer"))
+        .and_then(|x| x.get("captionTracks"))
+        .and_then(|x| x.as_array())
+        .unwrap_or(&empty_serde_vec)
+        .iter()
+        .map(|x| CaptionTrack {
+            base_url: x
+                .get("baseUrl")
+                .and_then(|x| x.as_str())
+                .unwrap_or("")
+                .to_string(),
+            name: get_text(x.get("name").unwrap_or(&serde_json::Value::Null))
+                .as_str()
+                .unwrap_or("")
+                .to_string(),
+            language_code: x
+                .get("languageCode")
+                .and_then(|x| x.as_str())
+                .unwrap_or("")
+                .to_string(),
+            kind: x
+                .get("kind")
+                .and_then(|x| x.as_str())
+                .map(|x| x.to_string()),
+            is_translatable: x
+                .get("isTranslatable")
+                .and_then(|x| x.as_bool())
+                .unwrap_or(false),
+        })
+        .filter(|x| !x.base_url.is_empty())
+        .collect::<Vec<CaptionTrack>>();
+
+    Some(caption_tracks)
+}
+
+/// Paths of the player response used by every playable video
+const PLAYER_RESPONSE_PATHS: &[&str] = &[
+    "playabilityStatus.status",
+    "videoDetails.videoId",
+    "videoDetails.title",
+    "videoDetails.lengthSeconds",
+    "microformat.playerMicroformatRenderer",
+    "streamingData.adaptiveFormats",
+];
+
+/// Paths of the initial data of the watch page
+const INITIAL_DATA_PATHS: &[&str] = &[
+    "contents.twoColumnWatchNextResults.results.results.contents.*.videoPrimaryInfoRenderer",
+    "contents.twoColumnWatchNextResults.results.results.contents.*.videoSecondaryInfoRenderer.owner.videoOwnerRenderer",
+    "contents.twoColumnWatchNextResults.secondaryResults.secondaryResults.results",
+];
+
+fn has_path(value: &serde_json::Value, path: &[&str]) -> bool {
+    match path.split_first() {
+        None => !value.is_null(),
+        Some((&"*", rest)) => value
+            .as_array()
+            .map_or(false, |x| x.iter().any(|x| has_path(x, rest))),
+        Some((key, rest)) => value.get(key).map_or(false, |x| has_path(x, rest)),
+    }
+}
+
+/// Expected paths missing from the responses. Initial data is only checked if the extractor returned one
+pub fn get_extraction_report(
+    initial_response: &serde_json::Value,
+    player_response: &serde_json::Value,
+) -> ExtractionReport {
+    let mut checks = vec![("playerResponse", player_response, PLAYER_RESPONSE_PATHS)];
+    if !initial_response.is_null() {
+        checks.push(("initialData", initial_response, INITIAL_DATA_PATHS));
+    }
+
+    let missing_paths = checks
+        .into_iter()
+        .flat_map(|(name, value, paths)| {
+            paths
+                .iter()
+                .filter(|path| !has_path(value, &path.split('.').collect::<Vec<&str>>()))
+                .map(move |path| format!("{name}.{path}"))
+        })
+        .collect::<Vec<String>>();
+
+    #[cfg(feature = "log")]
+    for path in &missing_paths {
+        log::warn!(
+            "[{}] rusty_ytdl extraction: missing {path}",
+            crate::correlation::log_label()
+        );
+    }
+
+    ExtractionReport { missing_paths }
+}
@@ -2,13 +2,27 @@
 #![allow(dead_code)]
 #![allow(unused_imports)]
 
+mod circuit_breaker;
+mod correlation;
+mod diagnose;
+mod identity;
 mod info;
 mod info_extras;
+mod pacer;
+mod proxy;
+mod sidecar;
 mod structs;
+mod tasks;
+mod throttle;
 mod utils;
 
+pub mod archive;
+pub mod compat;
 pub mod constants;
+pub mod extractor;
+pub mod postprocess;
 pub mod stream;
+pub mod transcode;
 
 #[cfg(feature = "blocking")]
 pub mod blocking;
@@ -16,12 +30,66 @@ pub mod blocking;
 #[cfg(feature = "search")]
 pub mod search;
 
+#[cfg(feature = "object-store")]
+pub mod storage;
+
+#[cfg(feature = "captions")]
+pub mod captions;
+
+#[cfg(feature = "ffmpeg")]
+pub mod ffmpeg;
+
+#[cfg(feature = "aria2")]
+pub mod external;
+
+#[cfg(feature = "live")]
+pub mod recorder;
+
+#[cfg(feature = "remux")]
+pub mod remux;
+
+#[cfg(feature = "serve")]
+pub mod serve;
+
+#[cfg(feature = "thumbnails")]
+pub mod thumbnails;
+
+pub use circuit_breaker::CircuitBreaker;
+pub use correlation::{current_request_id, RequestId};
+pub use diagnose::{
+    diagnose, diagnose_with_options, DiagnosticCheck, DiagnosticReport, DiagnosticStep,
+    DiagnosticVerdict,
+};
+pub use identity::{Identity, IdentityPool, RotationStrategy};
 pub use info::Video;
+pub use info_extras::parse_poll;
+pub use pacer::RequestPacer;
+pub use proxy::ProxyPool;
+pub use sidecar::SidecarFormat;
 pub use structs::{
-    Author, Chapter, ColorInfo, DownloadOptions, Embed, RangeObject, RelatedVideo, RequestOptions,
-    StoryBoard, Thumbnail, VideoDetails, VideoError, VideoFormat, VideoInfo, VideoOptions,
-    VideoQuality, VideoSearchOptions,
+    ApproximateDate, AudioTrackKind, Author, Badge, CaptionTrack, Chapter, ChecksumAlgorithm,
+    ClientFallback, ColorInfo, Config, CountryCode, DownloadOptions, DownloadResult,
+    DownloadSizeEstimate, Embed, ErrorContext, ExtractionReport, FormatFallback,
+    FormatFallbackCallback, FormatFilter, FormatId, FormatIdPart, FormatProtocol,
+    FormatSizeEstimate, FormatSortKey, FormatSorter, HeaderProfile, InnertubeClient,
+    InnertubeConfig, Poll, PollChoice, Product, ProxyOverride, RangeObject, RawSections,
+    RelatedVideo, RequestOptions, RichMetadata, SanitizeProfile, SizeSource, StoryBoard,
+    StoryboardTile, StreamSession, StreamUrlStatus, Thumbnail, TicketEvent, VideoDetails,
+    VideoError, VideoFormat, VideoInfo, VideoOptions, VideoQuality, VideoSearchOptions, ViewCount,
+};
+pub use tasks::TaskGroup;
+pub use throttle::ThrottledMode;
+pub use utils::{
+    chapters_from_description, check_stream_url, choose_format, finalize_url, get_random_v6_ip,
+    get_video_id, parse_approximate_date, render_output_template, sanitize_filename,
+    PlayerFunctions,
 };
-pub use utils::{choose_format, get_random_v6_ip, get_video_id};
+// export to reuse the parsers without the network stack
+pub use rusty_ytdl_core;
 // export to access proxy feature
 pub use reqwest;
+// export to access stream chunks
+pub use bytes;
+// export to build object stores for storage sinks
+#[cfg(feature = "object-store")]
+pub use object_store;
@@ -0,0 +1,110 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use reqwest::{Request, Response};
+use reqwest_middleware::{Middleware, Next};
+use task_local_extensions::Extensions;
+use tokio::time::Instant;
+
+use crate::structs::Config;
+
+/// Politeness delay between metadata requests (watch pages, player, innertube API), media bytes are not paced.
+/// Clones share the schedule, so every [`Video`](crate::Video), search and playlist using the same
+/// [`RequestOptions`](crate::RequestOptions) waits for the others
+///
+/// # Example
+/// ```ignore
+///     let request_options = RequestOptions {
+///         min_request_interval: Some(
+///             RequestPacer::new(Duration::from_secs(1)).with_jitter(Duration::from_millis(500)),
+///         ),
+///         ..Default::default()
+///     };
+/// ```
+#[derive(Clone, derivative::Derivative)]
+#[derivative(Debug)]
+pub struct RequestPacer {
+    interval: Duration,
+    jitter: Duration,
+    /// Start of the last scheduled request
+    #[derivative(Debug = "ignore")]
+    last: Arc<Mutex<Option<Instant>>>,
+}
+
+impl RequestPacer {
+    /// At least `interval` between the starts of two requests
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            jitter: Duration::ZERO,
+            last: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Add a random wait of up to `jitter` to every interval
+    pub fn with_jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Wait for the next free slot. Waiting callers are queued, each one takes the slot after the previous one
+    pub async fn wait(&self) {
+        let start = {
+            let mut last = self.last.lock().unwrap();
+            let now = Instant::now();
+
+            let start = match *last {
+                Some(last) => now.max(last + self.interval + self.random_jitter()),
+                None => now,
+            };
+            *last = Some(start);
+            start
+        };
+
+        tokio::time::sleep_until(start).await;
+    }
+
+    fn random_jitter(&self) -> Duration {
+        let jitter = self.jitter.as_millis() as u64;
+        if jitter == 0 {
+            return Duration::ZERO;
+        }
+
+        Duration::from_millis(rand::random::<u64>() % (jitter + 1))
+    }
+}
+
+/// Paces the requests to the hosts of a [`Config`], other requests are passed through
+pub(crate) struct PacedHosts {
+    pacer: RequestPacer,
+    hosts: Vec<String>,
+}
+
+impl PacedHosts {
+    pub(crate) fn new(pacer: RequestPacer, config: &Config) -> Self {
+        let hosts = [&config.origin, &config.base_url]
+            .iter()
+            .filter_map(|x| url::Url::parse(x).ok())
+            .filter_map(|x| x.host_str().map(|x| x.to_string()))
+            .collect();
+
+        Self { pacer, hosts }
+    }
+}
+
+#[async_trait::async_trait]
+impl Middleware for PacedHosts {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        let host = req.url().host_str().unwrap_or("");
+        if self.hosts.iter().any(|x| x == host) {
+            self.pacer.wait().await;
+        }
+
+        next.run(req, extensions).await
+    }
+}
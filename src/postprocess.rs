@@ -0,0 +1,252 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::structs::{DownloadResult, VideoError, VideoFormat, VideoInfo};
+use crate::Video;
+
+/// Step run on a finished download, like muxing, tagging or moving the file
+///
+/// # Example
+/// ```ignore
+///     struct Log;
+///
+///     #[async_trait::async_trait]
+///     impl PostProcessor for Log {
+///         fn name(&self) -> &str {
+///             "log"
+///         }
+///
+///         async fn process(&self, context: &mut PostProcessContext<'_>) -> Result<(), VideoError> {
+///             println!("{}", context.path.display());
+///             Ok(())
+///         }
+///     }
+/// ```
+#[async_trait]
+pub trait PostProcessor: Send + Sync {
+    /// Name of the step in [`PostProcessChain::skip`] and [`PostProcessEvent`]s
+    fn name(&self) -> &str;
+
+    /// Whether the step applies to this download, like thumbnails for containers without cover art
+    fn applies(&self, _context: &PostProcessContext<'_>) -> bool {
+        true
+    }
+
+    /// Process the file at [`PostProcessContext::path`], steps writing another file point `path` to it
+    async fn process(&self, context: &mut PostProcessContext<'_>) -> Result<(), VideoError>;
+}
+
+/// Download passed through the steps of a [`PostProcessChain`]
+pub struct PostProcessContext<'a> {
+    pub video: &'a Video,
+    pub info: &'a VideoInfo,
+    /// Downloaded format
+    pub format: &'a VideoFormat,
+    /// Current file, the downloaded one for the first step
+    pub path: PathBuf,
+    /// Files split from the download, like chapters
+    pub parts: Vec<PathBuf>,
+    step: String,
+    on_progress: Option<&'a PostProcessCallback>,
+}
+
+impl PostProcessContext<'_> {
+    /// Report progress of the current step, `fraction` in `0.0..=1.0`
+    pub fn report(&self, fraction: f64) {
+        self.emit(PostProcessStatus::Progress(fraction.clamp(0.0, 1.0)));
+    }
+
+    fn emit(&self, status: PostProcessStatus) {
+        if let Some(callback) = self.on_progress {
+            callback(&PostProcessEvent {
+                step: self.step.clone(),
+                status,
+            });
+        }
+    }
+}
+
+/// State change of a post-processing step
+#[derive(Clone, Debug, PartialEq)]
+pub struct PostProcessEvent {
+    /// [`PostProcessor::name`] of the step
+    pub step: String,
+    pub status: PostProcessStatus,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PostProcessStatus {
+    Started,
+    /// Reported by the step, in `0.0..=1.0`
+    Progress(f64),
+    Finished,
+    /// Skipped with [`PostProcessChain::skip`] or not applicable to the download
+    Skipped,
+}
+
+/// Callback of [`PostProcessChain::on_progress`]
+pub type PostProcessCallback = Arc<dyn Fn(&PostProcessEvent) + Send + Sync>;
+
+/// Ordered post-processing steps of [`DownloadOptions::post_process`](crate::DownloadOptions::post_process),
+/// run after every successful download
+///
+/// # Example
+/// ```ignore
+///     let chain = PostProcessChain::new()
+///         .then(Mux::default())
+///         .then(TagMetadata::default())
+///         .then(EmbedThumbnail::default())
+///         .then(MoveTo::new("library"))
+///         .skip("embed_thumbnail");
+/// ```
+#[derive(Clone, Default, derivative::Derivative)]
+#[derivative(Debug)]
+pub struct PostProcessChain {
+    #[derivative(Debug = "ignore")]
+    steps: Vec<Arc<dyn PostProcessor>>,
+    skipped: HashSet<String>,
+    #[derivative(Debug = "ignore")]
+    on_progress: Option<PostProcessCallback>,
+}
+
+impl PostProcessChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a step
+    pub fn then(mut self, step: impl PostProcessor + 'static) -> Self {
+        self.steps.push(Arc::new(step));
+        self
+    }
+
+    /// Skip the steps named `name`
+    pub fn skip(mut self, name: impl Into<String>) -> Self {
+        self.skipped.insert(name.into());
+        self
+    }
+
+    /// Call `callback` when a step starts, reports progress, finishes or is skipped
+    pub fn on_progress(mut self, callback: PostProcessCallback) -> Self {
+        self.on_progress = Some(callback);
+        self
+    }
+
+    /// Names of the steps in order
+    pub fn names(&self) -> Vec<&str> {
+        self.steps.iter().map(|x| x.name()).collect()
+    }
+
+    /// Run every step on a finished download, the result points to the files of the last step
+    pub async fn run(
+        &self,
+        video: &Video,
+        info: &VideoInfo,
+        format: &VideoFormat,
+        result: DownloadResult,
+    ) -> Result<DownloadResult, VideoError> {
+        let mut context = PostProcessContext {
+            video,
+            info,
+            format,
+            path: result.path.clone(),
+            parts: result.parts.clone(),
+            step: String::new(),
+            on_progress: self.on_progress.as_ref(),
+        };
+
+        for step in self.steps.iter() {
+            context.step = step.name().to_string();
+
+            if self.skipped.contains(step.name()) || !step.applies(&context) {
+                context.emit(PostProcessStatus::Skipped);
+                continue;
+            }
+
+            context.emit(PostProcessStatus::Started);
+            step.process(&mut context)
+                .await
+                .map_err(|e| VideoError::PostProcessError(step.name().to_string(), Box::new(e)))?;
+            context.emit(PostProcessStatus::Finished);
+        }
+
+        let size = std::fs::metadata(&context.path)
+            .map(|x| x.len())
+            .unwrap_or(result.size);
+
+        Ok(DownloadResult {
+            path: context.path,
+            parts: context.parts,
+            size,
+            ..result
+        })
+    }
+}
+
+/// Move the file and its parts into a directory, keeping their names. Files are copied if the directory is
+/// on another file system
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct MoveTo {
+    pub dir: PathBuf,
+}
+
+impl MoveTo {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn destination(&self, path: &Path) -> Result<PathBuf, VideoError> {
+        let file_name = path.file_name().ok_or_else(|| {
+            VideoError::DownloadError(format!("{} is not a file", path.display()))
+        })?;
+
+        Ok(self.dir.join(file_name))
+    }
+}
+
+#[async_trait]
+impl PostProcessor for MoveTo {
+    fn name(&self) -> &str {
+        "move"
+    }
+
+    async fn process(&self, context: &mut PostProcessContext<'_>) -> Result<(), VideoError> {
+        let mut path_moved = false;
+
+        for part in context.parts.iter_mut() {
+            let destination = self.destination(part)?;
+            move_file(part, &destination)?;
+
+            if *part == context.path {
+                context.path = destination.clone();
+                path_moved = true;
+            }
+            *part = destination;
+        }
+
+        if !path_moved {
+            let destination = self.destination(&context.path)?;
+            move_file(&context.path, &destination)?;
+            context.path = destination;
+        }
+
+        Ok(())
+    }
+}
+
+/// Rename `from` to `to`, falling back to copy and remove across file systems
+pub(crate) fn move_file(from: &Path, to: &Path) -> Result<(), VideoError> {
+    if let Some(dir) = to.parent().filter(|x| !x.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).map_err(|e| VideoError::DownloadError(e.to_string()))?;
+    }
+
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+
+    std::fs::copy(from, to).map_err(|e| VideoError::DownloadError(e.to_string()))?;
+    std::fs::remove_file(from).map_err(|e| VideoError::DownloadError(e.to_string()))
+}
@@ -0,0 +1,203 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::info::file_extension;
+use crate::structs::{DownloadResult, VideoError, VideoOptions};
+use crate::utils::{
+    build_client_with_proxy, choose_format, get_html, get_video_id, get_video_id_with_domains,
+    web_headers, with_request_middlewares,
+};
+use crate::Video;
+
+/// Called on every [`RecordEvent`] of [`Recorder::schedule`]
+pub type RecordEventCallback = Arc<dyn Fn(&RecordEvent) + Send + Sync>;
+
+/// Lifecycle of a scheduled recording
+#[derive(Clone, Debug, PartialEq)]
+pub enum RecordEvent {
+    /// Nothing to record yet, the video id is `None` while a channel is not live.
+    /// Checked again at `scheduled_start` minus [`RecordOptions::start_early`] if the start is known
+    Waiting {
+        video_id: Option<String>,
+        scheduled_start: Option<SystemTime>,
+    },
+    /// Stream went live and the recording started
+    Started { video_id: String },
+    /// Recording is written
+    Finished {
+        video_id: String,
+        result: DownloadResult,
+    },
+}
+
+/// Options of [`Recorder::schedule`]
+#[derive(Clone, derive_more::Display, derivative::Derivative)]
+#[display(fmt = "RecordOptions()")]
+#[derivative(Debug)]
+pub struct RecordOptions {
+    /// Check this long before the scheduled start, default 60 seconds
+    pub start_early: Duration,
+    /// Stop recording after this long, `None` (default) records until the stream ends
+    pub max_duration: Option<Duration>,
+    /// Output file, `{video_id}.{extension}` in the working directory if empty
+    pub output: PathBuf,
+    /// Interval between checks of streams without a known start, default 30 seconds
+    pub poll_interval: Duration,
+    #[derivative(Debug = "ignore")]
+    pub on_event: Option<RecordEventCallback>,
+}
+
+impl Default for RecordOptions {
+    fn default() -> Self {
+        Self {
+            start_early: Duration::from_secs(60),
+            max_duration: None,
+            output: PathBuf::new(),
+            poll_interval: Duration::from_secs(30),
+            on_event: None,
+        }
+    }
+}
+
+/// Records premieres and live streams as soon as they go live
+///
+/// # Example
+/// ```ignore
+///     let recorder = Recorder::new(VideoOptions::default());
+///
+///     let result = recorder
+///         .schedule(
+///             "@channel",
+///             RecordOptions {
+///                 max_duration: Some(std::time::Duration::from_secs(3600)),
+///                 ..Default::default()
+///             },
+///         )
+///         .await
+///         .unwrap();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Recorder {
+    options: VideoOptions,
+}
+
+impl Recorder {
+    pub fn new(options: VideoOptions) -> Self {
+        Self { options }
+    }
+
+    /// Wait until `video_or_channel` is live and record it. Channels are given by handle (`@name`), channel id
+    /// (`UC...`) or URL, their current live stream is recorded
+    pub async fn schedule(
+        &self,
+        video_or_channel: &str,
+        options: RecordOptions,
+    ) -> Result<DownloadResult, VideoError> {
+        let emit = |event: RecordEvent| {
+            if let Some(on_event) = options.on_event.as_ref() {
+                on_event(&event);
+            }
+        };
+
+        let video = loop {
+            let video = match self.resolve(video_or_channel).await? {
+                Some(video_id) => Video::new_with_options(video_id, self.options.clone())?,
+                None => {
+                    emit(RecordEvent::Waiting {
+                        video_id: None,
+                        scheduled_start: None,
+                    });
+                    tokio::time::sleep(options.poll_interval).await;
+                    continue;
+                }
+            };
+
+            let scheduled_start = match video.scheduled_start().await? {
+                Some(scheduled_start) => scheduled_start,
+                None => break video,
+            };
+
+            emit(RecordEvent::Waiting {
+                video_id: Some(video.get_video_id()),
+                scheduled_start: Some(scheduled_start),
+            });
+
+            let wake = scheduled_start
+                .checked_sub(options.start_early)
+                .and_then(|x| x.duration_since(SystemTime::now()).ok())
+                .unwrap_or_default();
+            tokio::time::sleep(wake.max(options.poll_interval)).await;
+        };
+
+        let video_id = video.get_video_id();
+        let info = video.get_info().await?;
+        let format = choose_format(&info.formats, &self.options)
+            .map_err(|_op| VideoError::VideoSourceNotFound)?;
+
+        let path = if options.output.as_os_str().is_empty() {
+            PathBuf::from(format!("{video_id}.{}", file_extension(&format)))
+        } else {
+            options.output.clone()
+        };
+
+        emit(RecordEvent::Started {
+            video_id: video_id.clone(),
+        });
+
+        let deadline = options.max_duration.map(|x| Instant::now() + x);
+        let result = video
+            .download_format_to_file_until(&format, &path, deadline)
+            .await?;
+
+        emit(RecordEvent::Finished {
+            video_id,
+            result: result.clone(),
+        });
+
+        Ok(result)
+    }
+
+    /// Video id of a video target or the current live stream of a channel target, `None` if the channel is not live
+    async fn resolve(&self, target: &str) -> Result<Option<String>, VideoError> {
+        let request_options = &self.options.request_options;
+        let config = &request_options.config;
+
+        if let Some(video_id) = get_video_id_with_domains(target, &config.valid_query_domains) {
+            return Ok(Some(video_id));
+        }
+
+        let target = target.trim().trim_end_matches('/');
+        let url = if target.starts_with("http") {
+            format!("{target}/live")
+        } else if target.starts_with('@') {
+            config.url(&format!("/{target}/live"))
+        } else if target.starts_with("UC") {
+            config.url(&format!("/channel/{target}/live"))
+        } else {
+            return Err(VideoError::VideoNotFound);
+        };
+
+        let client = build_client_with_proxy(request_options, request_options.info_proxy.as_ref())?
+            .build()
+            .map_err(VideoError::Reqwest)?;
+        let client = with_request_middlewares(
+            reqwest_middleware::ClientBuilder::new(client),
+            request_options,
+            request_options.info_proxy.as_ref(),
+            None,
+        )?
+        .build();
+
+        let html = get_html(&client, url, Some(&web_headers())).await?;
+
+        // Live pages of channels link the live stream, or the channel itself while offline
+        let canonical = html
+            .split("<link rel=\"canonical\" href=\"")
+            .nth(1)
+            .and_then(|x| x.split('"').next())
+            .unwrap_or_default();
+
+        Ok(get_video_id(canonical))
+    }
+}
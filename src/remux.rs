@@ -0,0 +1,876 @@
+use std::collections::HashMap;
+
+use bytes::Bytes;
+
+use crate::structs::VideoError;
+use crate::transcode::Transcoder;
+
+const TS_PACKET_SIZE: usize = 188;
+const TS_SYNC_BYTE: u8 = 0x47;
+const STREAM_TYPE_AAC: u8 = 0x0F;
+const STREAM_TYPE_H264: u8 = 0x1B;
+
+/// Timescale of MPEG-TS timestamps and of the video track
+const TS_TIMESCALE: u64 = 90_000;
+/// PCM samples in one AAC frame
+const AAC_FRAME_SAMPLES: u32 = 1024;
+const AAC_SAMPLE_RATES: [u32; 13] = [
+    96000, 88200, 64000, 48000, 44100, 32000, 24000, 22050, 16000, 12000, 11025, 8000, 7350,
+];
+/// Audio only streams are cut into fragments of this many frames
+const AUDIO_FRAGMENT_FRAMES: usize = 50;
+
+const VIDEO_TRACK_ID: u32 = 1;
+const AUDIO_TRACK_ID: u32 = 2;
+
+struct Sample {
+    data: Vec<u8>,
+    /// Decode time in the track timescale, from the first keyframe
+    dts: u64,
+    /// Presentation time minus decode time
+    cts_offset: i32,
+    keyframe: bool,
+}
+
+#[derive(Default)]
+struct VideoTrack {
+    sps: Option<Vec<u8>>,
+    pps: Option<Vec<u8>>,
+    samples: Vec<Sample>,
+    last_duration: u32,
+}
+
+#[derive(Default)]
+struct AudioTrack {
+    /// AudioSpecificConfig of the first ADTS header
+    config: Option<[u8; 2]>,
+    sample_rate: u32,
+    channels: u16,
+    samples: Vec<Sample>,
+    /// Decode time of the next frame in samples, frames of one PES follow each other
+    next_dts: Option<u64>,
+}
+
+struct Pes {
+    stream_type: u8,
+    data: Vec<u8>,
+}
+
+/// Remuxer of MPEG-TS (H.264 and AAC, as in Youtube HLS segments) into fragmented MP4.
+/// Input can be cut anywhere, output starts with `ftyp`/`moov` and continues with one `moof`/`mdat` per GOP
+///
+/// # Example
+/// ```ignore
+///     let mut remuxer = TsRemuxer::new();
+///     let mut mp4 = vec![];
+///
+///     while let Some(chunk) = stream.chunk().await.unwrap() {
+///         mp4.extend_from_slice(&remuxer.push(&chunk).unwrap());
+///     }
+///     mp4.extend_from_slice(&remuxer.finish().unwrap());
+/// ```
+#[derive(Default)]
+pub struct TsRemuxer {
+    buffer: Vec<u8>,
+    pmt_pid: Option<u16>,
+    streams: HashMap<u16, Pes>,
+    has_video: bool,
+    has_audio: bool,
+    video: VideoTrack,
+    audio: AudioTrack,
+    /// DTS of the first keyframe (first audio frame without video) in 90kHz, start of both tracks
+    base_dts: Option<u64>,
+    last_dts: u64,
+    init_written: bool,
+    sequence_number: u32,
+}
+
+impl TsRemuxer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remux the next bytes of the TS stream, output is empty until a fragment is complete
+    pub fn push(&mut self, bytes: &[u8]) -> Result<Bytes, VideoError> {
+        self.buffer.extend_from_slice(bytes);
+        let mut output = vec![];
+        let mut pos = 0;
+
+        while pos + TS_PACKET_SIZE <= self.buffer.len() {
+            if self.buffer[pos] != TS_SYNC_BYTE {
+                pos += 1;
+                continue;
+            }
+
+            let packet = self.buffer[pos..pos + TS_PACKET_SIZE].to_vec();
+            self.parse_packet(&packet, &mut output)?;
+            pos += TS_PACKET_SIZE;
+        }
+
+        self.buffer.drain(..pos);
+
+        Ok(output.into())
+    }
+
+    /// Remux buffered samples after the last bytes of the TS stream
+    pub fn finish(&mut self) -> Result<Bytes, VideoError> {
+        let mut output = vec![];
+
+        let pids = self.streams.keys().copied().collect::<Vec<u16>>();
+        for pid in pids {
+            self.flush_pes(pid, &mut output)?;
+        }
+
+        if !self.has_video && !self.has_audio {
+            return Err(VideoError::RemuxError(
+                "No H.264 or AAC stream found".to_string(),
+            ));
+        }
+
+        self.write_fragment(&mut output, None);
+
+        Ok(output.into())
+    }
+
+    fn parse_packet(&mut self, packet: &[u8], output: &mut Vec<u8>) -> Result<(), VideoError> {
+        let pid = (((packet[1] & 0x1F) as u16) << 8) | packet[2] as u16;
+        let payload_start = packet[1] & 0x40 != 0;
+        let adaptation_field = (packet[3] >> 4) & 0x03;
+
+        if adaptation_field & 0x01 == 0 {
+            return Ok(());
+        }
+
+        let offset = match adaptation_field & 0x02 {
+            0 => 4,
+            _ => 5 + packet[4] as usize,
+        };
+        let payload = match packet.get(offset..) {
+            Some(payload) => payload,
+            None => return Ok(()),
+        };
+
+        if pid == 0 {
+            if payload_start {
+                self.parse_pat(payload);
+            }
+        } else if Some(pid) == self.pmt_pid {
+            if payload_start {
+                self.parse_pmt(payload);
+            }
+        } else if self.streams.contains_key(&pid) {
+            if payload_start {
+                self.flush_pes(pid, output)?;
+            }
+
+            if let Some(pes) = self.streams.get_mut(&pid) {
+                pes.data.extend_from_slice(payload);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Section of a PSI table, without pointer field and CRC
+    fn section(payload: &[u8]) -> Option<&[u8]> {
+        let start = 1 + *payload.first()? as usize;
+        let section = payload.get(start..)?;
+        let length = ((((*section.get(1)? & 0x0F) as usize) << 8) | *section.get(2)? as usize) + 3;
+
+        section.get(..length.checked_sub(4)?)
+    }
+
+    fn parse_pat(&mut self, payload: &[u8]) {
+        let section = match Self::section(payload) {
+            Some(section) if section.len() > 8 => section,
+            _ => return,
+        };
+
+        self.pmt_pid = section[8..]
+            .chunks_exact(4)
+            .find(|x| x[0] != 0 || x[1] != 0)
+            .map(|x| (((x[2] & 0x1F) as u16) << 8) | x[3] as u16)
+            .or(self.pmt_pid);
+    }
+
+    fn parse_pmt(&mut self, payload: &[u8]) {
+        let section = match Self::section(payload) {
+            Some(section) if section.len() > 12 => section,
+            _ => return,
+        };
+
+        let program_info_length = (((section[10] & 0x0F) as usize) << 8) | section[11] as usize;
+        let mut pos = 12 + program_info_length;
+
+        while pos + 5 <= section.len() {
+            let stream_type = section[pos];
+            let pid = (((section[pos + 1] & 0x1F) as u16) << 8) | section[pos + 2] as u16;
+            let es_info_length =
+                (((section[pos + 3] & 0x0F) as usize) << 8) | section[pos + 4] as usize;
+            pos += 5 + es_info_length;
+
+            // First stream of each kind is remuxed
+            let wanted = match stream_type {
+                STREAM_TYPE_H264 => !std::mem::replace(&mut self.has_video, true),
+                STREAM_TYPE_AAC => !std::mem::replace(&mut self.has_audio, true),
+                _ => false,
+            };
+
+            if wanted {
+                self.streams.insert(
+                    pid,
+                    Pes {
+                        stream_type,
+                        data: vec![],
+                    },
+                );
+            }
+        }
+    }
+
+    fn flush_pes(&mut self, pid: u16, output: &mut Vec<u8>) -> Result<(), VideoError> {
+        let (stream_type, data) = match self.streams.get_mut(&pid) {
+            Some(pes) if !pes.data.is_empty() => (pes.stream_type, std::mem::take(&mut pes.data)),
+            _ => return Ok(()),
+        };
+
+        // Start code and stream id, length and flags
+        if data.len() < 9 || data[0..3] != [0, 0, 1] {
+            return Ok(());
+        }
+
+        let header_length = data[8] as usize;
+        let pts_dts_flags = data[7] >> 6;
+        let pts = match pts_dts_flags & 0x02 {
+            0 => None,
+            _ => data.get(9..14).map(read_timestamp),
+        };
+        let dts = match pts_dts_flags {
+            0x03 => data.get(14..19).map(read_timestamp),
+            _ => pts,
+        };
+        let payload = match data.get(9 + header_length..) {
+            Some(payload) => payload,
+            None => return Ok(()),
+        };
+
+        let (pts, dts) = match (pts, dts) {
+            (Some(pts), Some(dts)) => {
+                let dts = unwrap_timestamp(dts, self.last_dts);
+                self.last_dts = dts;
+                (unwrap_timestamp(pts, dts), dts)
+            }
+            _ => return Ok(()),
+        };
+
+        match stream_type {
+            STREAM_TYPE_H264 => self.push_video(payload, pts, dts, output),
+            _ => self.push_audio(payload, pts, output),
+        }
+
+        Ok(())
+    }
+
+    fn push_video(&mut self, payload: &[u8], pts: u64, dts: u64, output: &mut Vec<u8>) {
+        let mut data = vec![];
+        let mut keyframe = false;
+
+        for nal in split_nal_units(payload) {
+            match nal[0] & 0x1F {
+                // Parameter sets go to `avcC`, access unit delimiters are dropped
+                7 => self.video.sps = Some(nal.to_vec()),
+                8 => self.video.pps = Some(nal.to_vec()),
+                9 => {}
+                nal_type => {
+                    keyframe |= nal_type == 5;
+                    data.extend_from_slice(&(nal.len() as u32).to_be_bytes());
+                    data.extend_from_slice(nal);
+                }
+            }
+        }
+
+        if data.is_empty() {
+            return;
+        }
+
+        let base_dts = match self.base_dts {
+            Some(base_dts) => base_dts,
+            // Samples before the first keyframe can't be decoded
+            None if !keyframe => return,
+            None => *self.base_dts.insert(dts),
+        };
+
+        if dts < base_dts {
+            return;
+        }
+
+        if keyframe {
+            self.write_fragment(output, Some(dts - base_dts));
+        }
+
+        self.video.samples.push(Sample {
+            data,
+            dts: dts - base_dts,
+            cts_offset: (pts as i64 - dts as i64) as i32,
+            keyframe,
+        });
+    }
+
+    fn push_audio(&mut self, payload: &[u8], pts: u64, output: &mut Vec<u8>) {
+        let mut pos = 0;
+        let mut frame_pts = pts;
+
+        while pos + 7 <= payload.len() {
+            let header = &payload[pos..];
+            if header[0] != 0xFF || header[1] & 0xF0 != 0xF0 {
+                pos += 1;
+                continue;
+            }
+
+            let header_length = if header[1] & 0x01 == 0 { 9 } else { 7 };
+            let object_type = (header[2] >> 6) + 1;
+            let sample_rate_index = (header[2] >> 2) & 0x0F;
+            let channels = ((header[2] & 0x01) << 2) | (header[3] >> 6);
+            let frame_length = (((header[3] & 0x03) as usize) << 11)
+                | ((header[4] as usize) << 3)
+                | (header[5] as usize >> 5);
+
+            let sample_rate = match AAC_SAMPLE_RATES.get(sample_rate_index as usize) {
+                Some(sample_rate) => *sample_rate,
+                None => break,
+            };
+            let frame = match payload.get(pos + header_length..pos + frame_length) {
+                Some(frame) if frame_length > header_length => frame,
+                _ => break,
+            };
+            pos += frame_length;
+
+            if self.audio.config.is_none() {
+                self.audio.config = Some([
+                    (object_type << 3) | (sample_rate_index >> 1),
+                    ((sample_rate_index & 0x01) << 7) | (channels << 3),
+                ]);
+                self.audio.sample_rate = sample_rate;
+                self.audio.channels = channels as u16;
+            }
+
+            let base_dts = match (self.base_dts, self.has_video) {
+                (Some(base_dts), _) => base_dts,
+                (None, false) => *self.base_dts.insert(frame_pts),
+                // Audio before the first video keyframe is dropped
+                (None, true) => continue,
+            };
+
+            if frame_pts < base_dts {
+                frame_pts += AAC_FRAME_SAMPLES as u64 * TS_TIMESCALE / sample_rate as u64;
+                continue;
+            }
+
+            let dts = self
+                .audio
+                .next_dts
+                .unwrap_or((frame_pts - base_dts) * self.audio.sample_rate as u64 / TS_TIMESCALE);
+            self.audio.next_dts = Some(dts + AAC_FRAME_SAMPLES as u64);
+            frame_pts += AAC_FRAME_SAMPLES as u64 * TS_TIMESCALE / sample_rate as u64;
+
+            self.audio.samples.push(Sample {
+                data: frame.to_vec(),
+                dts,
+                cts_offset: 0,
+                keyframe: true,
+            });
+        }
+
+        if !self.has_video && self.audio.samples.len() >= AUDIO_FRAGMENT_FRAMES {
+            self.write_fragment(output, None);
+        }
+    }
+
+    /// Write buffered samples as one fragment, `next_video_dts` is the decode time after the last video sample.
+    /// Without it the last video sample keeps the previous duration
+    fn write_fragment(&mut self, output: &mut Vec<u8>, next_video_dts: Option<u64>) {
+        if !self.init_written {
+            match self.init_segment() {
+                Some(init) => output.extend_from_slice(&init),
+                None => return,
+            }
+            self.init_written = true;
+        }
+
+        let video_samples = std::mem::take(&mut self.video.samples);
+        let audio_samples = std::mem::take(&mut self.audio.samples);
+
+        let video_durations = durations(&video_samples, next_video_dts, self.video.last_duration);
+        if let Some(duration) = video_durations.last() {
+            self.video.last_duration = *duration;
+        }
+        let audio_durations = vec![AAC_FRAME_SAMPLES; audio_samples.len()];
+
+        let mut tracks = vec![];
+        if !video_samples.is_empty() {
+            tracks.push((VIDEO_TRACK_ID, &video_samples, &video_durations));
+        }
+        if !audio_samples.is_empty() {
+            tracks.push((AUDIO_TRACK_ID, &audio_samples, &audio_durations));
+        }
+        if tracks.is_empty() {
+            return;
+        }
+
+        self.sequence_number += 1;
+
+        let build_moof = |data_offset: u32| {
+            let mut offset = data_offset;
+            let mut moof = full_box(b"mfhd", 0, 0, &self.sequence_number.to_be_bytes());
+
+            for (track_id, samples, durations) in tracks.iter() {
+                moof.extend(traf(*track_id, samples, durations, offset));
+                offset += samples.iter().map(|x| x.data.len() as u32).sum::<u32>();
+            }
+
+            mp4_box(b"moof", &moof)
+        };
+
+        let moof_length = build_moof(0).len() as u32;
+        output.extend(build_moof(moof_length + 8));
+
+        let mdat = tracks
+            .iter()
+            .flat_map(|x| x.1.iter())
+            .flat_map(|x| x.data.iter().copied())
+            .collect::<Vec<u8>>();
+        output.extend(mp4_box(b"mdat", &mdat));
+    }
+
+    /// `ftyp` and `moov`, [`None`] until the codec configuration of every track is known
+    fn init_segment(&self) -> Option<Vec<u8>> {
+        let mut traks = vec![];
+        let mut trexs = vec![];
+
+        if self.has_video {
+            let sps = self.video.sps.as_ref()?;
+            let pps = self.video.pps.as_ref()?;
+            let (width, height) = parse_sps_dimensions(sps).unwrap_or((0, 0));
+
+            let mut avcc = vec![1, sps[1], sps[2], sps[3], 0xFF, 0xE1];
+            avcc.extend_from_slice(&(sps.len() as u16).to_be_bytes());
+            avcc.extend_from_slice(sps);
+            avcc.push(1);
+            avcc.extend_from_slice(&(pps.len() as u16).to_be_bytes());
+            avcc.extend_from_slice(pps);
+
+            let mut avc1 = vec![0; 6];
+            avc1.extend_from_slice(&1u16.to_be_bytes());
+            avc1.extend_from_slice(&[0; 16]);
+            avc1.extend_from_slice(&(width as u16).to_be_bytes());
+            avc1.extend_from_slice(&(height as u16).to_be_bytes());
+            avc1.extend_from_slice(&0x0048_0000u32.to_be_bytes());
+            avc1.extend_from_slice(&0x0048_0000u32.to_be_bytes());
+            avc1.extend_from_slice(&[0; 4]);
+            avc1.extend_from_slice(&1u16.to_be_bytes());
+            avc1.extend_from_slice(&[0; 32]);
+            avc1.extend_from_slice(&[0x00, 0x18, 0xFF, 0xFF]);
+            avc1.extend(mp4_box(b"avcC", &avcc));
+
+            traks.extend(trak(
+                VIDEO_TRACK_ID,
+                TS_TIMESCALE as u32,
+                (width, height),
+                mp4_box(b"avc1", &avc1),
+            ));
+            trexs.extend(trex(VIDEO_TRACK_ID));
+        }
+
+        if self.has_audio {
+            let config = self.audio.config?;
+
+            let mut decoder_specific_info = vec![0x05, config.len() as u8];
+            decoder_specific_info.extend_from_slice(&config);
+
+            let mut decoder_config = vec![0x40, 0x15, 0, 0, 0];
+            decoder_config.extend_from_slice(&[0; 8]);
+            decoder_config.extend(decoder_specific_info);
+
+            let mut es_descriptor = (AUDIO_TRACK_ID as u16).to_be_bytes().to_vec();
+            es_descriptor.push(0);
+            es_descriptor.extend_from_slice(&[0x04, decoder_config.len() as u8]);
+            es_descriptor.extend(decoder_config);
+            es_descriptor.extend_from_slice(&[0x06, 0x01, 0x02]);
+
+            let mut esds = vec![0x03, es_descriptor.len() as u8];
+            esds.extend(es_descriptor);
+
+            let mut mp4a = vec![0; 6];
+            mp4a.extend_from_slice(&1u16.to_be_bytes());
+            mp4a.extend_from_slice(&[0; 8]);
+            mp4a.extend_from_slice(&self.audio.channels.to_be_bytes());
+            mp4a.extend_from_slice(&16u16.to_be_bytes());
+            mp4a.extend_from_slice(&[0; 4]);
+            mp4a.extend_from_slice(&(self.audio.sample_rate << 16).to_be_bytes());
+            mp4a.extend(full_box(b"esds", 0, 0, &esds));
+
+            traks.extend(trak(
+                AUDIO_TRACK_ID,
+                self.audio.sample_rate,
+                (0, 0),
+                mp4_box(b"mp4a", &mp4a),
+            ));
+            trexs.extend(trex(AUDIO_TRACK_ID));
+        }
+
+        let mut ftyp = b"isom".to_vec();
+        ftyp.extend_from_slice(&0x200u32.to_be_bytes());
+        ftyp.extend_from_slice(b"isomiso6avc1mp41");
+
+        let mut mvhd = vec![0; 8];
+        mvhd.extend_from_slice(&1000u32.to_be_bytes());
+        mvhd.extend_from_slice(&[0; 4]);
+        mvhd.extend_from_slice(&0x0001_0000u32.to_be_bytes());
+        mvhd.extend_from_slice(&0x0100u16.to_be_bytes());
+        mvhd.extend_from_slice(&[0; 10]);
+        mvhd.extend_from_slice(&MATRIX);
+        mvhd.extend_from_slice(&[0; 24]);
+        mvhd.extend_from_slice(&(AUDIO_TRACK_ID + 1).to_be_bytes());
+
+        let mut moov = full_box(b"mvhd", 0, 0, &mvhd);
+        moov.extend(traks);
+        moov.extend(mp4_box(b"mvex", &trexs));
+
+        let mut init = mp4_box(b"ftyp", &ftyp);
+        init.extend(mp4_box(b"moov", &moov));
+
+        Some(init)
+    }
+}
+
+impl Transcoder for TsRemuxer {
+    fn transcode(&mut self, chunk: &[u8]) -> Result<Bytes, VideoError> {
+        self.push(chunk)
+    }
+
+    fn finish(&mut self) -> Result<Bytes, VideoError> {
+        TsRemuxer::finish(self)
+    }
+
+    fn content_type(&self) -> Option<&'static str> {
+        Some("video/mp4")
+    }
+}
+
+const MATRIX: [u8; 36] = [
+    0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0x40, 0, 0, 0,
+];
+
+fn mp4_box(kind: &[u8; 4], data: &[u8]) -> Vec<u8> {
+    let mut mp4_box = ((data.len() + 8) as u32).to_be_bytes().to_vec();
+    mp4_box.extend_from_slice(kind);
+    mp4_box.extend_from_slice(data);
+    mp4_box
+}
+
+fn full_box(kind: &[u8; 4], version: u8, flags: u32, data: &[u8]) -> Vec<u8> {
+    let mut full_box = ((version as u32) << 24 | flags).to_be_bytes().to_vec();
+    full_box.extend_from_slice(data);
+    mp4_box(kind, &full_box)
+}
+
+fn trak(
+    track_id: u32,
+    timescale: u32,
+    (width, height): (u32, u32),
+    sample_entry: Vec<u8>,
+) -> Vec<u8> {
+    let video = track_id == VIDEO_TRACK_ID;
+
+    let mut tkhd = vec![0; 8];
+    tkhd.extend_from_slice(&track_id.to_be_bytes());
+    tkhd.extend_from_slice(&[0; 16]);
+    tkhd.extend_from_slice(&if video { 0u16 } else { 0x0100 }.to_be_bytes());
+    tkhd.extend_from_slice(&[0; 2]);
+    tkhd.extend_from_slice(&MATRIX);
+    tkhd.extend_from_slice(&(width << 16).to_be_bytes());
+    tkhd.extend_from_slice(&(height << 16).to_be_bytes());
+
+    let mut mdhd = vec![0; 8];
+    mdhd.extend_from_slice(&timescale.to_be_bytes());
+    mdhd.extend_from_slice(&[0; 4]);
+    mdhd.extend_from_slice(&[0x55, 0xC4, 0, 0]);
+
+    let mut hdlr = vec![0; 4];
+    hdlr.extend_from_slice(if video { b"vide" } else { b"soun" });
+    hdlr.extend_from_slice(&[0; 12]);
+    hdlr.extend_from_slice(if video {
+        b"VideoHandler\0"
+    } else {
+        b"SoundHandler\0"
+    });
+
+    let media_header = match video {
+        true => full_box(b"vmhd", 0, 1, &[0; 8]),
+        false => full_box(b"smhd", 0, 0, &[0; 4]),
+    };
+
+    let mut dref = 1u32.to_be_bytes().to_vec();
+    dref.extend(full_box(b"url ", 0, 1, &[]));
+
+    let mut stsd = 1u32.to_be_bytes().to_vec();
+    stsd.extend(sample_entry);
+
+    let mut stbl = full_box(b"stsd", 0, 0, &stsd);
+    stbl.extend(full_box(b"stts", 0, 0, &[0; 4]));
+    stbl.extend(full_box(b"stsc", 0, 0, &[0; 4]));
+    stbl.extend(full_box(b"stsz", 0, 0, &[0; 8]));
+    stbl.extend(full_box(b"stco", 0, 0, &[0; 4]));
+
+    let mut minf = media_header;
+    minf.extend(mp4_box(b"dinf", &full_box(b"dref", 0, 0, &dref)));
+    minf.extend(mp4_box(b"stbl", &stbl));
+
+    let mut mdia = full_box(b"mdhd", 0, 0, &mdhd);
+    mdia.extend(full_box(b"hdlr", 0, 0, &hdlr));
+    mdia.extend(mp4_box(b"minf", &minf));
+
+    let mut trak = full_box(b"tkhd", 0, 0x03, &tkhd);
+    trak.extend(mp4_box(b"mdia", &mdia));
+
+    mp4_box(b"trak", &trak)
+}
+
+fn trex(track_id: u32) -> Vec<u8> {
+    let mut trex = track_id.to_be_bytes().to_vec();
+    trex.extend_from_slice(&1u32.to_be_bytes());
+    trex.extend_from_slice(&[0; 12]);
+
+    full_box(b"trex", 0, 0, &trex)
+}
+
+fn traf(track_id: u32, samples: &[Sample], durations: &[u32], data_offset: u32) -> Vec<u8> {
+    // default-base-is-moof
+    let tfhd = full_box(b"tfhd", 0, 0x02_0000, &track_id.to_be_bytes());
+    let tfdt = full_box(b"tfdt", 1, 0, &samples[0].dts.to_be_bytes());
+
+    let mut trun = (samples.len() as u32).to_be_bytes().to_vec();
+    trun.extend_from_slice(&data_offset.to_be_bytes());
+    for (sample, duration) in samples.iter().zip(durations) {
+        trun.extend_from_slice(&duration.to_be_bytes());
+        trun.extend_from_slice(&(sample.data.len() as u32).to_be_bytes());
+        trun.extend_from_slice(
+            &if sample.keyframe {
+                0x0200_0000u32
+            } else {
+                0x0101_0000
+            }
+            .to_be_bytes(),
+        );
+        trun.extend_from_slice(&sample.cts_offset.to_be_bytes());
+    }
+
+    // data offset, duration, size, flags and composition time offset of every sample
+    let mut traf = tfhd;
+    traf.extend(tfdt);
+    traf.extend(full_box(b"trun", 1, 0x0F01, &trun));
+
+    mp4_box(b"traf", &traf)
+}
+
+/// Sample durations from the decode times, the last one up to `next_dts` or `last_duration`
+fn durations(samples: &[Sample], next_dts: Option<u64>, last_duration: u32) -> Vec<u32> {
+    let mut durations = samples
+        .windows(2)
+        .map(|x| x[1].dts.saturating_sub(x[0].dts) as u32)
+        .collect::<Vec<u32>>();
+
+    if let Some(last) = samples.last() {
+        let duration = match next_dts {
+            Some(next_dts) => next_dts.saturating_sub(last.dts) as u32,
+            None => durations.last().copied().unwrap_or(last_duration),
+        };
+        durations.push(duration);
+    }
+
+    durations
+}
+
+/// 33 bit PES timestamp
+fn read_timestamp(bytes: &[u8]) -> u64 {
+    (((bytes[0] >> 1) & 0x07) as u64) << 30
+        | (bytes[1] as u64) << 22
+        | ((bytes[2] >> 1) as u64) << 15
+        | (bytes[3] as u64) << 7
+        | (bytes[4] >> 1) as u64
+}
+
+/// Timestamp after the 33 bit wrap around closest to `reference`
+fn unwrap_timestamp(timestamp: u64, reference: u64) -> u64 {
+    let mut timestamp = timestamp;
+
+    while timestamp + (1 << 32) < reference {
+        timestamp += 1 << 33;
+    }
+
+    timestamp
+}
+
+/// NAL units of an Annex B byte stream, without start codes
+fn split_nal_units(bytes: &[u8]) -> Vec<&[u8]> {
+    let mut starts = vec![];
+    let mut pos = 0;
+
+    while pos + 3 <= bytes.len() {
+        if bytes[pos..pos + 3] == [0, 0, 1] {
+            starts.push(pos + 3);
+            pos += 3;
+        } else {
+            pos += 1;
+        }
+    }
+
+    starts
+        .iter()
+        .enumerate()
+        .map(|(index, start)| {
+            let mut end = starts.get(index + 1).map(|x| x - 3).unwrap_or(bytes.len());
+            // 4 byte start codes leave a zero byte at the end of the previous unit
+            while end > *start && bytes[end - 1] == 0 {
+                end -= 1;
+            }
+            &bytes[*start..end]
+        })
+        .filter(|x| !x.is_empty())
+        .collect()
+}
+
+/// Exp-Golomb reader over an SPS without emulation prevention bytes
+struct BitReader {
+    bytes: Vec<u8>,
+    pos: usize,
+}
+
+impl BitReader {
+    fn bit(&mut self) -> Option<u32> {
+        let byte = *self.bytes.get(self.pos / 8)?;
+        let bit = (byte >> (7 - self.pos % 8)) & 0x01;
+        self.pos += 1;
+        Some(bit as u32)
+    }
+
+    fn bits(&mut self, count: usize) -> Option<u32> {
+        (0..count).try_fold(0, |acc, _| Some((acc << 1) | self.bit()?))
+    }
+
+    fn ue(&mut self) -> Option<u32> {
+        let mut zeros = 0;
+        while self.bit()? == 0 {
+            zeros += 1;
+            if zeros > 31 {
+                return None;
+            }
+        }
+
+        Some((1u32 << zeros) - 1 + self.bits(zeros)?)
+    }
+
+    fn se(&mut self) -> Option<i32> {
+        let value = self.ue()? as i64;
+
+        Some(if value % 2 == 1 {
+            (value + 1) / 2
+        } else {
+            -(value / 2)
+        } as i32)
+    }
+}
+
+/// Width and height of an H.264 SPS NAL unit
+fn parse_sps_dimensions(sps: &[u8]) -> Option<(u32, u32)> {
+    let mut bytes = Vec::with_capacity(sps.len());
+    for (index, byte) in sps.iter().enumerate().skip(1) {
+        // Emulation prevention byte after two zero bytes
+        if *byte == 3 && index >= 3 && sps[index - 1] == 0 && sps[index - 2] == 0 {
+            continue;
+        }
+        bytes.push(*byte);
+    }
+
+    let mut reader = BitReader { bytes, pos: 0 };
+    let profile_idc = reader.bits(8)?;
+    reader.bits(16)?;
+    reader.ue()?;
+
+    let mut chroma_format_idc = 1;
+    if [100, 110, 122, 244, 44, 83, 86, 118, 128, 138, 139, 134, 135].contains(&profile_idc) {
+        chroma_format_idc = reader.ue()?;
+        if chroma_format_idc == 3 {
+            reader.bit()?;
+        }
+        reader.ue()?;
+        reader.ue()?;
+        reader.bit()?;
+
+        if reader.bit()? == 1 {
+            let lists = if chroma_format_idc == 3 { 12 } else { 8 };
+            for index in 0..lists {
+                if reader.bit()? == 1 {
+                    let size = if index < 6 { 16 } else { 64 };
+                    let (mut last_scale, mut next_scale) = (8i32, 8i32);
+                    for _ in 0..size {
+                        if next_scale != 0 {
+                            next_scale = (last_scale + reader.se()? + 256) % 256;
+                        }
+                        if next_scale != 0 {
+                            last_scale = next_scale;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    reader.ue()?;
+    match reader.ue()? {
+        0 => {
+            reader.ue()?;
+        }
+        1 => {
+            reader.bit()?;
+            reader.se()?;
+            reader.se()?;
+            for _ in 0..reader.ue()? {
+                reader.se()?;
+            }
+        }
+        _ => {}
+    }
+
+    reader.ue()?;
+    reader.bit()?;
+    let width_in_mbs = reader.ue()? + 1;
+    let height_in_map_units = reader.ue()? + 1;
+    let frame_mbs_only = reader.bit()?;
+    if frame_mbs_only == 0 {
+        reader.bit()?;
+    }
+    reader.bit()?;
+
+    let (mut crop_left, mut crop_right, mut crop_top, mut crop_bottom) = (0, 0, 0, 0);
+    if reader.bit()? == 1 {
+        crop_left = reader.ue()?;
+        crop_right = reader.ue()?;
+        crop_top = reader.ue()?;
+        crop_bottom = reader.ue()?;
+    }
+
+    let (sub_width, sub_height) = match chroma_format_idc {
+        1 => (2, 2),
+        2 => (2, 1),
+        _ => (1, 1),
+    };
+    let crop_unit_y = sub_height * (2 - frame_mbs_only);
+
+    let width = (width_in_mbs * 16).checked_sub((crop_left + crop_right) * sub_width)?;
+    let height = ((2 - frame_mbs_only) * height_in_map_units * 16)
+        .checked_sub((crop_top + crop_bottom) * crop_unit_y)?;
+
+    Some((width, height))
+}
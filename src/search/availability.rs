@@ -0,0 +1,205 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::constants::AVAILABILITY_CHECK_CONCURRENCY;
+use crate::extractor::InnertubeExtractor;
+use crate::structs::{Config, InnertubeConfig, VideoError};
+use crate::utils::{get_text, get_video_id_with_domains, innertube_config};
+use crate::TaskGroup;
+
+use super::YouTube;
+
+/// Status of a video in an [`AvailabilityReport`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Availability {
+    /// Playable without signing in, upcoming streams and premieres too
+    Public,
+    Private,
+    /// Removed by the uploader or YouTube, or never existed
+    Deleted,
+    /// Requires a signed in adult account
+    AgeRestricted,
+    /// Not available in the country of the request
+    GeoBlocked,
+    /// Not a video id or URL
+    Invalid,
+    /// Anything else, like members-only videos, rentals, bot checks and failed requests.
+    /// See [`VideoAvailability::reason`]
+    Unknown,
+}
+
+/// One checked video of an [`AvailabilityReport`]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VideoAvailability {
+    /// Id of the video, the input for [`Availability::Invalid`]
+    pub id: String,
+    pub status: Availability,
+    /// Reason shown by YouTube or the error of the request
+    pub reason: Option<String>,
+}
+
+/// Result of [`YouTube::check_availability`], videos in input order
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AvailabilityReport {
+    pub videos: Vec<VideoAvailability>,
+}
+
+impl AvailabilityReport {
+    /// Videos which can't be watched without signing in
+    pub fn unavailable(&self) -> impl Iterator<Item = &VideoAvailability> {
+        self.videos
+            .iter()
+            .filter(|x| x.status != Availability::Public)
+    }
+
+    /// Number of videos with `status`
+    pub fn count(&self, status: Availability) -> usize {
+        self.videos.iter().filter(|x| x.status == status).count()
+    }
+}
+
+impl YouTube {
+    /// Status of many videos without full extractions: one innertube `player` request per video, without the watch
+    /// page and player script. Errors are told apart with the oEmbed endpoint, which answers `404` for deleted
+    /// videos. Ids or URLs are checked [`AVAILABILITY_CHECK_CONCURRENCY`] at a time
+    /// # Example
+    /// ```ignore
+    ///     let youtube = YouTube::new().unwrap();
+    ///
+    ///     let report = youtube.check_availability(&["FZ8BxMU3BYc", "xxxxxxxxxxx"]).await;
+    ///
+    ///     for video in report.unavailable() {
+    ///         println!("{} {:?}", video.id, video.status);
+    ///     }
+    /// ```
+    pub async fn check_availability<S: AsRef<str>>(&self, ids: &[S]) -> AvailabilityReport {
+        let innertube = Arc::new(innertube_config(&self.client, &self.config).await);
+        let semaphore = Arc::new(tokio::sync::Semaphore::new(AVAILABILITY_CHECK_CONCURRENCY));
+        let task_group = TaskGroup::shared();
+
+        let tasks = ids
+            .iter()
+            .map(|x| x.as_ref().to_string())
+            .map(|input| {
+                let id = get_video_id_with_domains(&input, &self.config.valid_query_domains);
+                let client = self.client.clone();
+                let config = self.config.clone();
+                let innertube = innertube.clone();
+                let semaphore = semaphore.clone();
+
+                let task = id.clone().map(|id| {
+                    task_group.spawn(async move {
+                        let _permit = semaphore.acquire_owned().await;
+                        check_video(&client, &config, &innertube, &id).await
+                    })
+                });
+
+                (id.unwrap_or(input), task)
+            })
+            .collect::<Vec<_>>();
+
+        let mut report = AvailabilityReport::default();
+        for (id, task) in tasks {
+            let (status, reason) = match task {
+                None => (Availability::Invalid, None),
+                Some(task) => match task.await {
+                    Ok(Some(Ok(result))) => result,
+                    Ok(Some(Err(err))) => (Availability::Unknown, Some(err.to_string())),
+                    Ok(None) => (
+                        Availability::Unknown,
+                        Some(VideoError::Cancelled.to_string()),
+                    ),
+                    Err(err) => (Availability::Unknown, Some(err.to_string())),
+                },
+            };
+
+            report.videos.push(VideoAvailability { id, status, reason });
+        }
+
+        report
+    }
+}
+
+async fn check_video(
+    client: &reqwest_middleware::ClientWithMiddleware,
+    config: &Config,
+    innertube: &InnertubeConfig,
+    video_id: &str,
+) -> Result<(Availability, Option<String>), VideoError> {
+    let extractor = InnertubeExtractor::default();
+    let player_response: serde_json::Value = client
+        .post(config.url(&format!("/youtubei/v1/player?key={}", innertube.api_key)))
+        .headers(extractor.client.header_profile().headers())
+        .json(&extractor.player_request(innertube, video_id, None))
+        .send()
+        .await
+        .map_err(VideoError::ReqwestMiddleware)?
+        .json()
+        .await
+        .map_err(VideoError::Reqwest)?;
+
+    let (status, reason) = classify(&player_response);
+    if let Some(status) = status {
+        return Ok((status, reason));
+    }
+
+    let response = client
+        .get(config.url(&format!(
+            "/oembed?format=json&url={}",
+            urlencoding::encode(&config.watch_url(video_id))
+        )))
+        .send()
+        .await
+        .map_err(VideoError::ReqwestMiddleware)?;
+
+    let status = match response.status().as_u16() {
+        404 => Availability::Deleted,
+        401 | 403 => Availability::Private,
+        _ => Availability::Unknown,
+    };
+
+    Ok((status, reason))
+}
+
+/// Status from the `playabilityStatus` of a player response, `None` for errors the oEmbed endpoint has to tell apart
+fn classify(player_response: &serde_json::Value) -> (Option<Availability>, Option<String>) {
+    let playability = &player_response["playabilityStatus"];
+    let error = &playability["errorScreen"]["playerErrorMessageRenderer"];
+
+    let reason = [
+        playability["reason"].as_str().map(|x| x.to_string()),
+        get_text(&error["reason"]).as_str().map(|x| x.to_string()),
+        get_text(&error["subreason"])
+            .as_str()
+            .map(|x| x.to_string()),
+    ]
+    .into_iter()
+    .flatten()
+    .filter(|x| !x.trim().is_empty())
+    .collect::<Vec<String>>()
+    .join(" ");
+    let lowercase = reason.to_lowercase();
+    let reason = Some(reason).filter(|x| !x.is_empty());
+
+    let status = match playability["status"].as_str().unwrap_or("") {
+        "OK" | "LIVE_STREAM_OFFLINE" => Some(Availability::Public),
+        "AGE_CHECK_REQUIRED" | "AGE_VERIFICATION_REQUIRED" | "CONTENT_CHECK_REQUIRED" => {
+            Some(Availability::AgeRestricted)
+        }
+        "LOGIN_REQUIRED" if lowercase.contains("private") => Some(Availability::Private),
+        "LOGIN_REQUIRED"
+            if ["your age", "age-restricted", "inappropriate"]
+                .iter()
+                .any(|x| lowercase.contains(x)) =>
+        {
+            Some(Availability::AgeRestricted)
+        }
+        "UNPLAYABLE" if lowercase.contains("country") => Some(Availability::GeoBlocked),
+        "ERROR" => None,
+        _ => Some(Availability::Unknown),
+    };
+
+    (status, reason)
+}
@@ -0,0 +1,291 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use crate::archive::DownloadArchive;
+use crate::stream::{BandwidthBudget, DownloadProgress};
+use crate::structs::{DownloadResult, SanitizeProfile, VideoError, VideoOptions};
+use crate::utils::{choose_format, render_output_template_with};
+use crate::TaskGroup;
+
+use super::Playlist;
+
+/// Callback of [`PlaylistDownloadOptions::on_progress`]
+pub type PlaylistProgressCallback = Arc<dyn Fn(&PlaylistProgress) + Send + Sync>;
+
+/// Options of [`Playlist::download_all`]
+#[derive(Clone, derivative::Derivative)]
+#[derivative(Debug)]
+pub struct PlaylistDownloadOptions {
+    /// Format selection and download options of every video
+    pub video_options: VideoOptions,
+    /// File name template, playlist entries also have `{playlist_index}` and `{playlist_title}` keys.
+    /// See [`render_output_template`](crate::render_output_template)
+    pub output_template: String,
+    /// File name sanitization rules
+    pub sanitize_profile: SanitizeProfile,
+    /// Download archive, recorded videos are skipped and downloaded ones are inserted.
+    /// See [`TextFileArchive`](crate::archive::TextFileArchive)
+    #[derivative(Debug = "ignore")]
+    pub archive: Option<Arc<dyn DownloadArchive>>,
+    /// Called after every received chunk of any video and when a video is finished
+    #[derivative(Debug = "ignore")]
+    pub on_progress: Option<PlaylistProgressCallback>,
+    /// Bytes per second shared fairly by all concurrent videos, `None` (default) is unlimited.
+    /// Replaces [`DownloadOptions::bandwidth`](crate::DownloadOptions::bandwidth) of `video_options`
+    pub max_total_bandwidth: Option<u64>,
+}
+
+impl Default for PlaylistDownloadOptions {
+    fn default() -> Self {
+        Self {
+            video_options: VideoOptions::default(),
+            output_template: "{playlist_index} - {title}.{ext}".to_string(),
+            sanitize_profile: SanitizeProfile::default(),
+            archive: None,
+            on_progress: None,
+            max_total_bandwidth: None,
+        }
+    }
+}
+
+/// Aggregate progress of [`Playlist::download_all`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlaylistProgress {
+    /// Number of playlist entries
+    pub total: usize,
+    /// Successfully downloaded videos
+    pub completed: usize,
+    /// Videos skipped because they are in the archive
+    pub skipped: usize,
+    /// Failed videos
+    pub failed: usize,
+    /// Bytes downloaded for all videos so far
+    pub downloaded_bytes: u64,
+    /// Id and progress of the video which triggered the event, `None` if a video is finished
+    pub current: Option<(String, DownloadProgress)>,
+}
+
+/// Result of [`Playlist::download_all`]
+#[derive(Debug, Default)]
+pub struct PlaylistDownloadReport {
+    /// Downloaded files in playlist order
+    pub downloaded: Vec<DownloadResult>,
+    /// Ids of the videos skipped because they are in the archive
+    pub skipped: Vec<String>,
+    /// Ids and errors of failed videos
+    pub failed: Vec<(String, VideoError)>,
+}
+
+/// State shared between concurrent downloads
+struct Shared {
+    progress: Mutex<PlaylistProgress>,
+    on_progress: Option<PlaylistProgressCallback>,
+}
+
+impl Shared {
+    fn update(&self, f: impl FnOnce(&mut PlaylistProgress)) {
+        let progress = match self.progress.lock() {
+            Ok(mut progress) => {
+                f(&mut progress);
+                progress.clone()
+            }
+            Err(_) => return,
+        };
+
+        if let Some(on_progress) = &self.on_progress {
+            on_progress(&progress);
+        }
+    }
+}
+
+impl Playlist {
+    /// Download every fetched video of the playlist into `dir`, at most `concurrency` videos at the same time.
+    /// One at a time with [`RequestOptions::throttled_mode`](crate::RequestOptions::throttled_mode)
+    ///
+    /// A failed video doesn't stop the others, failures are collected in [`PlaylistDownloadReport::failed`]
+    /// # Example
+    /// ```ignore
+    ///     let mut playlist = Playlist::get(url, None).await.unwrap();
+    ///     playlist.fetch(None).await;
+    ///
+    ///     let options = PlaylistDownloadOptions {
+    ///         archive: Some(Arc::new(TextFileArchive::new("archive.txt").unwrap())),
+    ///         ..Default::default()
+    ///     };
+    ///
+    ///     let report = playlist.download_all("videos", &options, 3).await.unwrap();
+    /// ```
+    pub async fn download_all<P: AsRef<Path>>(
+        &self,
+        dir: P,
+        options: &PlaylistDownloadOptions,
+        concurrency: usize,
+    ) -> Result<PlaylistDownloadReport, VideoError> {
+        let dir = dir.as_ref().to_path_buf();
+
+        std::fs::create_dir_all(&dir).map_err(|e| VideoError::DownloadError(e.to_string()))?;
+
+        let shared = Arc::new(Shared {
+            progress: Mutex::new(PlaylistProgress {
+                total: self.videos.len(),
+                completed: 0,
+                skipped: 0,
+                failed: 0,
+                downloaded_bytes: 0,
+                current: None,
+            }),
+            on_progress: options.on_progress.clone(),
+        });
+
+        // Videos join the budget while their stream is open, finished ones leave their share to the others
+        let mut options = options.clone();
+        if let Some(max_total_bandwidth) = options.max_total_bandwidth {
+            options.video_options.download_options.bandwidth =
+                Some(Arc::new(BandwidthBudget::new(max_total_bandwidth)));
+        }
+
+        let concurrency = match options.video_options.request_options.throttled_mode {
+            Some(_) => 1,
+            None => concurrency.max(1),
+        };
+        let semaphore = Arc::new(tokio::sync::Semaphore::new(concurrency));
+        let task_group = options
+            .video_options
+            .download_options
+            .tasks
+            .clone()
+            .unwrap_or_else(TaskGroup::shared);
+        let mut report = PlaylistDownloadReport::default();
+        let mut tasks = vec![];
+
+        for (index, entry) in self.videos.iter().enumerate() {
+            // A failed lookup fails only its video, spawned downloads keep being awaited
+            let archived = match options.archive.as_ref().map(|x| x.contains(&entry.id)) {
+                Some(Ok(archived)) => archived,
+                Some(Err(err)) => {
+                    report.failed.push((entry.id.clone(), err));
+                    shared.update(|x| {
+                        x.failed += 1;
+                        x.current = None;
+                    });
+                    continue;
+                }
+                None => false,
+            };
+
+            if archived {
+                report.skipped.push(entry.id.clone());
+                shared.update(|x| {
+                    x.skipped += 1;
+                    x.current = None;
+                });
+                continue;
+            }
+
+            let video_id = entry.id.clone();
+            let extra = vec![
+                ("playlist_index", format!("{:0>2}", index + 1)),
+                ("playlist_title", self.name.clone()),
+            ];
+            let options = options.clone();
+            let dir = dir.clone();
+            let shared = shared.clone();
+            let semaphore = semaphore.clone();
+
+            tasks.push((
+                entry.id.clone(),
+                task_group.spawn(async move {
+                    let _permit = semaphore.acquire_owned().await;
+
+                    let mut result =
+                        download_entry(&video_id, &dir, &options, &extra, shared.clone()).await;
+
+                    if let (Ok(_), Some(archive)) = (&result, &options.archive) {
+                        if let Err(err) = archive.insert(&video_id) {
+                            result = Err(err);
+                        }
+                    }
+
+                    match &result {
+                        Ok(_) => shared.update(|x| {
+                            x.completed += 1;
+                            x.current = None;
+                        }),
+                        Err(_) => shared.update(|x| {
+                            x.failed += 1;
+                            x.current = None;
+                        }),
+                    }
+
+                    result
+                }),
+            ));
+        }
+
+        for (video_id, task) in tasks {
+            match task.await {
+                Ok(Some(Ok(result))) => report.downloaded.push(result),
+                Ok(Some(Err(err))) => report.failed.push((video_id, err)),
+                Ok(None) => report.failed.push((video_id, VideoError::Cancelled)),
+                Err(err) => report
+                    .failed
+                    .push((video_id, VideoError::DownloadError(err.to_string()))),
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+async fn download_entry(
+    video_id: &str,
+    dir: &Path,
+    options: &PlaylistDownloadOptions,
+    extra: &[(&str, String)],
+    shared: Arc<Shared>,
+) -> Result<DownloadResult, VideoError> {
+    let mut video_options = options.video_options.clone();
+
+    // Forward video progress into the aggregate progress
+    let item_progress = video_options.download_options.on_progress.take();
+    let last_downloaded = Mutex::new(0_u64);
+    let id = video_id.to_string();
+
+    video_options.download_options.on_progress = Some(Arc::new(move |progress| {
+        if let Some(item_progress) = &item_progress {
+            item_progress(progress);
+        }
+
+        let delta = match last_downloaded.lock() {
+            Ok(mut last) => {
+                let delta = progress.downloaded.saturating_sub(*last);
+                *last = progress.downloaded;
+                delta
+            }
+            Err(_) => 0,
+        };
+
+        shared.update(|x| {
+            x.downloaded_bytes += delta;
+            x.current = Some((id.clone(), progress.clone()));
+        });
+    }));
+
+    let video = crate::Video::new_with_options(video_id, video_options.clone())?;
+    let info = video.get_info().await?;
+    let format = choose_format(&info.formats, &video_options)
+        .map_err(|_op| VideoError::VideoSourceNotFound)?;
+
+    let file_name = render_output_template_with(
+        &options.output_template,
+        &info,
+        &format,
+        options.sanitize_profile,
+        extra,
+    );
+
+    let result = video
+        .download_format_to_file(&format, &dir.join(file_name))
+        .await?;
+    video.post_process(&info, &format, result).await
+}
@@ -1,6 +1,17 @@
+mod availability;
+mod download;
 mod youtube;
 
+pub use availability::{Availability, AvailabilityReport, VideoAvailability};
+
+pub use download::{
+    PlaylistDownloadOptions, PlaylistDownloadReport, PlaylistProgress, PlaylistProgressCallback,
+};
+
 pub use youtube::{
-    Channel, EmbedOptions, Playlist, PlaylistSearchOptions, RequestOptions, SearchOptions,
-    SearchResult, SearchType, Video, YouTube,
+    Channel, ChannelAbout, ChannelLink, ChannelVideos, EmbedOptions, Movie, Playlist,
+    PlaylistChange, PlaylistDiff, PlaylistEntry, PlaylistMetadata, PlaylistSearchOptions,
+    PlaylistSnapshot, PlaylistVisibility, RequestOptions, SearchDuration, SearchFeature,
+    SearchFilters, SearchOptions, SearchResult, SearchType, Short, SortBy, UploadDate, Video,
+    YouTube,
 };
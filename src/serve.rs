@@ -0,0 +1,244 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use hyper::body::HttpBody;
+use hyper::header::{HeaderValue, ACCEPT_RANGES, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE};
+use hyper::{Response, StatusCode};
+
+use crate::stream::Stream;
+use crate::structs::{VideoError, VideoFormat};
+use crate::Video;
+
+type BoxedStream = Box<dyn Stream + Send + Sync>;
+type PendingChunk =
+    Pin<Box<dyn Future<Output = (BoxedStream, Result<Option<Bytes>, VideoError>)> + Send>>;
+
+/// [`HttpBody`] of [`serve_format`] and [`serve_stream`], stops after the requested range
+pub struct StreamBody {
+    stream: Option<BoxedStream>,
+    /// Chunk read before the response to learn an unknown length
+    first: Option<Bytes>,
+    pending: Option<PendingChunk>,
+    remaining: Option<u64>,
+}
+
+impl StreamBody {
+    fn new(stream: BoxedStream, first: Option<Bytes>, remaining: Option<u64>) -> Self {
+        Self {
+            stream: Some(stream),
+            first,
+            pending: None,
+            remaining,
+        }
+    }
+
+    fn empty() -> Self {
+        Self {
+            stream: None,
+            first: None,
+            pending: None,
+            remaining: Some(0),
+        }
+    }
+
+    fn truncate(&mut self, mut chunk: Bytes) -> Bytes {
+        if let Some(remaining) = self.remaining {
+            chunk.truncate(remaining.min(chunk.len() as u64) as usize);
+            self.remaining = Some(remaining - chunk.len() as u64);
+        }
+
+        chunk
+    }
+}
+
+impl HttpBody for StreamBody {
+    type Data = Bytes;
+    type Error = VideoError;
+
+    fn poll_data(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        if self.remaining == Some(0) {
+            return Poll::Ready(None);
+        }
+
+        if let Some(chunk) = self.first.take() {
+            return Poll::Ready(Some(Ok(self.truncate(chunk))));
+        }
+
+        if self.pending.is_none() {
+            let stream = match self.stream.take() {
+                Some(stream) => stream,
+                None => return Poll::Ready(None),
+            };
+
+            self.pending = Some(Box::pin(async move {
+                let chunk = stream.chunk().await;
+                (stream, chunk)
+            }));
+        }
+
+        let (stream, chunk) = match self.pending.as_mut().expect("IMPOSSIBLE").as_mut().poll(cx) {
+            Poll::Ready(result) => result,
+            Poll::Pending => return Poll::Pending,
+        };
+        self.pending = None;
+
+        match chunk {
+            Ok(Some(chunk)) => {
+                self.stream = Some(stream);
+
+                Poll::Ready(Some(Ok(self.truncate(chunk))))
+            }
+            Ok(None) => Poll::Ready(None),
+            Err(err) => Poll::Ready(Some(Err(err))),
+        }
+    }
+
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<hyper::HeaderMap>, Self::Error>> {
+        Poll::Ready(Ok(None))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.remaining == Some(0)
+    }
+
+    fn size_hint(&self) -> hyper::body::SizeHint {
+        match self.remaining {
+            Some(remaining) => hyper::body::SizeHint::with_exact(remaining),
+            None => hyper::body::SizeHint::default(),
+        }
+    }
+}
+
+/// Inclusive byte range of a `Range` header, `Err` if not satisfiable. Multiple ranges and other units are ignored
+fn parse_range(range: &str, total: u64) -> Option<Result<(u64, u64), ()>> {
+    let (start, end) = range.trim().strip_prefix("bytes=")?.split_once('-')?;
+    if end.contains(',') {
+        return None;
+    }
+
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => {
+            let suffix = suffix.parse::<u64>().ok()?;
+            if suffix == 0 {
+                return Some(Err(()));
+            }
+            (total.saturating_sub(suffix), total.saturating_sub(1))
+        }
+        (start, "") => (start.parse::<u64>().ok()?, total.saturating_sub(1)),
+        (start, end) => {
+            let (start, end) = (start.parse::<u64>().ok()?, end.parse::<u64>().ok()?);
+            if end < start {
+                return None;
+            }
+            (start, end.min(total.saturating_sub(1)))
+        }
+    };
+
+    if start >= total {
+        return Some(Err(()));
+    }
+
+    Some(Ok((start, end)))
+}
+
+/// Response answering the `Range` header of a request with `stream`. `200` with the whole stream without a
+/// range, `206` with the requested bytes or `416` if the range is past the end. Live streams ignore the range,
+/// an unknown length is learned from the first chunk before answering
+///
+/// # Example
+/// ```ignore
+///     let stream = video.stream_with_format(&format).await.unwrap();
+///
+///     let response = serve_stream(stream, &format.mime_type, request.headers().get(RANGE))
+///         .await
+///         .unwrap();
+/// ```
+pub async fn serve_stream(
+    stream: BoxedStream,
+    content_type: &str,
+    range: Option<&HeaderValue>,
+) -> Result<Response<StreamBody>, VideoError> {
+    let mut total = stream.content_length() as u64;
+
+    // Unknown length of a non live stream is taken from the `Content-Range` of its first chunk
+    let mut first = None;
+    if total == 0 && stream.live_report().is_none() {
+        first = stream.chunk().await?;
+        total = stream.content_length() as u64;
+    }
+
+    let response = Response::builder().header(CONTENT_TYPE, content_type);
+
+    // Live streams and servers without `Content-Range` have no length to answer ranges with
+    if total == 0 {
+        return response
+            .status(StatusCode::OK)
+            .body(StreamBody::new(stream, first, None))
+            .map_err(|e| VideoError::DownloadError(e.to_string()));
+    }
+
+    let mut response = response.header(ACCEPT_RANGES, "bytes");
+
+    let range = range
+        .and_then(|x| x.to_str().ok())
+        .and_then(|x| parse_range(x, total));
+
+    let (status, start, end) = match range {
+        None => (StatusCode::OK, 0, total - 1),
+        Some(Ok((start, end))) => {
+            response = response.header(CONTENT_RANGE, format!("bytes {start}-{end}/{total}"));
+            (StatusCode::PARTIAL_CONTENT, start, end)
+        }
+        Some(Err(())) => {
+            return response
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(CONTENT_RANGE, format!("bytes */{total}"))
+                .body(StreamBody::empty())
+                .map_err(|e| VideoError::DownloadError(e.to_string()));
+        }
+    };
+
+    // The first chunk starts at 0
+    if start > 0 {
+        first = None;
+        stream.seek(start).await?;
+    }
+
+    let length = end - start + 1;
+
+    response
+        .status(status)
+        .header(CONTENT_LENGTH, length)
+        .body(StreamBody::new(stream, first, Some(length)))
+        .map_err(|e| VideoError::DownloadError(e.to_string()))
+}
+
+/// [`serve_stream`] for `format` of `video`, the building block of streaming proxies
+///
+/// # Example
+/// ```ignore
+///     async fn handler(headers: axum::http::HeaderMap) -> impl axum::response::IntoResponse {
+///         let video = Video::new("FZ8BxMU3BYc").unwrap();
+///         let info = video.get_info().await.unwrap();
+///         let format = choose_format(&info.formats, &VideoOptions::default()).unwrap();
+///
+///         serve_format(&video, &format, headers.get(axum::http::header::RANGE)).await.unwrap()
+///     }
+/// ```
+pub async fn serve_format(
+    video: &Video,
+    format: &VideoFormat,
+    range: Option<&HeaderValue>,
+) -> Result<Response<StreamBody>, VideoError> {
+    let stream = video.stream_with_format(format).await?;
+
+    serve_stream(stream, &format.mime_type, range).await
+}
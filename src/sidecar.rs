@@ -0,0 +1,143 @@
+use crate::structs::{VideoError, VideoInfo};
+
+/// Metadata sidecar file formats
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, derive_more::Display)]
+pub enum SidecarFormat {
+    /// Kodi/Jellyfin `<movie>` NFO
+    #[default]
+    #[display(fmt = "nfo")]
+    Nfo,
+    /// [`VideoInfo::to_info_json`] document
+    #[display(fmt = "json")]
+    Json,
+    /// OPF package metadata (Dublin Core)
+    #[display(fmt = "opf")]
+    Opf,
+}
+
+impl SidecarFormat {
+    /// File extension of the format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Nfo => "nfo",
+            Self::Json => "info.json",
+            Self::Opf => "opf",
+        }
+    }
+}
+
+impl VideoInfo {
+    /// Write metadata sidecar file next to the downloaded video
+    /// # Example
+    /// ```ignore
+    ///     let video_info = video.get_info().await.unwrap();
+    ///
+    ///     video.download("video.mp4").await.unwrap();
+    ///     video_info.write_sidecar("video.nfo", SidecarFormat::Nfo).unwrap();
+    /// ```
+    pub fn write_sidecar<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+        format: SidecarFormat,
+    ) -> Result<(), VideoError> {
+        std::fs::write(path, self.to_sidecar_string(format))
+            .map_err(|e| VideoError::DownloadError(e.to_string()))
+    }
+
+    /// Render metadata sidecar file content
+    pub fn to_sidecar_string(&self, format: SidecarFormat) -> String {
+        match format {
+            SidecarFormat::Nfo => self.to_nfo(),
+            SidecarFormat::Json => {
+                serde_json::to_string_pretty(&self.to_info_json()).unwrap_or_default()
+            }
+            SidecarFormat::Opf => self.to_opf(),
+        }
+    }
+
+    fn to_nfo(&self) -> String {
+        let details = &self.video_details;
+        let author = self.author_name();
+
+        let mut nfo = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n<movie>\n",
+        );
+
+        let mut push = |tag: &str, value: &str| {
+            if !value.is_empty() {
+                nfo.push_str(&format!("  <{tag}>{}</{tag}>\n", escape_xml(value)));
+            }
+        };
+
+        push("title", &details.title);
+        push("plot", &details.description);
+        push("director", author);
+        push("studio", author);
+        push("premiered", &details.publish_date);
+        push("aired", &details.upload_date);
+        push("year", details.publish_date.get(0..4).unwrap_or(""));
+        push("genre", &details.category);
+        push("runtime", &(details.duration.as_secs() / 60).to_string());
+        for keyword in details.keywords.iter() {
+            push("tag", keyword);
+        }
+
+        if let Some(thumbnail) = details.thumbnails.iter().max_by_key(|x| x.width * x.height) {
+            nfo.push_str(&format!(
+                "  <thumb aspect=\"poster\">{}</thumb>\n",
+                escape_xml(&thumbnail.url)
+            ));
+        }
+
+        nfo.push_str(&format!(
+            "  <uniqueid type=\"youtube\" default=\"true\">{}</uniqueid>\n",
+            escape_xml(&details.video_id)
+        ));
+        nfo.push_str("</movie>\n");
+
+        nfo
+    }
+
+    fn to_opf(&self) -> String {
+        let details = &self.video_details;
+
+        let mut metadata = String::new();
+
+        let mut push = |tag: &str, value: &str| {
+            if !value.is_empty() {
+                metadata.push_str(&format!("    <{tag}>{}</{tag}>\n", escape_xml(value)));
+            }
+        };
+
+        push("dc:title", &details.title);
+        push("dc:creator", self.author_name());
+        push("dc:date", &details.publish_date);
+        push("dc:description", &details.description);
+        push("dc:source", &details.video_url);
+        for keyword in details.keywords.iter() {
+            push("dc:subject", keyword);
+        }
+
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<package xmlns=\"http://www.idpf.org/2007/opf\" version=\"2.0\" unique-identifier=\"id\">\n  <metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n    <dc:identifier id=\"id\">youtube:{}</dc:identifier>\n{metadata}  </metadata>\n</package>\n",
+            escape_xml(&details.video_id)
+        )
+    }
+
+    fn author_name(&self) -> &str {
+        self.video_details
+            .author
+            .as_ref()
+            .map(|x| x.name.as_str())
+            .unwrap_or(self.video_details.owner_channel_name.as_str())
+    }
+}
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
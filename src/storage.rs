@@ -0,0 +1,80 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use object_store::{path::Path, ObjectStore};
+use tokio::io::AsyncWriteExt;
+
+use crate::stream::Stream;
+use crate::VideoError;
+
+/// Destination that a [`Stream`] can be written into without staging it on local disk
+#[async_trait]
+pub trait StorageSink {
+    /// Write the remaining bytes of the stream into the sink and return written bytes count
+    async fn write_stream(&self, stream: &(dyn Stream + Send + Sync)) -> Result<u64, VideoError>;
+}
+
+/// [`StorageSink`] uploading to any [`ObjectStore`] (S3, GCS, Azure, local filesystem...) with multipart uploads
+///
+/// The sink doesn't retry, failed part uploads are retried with the [`object_store::RetryConfig`] the store was built
+/// with. The upload is aborted if the stream or the upload fails
+/// # Example
+/// ```ignore
+///     let store = object_store::aws::AmazonS3Builder::from_env()
+///         .with_bucket_name("videos")
+///         .build()
+///         .unwrap();
+///
+///     let sink = ObjectStoreSink::new(Arc::new(store), "archive/FZ8BxMU3BYc.mp4");
+///
+///     video.download_to_sink(&sink).await.unwrap();
+/// ```
+#[derive(Clone, Debug, derive_more::Display)]
+#[display(fmt = "ObjectStoreSink({path})")]
+pub struct ObjectStoreSink {
+    store: Arc<dyn ObjectStore>,
+    path: Path,
+}
+
+impl ObjectStoreSink {
+    pub fn new(store: Arc<dyn ObjectStore>, path: impl Into<Path>) -> Self {
+        Self {
+            store,
+            path: path.into(),
+        }
+    }
+
+    /// Object path of the upload
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[async_trait]
+impl StorageSink for ObjectStoreSink {
+    async fn write_stream(&self, stream: &(dyn Stream + Send + Sync)) -> Result<u64, VideoError> {
+        let (multipart_id, mut writer) = self
+            .store
+            .put_multipart(&self.path)
+            .await
+            .map_err(|e| VideoError::DownloadError(e.to_string()))?;
+
+        let written = stream.write_to(&mut writer).await;
+
+        // Complete the upload, or abort it to not leave orphan parts behind
+        let result = match written {
+            Ok(written) => writer
+                .shutdown()
+                .await
+                .map(|_| written)
+                .map_err(|e| VideoError::DownloadError(e.to_string())),
+            Err(e) => Err(e),
+        };
+
+        if result.is_err() {
+            let _ = self.store.abort_multipart(&self.path, &multipart_id).await;
+        }
+
+        result
+    }
+}
@@ -0,0 +1,148 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+
+use super::{ContainerType, LiveDownloadReport, Stream};
+use crate::structs::VideoError;
+
+/// Bandwidth shared fairly by concurrent downloads, every active download gets an equal part of it.
+/// Parts are recomputed on every chunk, so running downloads speed up when others finish
+///
+/// Downloads are paused after each chunk, smaller [`DownloadOptions::dl_chunk_size`](crate::DownloadOptions::dl_chunk_size)
+/// values keep the rate smoother
+///
+/// # Example
+/// ```ignore
+///     let download_options = DownloadOptions {
+///         // 5 MB/s for all videos together
+///         bandwidth: Some(Arc::new(BandwidthBudget::new(5 * 1024 * 1024))),
+///         ..Default::default()
+///     };
+/// ```
+#[derive(Debug)]
+pub struct BandwidthBudget {
+    bytes_per_second: u64,
+    active: AtomicUsize,
+}
+
+impl BandwidthBudget {
+    pub fn new(bytes_per_second: u64) -> Self {
+        Self {
+            bytes_per_second: bytes_per_second.max(1),
+            active: AtomicUsize::new(0),
+        }
+    }
+
+    /// Total bytes per second
+    pub fn bytes_per_second(&self) -> u64 {
+        self.bytes_per_second
+    }
+
+    /// Downloads holding a [`BandwidthShare`]
+    pub fn active(&self) -> usize {
+        self.active.load(Ordering::SeqCst)
+    }
+
+    /// Bytes per second of every active download
+    pub fn share(&self) -> u64 {
+        (self.bytes_per_second / self.active().max(1) as u64).max(1)
+    }
+
+    /// Register a download until the returned share is dropped
+    pub fn join(self: &Arc<Self>) -> BandwidthShare {
+        self.active.fetch_add(1, Ordering::SeqCst);
+
+        BandwidthShare {
+            budget: self.clone(),
+            next: Mutex::new(Instant::now()),
+        }
+    }
+}
+
+/// Part of a [`BandwidthBudget`] used by one download
+#[derive(Debug)]
+pub struct BandwidthShare {
+    budget: Arc<BandwidthBudget>,
+    /// Earliest time of the next chunk
+    next: Mutex<Instant>,
+}
+
+impl BandwidthShare {
+    /// Wait until `bytes` fit into the current share
+    pub async fn consume(&self, bytes: u64) {
+        let delay = Duration::from_secs_f64(bytes as f64 / self.budget.share() as f64);
+
+        let next = {
+            let mut next = self.next.lock().unwrap();
+            let now = Instant::now();
+
+            // Time spent receiving the bytes counts, idle time doesn't add up to bursts
+            *next = (*next).max(now.checked_sub(delay).unwrap_or(now)) + delay;
+            *next
+        };
+
+        tokio::time::sleep_until(tokio::time::Instant::from_std(next)).await;
+    }
+}
+
+impl Drop for BandwidthShare {
+    fn drop(&mut self) {
+        self.budget.active.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// [`Stream`] limited by a [`BandwidthShare`]
+pub(crate) struct ThrottledStream {
+    stream: Box<dyn Stream + Send + Sync>,
+    share: BandwidthShare,
+}
+
+impl ThrottledStream {
+    pub(crate) fn new(stream: Box<dyn Stream + Send + Sync>, share: BandwidthShare) -> Self {
+        Self { stream, share }
+    }
+}
+
+#[async_trait]
+impl Stream for ThrottledStream {
+    async fn chunk(&self) -> Result<Option<Bytes>, VideoError> {
+        let chunk = self.stream.chunk().await?;
+
+        if let Some(chunk) = &chunk {
+            self.share.consume(chunk.len() as u64).await;
+        }
+
+        Ok(chunk)
+    }
+
+    fn content_length(&self) -> usize {
+        self.stream.content_length()
+    }
+
+    fn live_report(&self) -> Option<LiveDownloadReport> {
+        self.stream.live_report()
+    }
+
+    fn content_type(&self) -> Option<&str> {
+        self.stream.content_type()
+    }
+
+    fn response_content_type(&self) -> Option<&str> {
+        self.stream.response_content_type()
+    }
+
+    fn sniffed_container(&self) -> Option<ContainerType> {
+        self.stream.sniffed_container()
+    }
+
+    async fn seek(&self, byte_offset: u64) -> Result<(), VideoError> {
+        self.stream.seek(byte_offset).await
+    }
+
+    async fn seek_time(&self, time: Duration) -> Result<u64, VideoError> {
+        self.stream.seek_time(time).await
+    }
+}
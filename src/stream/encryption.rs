@@ -1,6 +1,8 @@
 use aes::cipher::block_padding::Pkcs7;
 use aes::cipher::{BlockDecryptMut, KeyIvInit};
 
+use bytes::Bytes;
+
 use m3u8_rs::Key;
 use reqwest::Url;
 
@@ -82,17 +84,18 @@ impl Encryption {
     pub async fn decrypt(
         &self,
         client: &reqwest_middleware::ClientWithMiddleware,
-        data: &[u8],
-    ) -> Result<Vec<u8>, VideoError> {
+        data: Bytes,
+    ) -> Result<Bytes, VideoError> {
         let r = match self {
-            Self::None => Vec::from(data),
+            Self::None => data,
             Self::Aes128 { key_uri, iv } => {
                 let body = client.get(key_uri.clone()).send().await?.bytes().await?;
                 let mut key = [0_u8; 16];
                 key.copy_from_slice(&body[..16]);
                 Aes128CbcDec::new(&key.into(), iv.into())
-                    .decrypt_padded_vec_mut::<Pkcs7>(data)
+                    .decrypt_padded_vec_mut::<Pkcs7>(&data)
                     .map_err(|e| VideoError::DecryptionError(e.to_string()))?
+                    .into()
             }
             Self::SampleAes => unimplemented!(),
         };
@@ -0,0 +1,292 @@
+use std::time::Duration;
+
+/// Cues element of WebM, the index range of `webm` formats
+const WEBM_CUES: u64 = 0x1C53BB6B;
+const WEBM_CUE_POINT: u64 = 0xBB;
+const WEBM_CUE_TIME: u64 = 0xB3;
+const WEBM_CUE_TRACK_POSITIONS: u64 = 0xB7;
+const WEBM_CUE_CLUSTER_POSITION: u64 = 0xF1;
+const WEBM_SEGMENT: u64 = 0x18538067;
+const WEBM_INFO: u64 = 0x1549A966;
+const WEBM_TIMECODE_SCALE: u64 = 0x2AD7B1;
+
+/// Default WebM timecode scale, 1ms in nanoseconds
+const WEBM_DEFAULT_TIMECODE_SCALE: u64 = 1_000_000;
+
+/// Start offset of the fragment containing `time`, from the `sidx` box of `mp4` or the Cues of `webm` formats.
+/// `init` is the initialization range starting at byte 0, `index` the index range starting at byte `index_start`
+pub(crate) fn fragment_offset(
+    init: &[u8],
+    index: &[u8],
+    index_start: u64,
+    time: Duration,
+) -> Option<u64> {
+    let fragments = fragments(init, index, index_start)?;
+
+    fragments
+        .iter()
+        .rev()
+        .find(|x| x.0 <= time)
+        .or(fragments.first())
+        .map(|x| x.1)
+}
+
+/// Byte range `start..end` of the fragments covering `from..to`, `end` is `None` if they reach the end of the format
+pub(crate) fn fragment_range(
+    fragments: &[(Duration, u64)],
+    from: Duration,
+    to: Duration,
+) -> Option<(u64, Option<u64>)> {
+    let start = fragments
+        .iter()
+        .rev()
+        .find(|x| x.0 <= from)
+        .or(fragments.first())?
+        .1;
+    let end = fragments
+        .iter()
+        .find(|x| x.0 >= to && x.1 > start)
+        .map(|x| x.1);
+
+    Some((start, end))
+}
+
+/// `(start time, byte offset)` of every fragment in order. The `sidx` list ends with the end of the last fragment
+pub(crate) fn fragments(
+    init: &[u8],
+    index: &[u8],
+    index_start: u64,
+) -> Option<Vec<(Duration, u64)>> {
+    if index.get(4..8) == Some(b"sidx") {
+        return sidx_fragments(index, index_start);
+    }
+
+    webm_cues_fragments(init, index)
+}
+
+pub(crate) fn read_uint(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0, |acc, x| (acc << 8) | *x as u64)
+}
+
+fn sidx_fragments(index: &[u8], index_start: u64) -> Option<Vec<(Duration, u64)>> {
+    let mut box_size = read_uint(index.get(0..4)?);
+    let mut pos = 8;
+    if box_size == 1 {
+        box_size = read_uint(index.get(8..16)?);
+        pos = 16;
+    }
+
+    let version = *index.get(pos)?;
+    // version, flags and reference_ID
+    pos += 8;
+    let timescale = read_uint(index.get(pos..pos + 4)?);
+    pos += 4;
+
+    let field = if version == 0 { 4 } else { 8 };
+    let mut presentation_time = read_uint(index.get(pos..pos + field)?);
+    let first_offset = read_uint(index.get(pos + field..pos + 2 * field)?);
+    pos += 2 * field + 2;
+
+    let reference_count = read_uint(index.get(pos..pos + 2)?);
+    pos += 2;
+
+    if timescale == 0 {
+        return None;
+    }
+
+    let time = |x: u64| Duration::from_secs_f64(x as f64 / timescale as f64);
+    let mut offset = index_start + box_size + first_offset;
+    let mut fragments = vec![(time(presentation_time), offset)];
+
+    for _ in 0..reference_count {
+        let reference = index.get(pos..pos + 12)?;
+        let referenced_size = read_uint(&reference[0..4]) & 0x7FFF_FFFF;
+        let duration = read_uint(&reference[4..8]);
+
+        presentation_time += duration;
+        offset += referenced_size;
+        pos += 12;
+
+        fragments.push((time(presentation_time), offset));
+    }
+
+    Some(fragments)
+}
+
+/// EBML variable size integer at `pos`, `(value, length)`. Element ids keep their marker bits
+pub(crate) fn read_vint(bytes: &[u8], pos: usize, keep_marker: bool) -> Option<(u64, usize)> {
+    let first = *bytes.get(pos)?;
+    let length = first.leading_zeros() as usize + 1;
+    if length > 8 {
+        return None;
+    }
+
+    let value = read_uint(bytes.get(pos..pos + length)?);
+    if keep_marker {
+        return Some((value, length));
+    }
+
+    Some((value & (u64::MAX >> (64 - 7 * length)), length))
+}
+
+/// Element at `pos`, `(id, data start, data end)`. Data end is clamped to the buffer for unknown sizes
+fn read_element(bytes: &[u8], pos: usize) -> Option<(u64, usize, usize)> {
+    let (id, id_length) = read_vint(bytes, pos, true)?;
+    let (size, size_length) = read_vint(bytes, pos + id_length, false)?;
+    let start = pos + id_length + size_length;
+
+    Some((
+        id,
+        start,
+        start.saturating_add(size as usize).min(bytes.len()),
+    ))
+}
+
+fn children(bytes: &[u8], start: usize, end: usize) -> Vec<(u64, usize, usize)> {
+    let mut elements = vec![];
+    let mut pos = start;
+
+    while pos < end {
+        match read_element(&bytes[..end], pos) {
+            Some(element) => {
+                elements.push(element);
+                if element.2 <= pos {
+                    break;
+                }
+                pos = element.2;
+            }
+            None => break,
+        }
+    }
+
+    elements
+}
+
+fn webm_cues_fragments(init: &[u8], index: &[u8]) -> Option<Vec<(Duration, u64)>> {
+    // Cluster positions are relative to the data of the Segment element
+    let (segment_start, timecode_scale) = children(init, 0, init.len())
+        .into_iter()
+        .find(|x| x.0 == WEBM_SEGMENT)
+        .map(|(_, start, end)| {
+            let timecode_scale = children(init, start, end)
+                .into_iter()
+                .filter(|x| x.0 == WEBM_INFO)
+                .flat_map(|(_, start, end)| children(init, start, end))
+                .find(|x| x.0 == WEBM_TIMECODE_SCALE)
+                .map(|(_, start, end)| read_uint(&init[start..end]))
+                .unwrap_or(WEBM_DEFAULT_TIMECODE_SCALE);
+
+            (start as u64, timecode_scale)
+        })?;
+
+    let (id, start, end) = read_element(index, 0)?;
+    if id != WEBM_CUES {
+        return None;
+    }
+
+    let mut fragments = vec![];
+
+    for (_, start, end) in children(index, start, end)
+        .into_iter()
+        .filter(|x| x.0 == WEBM_CUE_POINT)
+    {
+        let cue_point = children(index, start, end);
+
+        let cue_time = cue_point
+            .iter()
+            .find(|x| x.0 == WEBM_CUE_TIME)
+            .map(|(_, start, end)| read_uint(&index[*start..*end]))?;
+
+        let cluster_position = cue_point
+            .iter()
+            .filter(|x| x.0 == WEBM_CUE_TRACK_POSITIONS)
+            .flat_map(|(_, start, end)| children(index, *start, *end))
+            .find(|x| x.0 == WEBM_CUE_CLUSTER_POSITION)
+            .map(|(_, start, end)| read_uint(&index[start..end]))?;
+
+        fragments.push((
+            Duration::from_nanos(cue_time.saturating_mul(timecode_scale.max(1))),
+            segment_start + cluster_position,
+        ));
+    }
+
+    Some(fragments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// WebM element with a one byte size
+    fn element(id: &[u8], data: &[u8]) -> Vec<u8> {
+        [id, &[0x80 | data.len() as u8], data].concat()
+    }
+
+    #[test]
+    fn test_sidx_fragment_range() {
+        // `sidx` at byte 16 with 2 fragments of 2 seconds, 50 and 60 bytes long
+        let mut index = 56u32.to_be_bytes().to_vec();
+        index.extend_from_slice(b"sidx");
+        index.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 1]);
+        index.extend_from_slice(&1000u32.to_be_bytes());
+        index.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2]);
+        for size in [50u32, 60] {
+            index.extend_from_slice(&size.to_be_bytes());
+            index.extend_from_slice(&2000u32.to_be_bytes());
+            index.extend_from_slice(&[0x90, 0, 0, 0]);
+        }
+
+        let fragments = fragments(&[0; 16], &index, 16).unwrap();
+        let secs = Duration::from_secs;
+        assert_eq!(fragments, [(secs(0), 72), (secs(2), 122), (secs(4), 182)]);
+
+        assert_eq!(
+            fragment_range(&fragments, secs(1), secs(3)),
+            Some((72, Some(182)))
+        );
+        assert_eq!(
+            fragment_range(&fragments, secs(0), secs(2)),
+            Some((72, Some(122)))
+        );
+        // Past the last fragment the range reaches the end of the format
+        assert_eq!(
+            fragment_range(&fragments, secs(3), secs(10)),
+            Some((122, None))
+        );
+        assert_eq!(fragment_range(&[], secs(0), secs(1)), None);
+    }
+
+    #[test]
+    fn test_webm_cues_fragments() {
+        // EBML header, then a Segment with its data at byte 10 and a 1ms timecode scale
+        let info = element(
+            &[0x15, 0x49, 0xA9, 0x66],
+            &element(&[0x2A, 0xD7, 0xB1], &[0x0F, 0x42, 0x40]),
+        );
+        let init = [
+            element(&[0x1A, 0x45, 0xDF, 0xA3], &[]),
+            element(&[0x18, 0x53, 0x80, 0x67], &info),
+        ]
+        .concat();
+
+        // Clusters at 0 and 2 seconds, 32 and 96 bytes into the Segment data
+        let cue_point = |time: &[u8], position: u8| {
+            let positions = element(&[0xB7], &element(&[0xF1], &[position]));
+            element(&[0xBB], &[element(&[0xB3], time), positions].concat())
+        };
+        let index = element(
+            &[0x1C, 0x53, 0xBB, 0x6B],
+            &[cue_point(&[0], 0x20), cue_point(&[0x07, 0xD0], 0x60)].concat(),
+        );
+
+        let secs = Duration::from_secs;
+        assert_eq!(
+            fragments(&init, &index, 500).unwrap(),
+            [(secs(0), 42), (secs(2), 106)]
+        );
+        assert_eq!(fragment_offset(&init, &index, 500, secs(3)), Some(106));
+
+        // Not a Cues element
+        assert_eq!(fragments(&init, &info, 500), None);
+    }
+}
@@ -4,7 +4,7 @@ use std::process::Stdio;
 use serde::Deserialize;
 use tokio::{io::AsyncWriteExt, process};
 
-use crate::VideoError;
+use crate::{TaskGroup, VideoError};
 
 #[non_exhaustive]
 #[allow(dead_code)]
@@ -62,7 +62,7 @@ impl MediaFormat {
             .ok_or_else(|| VideoError::ChildProcessError("Can't open ffprobe stdin".to_string()))?;
 
         // Write to ffprobe stdin
-        tokio::spawn(async move { stdin.write_all(&data).await });
+        TaskGroup::shared().spawn(async move { stdin.write_all(&data).await });
 
         // Run ffprobe
         let output = child
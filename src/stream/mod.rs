@@ -1,8 +1,26 @@
+mod bandwidth;
 mod encryption;
 mod hashable_byte_range;
+mod index;
 mod media_format;
+mod opus;
+mod progress;
+mod progressive;
 mod remote_data;
 mod segment;
+mod sniff;
 mod streams;
 
-pub use streams::{LiveStream, LiveStreamOptions, NonLiveStream, NonLiveStreamOptions, Stream};
+pub(crate) use bandwidth::ThrottledStream;
+pub use bandwidth::{BandwidthBudget, BandwidthShare};
+pub(crate) use index::{fragment_range, fragments};
+pub use opus::{AudioFrames, OpusPacket};
+pub(crate) use progress::ProgressTracker;
+pub use progress::{DownloadProgress, ProgressCallback};
+pub use progressive::ProgressiveReader;
+pub use sniff::ContainerType;
+pub(crate) use sniff::Sniffed;
+pub use streams::{
+    LiveDownloadReport, LiveGap, LiveStream, LiveStreamOptions, NonLiveStream,
+    NonLiveStreamOptions, Stream,
+};
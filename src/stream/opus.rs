@@ -0,0 +1,292 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use bytes::{Buf, Bytes, BytesMut};
+
+use super::index::{read_uint, read_vint};
+use super::Stream;
+use crate::structs::VideoError;
+
+const EBML_SEGMENT: u64 = 0x18538067;
+const EBML_INFO: u64 = 0x1549A966;
+const EBML_TIMECODE_SCALE: u64 = 0x2AD7B1;
+const EBML_TRACKS: u64 = 0x1654AE6B;
+const EBML_TRACK_ENTRY: u64 = 0xAE;
+const EBML_TRACK_NUMBER: u64 = 0xD7;
+const EBML_CODEC_ID: u64 = 0x86;
+const EBML_CODEC_PRIVATE: u64 = 0x63A2;
+const EBML_CLUSTER: u64 = 0x1F43B675;
+const EBML_CLUSTER_TIMECODE: u64 = 0xE7;
+const EBML_BLOCK_GROUP: u64 = 0xA0;
+const EBML_BLOCK: u64 = 0xA1;
+const EBML_SIMPLE_BLOCK: u64 = 0xA3;
+
+/// Default WebM timecode scale, 1ms in nanoseconds
+const DEFAULT_TIMECODE_SCALE: u64 = 1_000_000;
+
+/// One Opus packet of a WebM audio stream
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OpusPacket {
+    /// Raw Opus packet, starts with the TOC byte
+    pub data: Bytes,
+    /// Presentation time from the start of the stream
+    pub timestamp: Duration,
+}
+
+impl OpusPacket {
+    /// Audio duration of the packet read from its TOC byte, usually 20ms
+    pub fn duration(&self) -> Duration {
+        let toc = match self.data.first() {
+            Some(toc) => *toc,
+            None => return Duration::ZERO,
+        };
+
+        let config = (toc >> 3) as usize;
+        let frame_micros: u64 = match config {
+            0..=11 => [10_000, 20_000, 40_000, 60_000][config % 4],
+            12..=15 => [10_000, 20_000][config % 2],
+            _ => [2_500, 5_000, 10_000, 20_000][config % 4],
+        };
+
+        let frames = match toc & 0x03 {
+            0 => 1,
+            1 | 2 => 2,
+            _ => self.data.get(1).map(|x| x & 0x3F).unwrap_or(0) as u64,
+        };
+
+        Duration::from_micros(frame_micros * frames)
+    }
+}
+
+#[derive(Default)]
+struct TrackEntry {
+    number: Option<u64>,
+    codec_id: Option<String>,
+}
+
+/// Opus packets of a WebM audio [`Stream`], demuxed while the stream downloads
+///
+/// # Example
+/// ```ignore
+///     let mut frames = video.audio_frames().await.unwrap();
+///
+///     while let Some(packet) = frames.next().await.unwrap() {
+///           voice_connection.send_opus(packet.data, packet.timestamp);
+///     }
+/// ```
+pub struct AudioFrames {
+    stream: Box<dyn Stream + Send + Sync>,
+    buffer: BytesMut,
+    skip: u64,
+    timecode_scale: u64,
+    cluster_timecode: u64,
+    track_entry: TrackEntry,
+    opus_track: Option<u64>,
+    opus_head: Option<Bytes>,
+    packets: VecDeque<OpusPacket>,
+}
+
+impl AudioFrames {
+    /// Demux `stream`, which must yield a WebM file from its first byte
+    pub fn new(stream: Box<dyn Stream + Send + Sync>) -> Self {
+        Self {
+            stream,
+            buffer: BytesMut::new(),
+            skip: 0,
+            timecode_scale: DEFAULT_TIMECODE_SCALE,
+            cluster_timecode: 0,
+            track_entry: TrackEntry::default(),
+            opus_track: None,
+            opus_head: None,
+            packets: VecDeque::new(),
+        }
+    }
+
+    /// `OpusHead` identification header of the Opus track (channels, pre-skip, sample rate), known after the first packet
+    pub fn opus_head(&self) -> Option<&Bytes> {
+        self.opus_head.as_ref()
+    }
+
+    /// Next Opus packet, [`None`] when the stream has ended
+    pub async fn next(&mut self) -> Result<Option<OpusPacket>, VideoError> {
+        loop {
+            if let Some(packet) = self.packets.pop_front() {
+                return Ok(Some(packet));
+            }
+
+            if !self.parse_element()? {
+                match self.stream.chunk().await? {
+                    Some(chunk) => self.buffer.extend_from_slice(&chunk),
+                    None => return Ok(None),
+                }
+            }
+        }
+    }
+
+    /// Parse one element of the buffer, `false` if more bytes are needed
+    fn parse_element(&mut self) -> Result<bool, VideoError> {
+        if self.skip > 0 {
+            let skipped = self.skip.min(self.buffer.len() as u64);
+            self.buffer.advance(skipped as usize);
+            self.skip -= skipped;
+
+            return Ok(self.skip == 0);
+        }
+
+        let (id, id_length) = match read_vint(&self.buffer, 0, true) {
+            Some(id) => id,
+            None => return Ok(false),
+        };
+        let (size, size_length) = match read_vint(&self.buffer, id_length, false) {
+            Some(size) => size,
+            None => return Ok(false),
+        };
+        let header_length = id_length + size_length;
+        let unknown_size = size == u64::MAX >> (64 - 7 * size_length);
+
+        match id {
+            // Master elements are entered, their children are parsed one by one
+            EBML_SEGMENT | EBML_INFO | EBML_TRACKS | EBML_CLUSTER | EBML_BLOCK_GROUP => {
+                self.buffer.advance(header_length);
+            }
+            EBML_TRACK_ENTRY => {
+                self.track_entry = TrackEntry::default();
+                self.buffer.advance(header_length);
+            }
+            _ if unknown_size => {
+                return Err(VideoError::InvalidWebm(format!(
+                    "element {id:#X} has an unknown size"
+                )));
+            }
+            EBML_TIMECODE_SCALE
+            | EBML_TRACK_NUMBER
+            | EBML_CODEC_ID
+            | EBML_CODEC_PRIVATE
+            | EBML_CLUSTER_TIMECODE
+            | EBML_BLOCK
+            | EBML_SIMPLE_BLOCK => {
+                if self.buffer.len() < header_length + size as usize {
+                    return Ok(false);
+                }
+
+                self.buffer.advance(header_length);
+                let data = self.buffer.split_to(size as usize).freeze();
+                self.parse_leaf(id, data)?;
+            }
+            _ => {
+                self.buffer.advance(header_length);
+                self.skip = size;
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn parse_leaf(&mut self, id: u64, data: Bytes) -> Result<(), VideoError> {
+        match id {
+            EBML_TIMECODE_SCALE => self.timecode_scale = read_uint(&data).max(1),
+            EBML_CLUSTER_TIMECODE => self.cluster_timecode = read_uint(&data),
+            EBML_TRACK_NUMBER => self.track_entry.number = Some(read_uint(&data)),
+            EBML_CODEC_ID => {
+                self.track_entry.codec_id = Some(String::from_utf8_lossy(&data).to_string())
+            }
+            EBML_CODEC_PRIVATE => {
+                if self.track_entry.codec_id.as_deref() == Some("A_OPUS") {
+                    self.opus_head = Some(data);
+                }
+            }
+            _ => return self.parse_block(data),
+        }
+
+        if self.track_entry.codec_id.as_deref() == Some("A_OPUS") {
+            self.opus_track = self.track_entry.number.or(self.opus_track);
+        }
+
+        Ok(())
+    }
+
+    fn parse_block(&mut self, data: Bytes) -> Result<(), VideoError> {
+        let invalid_block = || VideoError::InvalidWebm("invalid block".to_string());
+
+        let (track, track_length) = read_vint(&data, 0, false).ok_or_else(invalid_block)?;
+        if self.opus_track.map(|x| x != track).unwrap_or(false) {
+            return Ok(());
+        }
+
+        let header = data
+            .get(track_length..track_length + 3)
+            .ok_or_else(invalid_block)?;
+        let relative_timecode = i16::from_be_bytes([header[0], header[1]]) as i64;
+        let lacing = header[2] & 0x06;
+
+        let payload = data.slice(track_length + 3..);
+        let frames = match lacing {
+            0 => vec![payload],
+            _ => split_laces(payload, lacing).ok_or_else(invalid_block)?,
+        };
+
+        let timecode = (self.cluster_timecode as i64 + relative_timecode).max(0) as u64;
+        let mut timestamp = Duration::from_nanos(timecode.saturating_mul(self.timecode_scale));
+
+        for frame in frames {
+            let packet = OpusPacket {
+                data: frame,
+                timestamp,
+            };
+            timestamp += packet.duration();
+            self.packets.push_back(packet);
+        }
+
+        Ok(())
+    }
+}
+
+/// Frames of a laced block, `lacing` is `0x02` for Xiph, `0x04` for fixed size and `0x06` for EBML lacing
+fn split_laces(payload: Bytes, lacing: u8) -> Option<Vec<Bytes>> {
+    let count = *payload.first()? as usize + 1;
+    let mut pos = 1;
+    let mut sizes = Vec::with_capacity(count);
+
+    match lacing {
+        0x02 => {
+            for _ in 1..count {
+                let mut size = 0;
+                loop {
+                    let byte = *payload.get(pos)?;
+                    pos += 1;
+                    size += byte as usize;
+                    if byte != 0xFF {
+                        break;
+                    }
+                }
+                sizes.push(size);
+            }
+        }
+        0x06 => {
+            let (first, length) = read_vint(&payload, pos, false)?;
+            pos += length;
+            sizes.push(first as usize);
+
+            for _ in 2..count {
+                let (raw, length) = read_vint(&payload, pos, false)?;
+                pos += length;
+                // Sizes after the first are signed differences, biased by half the range of the vint
+                let bias = (1i64 << (7 * length - 1)) - 1;
+                let size = *sizes.last()? as i64 + raw as i64 - bias;
+                sizes.push(usize::try_from(size).ok()?);
+            }
+        }
+        _ => sizes = vec![(payload.len() - 1) / count; count - 1],
+    }
+
+    let laced: usize = sizes.iter().sum();
+    sizes.push(payload.len().checked_sub(pos + laced)?);
+
+    let mut frames = Vec::with_capacity(count);
+    for size in sizes {
+        frames.push(payload.slice(pos..pos + size));
+        pos += size;
+    }
+
+    Some(frames)
+}
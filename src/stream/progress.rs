@@ -0,0 +1,114 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Callback of [`DownloadOptions::on_progress`](crate::DownloadOptions::on_progress), called after every received chunk
+pub type ProgressCallback = Arc<dyn Fn(&DownloadProgress) + Send + Sync>;
+
+/// Time constant of the average speed in seconds, older samples fade out exponentially
+const SPEED_WINDOW_SECS: f64 = 5.0;
+
+/// Progress event payload
+#[derive(Clone, Debug, PartialEq)]
+pub struct DownloadProgress {
+    /// Bytes delivered by the stream so far
+    pub downloaded: u64,
+    /// Total bytes of the stream, `None` for live streams
+    pub total: Option<u64>,
+    /// Speed of the last chunk in bytes per second
+    pub speed: f64,
+    /// Exponentially weighted average speed in bytes per second
+    pub average_speed: f64,
+    /// Estimated remaining time from the average speed, `None` if total is unknown or nothing downloaded yet
+    pub eta: Option<Duration>,
+    /// Bytes received but discarded because the chunk has been requested again
+    pub retransmitted: u64,
+    /// Time since the first request
+    pub elapsed: Duration,
+}
+
+impl DownloadProgress {
+    /// Downloaded percentage in `0.0..=100.0`, `None` if total is unknown
+    pub fn percentage(&self) -> Option<f64> {
+        self.total
+            .filter(|x| *x > 0)
+            .map(|total| (self.downloaded as f64 / total as f64 * 100.0).min(100.0))
+    }
+}
+
+/// Speed and ETA bookkeeping shared by the stream and its prefetch task
+pub(crate) struct ProgressTracker {
+    callback: ProgressCallback,
+    total: Option<u64>,
+    downloaded: u64,
+    retransmitted: u64,
+    average_speed: Option<f64>,
+    started: Instant,
+    last_sample: Instant,
+}
+
+impl ProgressTracker {
+    pub(crate) fn new(callback: ProgressCallback, total: Option<u64>) -> Self {
+        let now = Instant::now();
+
+        Self {
+            callback,
+            total,
+            downloaded: 0,
+            retransmitted: 0,
+            average_speed: None,
+            started: now,
+            last_sample: now,
+        }
+    }
+
+    /// Record delivered bytes and notify the callback
+    pub(crate) fn record(&mut self, bytes: u64) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_sample).as_secs_f64();
+        self.last_sample = now;
+        self.downloaded += bytes;
+
+        let speed = if elapsed > 0.0 {
+            bytes as f64 / elapsed
+        } else {
+            self.average_speed.unwrap_or(0.0)
+        };
+
+        // Time weighted EWMA, so chunk size doesn't change how fast old samples fade out
+        let alpha = 1.0 - (-elapsed / SPEED_WINDOW_SECS).exp();
+        let average_speed = match self.average_speed {
+            Some(average_speed) => average_speed + alpha * (speed - average_speed),
+            None => speed,
+        };
+        self.average_speed = Some(average_speed);
+
+        let eta = self.total.filter(|_| average_speed > 0.0).map(|total| {
+            Duration::from_secs_f64(total.saturating_sub(self.downloaded) as f64 / average_speed)
+        });
+
+        (self.callback)(&DownloadProgress {
+            downloaded: self.downloaded,
+            total: self.total,
+            speed,
+            average_speed,
+            eta,
+            retransmitted: self.retransmitted,
+            elapsed: now.duration_since(self.started),
+        });
+    }
+
+    /// Stream continues from `offset`, bytes before it count as downloaded
+    pub(crate) fn seek(&mut self, offset: u64) {
+        self.downloaded = offset;
+    }
+
+    /// Total learned after the start, from the first response
+    pub(crate) fn set_total(&mut self, total: u64) {
+        self.total = Some(total);
+    }
+
+    /// Record bytes which are discarded and requested again
+    pub(crate) fn record_retransmitted(&mut self, bytes: u64) {
+        self.retransmitted += bytes;
+    }
+}
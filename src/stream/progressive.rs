@@ -0,0 +1,272 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use bytes::{Bytes, BytesMut};
+use tokio::io::{AsyncRead, ReadBuf};
+
+use super::index::read_uint;
+use super::Stream;
+use crate::structs::VideoError;
+
+type BoxedStream = Box<dyn Stream + Send + Sync>;
+type PendingChunk =
+    Pin<Box<dyn Future<Output = (State, Result<Option<Bytes>, VideoError>)> + Send>>;
+
+/// Boxes on the path from `moov` to the chunk offset tables
+const MOOV_CONTAINERS: [&[u8; 4]; 5] = [b"moov", b"trak", b"mdia", b"minf", b"stbl"];
+
+enum Phase {
+    Start,
+    Passthrough,
+    /// Bytes left of the `mdat` box after the relocated `moov`
+    Remaining(u64),
+}
+
+struct State {
+    stream: BoxedStream,
+    phase: Phase,
+}
+
+/// Box header at `pos`, `(type, size, header length)`. Size `0` (box extends to the end of the file) is returned as is
+fn read_box_header(bytes: &[u8], pos: usize) -> Option<([u8; 4], u64, usize)> {
+    let size = read_uint(bytes.get(pos..pos + 4)?);
+    let kind: [u8; 4] = bytes.get(pos + 4..pos + 8)?.try_into().ok()?;
+
+    if size == 1 {
+        return Some((kind, read_uint(bytes.get(pos + 8..pos + 16)?), 16));
+    }
+
+    Some((kind, size, 8))
+}
+
+/// Add `shift` to every `stco` and `co64` entry of `moov`, [`None`] if the box is malformed or an entry overflows
+fn shift_chunk_offsets(moov: &mut [u8], depth: usize, shift: u64) -> Option<()> {
+    let mut pos = 0;
+
+    while pos < moov.len() {
+        let (kind, size, header_length) = read_box_header(moov, pos)?;
+        let size = if size == 0 {
+            moov.len() - pos
+        } else {
+            size as usize
+        };
+        if size < header_length || pos + size > moov.len() {
+            return None;
+        }
+
+        let data = &mut moov[pos + header_length..pos + size];
+
+        match &kind {
+            kind if depth < MOOV_CONTAINERS.len() && kind == MOOV_CONTAINERS[depth] => {
+                shift_chunk_offsets(data, depth + 1, shift)?;
+            }
+            b"stco" | b"co64" if depth == MOOV_CONTAINERS.len() => {
+                let field = if &kind == b"stco" { 4 } else { 8 };
+                let count = read_uint(data.get(4..8)?) as usize;
+
+                for index in 0..count {
+                    let entry = data.get_mut(8 + index * field..8 + (index + 1) * field)?;
+                    let offset = read_uint(entry).checked_add(shift)?;
+
+                    if field == 4 {
+                        entry.copy_from_slice(&u32::try_from(offset).ok()?.to_be_bytes());
+                    } else {
+                        entry.copy_from_slice(&offset.to_be_bytes());
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        pos += size;
+    }
+
+    Some(())
+}
+
+impl State {
+    async fn next(&mut self) -> Result<Option<Bytes>, VideoError> {
+        match self.phase {
+            Phase::Start => {
+                self.phase = Phase::Passthrough;
+                self.start().await.map(Some)
+            }
+            Phase::Passthrough => self.stream.chunk().await,
+            Phase::Remaining(0) => Ok(None),
+            Phase::Remaining(remaining) => match self.stream.chunk().await? {
+                Some(mut chunk) => {
+                    chunk.truncate(remaining.min(chunk.len() as u64) as usize);
+                    self.phase = Phase::Remaining(remaining - chunk.len() as u64);
+                    Ok(Some(chunk))
+                }
+                None => Ok(None),
+            },
+        }
+    }
+
+    /// Boxes before `moov` if it is at the front, otherwise boxes before `mdat` followed by the relocated `moov`
+    async fn start(&mut self) -> Result<Bytes, VideoError> {
+        let mut head = BytesMut::new();
+        let mut pos = 0;
+
+        let (mdat_start, mdat_end) = loop {
+            let header = match read_box_header(&head, pos) {
+                Some(header) => header,
+                None => match self.stream.chunk().await? {
+                    Some(chunk) => {
+                        head.extend_from_slice(&chunk);
+                        continue;
+                    }
+                    None => return Ok(head.freeze()),
+                },
+            };
+
+            match header {
+                (kind, _, _) if pos == 0 && &kind != b"ftyp" => return Ok(head.freeze()),
+                (kind, _, _) if &kind == b"moov" || &kind == b"moof" => return Ok(head.freeze()),
+                (kind, size, _) if &kind == b"mdat" && size > 0 => {
+                    break (pos as u64, pos as u64 + size);
+                }
+                (_, size, header_length) if size >= header_length as u64 => pos += size as usize,
+                _ => return Ok(head.freeze()),
+            }
+        };
+
+        // Streams that cannot seek are passed through
+        if self.stream.seek(mdat_end).await.is_err() {
+            return Ok(head.freeze());
+        }
+
+        let moov = match self.tail_moov().await? {
+            Some(moov) => moov,
+            None => {
+                // Continue as a plain stream after the buffered bytes
+                self.stream.seek(head.len() as u64).await?;
+                return Ok(head.freeze());
+            }
+        };
+
+        self.stream.seek(mdat_start).await?;
+        self.phase = Phase::Remaining(mdat_end - mdat_start);
+
+        head.truncate(mdat_start as usize);
+        head.extend_from_slice(&moov);
+
+        Ok(head.freeze())
+    }
+
+    /// `moov` box of the rest of the stream with chunk offsets moved past itself, [`None`] if there is no `moov`
+    async fn tail_moov(&mut self) -> Result<Option<Vec<u8>>, VideoError> {
+        let mut tail = vec![];
+        while let Some(chunk) = self.stream.chunk().await? {
+            tail.extend_from_slice(&chunk);
+        }
+
+        let mut pos = 0;
+        while let Some((kind, size, header_length)) = read_box_header(&tail, pos) {
+            let size = if size == 0 {
+                (tail.len() - pos) as u64
+            } else {
+                size
+            };
+            if size < header_length as u64 || pos as u64 + size > tail.len() as u64 {
+                return Ok(None);
+            }
+
+            if &kind == b"moov" {
+                let mut moov = tail[pos..pos + size as usize].to_vec();
+
+                return Ok(shift_chunk_offsets(&mut moov, 0, size).map(|_| moov));
+            }
+
+            pos += size as usize;
+        }
+
+        Ok(None)
+    }
+}
+
+/// [`AsyncRead`] of a [`Stream`] that players can start on before the download finishes.
+/// `mp4` files with the `moov` box at the tail are reordered to `moov` first, the tail is requested with
+/// [`Stream::seek`]. Fragmented and other files are passed through
+///
+/// # Example
+/// ```ignore
+///     let mut reader = video.stream().await.unwrap().into_progressive_reader();
+///
+///     tokio::io::copy(&mut reader, &mut player_stdin).await.unwrap();
+/// ```
+pub struct ProgressiveReader {
+    state: Option<State>,
+    pending: Option<PendingChunk>,
+    chunk: Bytes,
+}
+
+impl ProgressiveReader {
+    pub fn new(stream: BoxedStream) -> Self {
+        Self {
+            state: Some(State {
+                stream,
+                phase: Phase::Start,
+            }),
+            pending: None,
+            chunk: Bytes::new(),
+        }
+    }
+}
+
+impl dyn Stream + Send + Sync {
+    /// Turn the stream into a [`ProgressiveReader`]
+    pub fn into_progressive_reader(self: Box<Self>) -> ProgressiveReader {
+        ProgressiveReader::new(self)
+    }
+}
+
+impl AsyncRead for ProgressiveReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+
+        loop {
+            if !this.chunk.is_empty() {
+                let n = buf.remaining().min(this.chunk.len());
+                buf.put_slice(&this.chunk.split_to(n));
+                return Poll::Ready(Ok(()));
+            }
+
+            if this.pending.is_none() {
+                let mut state = match this.state.take() {
+                    Some(state) => state,
+                    None => return Poll::Ready(Ok(())),
+                };
+
+                this.pending = Some(Box::pin(async move {
+                    let chunk = state.next().await;
+                    (state, chunk)
+                }));
+            }
+
+            let (state, chunk) = match this.pending.as_mut().expect("IMPOSSIBLE").as_mut().poll(cx)
+            {
+                Poll::Ready(result) => result,
+                Poll::Pending => return Poll::Pending,
+            };
+            this.pending = None;
+
+            match chunk {
+                Ok(Some(chunk)) => {
+                    this.state = Some(state);
+                    this.chunk = chunk;
+                }
+                Ok(None) => return Poll::Ready(Ok(())),
+                Err(err) => {
+                    return Poll::Ready(Err(std::io::Error::new(std::io::ErrorKind::Other, err)))
+                }
+            }
+        }
+    }
+}
@@ -8,6 +8,8 @@ pub struct Segment {
     pub data: RemoteData,
     pub discon_seq: u64,
     pub seq: u64,
+    /// Duration from `#EXTINF`
+    pub duration: std::time::Duration,
     pub format: MediaFormat,
     pub initialization: Option<RemoteData>,
 }
@@ -0,0 +1,80 @@
+use once_cell::sync::OnceCell;
+
+/// Container of streamed bytes detected from their first chunk, see [`Stream::sniffed_container`](super::Stream::sniffed_container)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ContainerType {
+    /// ISO BMFF, plain or fragmented
+    Mp4,
+    /// Matroska / WebM
+    WebM,
+    /// MPEG-2 transport stream
+    Ts,
+}
+
+impl ContainerType {
+    /// Detect the container from the first bytes of a file or of a fragment. Streams starting in the middle of a
+    /// box or cluster are not detected
+    pub fn sniff(bytes: &[u8]) -> Option<Self> {
+        const MP4_BOXES: [&[u8; 4]; 8] = [
+            b"ftyp", b"styp", b"moov", b"moof", b"sidx", b"mdat", b"free", b"emsg",
+        ];
+        // EBML header, Segment and Cluster element ids
+        const WEBM_IDS: [[u8; 4]; 3] = [
+            [0x1A, 0x45, 0xDF, 0xA3],
+            [0x18, 0x53, 0x80, 0x67],
+            [0x1F, 0x43, 0xB6, 0x75],
+        ];
+        const TS_PACKET: usize = 188;
+
+        if bytes.len() >= 8 && MP4_BOXES.iter().any(|x| &bytes[4..8] == *x) {
+            return Some(Self::Mp4);
+        }
+
+        if bytes.len() >= 4 && WEBM_IDS.iter().any(|x| bytes[..4] == *x) {
+            return Some(Self::WebM);
+        }
+
+        // Sync byte at the start of every packet
+        if bytes.first() == Some(&0x47)
+            && bytes.iter().step_by(TS_PACKET).take(3).all(|x| *x == 0x47)
+        {
+            return Some(Self::Ts);
+        }
+
+        None
+    }
+}
+
+/// `Content-Type` of the first media response and the container of the first chunk, shared with prefetch tasks
+#[derive(Default, Debug)]
+pub(crate) struct Sniffed {
+    content_type: OnceCell<String>,
+    container: OnceCell<Option<ContainerType>>,
+}
+
+impl Sniffed {
+    pub(crate) fn record_response(&self, response: &reqwest::Response) {
+        if let Some(content_type) = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|x| x.to_str().ok())
+        {
+            let _ = self.content_type.set(content_type.to_string());
+        }
+    }
+
+    /// Empty chunks are skipped, the first one with bytes decides
+    pub(crate) fn record_chunk(&self, chunk: &[u8]) {
+        if !chunk.is_empty() {
+            self.container.get_or_init(|| ContainerType::sniff(chunk));
+        }
+    }
+
+    pub(crate) fn content_type(&self) -> Option<&str> {
+        self.content_type.get().map(|x| x.as_str())
+    }
+
+    pub(crate) fn container(&self) -> Option<ContainerType> {
+        self.container.get().copied().flatten()
+    }
+}
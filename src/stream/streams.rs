@@ -1,33 +1,85 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use async_trait::async_trait;
+use bytes::{Bytes, BytesMut};
 use m3u8_rs::parse_media_playlist;
-use tokio::sync::RwLock;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::sync::{mpsc, Mutex, RwLock};
 
 use super::encryption::Encryption;
+use super::index::fragment_offset;
 use super::media_format::MediaFormat;
+use super::progress::{ProgressCallback, ProgressTracker};
 use super::remote_data::RemoteData;
 use super::segment::Segment;
+use super::sniff::{ContainerType, Sniffed};
 
-use crate::constants::DEFAULT_HEADERS;
-use crate::utils::{get_html, make_absolute_url};
-use crate::VideoError;
+use crate::constants::{LIVE_SEGMENT_ATTEMPTS, LIVE_SEGMENT_RETRY_DELAY};
+use crate::structs::RequestOptions;
+use crate::utils::{build_client, get_html, make_absolute_url};
+use crate::{TaskGroup, VideoError};
 
+/// Options of [`NonLiveStream::new`], use `..Default::default()` for the unset fields
+#[derive(Default)]
 pub struct NonLiveStreamOptions {
     pub client: Option<reqwest_middleware::ClientWithMiddleware>,
     pub link: String,
+    /// `0` if unknown, the total of the first response's `Content-Range` is used instead
     pub content_length: u64,
     pub dl_chunk_size: u64,
     pub start: u64,
     pub end: u64,
+    /// Number of chunks to request ahead, `0` disables prefetching
+    pub prefetch_chunks: usize,
+    /// Called after every received chunk
+    pub on_progress: Option<ProgressCallback>,
+    /// Inclusive byte range of the initialization segment, see [`VideoFormat::init_range`](crate::VideoFormat::init_range)
+    pub init_range: Option<(u64, u64)>,
+    /// Inclusive byte range of the `sidx` box or WebM Cues, needed by [`Stream::seek_time`]
+    pub index_range: Option<(u64, u64)>,
+    /// MIME type of the format, see [`VideoFormat::mime_type`](crate::VideoFormat::mime_type)
+    pub content_type: Option<String>,
+    /// Group of the prefetch task, default is [`TaskGroup::shared`]
+    pub tasks: Option<TaskGroup>,
+}
+
+/// Chunk of the prefetch task, `Ok(None)` at the end
+type PrefetchedChunk = Result<Option<Bytes>, VideoError>;
+
+/// Receiver of the prefetch task, `finished` once the end or an error was received
+#[derive(Default)]
+struct Prefetched {
+    receiver: Option<mpsc::Receiver<PrefetchedChunk>>,
+    finished: bool,
+}
+
+/// Times a chunk is requested again if its body fails mid-transfer
+const CHUNK_RETRIES: usize = 2;
+
+/// `link` followed by its mirrors
+fn fallback_links(link: &str) -> Vec<String> {
+    let mirrors = url::Url::parse(link)
+        .map(|url| crate::circuit_breaker::mirror_urls(&url))
+        .unwrap_or_default();
+
+    std::iter::once(link.to_string())
+        .chain(mirrors.into_iter().map(String::from))
+        .collect()
 }
 
 #[async_trait]
 pub trait Stream {
-    /// Stream a chunk of the [`u8`] bytes
+    /// Stream a chunk of the [`u8`] bytes as [`Bytes`], without copying the response buffer
     ///
     /// When the bytes has been exhausted, this will return `None`.
-    async fn chunk(&self) -> Result<Option<Vec<u8>>, VideoError>;
+    async fn chunk(&self) -> Result<Option<Bytes>, VideoError>;
+
+    /// Same as [`Stream::chunk`] but copies the bytes into an owned [`Vec<u8>`]
+    async fn chunk_vec(&self) -> Result<Option<Vec<u8>>, VideoError> {
+        Ok(self.chunk().await?.map(|chunk| chunk.to_vec()))
+    }
 
     /// Content length of the stream
     ///
@@ -35,14 +87,99 @@ pub trait Stream {
     fn content_length(&self) -> usize {
         0
     }
+
+    /// Downloaded and missed segments so far, [`None`] if the stream is not a [`LiveStream`]
+    fn live_report(&self) -> Option<LiveDownloadReport> {
+        None
+    }
+
+    /// MIME type of the streamed bytes, [`None`] if unknown.
+    /// Every stream returned by [`Video::stream`](crate::Video::stream) can be read the same way, live or not
+    fn content_type(&self) -> Option<&str> {
+        None
+    }
+
+    /// `Content-Type` header of the first media response, may disagree with [`Stream::content_type`]
+    fn response_content_type(&self) -> Option<&str> {
+        None
+    }
+
+    /// Container detected from the first received chunk, [`None`] before it or if not recognized.
+    /// Ground truth for muxers when YouTube serves a different container than announced
+    fn sniffed_container(&self) -> Option<ContainerType> {
+        None
+    }
+
+    /// Reposition the stream, the next [`Stream::chunk`] returns bytes starting at `byte_offset`
+    ///
+    /// # Example
+    /// ```ignore
+    ///     // Client asked for `Range: bytes=1048576-`
+    ///     stream.seek(1048576).await.unwrap();
+    ///
+    ///     while let Some(chunk) = stream.chunk().await.unwrap() {
+    ///           println!("{:#?}", chunk);
+    ///     }
+    /// ```
+    async fn seek(&self, _byte_offset: u64) -> Result<(), VideoError> {
+        Err(VideoError::SeekNotSupported)
+    }
+
+    /// Seek to the start of the fragment containing `time` and return its byte offset.
+    /// Needs the index range of an `mp4` or `webm` format
+    async fn seek_time(&self, _time: Duration) -> Result<u64, VideoError> {
+        Err(VideoError::SeekNotSupported)
+    }
+
+    /// Write the remaining bytes of the stream to `writer` and return written bytes count
+    ///
+    /// # Example
+    /// ```ignore
+    ///     let mut file = tokio::fs::File::create("video.mp4").await.unwrap();
+    ///
+    ///     stream.write_to(&mut file).await.unwrap();
+    /// ```
+    async fn write_to(
+        &self,
+        writer: &mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Result<u64, VideoError> {
+        let mut written = 0_u64;
+
+        while let Some(chunk) = self.chunk().await? {
+            writer
+                .write_all(&chunk)
+                .await
+                .map_err(|e| VideoError::DownloadError(e.to_string()))?;
+            written += chunk.len() as u64;
+        }
+
+        writer
+            .flush()
+            .await
+            .map_err(|e| VideoError::DownloadError(e.to_string()))?;
+
+        Ok(written)
+    }
 }
 
 pub struct NonLiveStream {
-    link: String,
-    content_length: u64,
+    /// Link and its mirrors, see [`VideoFormat::fallback_urls`](crate::VideoFormat::fallback_urls)
+    links: Vec<String>,
+    link_index: AtomicUsize,
+    /// Shared with the prefetch fetcher, `0` until known
+    content_length: Arc<AtomicU64>,
     dl_chunk_size: u64,
     start: RwLock<u64>,
     end: RwLock<u64>,
+    prefetch_chunks: usize,
+    prefetched: Mutex<Prefetched>,
+    tasks: TaskGroup,
+    progress: Option<Arc<std::sync::Mutex<ProgressTracker>>>,
+    init_range: Option<(u64, u64)>,
+    index_range: Option<(u64, u64)>,
+    content_type: Option<String>,
+    /// Shared with the prefetch fetcher
+    sniffed: Arc<Sniffed>,
 
     client: reqwest_middleware::ClientWithMiddleware,
 }
@@ -52,7 +189,7 @@ impl NonLiveStream {
         let client = if options.client.is_some() {
             options.client.unwrap()
         } else {
-            let client = reqwest::Client::builder()
+            let client = build_client(&RequestOptions::default())?
                 .build()
                 .map_err(VideoError::Reqwest)?;
 
@@ -71,16 +208,31 @@ impl NonLiveStream {
 
         Ok(Self {
             client,
-            link: options.link,
-            content_length: options.content_length,
+            links: fallback_links(&options.link),
+            link_index: AtomicUsize::new(0),
+            content_length: Arc::new(AtomicU64::new(options.content_length)),
             dl_chunk_size: options.dl_chunk_size,
             start: RwLock::new(options.start),
             end: RwLock::new(options.end),
+            prefetch_chunks: options.prefetch_chunks,
+            prefetched: Mutex::new(Prefetched::default()),
+            init_range: options.init_range,
+            index_range: options.index_range,
+            content_type: options.content_type,
+            sniffed: Arc::new(Sniffed::default()),
+            tasks: options.tasks.unwrap_or_else(TaskGroup::shared),
+            progress: options.on_progress.map(|callback| {
+                Arc::new(std::sync::Mutex::new(ProgressTracker::new(
+                    callback,
+                    Some(options.content_length).filter(|x| *x > 0),
+                )))
+            }),
         })
     }
 
+    /// Total bytes of the format, `0` until the first response if it wasn't known up front
     pub fn content_length(&self) -> u64 {
-        self.content_length
+        self.content_length.load(Ordering::Relaxed)
     }
 
     async fn end_index(&self) -> u64 {
@@ -90,11 +242,9 @@ impl NonLiveStream {
     async fn start_index(&self) -> u64 {
         *self.start.read().await
     }
-}
 
-#[async_trait]
-impl Stream for NonLiveStream {
-    async fn chunk(&self) -> Result<Option<Vec<u8>>, VideoError> {
+    /// Send request for the next range. Returns `None` when all ranges requested
+    async fn next_range(&self) -> Result<Option<(reqwest::Response, (u64, u64))>, VideoError> {
         let end = self.end_index().await;
 
         // Nothing else remain send None to finish
@@ -103,14 +253,48 @@ impl Stream for NonLiveStream {
             return Ok(None);
         }
 
-        if end >= self.content_length {
+        let content_length = self.content_length();
+
+        // Reconciled offset already reached the end
+        if content_length > 0 && self.start_index().await >= content_length {
+            return Ok(None);
+        }
+
+        if content_length == 0 {
+            // Unknown length, the first range is requested as is and the total is taken from its `Content-Range`
+        } else if end >= content_length {
+            // Last range is requested open ended
             let mut end = self.end.write().await;
             *end = 0;
         }
 
-        let mut headers = DEFAULT_HEADERS.clone();
-
+        let start = self.start_index().await;
         let end = self.end_index().await;
+
+        let response = self.request_range(start, end).await?;
+
+        // `416` ends the stream, other errors must not be written as content
+        let status = response.status();
+        if !status.is_success() && status != reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+            return Err(VideoError::FormatRequestFailed(format!(
+                "Range {start}-{end} answered with {status}"
+            )));
+        }
+
+        if end != 0 {
+            let mut start = self.start.write().await;
+            *start = end + 1;
+            let mut end = self.end.write().await;
+            *end += self.dl_chunk_size;
+        }
+
+        Ok(Some((response, (start, end))))
+    }
+
+    /// Send request for `start..=end`, `end` is open if `0`
+    async fn request_range(&self, start: u64, end: u64) -> Result<reqwest::Response, VideoError> {
+        let mut headers = reqwest::header::HeaderMap::new();
+
         let range_end = if end == 0 {
             "".to_string()
         } else {
@@ -119,44 +303,421 @@ impl Stream for NonLiveStream {
 
         headers.insert(
             reqwest::header::RANGE,
-            format!("bytes={}-{}", self.start_index().await, range_end)
-                .parse()
-                .unwrap(),
+            format!("bytes={}-{}", start, range_end).parse().unwrap(),
         );
 
-        let response = self.client.get(&self.link).headers(headers).send().await;
+        // Failed links rotate to the next mirror, every link is tried once
+        let mut attempts = self.links.len();
+
+        loop {
+            let result = self
+                .client
+                .get(self.link())
+                .headers(headers.clone())
+                .send()
+                .await;
+
+            let failed = match &result {
+                Ok(response) => response.status().is_server_error(),
+                Err(_) => true,
+            };
+
+            attempts -= 1;
+            if !failed || attempts == 0 {
+                if let Ok(response) = &result {
+                    if response.status().is_success() {
+                        self.sniffed.record_response(response);
+                        self.record_total(response);
+                    }
+                }
+
+                return result.map_err(VideoError::ReqwestMiddleware);
+            }
+
+            self.rotate_link();
+        }
+    }
 
-        if response.is_err() {
-            return Err(VideoError::ReqwestMiddleware(response.err().unwrap()));
+    /// Learn the content length from `Content-Range: bytes <start>-<end>/<total>` if it isn't known yet
+    fn record_total(&self, response: &reqwest::Response) {
+        if self.content_length() > 0 {
+            return;
         }
 
-        let mut response = response.expect("IMPOSSIBLE");
+        let total = response
+            .headers()
+            .get(reqwest::header::CONTENT_RANGE)
+            .and_then(|x| x.to_str().ok())
+            .and_then(|x| x.rsplit_once('/'))
+            .and_then(|(_, total)| total.trim().parse::<u64>().ok());
 
-        let mut buf: Vec<u8> = vec![];
+        if let Some(total) = total {
+            self.content_length.store(total, Ordering::Relaxed);
 
-        while let Some(chunk) = response.chunk().await.map_err(VideoError::Reqwest)? {
-            let chunk = chunk.to_vec();
-            buf.extend(chunk.iter());
+            if let Some(progress) = &self.progress {
+                if let Ok(mut progress) = progress.lock() {
+                    progress.set_total(total);
+                }
+            }
         }
+    }
 
-        if end != 0 {
-            let mut start = self.start.write().await;
-            *start = end + 1;
-            let mut end = self.end.write().await;
-            *end += self.dl_chunk_size;
+    fn link(&self) -> &str {
+        &self.links[self.link_index.load(Ordering::Relaxed) % self.links.len()]
+    }
+
+    fn rotate_link(&self) {
+        self.link_index.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Continue from the first byte not received if the body is shorter than requested range.
+    /// Empty body means the server has no more bytes, stream is finished
+    async fn reconcile_range(&self, (start, end): (u64, u64), received: u64) {
+        if received == 0 {
+            *self.end.write().await = 0;
+            return;
         }
 
-        Ok(Some(buf))
+        let expected = if end == 0 {
+            self.content_length().saturating_sub(start)
+        } else {
+            end - start + 1
+        };
+
+        if received < expected {
+            *self.start.write().await = start + received;
+            *self.end.write().await = start + received + self.dl_chunk_size;
+        }
+    }
+
+    /// Bytes of the inclusive range `start..=end`
+    async fn fetch_range(&self, (start, end): (u64, u64)) -> Result<Bytes, VideoError> {
+        let response = self.request_range(start, end).await?;
+
+        if !response.status().is_success() {
+            return Err(VideoError::FormatRequestFailed(format!(
+                "Range {start}-{end} answered with {}",
+                response.status()
+            )));
+        }
+
+        response.bytes().await.map_err(VideoError::Reqwest)
+    }
+
+    /// Request the next range and collect its body, the range is requested again if the body fails mid-transfer
+    async fn fetch_chunk(&self) -> Result<Option<Bytes>, VideoError> {
+        let (mut response, (start, end)) = match self.next_range().await? {
+            Some(next) => next,
+            None => return Ok(None),
+        };
+
+        let mut retries = 0;
+
+        loop {
+            // Requested range is past the real end of the content
+            if response.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+                self.reconcile_range((start, end), 0).await;
+                return Ok(None);
+            }
+
+            let mut buf = BytesMut::new();
+
+            let result = async {
+                while let Some(chunk) = response.chunk().await? {
+                    buf.extend_from_slice(&chunk);
+                }
+                Ok::<(), reqwest::Error>(())
+            }
+            .await;
+
+            match result {
+                Ok(()) => {
+                    self.reconcile_range((start, end), buf.len() as u64).await;
+
+                    if buf.is_empty() {
+                        return Ok(None);
+                    }
+
+                    return Ok(Some(buf.freeze()));
+                }
+                Err(err) if retries >= CHUNK_RETRIES => return Err(VideoError::Reqwest(err)),
+                Err(_) => {
+                    retries += 1;
+                    self.record_retransmitted(buf.len() as u64);
+                    self.rotate_link();
+                    response = self.request_range(start, end).await?;
+                }
+            }
+        }
+    }
+
+    fn record_progress(&self, bytes: usize) {
+        if let Some(progress) = &self.progress {
+            if let Ok(mut progress) = progress.lock() {
+                progress.record(bytes as u64);
+            }
+        }
+    }
+
+    fn record_retransmitted(&self, bytes: u64) {
+        if let Some(progress) = &self.progress {
+            if let Ok(mut progress) = progress.lock() {
+                progress.record_retransmitted(bytes);
+            }
+        }
+    }
+
+    /// Receive the next chunk from the prefetch task, spawning it on the first call
+    async fn prefetched_chunk(&self) -> Result<Option<Bytes>, VideoError> {
+        let mut prefetched = self.prefetched.lock().await;
+
+        // Like without prefetching, an ended stream keeps returning `Ok(None)`
+        if prefetched.finished {
+            return Ok(None);
+        }
+
+        if prefetched.receiver.is_none() {
+            // Fetcher continues from the current position with its own range bookkeeping
+            let fetcher = NonLiveStream {
+                links: self.links.clone(),
+                link_index: AtomicUsize::new(self.link_index.load(Ordering::Relaxed)),
+                content_length: self.content_length.clone(),
+                dl_chunk_size: self.dl_chunk_size,
+                start: RwLock::new(self.start_index().await),
+                end: RwLock::new(self.end_index().await),
+                prefetch_chunks: 0,
+                prefetched: Mutex::new(Prefetched::default()),
+                progress: self.progress.clone(),
+                init_range: None,
+                index_range: None,
+                content_type: None,
+                sniffed: self.sniffed.clone(),
+                tasks: self.tasks.clone(),
+                client: self.client.clone(),
+            };
+
+            // Bounded channel, fetcher waits when the buffer is full
+            let (sender, receiver) = mpsc::channel(self.prefetch_chunks);
+
+            self.tasks.spawn(async move {
+                loop {
+                    let chunk = fetcher.fetch_chunk().await;
+                    let is_last = !matches!(chunk, Ok(Some(_)));
+
+                    // Receiver dropped, error or end sent, stop fetching
+                    if sender.send(chunk).await.is_err() || is_last {
+                        break;
+                    }
+                }
+            });
+
+            prefetched.receiver = Some(receiver);
+        }
+
+        let chunk = match prefetched
+            .receiver
+            .as_mut()
+            .expect("IMPOSSIBLE")
+            .recv()
+            .await
+        {
+            Some(chunk) => chunk,
+            // Channel closed before the end
+            None => return Err(VideoError::Cancelled),
+        };
+
+        // Fetcher stopped after sending it
+        if !matches!(chunk, Ok(Some(_))) {
+            *prefetched = Prefetched {
+                receiver: None,
+                finished: true,
+            };
+        }
+
+        chunk
+    }
+}
+
+#[async_trait]
+impl Stream for NonLiveStream {
+    async fn chunk(&self) -> Result<Option<Bytes>, VideoError> {
+        let chunk = if self.prefetch_chunks > 0 {
+            self.prefetched_chunk().await?
+        } else {
+            self.fetch_chunk().await?
+        };
+
+        if let Some(chunk) = &chunk {
+            self.sniffed.record_chunk(chunk);
+            self.record_progress(chunk.len());
+        }
+
+        Ok(chunk)
+    }
+
+    /// Without prefetching, body chunks are written as they arrive instead of collecting the whole range first
+    async fn write_to(
+        &self,
+        writer: &mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Result<u64, VideoError> {
+        let mut written = 0_u64;
+
+        if self.prefetch_chunks > 0 {
+            while let Some(chunk) = self.prefetched_chunk().await? {
+                writer
+                    .write_all(&chunk)
+                    .await
+                    .map_err(|e| VideoError::DownloadError(e.to_string()))?;
+                written += chunk.len() as u64;
+                self.sniffed.record_chunk(&chunk);
+                self.record_progress(chunk.len());
+            }
+        } else {
+            while let Some((mut response, range)) = self.next_range().await? {
+                if response.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+                    break;
+                }
+
+                let mut received = 0_u64;
+
+                while let Some(chunk) = response.chunk().await.map_err(VideoError::Reqwest)? {
+                    writer
+                        .write_all(&chunk)
+                        .await
+                        .map_err(|e| VideoError::DownloadError(e.to_string()))?;
+                    received += chunk.len() as u64;
+                    self.sniffed.record_chunk(&chunk);
+                    self.record_progress(chunk.len());
+                }
+
+                written += received;
+                self.reconcile_range(range, received).await;
+            }
+        }
+
+        writer
+            .flush()
+            .await
+            .map_err(|e| VideoError::DownloadError(e.to_string()))?;
+
+        Ok(written)
     }
 
     fn content_length(&self) -> usize {
         self.content_length() as usize
     }
+
+    fn content_type(&self) -> Option<&str> {
+        self.content_type.as_deref()
+    }
+
+    fn response_content_type(&self) -> Option<&str> {
+        self.sniffed.content_type()
+    }
+
+    fn sniffed_container(&self) -> Option<ContainerType> {
+        self.sniffed.container()
+    }
+
+    async fn seek(&self, byte_offset: u64) -> Result<(), VideoError> {
+        let content_length = self.content_length();
+        if content_length > 0 && byte_offset > content_length {
+            return Err(VideoError::DownloadError(format!(
+                "Seek offset {byte_offset} is past the content length {content_length}"
+            )));
+        }
+
+        // Chunks prefetched from the old position are dropped, the prefetch task stops on the closed channel
+        let mut prefetched = self.prefetched.lock().await;
+        *prefetched = Prefetched::default();
+
+        *self.start.write().await = byte_offset;
+        *self.end.write().await = byte_offset + self.dl_chunk_size;
+
+        if let Some(progress) = &self.progress {
+            if let Ok(mut progress) = progress.lock() {
+                progress.seek(byte_offset);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn seek_time(&self, time: Duration) -> Result<u64, VideoError> {
+        let (index_range, init_range) = match (self.index_range, self.init_range) {
+            (Some(index_range), Some(init_range)) => (index_range, init_range),
+            _ => return Err(VideoError::SeekNotSupported),
+        };
+
+        let index = self.fetch_range(index_range).await?;
+        let init = self.fetch_range((0, init_range.1)).await?;
+
+        let offset = fragment_offset(&init, &index, index_range.0, time)
+            .ok_or(VideoError::SeekNotSupported)?;
+
+        self.seek(offset).await?;
+
+        Ok(offset)
+    }
+}
+
+/// Missed segments of a live download, sequence numbers are inclusive
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct LiveGap {
+    pub discontinuity_sequence: u64,
+    pub first_sequence: u64,
+    pub last_sequence: u64,
+    /// Media duration of the missed segments, estimated with the target duration for expired segments
+    pub duration: Duration,
 }
 
+/// Segment accounting of a [`LiveStream`]
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct LiveDownloadReport {
+    /// Downloaded and missed segments
+    pub segments_total: u64,
+    /// Segments that failed every attempt or expired from the playlist before they were seen
+    pub segments_missed: u64,
+    /// Missed segments in playlist order, adjacent segments are merged
+    pub gaps: Vec<LiveGap>,
+}
+
+impl LiveDownloadReport {
+    fn record_missed(&mut self, discon_seq: u64, first: u64, last: u64, duration: Duration) {
+        let count = last - first + 1;
+        self.segments_total += count;
+        self.segments_missed += count;
+
+        match self.gaps.last_mut() {
+            Some(gap)
+                if gap.discontinuity_sequence == discon_seq && gap.last_sequence + 1 == first =>
+            {
+                gap.last_sequence = last;
+                gap.duration += duration;
+            }
+            _ => self.gaps.push(LiveGap {
+                discontinuity_sequence: discon_seq,
+                first_sequence: first,
+                last_sequence: last,
+                duration,
+            }),
+        }
+    }
+}
+
+/// Failed front segment of a [`LiveStream`]
+struct SegmentRetry {
+    id: (u64, u64),
+    attempts: u32,
+    first_failure: std::time::Instant,
+}
+
+#[derive(Default)]
 pub struct LiveStreamOptions {
     pub client: Option<reqwest_middleware::ClientWithMiddleware>,
     pub stream_url: String,
+    /// Called after every received segment
+    pub on_progress: Option<ProgressCallback>,
 }
 
 pub struct LiveStream {
@@ -167,6 +728,12 @@ pub struct LiveStream {
     segments: RwLock<Vec<(Segment, Encryption)>>,
     is_end: RwLock<bool>,
     last_seg: RwLock<Option<(u64, u64)>>,
+    progress: Option<std::sync::Mutex<ProgressTracker>>,
+    /// Segments stay in the playlist for about the target duration times the playlist length
+    window: RwLock<Duration>,
+    retry: RwLock<Option<SegmentRetry>>,
+    report: std::sync::Mutex<LiveDownloadReport>,
+    sniffed: Sniffed,
 }
 
 impl LiveStream {
@@ -174,7 +741,7 @@ impl LiveStream {
         let client = if options.client.is_some() {
             options.client.unwrap()
         } else {
-            let client = reqwest::Client::builder()
+            let client = build_client(&RequestOptions::default())?
                 .build()
                 .map_err(VideoError::Reqwest)?;
 
@@ -198,6 +765,13 @@ impl LiveStream {
             segments: RwLock::new(vec![]),
             is_end: RwLock::new(false),
             last_seg: RwLock::new(None),
+            window: RwLock::new(Duration::ZERO),
+            retry: RwLock::new(None),
+            report: std::sync::Mutex::new(LiveDownloadReport::default()),
+            sniffed: Sniffed::default(),
+            progress: options
+                .on_progress
+                .map(|callback| std::sync::Mutex::new(ProgressTracker::new(callback, None))),
         })
     }
 
@@ -217,6 +791,57 @@ impl LiveStream {
         *self.last_seg.read().await
     }
 
+    async fn fetch_segment(&self, segment: &Segment) -> Result<Bytes, VideoError> {
+        let response = self.client.get(segment.url().as_str()).send().await?;
+
+        if !response.status().is_success() {
+            return Err(VideoError::DownloadError(format!(
+                "Segment {} answered with {}",
+                segment.id(),
+                response.status()
+            )));
+        }
+
+        self.sniffed.record_response(&response);
+        response.bytes().await.map_err(VideoError::Reqwest)
+    }
+
+    /// Keep a failed front segment for another attempt within its availability window, otherwise drop it as missed
+    async fn segment_failed(&self, segment: &Segment) {
+        let id = (segment.discon_seq, segment.seq);
+        let window = *self.window.read().await;
+
+        let mut retry = self.retry.write().await;
+        let (attempts, first_failure) = match retry.as_ref() {
+            Some(retry) if retry.id == id => (retry.attempts + 1, retry.first_failure),
+            _ => (1, std::time::Instant::now()),
+        };
+
+        if attempts < LIVE_SEGMENT_ATTEMPTS && first_failure.elapsed() < window {
+            *retry = Some(SegmentRetry {
+                id,
+                attempts,
+                first_failure,
+            });
+            drop(retry);
+
+            tokio::time::sleep(LIVE_SEGMENT_RETRY_DELAY).await;
+            return;
+        }
+
+        *retry = None;
+        drop(retry);
+
+        self.segments
+            .write()
+            .await
+            .retain(|x| (x.0.discon_seq, x.0.seq) != id);
+        self.report
+            .lock()
+            .unwrap()
+            .record_missed(id.0, id.1, id.1, segment.duration);
+    }
+
     async fn refresh_playlist(&self) -> Result<(), VideoError> {
         let body = get_html(&self.client, &self.stream_url, None).await?;
 
@@ -242,6 +867,19 @@ impl LiveStream {
                 if s >= (discon_seq, seq) {
                     continue;
                 }
+
+                // Segments between the last seen one and this one expired before a refresh
+                if s.0 == discon_seq && seq > s.1 + 1 {
+                    let duration = Duration::from_secs_f32(media_playlist.target_duration)
+                        * (seq - s.1 - 1) as u32;
+
+                    self.report.lock().unwrap().record_missed(
+                        discon_seq,
+                        s.1 + 1,
+                        seq - 1,
+                        duration,
+                    );
+                }
             }
 
             // Check encryption
@@ -272,6 +910,7 @@ impl LiveStream {
                 data: RemoteData::new(seg_url, segment.byte_range.clone()),
                 discon_seq,
                 seq,
+                duration: Duration::from_secs_f32(segment.duration.max(0.0)),
                 format: MediaFormat::Unknown,
                 initialization: init,
             };
@@ -288,6 +927,10 @@ impl LiveStream {
             }
         }
 
+        *self.window.write().await =
+            Duration::from_secs_f32(media_playlist.target_duration.max(0.0))
+                * media_playlist.segments.len() as u32;
+
         // Set last refresh to check refresh playlist functionality
         let mut last_refresh = self.last_refresh.write().await;
         let start = SystemTime::now();
@@ -310,7 +953,7 @@ impl LiveStream {
 
 #[async_trait]
 impl Stream for LiveStream {
-    async fn chunk(&self) -> Result<Option<Vec<u8>>, VideoError> {
+    async fn chunk(&self) -> Result<Option<Bytes>, VideoError> {
         let segments = self.segments().await;
 
         // if stream end and no segments left end it
@@ -351,40 +994,54 @@ impl Stream for LiveStream {
         // cannot get any segments return empty buffer array
         let segments = self.segments().await;
         if segments.is_empty() {
-            return Ok(Some(vec![]));
+            return Ok(Some(Bytes::new()));
         }
 
         let first_segment = segments.get(0).unwrap();
 
-        let headers = DEFAULT_HEADERS.clone();
-
-        let response = self
-            .client
-            .get(first_segment.0.url().as_str())
-            .headers(headers)
-            .send()
-            .await;
-
-        if response.is_err() {
-            return Err(VideoError::ReqwestMiddleware(response.err().unwrap()));
-        }
-
-        let mut response = response.expect("IMPOSSIBLE");
-
-        let mut buf: Vec<u8> = vec![];
-
-        while let Some(chunk) = response.chunk().await.map_err(VideoError::Reqwest)? {
-            let chunk = chunk.to_vec();
-            buf.extend(chunk.iter());
-        }
+        let buf = match self.fetch_segment(&first_segment.0).await {
+            Ok(buf) => buf,
+            Err(_) => {
+                self.segment_failed(&first_segment.0).await;
+                return Ok(Some(Bytes::new()));
+            }
+        };
 
         // Decrypt data bytes
-        buf = first_segment.1.decrypt(&self.client, &buf).await?;
+        let buf = first_segment.1.decrypt(&self.client, buf).await?;
+        self.sniffed.record_chunk(&buf);
 
         // Delete downloaded segment from segments array
         let mut segment_vector = self.segments.write().await;
         segment_vector.remove(0);
+        drop(segment_vector);
+
+        *self.retry.write().await = None;
+        self.report.lock().unwrap().segments_total += 1;
+
+        if let Some(progress) = &self.progress {
+            if let Ok(mut progress) = progress.lock() {
+                progress.record(buf.len() as u64);
+            }
+        }
 
         Ok(Some(buf))
     }
+
+    fn live_report(&self) -> Option<LiveDownloadReport> {
+        Some(self.report.lock().unwrap().clone())
+    }
+
+    /// HLS segments of YouTube live streams are MPEG-TS
+    fn content_type(&self) -> Option<&str> {
+        Some("video/mp2t")
+    }
+
+    fn response_content_type(&self) -> Option<&str> {
+        self.sniffed.content_type()
+    }
+
+    fn sniffed_container(&self) -> Option<ContainerType> {
+        self.sniffed.container()
+    }
 }
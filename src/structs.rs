@@ -1,7 +1,26 @@
 use serde::{Deserialize, Serialize};
 use std::ops::{Bound, RangeBounds};
 
-#[derive(Debug, Serialize, Deserialize)]
+use crate::constants::{
+    ANDROID_CLIENT_NAME, ANDROID_CLIENT_VERSION, ANDROID_USER_AGENT, AUDIO_ENCODING_RANKS,
+    BASE_URL, DEFAULT_INNERTUBE_API_KEY, DEFAULT_INNERTUBE_CLIENT_VERSION, DESKTOP_CLIENT_HINT,
+    DESKTOP_CLIENT_NAME, DESKTOP_CLIENT_VERSION, DESKTOP_USER_AGENT, INFO_JSON_SCHEMA_VERSION,
+    IOS_CLIENT_NAME, IOS_CLIENT_VERSION, IOS_USER_AGENT, ORIGIN, TV_CLIENT_NAME, TV_CLIENT_VERSION,
+    TV_USER_AGENT, VALID_QUERY_DOMAINS, VIDEO_ENCODING_RANKS,
+};
+use crate::correlation::RequestId;
+
+/// Information about a video. Like the other data model structs it is `#[non_exhaustive]`, so new fields
+/// are not breaking changes. Values are built from [`Default`] with the fields assigned afterwards
+///
+/// # Example
+/// ```ignore
+///     let mut format = VideoFormat::default();
+///     format.itag = 251;
+///     format.mime_type = r#"audio/webm; codecs="opus""#.to_string();
+/// ```
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[non_exhaustive]
 pub struct VideoInfo {
     #[serde(rename = "dashManifestUrl")]
     pub dash_manifest_url: Option<String>,
@@ -12,6 +31,269 @@ pub struct VideoInfo {
     pub related_videos: Vec<RelatedVideo>,
     #[serde(rename = "videoDetails")]
     pub video_details: VideoDetails,
+    #[serde(rename = "captionTracks", default)]
+    pub caption_tracks: Vec<CaptionTrack>,
+    /// Expected response fields which were missing, see [`ExtractionReport`]
+    #[serde(rename = "extractionReport", default)]
+    pub extraction_report: ExtractionReport,
+    /// Innertube client of the extractor the info was extracted with, [`None`] for custom extractors
+    #[serde(rename = "extractionClient", default)]
+    pub extraction_client: Option<InnertubeClient>,
+    /// Products of the merch shelf and product list, see [`Product`]
+    #[serde(default)]
+    pub products: Vec<Product>,
+    /// Tour dates of the ticket shelf, see [`TicketEvent`]
+    #[serde(rename = "ticketEvents", default)]
+    pub ticket_events: Vec<TicketEvent>,
+    /// Polls of the info cards, community post polls can be parsed with [`parse_poll`](crate::parse_poll)
+    #[serde(default)]
+    pub polls: Vec<Poll>,
+    #[serde(skip)]
+    pub(crate) raw_sections: RawSections,
+}
+
+/// Renderer sections of the watch page initial data, as sent by YouTube.
+/// Sections are shared, cloning is cheap
+#[derive(Clone, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct RawSections {
+    /// `videoPrimaryInfoRenderer`, title, views and actions
+    pub primary_info: Option<std::sync::Arc<serde_json::Value>>,
+    /// `videoSecondaryInfoRenderer`, owner, description and metadata rows
+    pub secondary_info: Option<std::sync::Arc<serde_json::Value>>,
+    /// `merchandiseShelfRenderer`, merchandise of the channel
+    pub merch_shelf: Option<std::sync::Arc<serde_json::Value>>,
+    /// `commentsEntryPointHeaderRenderer`, comment count and teaser
+    pub comments_teaser: Option<std::sync::Arc<serde_json::Value>>,
+}
+
+impl RawSections {
+    /// No section was found, like for infos of [`crate::compat`] imports
+    pub fn is_empty(&self) -> bool {
+        self.primary_info.is_none()
+            && self.secondary_info.is_none()
+            && self.merch_shelf.is_none()
+            && self.comments_teaser.is_none()
+    }
+}
+
+/// JSON paths the extraction relies on but YouTube didn't send, early sign of layout changes.
+/// Missing paths are also logged at warn level with the `log` feature
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ExtractionReport {
+    /// Dot separated paths prefixed with `playerResponse` or `initialData`, `*` matches any array element
+    #[serde(rename = "missingPaths", default)]
+    pub missing_paths: Vec<String>,
+}
+
+impl ExtractionReport {
+    /// Every expected path was found
+    pub fn is_clean(&self) -> bool {
+        self.missing_paths.is_empty()
+    }
+}
+
+impl VideoInfo {
+    /// Watch page sections the info was parsed from, empty for deserialized infos
+    pub fn raw_sections(&self) -> &RawSections {
+        &self.raw_sections
+    }
+
+    /// Best format matching the filter, ordered with the default [`FormatSorter`]
+    pub fn best_format(&self, filter: &FormatFilter) -> Option<&VideoFormat> {
+        let sorter = FormatSorter::default();
+
+        self.formats
+            .iter()
+            .filter(|x| filter.matches(x))
+            .min_by(|a, b| sorter.compare(a, b))
+    }
+
+    /// Formats of every part of `id`, in the order of the parts
+    pub fn formats_by_id(&self, id: &FormatId) -> Result<Vec<&VideoFormat>, VideoError> {
+        id.parts()
+            .iter()
+            .map(|part| {
+                self.formats
+                    .iter()
+                    .find(|x| part.matches(x))
+                    .ok_or(VideoError::FormatNotFound)
+            })
+            .collect()
+    }
+
+    /// Highest audio only format of the original audio track (default track if the original is unknown)
+    pub fn best_audio(&self) -> Option<&VideoFormat> {
+        let sorter = FormatSorter::audio();
+        let filter = FormatFilter::has_audio().and(!FormatFilter::has_video());
+
+        let mut formats = self
+            .formats
+            .iter()
+            .filter(|x| filter.matches(x))
+            .collect::<Vec<&VideoFormat>>();
+
+        formats.sort_by(|a, b| sorter.compare(a, b));
+
+        formats
+            .iter()
+            .find(|x| x.audio_track_kind() == Some(AudioTrackKind::Original))
+            .or_else(|| formats.iter().find(|x| x.is_default_audio))
+            .or_else(|| formats.first())
+            .copied()
+    }
+
+    /// Export video info as a stable JSON document for external tools (similar to yt-dlp `info.json`)
+    ///
+    /// Schema (version [`INFO_JSON_SCHEMA_VERSION`]):
+    /// - `schema_version`, `id`, `title`, `description`, `url`, `duration` (seconds), `view_count`,
+    ///   `upload_date`, `publish_date`, `category`, `keywords`, `is_live`, `age_restricted`
+    /// - `channel`: `id`, `name`, `url`
+    /// - `thumbnails`: `url`, `width`, `height`
+    /// - `chapters`: `title`, `start_time` (seconds)
+    /// - `formats`: `itag`, `url` (deciphered), `mime_type`, `container`, `codecs`, `video_codec`, `audio_codec`,
+    ///   `has_video`, `has_audio`, `width`, `height`, `fps`, `bitrate`, `average_bitrate`, `audio_bitrate`,
+    ///   `audio_sample_rate`, `audio_channels`, `content_length`, `duration_ms`, `quality`, `quality_label`,
+    ///   `audio_quality`, `is_live`, `is_hls`, `is_dash_mpd`
+    ///
+    /// Missing values are `null`, numbers sent by Youtube as strings are parsed to numbers
+    pub fn to_info_json(&self) -> serde_json::Value {
+        let details = &self.video_details;
+
+        let formats: Vec<serde_json::Value> =
+            self.formats.iter().map(|x| x.to_info_json()).collect();
+
+        let channel = match &details.author {
+            Some(author) => serde_json::json!({
+                "id": author.id,
+                "name": author.name,
+                "url": author.channel_url,
+            }),
+            None => serde_json::json!({
+                "id": details.channel_id,
+                "name": details.owner_channel_name,
+                "url": details.owner_profile_url,
+            }),
+        };
+
+        serde_json::json!({
+            "schema_version": INFO_JSON_SCHEMA_VERSION,
+            "id": details.video_id,
+            "title": details.title,
+            "description": details.description,
+            "url": details.video_url,
+            "duration": details.duration.as_secs(),
+            "view_count": details.views.count(),
+            "upload_date": details.upload_date,
+            "publish_date": details.publish_date,
+            "category": details.category,
+            "keywords": details.keywords,
+            "is_live": details.is_live_content,
+            "age_restricted": details.age_restricted,
+            "channel": channel,
+            "thumbnails": details
+                .thumbnails
+                .iter()
+                .map(|x| serde_json::json!({ "url": x.url, "width": x.width, "height": x.height }))
+                .collect::<Vec<serde_json::Value>>(),
+            "chapters": details
+                .chapters
+                .iter()
+                .map(|x| serde_json::json!({ "title": x.title, "start_time": x.start_time }))
+                .collect::<Vec<serde_json::Value>>(),
+            "formats": formats,
+        })
+    }
+
+    /// Download sizes of the formats matching `filter`, from `content_length` or estimated from the
+    /// average bitrate and the duration. `combined` is the size of the best video only format matching the
+    /// filter plus [`VideoInfo::best_audio`], like a muxed download
+    pub fn estimate_download_size(&self, filter: &FormatFilter) -> DownloadSizeEstimate {
+        let length = Some(self.video_details.duration).filter(|x| !x.is_zero());
+
+        let estimate = |format: &VideoFormat| {
+            if let Some(bytes) = format.content_length_bytes() {
+                return FormatSizeEstimate {
+                    itag: format.itag,
+                    bytes: Some(bytes),
+                    source: SizeSource::ContentLength,
+                };
+            }
+
+            let bitrate = format.average_bitrate.unwrap_or(format.bitrate);
+
+            match format.duration().or(length) {
+                Some(duration) if bitrate > 0 && !format.is_live => FormatSizeEstimate {
+                    itag: format.itag,
+                    bytes: Some(bitrate * duration.as_millis() as u64 / 8000),
+                    source: SizeSource::Bitrate,
+                },
+                _ => FormatSizeEstimate {
+                    itag: format.itag,
+                    bytes: None,
+                    source: SizeSource::Unknown,
+                },
+            }
+        };
+
+        let video = self.best_format(&filter.clone().and(FormatFilter::video()));
+        let combined = match (video.map(estimate), self.best_audio().map(estimate)) {
+            (Some(video), Some(audio)) => match (video.bytes, audio.bytes) {
+                (Some(video_bytes), Some(audio_bytes)) => Some(FormatSizeEstimate {
+                    itag: video.itag,
+                    bytes: Some(video_bytes + audio_bytes),
+                    source: video.source.max(audio.source),
+                }),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        DownloadSizeEstimate {
+            formats: self
+                .formats
+                .iter()
+                .filter(|x| filter.matches(x))
+                .map(estimate)
+                .collect(),
+            best: self.best_format(filter).map(estimate),
+            combined,
+        }
+    }
+}
+
+/// How the size of a [`FormatSizeEstimate`] is known, ordered from exact to unknown
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, derive_more::Display)]
+pub enum SizeSource {
+    /// Exact `content_length` of the format
+    #[display(fmt = "content length")]
+    ContentLength,
+    /// Average bitrate times duration
+    #[display(fmt = "bitrate")]
+    Bitrate,
+    /// Live formats and formats without bitrate or duration
+    #[display(fmt = "unknown")]
+    Unknown,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FormatSizeEstimate {
+    pub itag: u64,
+    /// Size in bytes, `None` if [`SizeSource::Unknown`]
+    pub bytes: Option<u64>,
+    pub source: SizeSource,
+}
+
+/// Result of [`VideoInfo::estimate_download_size`]
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct DownloadSizeEstimate {
+    /// Every format matching the filter
+    pub formats: Vec<FormatSizeEstimate>,
+    /// Best format matching the filter, like [`VideoInfo::best_format`]
+    pub best: Option<FormatSizeEstimate>,
+    /// Best video only format plus best audio format, the itag is the video one and the source the least exact
+    pub combined: Option<FormatSizeEstimate>,
 }
 
 #[derive(Clone, PartialEq, Debug, derive_more::Display)]
@@ -25,6 +307,173 @@ pub enum VideoSearchOptions {
     /// Only Audio
     #[display(fmt = "Audio")]
     Audio,
+    /// Formats matching the [`FormatFilter`]
+    #[display(fmt = "Custom")]
+    Custom(FormatFilter),
+}
+
+impl From<FormatFilter> for VideoSearchOptions {
+    fn from(filter: FormatFilter) -> Self {
+        Self::Custom(filter)
+    }
+}
+
+/// Composable [`VideoFormat`] predicate
+/// # Example
+/// ```ignore
+///     let video_options = VideoOptions {
+///         quality: VideoQuality::HighestAudio,
+///         filter: FormatFilter::audio()
+///             .and(FormatFilter::container("webm"))
+///             .into(),
+///         ..Default::default()
+///     };
+/// ```
+#[derive(Clone)]
+pub struct FormatFilter(std::sync::Arc<dyn Fn(&VideoFormat) -> bool + Send + Sync>);
+
+impl std::fmt::Debug for FormatFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("FormatFilter")
+    }
+}
+
+/// Filters are equal only if they are clones of each other
+impl PartialEq for FormatFilter {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(
+            std::sync::Arc::as_ptr(&self.0) as *const u8,
+            std::sync::Arc::as_ptr(&other.0) as *const u8,
+        )
+    }
+}
+
+impl FormatFilter {
+    /// Filter from a closure
+    pub fn new(predicate: impl Fn(&VideoFormat) -> bool + Send + Sync + 'static) -> Self {
+        Self(std::sync::Arc::new(predicate))
+    }
+
+    /// Format passes the filter or not
+    pub fn matches(&self, format: &VideoFormat) -> bool {
+        (self.0)(format)
+    }
+
+    /// Both filters must pass
+    pub fn and(self, other: FormatFilter) -> Self {
+        Self::new(move |x| self.matches(x) && other.matches(x))
+    }
+
+    /// One of the filters must pass
+    pub fn or(self, other: FormatFilter) -> Self {
+        Self::new(move |x| self.matches(x) || other.matches(x))
+    }
+
+    /// Every format
+    pub fn any() -> Self {
+        Self::new(|_| true)
+    }
+
+    /// Same as [`VideoSearchOptions::Audio`], only audio formats (and live formats)
+    pub fn audio() -> Self {
+        Self::new(|x| (!x.has_video && x.has_audio) || x.is_live)
+    }
+
+    /// Same as [`VideoSearchOptions::Video`], only video formats (and live formats)
+    pub fn video() -> Self {
+        Self::new(|x| (x.has_video && !x.has_audio) || x.is_live)
+    }
+
+    /// Same as [`VideoSearchOptions::VideoAudio`], formats with both video and audio (and live formats)
+    pub fn video_audio() -> Self {
+        Self::new(|x| (x.has_video && x.has_audio) || x.is_live)
+    }
+
+    /// Formats with audio, muxed or not
+    pub fn has_audio() -> Self {
+        Self::new(|x| x.has_audio)
+    }
+
+    /// Formats with video, muxed or not
+    pub fn has_video() -> Self {
+        Self::new(|x| x.has_video)
+    }
+
+    /// Formats in the given container (`mp4`, `webm`...)
+    pub fn container(container: impl Into<String>) -> Self {
+        let container = container.into();
+        Self::new(move |x| x.container.as_deref() == Some(container.as_str()))
+    }
+
+    /// Formats with a codec starting with `codec` (`avc1`, `vp9`, `opus`...), case-insensitive
+    pub fn codec(codec: impl Into<String>) -> Self {
+        let codec = codec.into().to_lowercase();
+        Self::new(move |x| {
+            x.codecs
+                .as_deref()
+                .unwrap_or("")
+                .to_lowercase()
+                .split(',')
+                .any(|x| x.trim().starts_with(&codec))
+        })
+    }
+
+    /// Formats with the given itag
+    pub fn itag(itag: u64) -> Self {
+        Self::new(move |x| x.itag == itag)
+    }
+
+    /// Video formats with height up to `height` pixels
+    pub fn max_height(height: u64) -> Self {
+        Self::new(move |x| x.height.map(|x| x <= height).unwrap_or(false))
+    }
+
+    /// Video formats with height at least `height` pixels
+    pub fn min_height(height: u64) -> Self {
+        Self::new(move |x| x.height.map(|x| x >= height).unwrap_or(false))
+    }
+
+    /// Formats with bitrate up to `bitrate` bits per second
+    pub fn max_bitrate(bitrate: u64) -> Self {
+        Self::new(move |x| x.bitrate <= bitrate)
+    }
+
+    /// Audio formats with dynamic range compression, see [`VideoFormat::is_drc`]
+    pub fn drc() -> Self {
+        Self::new(|x| x.is_drc)
+    }
+
+    /// Audio formats of the given audio track language, see [`VideoOptions::audio_language`]
+    pub fn audio_language(language: impl Into<String>) -> Self {
+        let language = language.into().to_lowercase();
+        Self::new(move |x| {
+            x.audio_language()
+                .map(|x| {
+                    let x = x.to_lowercase();
+                    x == language || x.starts_with(&format!("{language}-"))
+                })
+                .unwrap_or(false)
+        })
+    }
+}
+
+impl std::ops::Not for FormatFilter {
+    type Output = FormatFilter;
+
+    fn not(self) -> Self::Output {
+        Self::new(move |x| !self.matches(x))
+    }
+}
+
+impl From<&VideoSearchOptions> for FormatFilter {
+    fn from(options: &VideoSearchOptions) -> Self {
+        match options {
+            VideoSearchOptions::VideoAudio => Self::video_audio(),
+            VideoSearchOptions::Video => Self::video(),
+            VideoSearchOptions::Audio => Self::audio(),
+            VideoSearchOptions::Custom(filter) => filter.clone(),
+        }
+    }
 }
 
 #[derive(Clone, PartialEq, Debug, derive_more::Display)]
@@ -49,6 +498,98 @@ pub enum VideoQuality {
     LowestVideo,
 }
 
+pub use rusty_ytdl_core::FormatSortKey;
+
+/// Deterministic [`VideoFormat`] ordering, best format first.
+///
+/// Formats are compared by `keys` in order, the first key which is not equal decides.
+/// Formats that are equal by every key are ordered by ascending itag, so sorting the same list always gives the same result
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct FormatSorter {
+    pub keys: Vec<FormatSortKey>,
+    /// Video codec preference, most preferred first. Matched case-insensitively as prefix of `codecs`
+    pub video_codecs: Vec<String>,
+    /// Audio codec preference, most preferred first. Matched case-insensitively as prefix of `codecs`
+    pub audio_codecs: Vec<String>,
+}
+
+impl Default for FormatSorter {
+    /// Order of `VideoInfo::formats`:
+    /// protocol, audio & video, has video, content length, resolution, fps, bitrate, audio bitrate, video codec, audio codec,
+    /// no DRC
+    fn default() -> Self {
+        Self::new(vec![
+            FormatSortKey::Protocol,
+            FormatSortKey::AudioVideo,
+            FormatSortKey::HasVideo,
+            FormatSortKey::ContentLength,
+            FormatSortKey::Resolution,
+            FormatSortKey::Fps,
+            FormatSortKey::Bitrate,
+            FormatSortKey::AudioBitrate,
+            FormatSortKey::VideoCodec,
+            FormatSortKey::AudioCodec,
+            FormatSortKey::NoDrc,
+        ])
+    }
+}
+
+impl FormatSorter {
+    /// Create sorter with the given keys and default codec preferences
+    pub fn new(keys: Vec<FormatSortKey>) -> Self {
+        Self {
+            keys,
+            video_codecs: VIDEO_ENCODING_RANKS
+                .iter()
+                .rev()
+                .map(|x| x.to_string())
+                .collect(),
+            audio_codecs: AUDIO_ENCODING_RANKS
+                .iter()
+                .rev()
+                .map(|x| x.to_string())
+                .collect(),
+        }
+    }
+
+    /// Sorter used for [`VideoQuality::HighestVideo`] and [`VideoQuality::LowestVideo`]:
+    /// resolution, fps, bitrate, video codec
+    pub fn video() -> Self {
+        Self::new(vec![
+            FormatSortKey::Resolution,
+            FormatSortKey::Fps,
+            FormatSortKey::Bitrate,
+            FormatSortKey::VideoCodec,
+        ])
+    }
+
+    /// Sorter used for [`VideoQuality::HighestAudio`] and [`VideoQuality::LowestAudio`]:
+    /// audio bitrate, audio codec, no DRC
+    pub fn audio() -> Self {
+        Self::new(vec![
+            FormatSortKey::AudioBitrate,
+            FormatSortKey::AudioCodec,
+            FormatSortKey::NoDrc,
+        ])
+    }
+
+    /// Sort formats best first
+    pub fn sort(&self, formats: &mut [VideoFormat]) {
+        formats.sort_by(|a, b| self.compare(a, b));
+    }
+
+    /// Compare formats, [`std::cmp::Ordering::Less`] means `a` is better than `b`
+    pub fn compare(&self, a: &VideoFormat, b: &VideoFormat) -> std::cmp::Ordering {
+        rusty_ytdl_core::compare_formats(
+            &self.keys,
+            &self.video_codecs,
+            &self.audio_codecs,
+            &a.into(),
+            &b.into(),
+        )
+    }
+}
+
 /// Video search and download options
 #[derive(Clone, derive_more::Display, derivative::Derivative)]
 #[display(fmt = "VideoOptions(quality: {quality}, filter: {filter})")]
@@ -59,6 +600,16 @@ pub struct VideoOptions {
     pub download_options: DownloadOptions,
     #[derivative(PartialEq = "ignore")]
     pub request_options: RequestOptions,
+    /// Audio track language (`en`, `es-419`...) for multi-audio videos, original audio track is chosen if `None`
+    pub audio_language: Option<String>,
+    /// Choose the DRC variant of audio formats served with and without dynamic range compression,
+    /// the variant without is chosen by default
+    pub prefer_drc: bool,
+    /// Backend of [`Video::get_basic_info`](crate::Video::get_basic_info), [`WebExtractor`](crate::extractor::WebExtractor) if `None`
+    #[derivative(Debug = "ignore", PartialEq = "ignore")]
+    pub extractor: Option<std::sync::Arc<dyn crate::extractor::Extractor>>,
+    /// Retry failed extractions with other innertube clients, see [`ClientFallback`]
+    pub client_fallback: Option<ClientFallback>,
 }
 
 impl Default for VideoOptions {
@@ -68,20 +619,157 @@ impl Default for VideoOptions {
             filter: VideoSearchOptions::Audio,
             download_options: DownloadOptions::default(),
             request_options: RequestOptions::default(),
+            audio_language: None,
+            prefer_drc: false,
+            extractor: None,
+            client_fallback: None,
+        }
+    }
+}
+
+/// Policy of [`VideoOptions::client_fallback`]: when the extractor fails in one of the enabled ways, the info is
+/// extracted again with the next client of [`ClientFallback::clients`] through an
+/// [`InnertubeExtractor`](crate::extractor::InnertubeExtractor)
+///
+/// # Example
+/// ```ignore
+///     let video_options = VideoOptions {
+///         client_fallback: Some(ClientFallback {
+///             clients: vec![InnertubeClient::Ios, InnertubeClient::Tv],
+///             ..Default::default()
+///         }),
+///         ..Default::default()
+///     };
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ClientFallback {
+    /// Clients tried in order after the extractor of [`VideoOptions::extractor`]
+    pub clients: Vec<InnertubeClient>,
+    /// Retry when the player response has no `streamingData`, like for bot checks of one client
+    pub on_missing_streaming_data: bool,
+    /// Probe the chosen format and retry if it fails, like with `403` for URLs of a wrongly deciphered client.
+    /// Costs one request per extraction
+    pub on_forbidden_format: bool,
+}
+
+impl Default for ClientFallback {
+    fn default() -> Self {
+        Self {
+            clients: vec![
+                InnertubeClient::Android,
+                InnertubeClient::Ios,
+                InnertubeClient::Tv,
+            ],
+            on_missing_streaming_data: true,
+            on_forbidden_format: true,
         }
     }
 }
 
 /// Video download options
-#[derive(Clone, PartialEq, Debug, Default, derive_more::Display)]
+#[derive(Clone, Default, derive_more::Display, derivative::Derivative)]
 #[display(fmt = "DownloadOptions()")]
+#[derivative(Debug, PartialEq)]
 pub struct DownloadOptions {
     /// Maximum chunk size on per request
     pub dl_chunk_size: Option<u64>,
+    /// Number of chunks to request ahead while the current chunk is consumed
+    ///
+    /// Prefetched chunks are kept in a bounded buffer, `0` (default) disables prefetching
+    pub prefetch_chunks: usize,
+    /// Called after every received chunk with downloaded bytes, speed and ETA
+    /// # Example
+    /// ```ignore
+    ///     let download_options = DownloadOptions {
+    ///         on_progress: Some(std::sync::Arc::new(|progress: &DownloadProgress| {
+    ///             println!("{:?}% ETA {:?}", progress.percentage(), progress.eta);
+    ///         })),
+    ///         ..Default::default()
+    ///     };
+    /// ```
+    #[derivative(Debug = "ignore", PartialEq = "ignore")]
+    pub on_progress: Option<crate::stream::ProgressCallback>,
+    /// Keep `.part` file of a failed file download and resume from it on the next download to the same path
+    pub keep_part_files: bool,
+    /// Allocate the whole `.part` file before the first chunk instead of growing it, a full disk fails up front.
+    /// Chunks are written at their offsets, a kept part file resumes from its first missing byte
+    pub preallocate: bool,
+    /// Hash downloaded bytes on the fly, digest is returned in [`DownloadResult::checksum`]
+    pub checksum: Option<ChecksumAlgorithm>,
+    /// Convert downloaded bytes on the fly, downloads write the transcoder output instead of the format bytes.
+    /// Part files are not resumed while transcoding
+    #[derivative(Debug = "ignore", PartialEq = "ignore")]
+    pub transcoder: Option<crate::transcode::TranscoderFactory>,
+    /// Bandwidth shared with other downloads, every stream of the video joins it
+    #[derivative(PartialEq = "ignore")]
+    pub bandwidth: Option<std::sync::Arc<crate::stream::BandwidthBudget>>,
+    /// Switch to the next best format matching [`VideoOptions::filter`] when the URL of the chosen format fails.
+    /// Formats are tried with a one byte request before downloading or streaming, not in the middle of a download
+    pub format_fallback: bool,
+    /// Called when [`DownloadOptions::format_fallback`] skips a format
+    /// # Example
+    /// ```ignore
+    ///     let download_options = DownloadOptions {
+    ///         format_fallback: true,
+    ///         on_format_fallback: Some(std::sync::Arc::new(|fallback: &FormatFallback| {
+    ///             eprintln!("itag {} failed: {}", fallback.failed_itag, fallback.error);
+    ///         })),
+    ///         ..Default::default()
+    ///     };
+    /// ```
+    #[derivative(Debug = "ignore", PartialEq = "ignore")]
+    pub on_format_fallback: Option<FormatFallbackCallback>,
+    /// Group of the background tasks of downloads, default is [`TaskGroup::shared`](crate::TaskGroup::shared)
+    #[derivative(PartialEq = "ignore")]
+    pub tasks: Option<crate::TaskGroup>,
+    /// Steps run on files of [`Video::download`](crate::Video::download), [`Video::download_to_dir`](crate::Video::download_to_dir)
+    /// and playlist downloads, [`DownloadResult::path`] is the path after the last step
+    #[derivative(PartialEq = "ignore")]
+    pub post_process: Option<crate::postprocess::PostProcessChain>,
+}
+
+/// Callback of [`DownloadOptions::on_format_fallback`]
+pub type FormatFallbackCallback = std::sync::Arc<dyn Fn(&FormatFallback) + Send + Sync>;
+
+/// Format skipped by [`DownloadOptions::format_fallback`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FormatFallback {
+    pub failed_itag: u64,
+    /// Format used instead
+    pub fallback_itag: u64,
+    /// Why the failed format was skipped
+    pub error: String,
+    pub request_id: Option<RequestId>,
 }
 
-#[derive(Clone, Debug, Default, derive_more::Display)]
+/// Hash algorithms of [`DownloadOptions::checksum`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, derive_more::Display)]
+pub enum ChecksumAlgorithm {
+    #[display(fmt = "sha256")]
+    Sha256,
+    #[display(fmt = "md5")]
+    Md5,
+}
+
+/// Result of a file download
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[non_exhaustive]
+pub struct DownloadResult {
+    /// Path of the downloaded file
+    pub path: std::path::PathBuf,
+    /// File size in bytes
+    pub size: u64,
+    /// Lowercase hex digest of the downloaded bytes before post-processing, `None` if [`DownloadOptions::checksum`] is not set
+    pub checksum: Option<String>,
+    /// Missed segments of live downloads, `None` for other formats
+    pub live_report: Option<crate::stream::LiveDownloadReport>,
+    /// Files split by [`DownloadOptions::post_process`], like chapters
+    pub parts: Vec<std::path::PathBuf>,
+}
+
+#[derive(Clone, Default, derive_more::Display, derivative::Derivative)]
 #[display(fmt = "RequestOptions()")]
+#[derivative(Debug)]
 pub struct RequestOptions {
     /// [`reqwest::Proxy`] to on use request
     ///
@@ -100,6 +788,35 @@ pub struct RequestOptions {
     ///     };
     /// ```
     pub proxy: Option<reqwest::Proxy>,
+    /// Proxies with health tracking and failover, used when [`RequestOptions::proxy`] is not set.
+    /// See [`ProxyPool`](crate::ProxyPool)
+    pub proxy_pool: Option<crate::ProxyPool>,
+    /// Rotated cookies, visitor data, user agent and proxy, see [`IdentityPool`](crate::IdentityPool).
+    /// [`RequestOptions::cookies`] is ignored when set
+    pub identity_pool: Option<crate::IdentityPool>,
+    /// Media host failover, `None` uses [`CircuitBreaker::shared`](crate::CircuitBreaker::shared).
+    /// See [`CircuitBreaker`](crate::CircuitBreaker)
+    pub circuit_breaker: Option<crate::CircuitBreaker>,
+    /// Send requests one at a time with fixed delays and log them, for reproducing rate limits.
+    /// See [`ThrottledMode`](crate::ThrottledMode)
+    pub throttled_mode: Option<crate::ThrottledMode>,
+    /// Least time between two metadata requests, media requests are not paced.
+    /// See [`RequestPacer`](crate::RequestPacer)
+    pub min_request_interval: Option<crate::RequestPacer>,
+    /// Proxy of metadata requests (watch page, player, search, playlist), overrides [`RequestOptions::proxy`]
+    ///
+    /// # Example
+    /// ```ignore
+    ///     // Proxy the rate limited info requests, download media directly
+    ///     let request_options = RequestOptions {
+    ///          info_proxy: Some(ProxyOverride::Proxy(reqwest::Proxy::all("http://my.prox").unwrap())),
+    ///          stream_proxy: Some(ProxyOverride::Direct),
+    ///          ..Default::default()
+    ///     };
+    /// ```
+    pub info_proxy: Option<ProxyOverride>,
+    /// Proxy of media downloads, overrides [`RequestOptions::proxy`]
+    pub stream_proxy: Option<ProxyOverride>,
     /// Cookies String
     ///
     /// # Example
@@ -123,6 +840,384 @@ pub struct RequestOptions {
     ///     };
     /// ```
     pub ipv6_block: Option<String>,
+    /// Custom headers sent on every request. Overrides same-named headers of the [`HeaderProfile`] of page and
+    /// innertube requests
+    ///
+    /// # Example
+    /// ```ignore
+    ///     let mut headers = reqwest::header::HeaderMap::new();
+    ///     headers.insert(reqwest::header::ACCEPT_LANGUAGE, "en-US".parse().unwrap());
+    ///
+    ///     let request_options = RequestOptions {
+    ///          headers,
+    ///          ..Default::default()
+    ///     };
+    /// ```
+    pub headers: reqwest::header::HeaderMap,
+    /// Pinned addresses per host (e.g. `youtube.com`, `rr1---sn-abc.googlevideo.com`). Bypasses DNS for these hosts
+    ///
+    /// See [`RequestOptions::resolve`]
+    pub dns_overrides: Vec<(String, std::net::SocketAddr)>,
+    /// Custom [`reqwest::dns::Resolve`] used for every host without an override
+    ///
+    /// # Example
+    /// ```ignore
+    ///     let request_options = RequestOptions {
+    ///          dns_resolver: Some(std::sync::Arc::new(MyResolver)),
+    ///          ..Default::default()
+    ///     };
+    /// ```
+    #[derivative(Debug = "ignore")]
+    pub dns_resolver: Option<std::sync::Arc<dyn reqwest::dns::Resolve>>,
+    /// Hosts requests are sent to, see [`Config`]
+    pub config: Config,
+    /// Tokens and client parameters added to media URLs when they are requested, see [`StreamSession`]
+    pub stream_session: StreamSession,
+}
+
+/// Result of [`check_stream_url`](crate::check_stream_url)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StreamUrlStatus {
+    /// Media host answered, `expires_at` is the `expire` parameter of the URL
+    Valid {
+        expires_at: Option<std::time::SystemTime>,
+    },
+    /// `expire` parameter is in the past
+    Expired { expired_at: std::time::SystemTime },
+    /// Media host refused the URL before its expiry, like for another IP or a taken down video
+    Revoked { status: u16 },
+}
+
+/// Query parameters media URLs need at request time instead of at extraction: the PO token (`pot`), the client
+/// (`c`, `cver`) the token was minted for and other parameters like `ctt`. Every media request goes through
+/// [`finalize_url`](crate::finalize_url), so new tokens only have to be added here
+///
+/// # Example
+/// ```ignore
+///     let request_options = RequestOptions {
+///         stream_session: StreamSession {
+///             po_token: Some(po_token),
+///             client_name: Some("WEB".to_string()),
+///             ..Default::default()
+///         },
+///         ..Default::default()
+///     };
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct StreamSession {
+    /// Proof of origin token, sent as `pot`
+    pub po_token: Option<String>,
+    /// Sent as `c`, like `WEB`
+    pub client_name: Option<String>,
+    /// Sent as `cver`
+    pub client_version: Option<String>,
+    /// Other parameters, set after the ones above so they can override them
+    pub params: Vec<(String, String)>,
+}
+
+impl StreamSession {
+    /// Parameters of the session, a name set twice keeps its last value
+    pub fn query_pairs(&self) -> Vec<(&str, &str)> {
+        let pairs = [
+            ("pot", self.po_token.as_deref()),
+            ("c", self.client_name.as_deref()),
+            ("cver", self.client_version.as_deref()),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| value.map(|value| (name, value)))
+        .chain(
+            self.params
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_str())),
+        )
+        .collect::<Vec<(&str, &str)>>();
+
+        pairs
+            .iter()
+            .enumerate()
+            .filter(|(index, (name, _))| !pairs[index + 1..].iter().any(|(x, _)| x == name))
+            .map(|(_, pair)| *pair)
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.query_pairs().is_empty()
+    }
+}
+
+impl RequestOptions {
+    /// [`RequestOptions::cookies`] or the cookies of an [`Identity`](crate::Identity) belong to a signed in Google account.
+    /// Formats which [require Premium](VideoFormat::requires_premium) are only chosen when authenticated
+    pub fn is_authenticated(&self) -> bool {
+        let is_login = |cookies: &str| {
+            cookies.split(';').any(|cookie| {
+                let name = cookie.split('=').next().unwrap_or("").trim();
+                crate::constants::LOGIN_COOKIES.contains(&name)
+            })
+        };
+
+        match &self.identity_pool {
+            Some(pool) => pool
+                .identities()
+                .iter()
+                .any(|x| x.cookies.as_deref().map(is_login).unwrap_or(false)),
+            None => self.cookies.as_deref().map(is_login).unwrap_or(false),
+        }
+    }
+
+    /// Pin `host` to `addr` instead of resolving it. Can be called multiple times for the same host
+    ///
+    /// # Example
+    /// ```ignore
+    ///     let request_options = RequestOptions::default()
+    ///         .resolve("www.youtube.com", "142.250.184.206:443".parse().unwrap())
+    ///         .resolve("rr1---sn-u0g3uxax3-xncl.googlevideo.com", "173.194.8.6:443".parse().unwrap());
+    /// ```
+    pub fn resolve(mut self, host: impl Into<String>, addr: std::net::SocketAddr) -> Self {
+        self.dns_overrides.push((host.into(), addr));
+        self
+    }
+}
+
+/// Hosts used instead of [`BASE_URL`](crate::constants::BASE_URL) and friends, e.g. for an Invidious-compatible host,
+/// a testing server or a region-specific domain. URLs of returned metadata keep pointing to `youtube.com`
+///
+/// # Example
+/// ```ignore
+///     let request_options = RequestOptions {
+///          config: Config {
+///              origin: "http://127.0.0.1:8080".to_string(),
+///              base_url: "http://127.0.0.1:8080/watch?v=".to_string(),
+///              ..Default::default()
+///          },
+///          ..Default::default()
+///     };
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Config {
+    /// Watch page URL the video id is appended to. Default is [`BASE_URL`](crate::constants::BASE_URL)
+    pub base_url: String,
+    /// Origin of search, playlist, player and innertube API requests. Default is [`ORIGIN`](crate::constants::ORIGIN)
+    pub origin: String,
+    /// Hosts whose `?v=` URLs are accepted as video URLs. Default is [`VALID_QUERY_DOMAINS`](crate::constants::VALID_QUERY_DOMAINS)
+    pub valid_query_domains: Vec<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            base_url: BASE_URL.to_string(),
+            origin: ORIGIN.to_string(),
+            valid_query_domains: VALID_QUERY_DOMAINS.iter().map(|x| x.to_string()).collect(),
+        }
+    }
+}
+
+impl Config {
+    /// Watch page URL of `video_id`
+    pub fn watch_url(&self, video_id: &str) -> String {
+        format!("{}{video_id}", self.base_url)
+    }
+
+    /// `path` joined to [`Config::origin`]
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{path}", self.origin.trim_end_matches('/'))
+    }
+}
+
+/// Proxy of [`RequestOptions::info_proxy`] and [`RequestOptions::stream_proxy`]
+#[derive(Clone, Debug)]
+pub enum ProxyOverride {
+    /// Connect directly, system proxies are ignored too
+    Direct,
+    /// Use this proxy
+    Proxy(reqwest::Proxy),
+}
+
+/// Innertube API values read from the `ytcfg` of fetched YouTube pages
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct InnertubeConfig {
+    /// `INNERTUBE_API_KEY`
+    pub api_key: String,
+    /// `INNERTUBE_CONTEXT_CLIENT_VERSION` of the `WEB` client
+    pub client_version: String,
+    /// `VISITOR_DATA`, sent as `visitorData` of the request context
+    pub visitor_data: Option<String>,
+    /// `INNERTUBE_CONTEXT`, [`serde_json::Value::Null`] if the page had none
+    pub context: serde_json::Value,
+}
+
+impl Default for InnertubeConfig {
+    fn default() -> Self {
+        Self {
+            api_key: DEFAULT_INNERTUBE_API_KEY.to_string(),
+            client_version: DEFAULT_INNERTUBE_CLIENT_VERSION.to_string(),
+            visitor_data: None,
+            context: serde_json::Value::Null,
+        }
+    }
+}
+
+impl InnertubeConfig {
+    /// `context` of innertube API requests: the page context with client name, version, language and region of this crate
+    pub fn request_context(&self) -> serde_json::Value {
+        self.client_context(InnertubeClient::Web)
+    }
+
+    /// [`InnertubeConfig::request_context`] of another client
+    pub fn client_context(&self, innertube_client: InnertubeClient) -> serde_json::Value {
+        let mut context = if self.context.is_object() {
+            self.context.clone()
+        } else {
+            serde_json::json!({})
+        };
+
+        let client = &mut context["client"];
+        client["utcOffsetMinutes"] = 0.into();
+        client["gl"] = "US".into();
+        client["hl"] = "en".into();
+        client["clientName"] = innertube_client.name().into();
+        client["clientVersion"] = match innertube_client {
+            InnertubeClient::Web => self.client_version.clone().into(),
+            _ => innertube_client.version().into(),
+        };
+
+        if let Some(visitor_data) = self.visitor_data.as_ref() {
+            client["visitorData"] = visitor_data.clone().into();
+        }
+
+        context
+    }
+}
+
+/// Rules of [`sanitize_filename`](crate::sanitize_filename)
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, derive_more::Display)]
+pub enum SanitizeProfile {
+    /// Windows rules: reserved characters and device names, no trailing dots or spaces
+    #[default]
+    Windows,
+    /// Unix rules: only `/` and NUL are reserved
+    Unix,
+    /// Windows rules and only ASCII letters, digits, `.`, `-` and `_`
+    Strict,
+}
+
+/// Innertube client an [`InnertubeExtractor`](crate::extractor::InnertubeExtractor) requests the player as
+#[derive(
+    Clone, Copy, PartialEq, Eq, Hash, Debug, Default, Serialize, Deserialize, derive_more::Display,
+)]
+pub enum InnertubeClient {
+    #[default]
+    #[display(fmt = "WEB")]
+    Web,
+    #[display(fmt = "ANDROID")]
+    Android,
+    #[display(fmt = "IOS")]
+    Ios,
+    #[display(fmt = "TVHTML5")]
+    Tv,
+}
+
+impl InnertubeClient {
+    /// `clientName` of the request context
+    pub fn name(&self) -> &'static str {
+        match self {
+            InnertubeClient::Web => "WEB",
+            InnertubeClient::Android => "ANDROID",
+            InnertubeClient::Ios => "IOS",
+            InnertubeClient::Tv => "TVHTML5",
+        }
+    }
+
+    /// `clientVersion` of the request context, the web version is read from the page by [`InnertubeConfig`]
+    pub fn version(&self) -> &'static str {
+        match self {
+            InnertubeClient::Web => DEFAULT_INNERTUBE_CLIENT_VERSION,
+            InnertubeClient::Android => ANDROID_CLIENT_VERSION,
+            InnertubeClient::Ios => IOS_CLIENT_VERSION,
+            InnertubeClient::Tv => TV_CLIENT_VERSION,
+        }
+    }
+
+    /// Headers matching the client, sent with its innertube requests
+    pub fn header_profile(&self) -> HeaderProfile {
+        match self {
+            InnertubeClient::Web => HeaderProfile::Desktop,
+            InnertubeClient::Android => HeaderProfile::AndroidApp,
+            InnertubeClient::Ios => HeaderProfile::IosApp,
+            InnertubeClient::Tv => HeaderProfile::Tv,
+        }
+    }
+}
+
+/// Set of consistent `User-Agent` and client hint headers matching a YouTube client
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, derive_more::Display)]
+pub enum HeaderProfile {
+    /// Desktop web browser
+    #[default]
+    #[display(fmt = "Desktop")]
+    Desktop,
+    /// Official Android application
+    #[display(fmt = "Android App")]
+    AndroidApp,
+    /// Official iOS application
+    #[display(fmt = "iOS App")]
+    IosApp,
+    /// Smart TV (Cobalt) application
+    #[display(fmt = "TV")]
+    Tv,
+}
+
+impl HeaderProfile {
+    /// Headers sent with every request made by this profile
+    pub fn headers(&self) -> reqwest::header::HeaderMap {
+        let (user_agent, client_name, client_version) = match self {
+            HeaderProfile::Desktop => (
+                DESKTOP_USER_AGENT,
+                DESKTOP_CLIENT_NAME,
+                DESKTOP_CLIENT_VERSION,
+            ),
+            HeaderProfile::AndroidApp => (
+                ANDROID_USER_AGENT,
+                ANDROID_CLIENT_NAME,
+                ANDROID_CLIENT_VERSION,
+            ),
+            HeaderProfile::IosApp => (IOS_USER_AGENT, IOS_CLIENT_NAME, IOS_CLIENT_VERSION),
+            HeaderProfile::Tv => (TV_USER_AGENT, TV_CLIENT_NAME, TV_CLIENT_VERSION),
+        };
+
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert(
+            reqwest::header::USER_AGENT,
+            reqwest::header::HeaderValue::from_static(user_agent),
+        );
+        headers.insert(
+            "X-YouTube-Client-Name",
+            reqwest::header::HeaderValue::from_static(client_name),
+        );
+        headers.insert(
+            "X-YouTube-Client-Version",
+            reqwest::header::HeaderValue::from_static(client_version),
+        );
+
+        // Only browsers send client hints
+        if *self == HeaderProfile::Desktop {
+            headers.insert(
+                "Sec-CH-UA",
+                reqwest::header::HeaderValue::from_static(DESKTOP_CLIENT_HINT),
+            );
+            headers.insert(
+                "Sec-CH-UA-Mobile",
+                reqwest::header::HeaderValue::from_static("?0"),
+            );
+            headers.insert(
+                "Sec-CH-UA-Platform",
+                reqwest::header::HeaderValue::from_static(r#""Windows""#),
+            );
+        }
+
+        headers
+    }
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -145,6 +1240,9 @@ pub enum VideoError {
     /// URL cannot parsed
     #[error(transparent)]
     URLParseError(#[from] url::ParseError),
+    /// Response or file is not valid JSON
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
     /// Body cannot parsed
     #[error("Body cannot parsed")]
     BodyCannotParsed,
@@ -181,9 +1279,111 @@ pub enum VideoError {
     /// Child process error
     #[error("Process Error: {0}")]
     ChildProcessError(String),
+    /// `ffmpeg` binary can't be found or run
+    #[error("ffmpeg not found: {0}")]
+    FFmpegNotFound(String),
+    /// `ffmpeg` build lacks a muxer or encoder, (binary, requirement)
+    #[error(
+        "ffmpeg at {0} doesn't support {1}, install a build with it or set FFmpegLocator::path"
+    )]
+    FFmpegUnsupported(String, String),
+    /// Post-processing step failed, (step name, error)
+    #[error("Post-processing step {0} failed: {1}")]
+    PostProcessError(String, #[source] Box<VideoError>),
+    /// In-memory download exceeded the size cap
+    #[error("Download size exceeds the limit of {0} bytes")]
+    DownloadSizeLimitExceeded(usize),
+    /// Destination doesn't have enough free space, (required, available) bytes
+    #[error("Insufficient disk space: {0} bytes required, {1} bytes available")]
+    InsufficientDiskSpace(u64, u64),
+    /// Download archive cannot be read or written
+    #[error("Archive Error: {0}")]
+    ArchiveError(String),
+    /// Identity value cannot be used as a header, e.g. invalid characters in the user agent
+    #[error("Invalid identity value: {0}")]
+    InvalidIdentity(String),
+    /// YouTube answered with a captcha page, solve it in a browser with the same IP and cookies
+    #[error("Bot detection, captcha required: {captcha_url}")]
+    BotDetection { captcha_url: String },
+    /// Consent page is still served after sending the consent cookies
+    #[error("Consent required: {0}")]
+    ConsentRequired(String),
+    /// Stream cannot be repositioned, e.g. live streams or formats without an index
+    #[error("Seek not supported")]
+    SeekNotSupported,
+    /// [`CountryCode`] string is not two ASCII letters
+    #[error("Invalid country code: {0}")]
+    InvalidCountryCode(String),
+    /// [`FormatId`] string cannot be parsed
+    #[error("Invalid format id: {0}")]
+    InvalidFormatId(String),
+    /// Saved paginator state cannot be parsed
+    #[error("Invalid paginator state: {0}")]
+    InvalidPaginatorState(String),
+    /// Stream is not a WebM file that can be demuxed
+    #[error("Invalid WebM: {0}")]
+    InvalidWebm(String),
+    /// [`Transcoder`](crate::transcode::Transcoder) failed on downloaded bytes
+    #[error("Transcode error: {0}")]
+    TranscodeError(String),
+    /// MPEG-TS stream cannot be remuxed into MP4
+    #[error("Remux error: {0}")]
+    RemuxError(String),
+    /// [`WebExtractor`](crate::extractor::WebExtractor) is used without the `extract` feature
+    #[error("Extractor unavailable, enable the `extract` feature or set VideoOptions::extractor")]
+    ExtractorUnavailable,
+    /// Media host answered a format request with an error status (`403` of expired or wrongly deciphered URLs...)
+    #[error("Format request failed: {0}")]
+    FormatRequestFailed(String),
+    /// Video has no caption track in the requested language
+    #[error("No caption track for language {0}")]
+    CaptionTrackNotFound(String),
+    /// Storyboard thumbnail cannot be fetched or cut out
+    #[error("Thumbnail error: {0}")]
+    ThumbnailError(String),
+    /// Background task stopped by [`TaskGroup::shutdown`](crate::TaskGroup::shutdown)
+    #[error("Cancelled by shutdown")]
+    Cancelled,
+    /// Channel handle or name does not resolve to a channel
+    #[error("Channel not found: {0}")]
+    ChannelNotFound(String),
+}
+
+/// Where the last failed operation of a [`Video`](crate::Video) happened, see
+/// [`Video::last_error_context`](crate::Video::last_error_context)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ErrorContext {
+    /// Id of the failed operation, in the log lines of the `log` feature too
+    pub request_id: Option<RequestId>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+impl VideoError {
+    /// Hint on fixing common failures, like missing cookies for bot detection or age-restricted videos
+    pub fn help(&self) -> Option<&'static str> {
+        match self {
+            VideoError::BotDetection { .. } => Some(
+                "Pass cookies of a signed in account with RequestOptions::cookies, rotate them with an IdentityPool or use another IP",
+            ),
+            VideoError::ConsentRequired(_) => {
+                Some("Pass a consent cookie like `SOCS=CAI` with RequestOptions::cookies")
+            }
+            VideoError::VideoIsPrivate => Some(
+                "Private and age-restricted videos need cookies of an account allowed to watch them, set RequestOptions::cookies",
+            ),
+            VideoError::FormatRequestFailed(_) => Some(
+                "Format URLs expire after a few hours and are bound to the IP they were fetched from, fetch the info again",
+            ),
+            VideoError::FFmpegNotFound(_) => {
+                Some("Install ffmpeg into PATH or set FFmpegLocator::path")
+            }
+            VideoError::PostProcessError(_, source) => source.help(),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[non_exhaustive]
 pub struct VideoFormat {
     /// Video format itag number
     pub itag: u64,
@@ -250,15 +1450,444 @@ pub struct VideoFormat {
     /// Video format is DashMPD or not
     #[serde(rename = "isDashMPD")]
     pub is_dash_mpd: bool,
+    /// Audio track id of multi-audio videos (`en.4`, `es-419.3`...)
+    #[serde(rename = "audioTrackId", default)]
+    pub audio_track_id: Option<String>, // AUDIO ONLY
+    /// Audio track display name (`English (United States) original`...)
+    #[serde(rename = "audioTrackName", default)]
+    pub audio_track_name: Option<String>, // AUDIO ONLY
+    /// Audio is the default track of the video, formats without audio track info are default
+    #[serde(rename = "isDefaultAudio", default)]
+    pub is_default_audio: bool,
+    /// Audio has dynamic range compression (`xtags=drc`), Youtube serves these next to the same itag without
+    #[serde(rename = "isDrc", default)]
+    pub is_drc: bool, // AUDIO ONLY
+}
+
+impl<'a> From<&'a VideoFormat> for rusty_ytdl_core::FormatFacts<'a> {
+    fn from(format: &'a VideoFormat) -> Self {
+        Self {
+            itag: format.itag,
+            is_hls: format.is_hls,
+            is_dash_mpd: format.is_dash_mpd,
+            has_video: format.has_video,
+            has_audio: format.has_audio,
+            content_length: format.content_length_bytes(),
+            quality_label: format.quality_label.as_deref(),
+            height: format.height,
+            fps: format.fps,
+            bitrate: format.bitrate,
+            audio_bitrate: format.audio_bitrate,
+            codecs: format.codecs.as_deref(),
+            is_drc: format.is_drc,
+        }
+    }
+}
+
+impl VideoFormat {
+    /// Format object of [`VideoInfo::to_info_json`]
+    pub(crate) fn to_info_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "itag": self.itag,
+            "url": self.url,
+            "mime_type": self.mime_type,
+            "container": self.container,
+            "codecs": self.codecs,
+            "video_codec": self.video_codec,
+            "audio_codec": self.audio_codec,
+            "has_video": self.has_video,
+            "has_audio": self.has_audio,
+            "width": self.width,
+            "height": self.height,
+            "fps": self.fps,
+            "bitrate": self.bitrate,
+            "average_bitrate": self.average_bitrate,
+            "audio_bitrate": self.audio_bitrate,
+            "audio_sample_rate": self.sample_rate(),
+            "audio_channels": self.audio_channels,
+            "content_length": self.content_length_bytes(),
+            "duration_ms": self.duration().map(|x| x.as_millis() as u64),
+            "quality": self.quality,
+            "quality_label": self.quality_label,
+            "audio_quality": self.audio_quality,
+            "is_live": self.is_live,
+            "is_hls": self.is_hls,
+            "is_dash_mpd": self.is_dash_mpd,
+        })
+    }
+
+    /// [`VideoFormat::content_length`] as a number
+    pub fn content_length_bytes(&self) -> Option<u64> {
+        self.content_length.as_ref()?.parse().ok()
+    }
+
+    /// [`VideoFormat::approx_duration_ms`] as a [`Duration`](std::time::Duration)
+    pub fn duration(&self) -> Option<std::time::Duration> {
+        self.approx_duration_ms
+            .as_ref()?
+            .parse()
+            .ok()
+            .map(std::time::Duration::from_millis)
+    }
+
+    /// [`VideoFormat::audio_sample_rate`] in Hz
+    pub fn sample_rate(&self) -> Option<u32> {
+        self.audio_sample_rate.as_ref()?.parse().ok()
+    }
+
+    /// Mirror URLs of the format on the other media servers of its `mn` and `fvip` URL parameters.
+    /// Streams rotate to them when a request fails
+    pub fn fallback_urls(&self) -> Vec<String> {
+        url::Url::parse(&self.url)
+            .map(|url| {
+                crate::circuit_breaker::mirror_urls(&url)
+                    .into_iter()
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Language code of the audio track
+    pub fn audio_language(&self) -> Option<&str> {
+        self.audio_track_id
+            .as_deref()
+            .and_then(|x| x.split('.').next())
+            .filter(|x| !x.is_empty())
+    }
+
+    /// Designation of the audio track from `acont` url tag, track name is used if the tag is missing.
+    /// `None` if the format has no audio track info
+    pub fn audio_track_kind(&self) -> Option<AudioTrackKind> {
+        self.audio_track_id.as_ref()?;
+
+        // xtags=acont%3Doriginal%3Alang%3Den-US
+        let url = self.url.replace("%3D", "=").replace("%3d", "=");
+        let acont = url.find("acont=").map(|index| {
+            url[index + "acont=".len()..]
+                .chars()
+                .take_while(|c| c.is_ascii_alphanumeric() || *c == '-')
+                .collect::<String>()
+        });
+
+        let kind = match acont.as_deref() {
+            Some("original") => AudioTrackKind::Original,
+            Some("dubbed-auto") => AudioTrackKind::AutoDubbed,
+            Some("dubbed") | Some("secondary") => AudioTrackKind::Dubbed,
+            Some("descriptive") => AudioTrackKind::Descriptive,
+            _ => {
+                let name = self
+                    .audio_track_name
+                    .as_deref()
+                    .unwrap_or("")
+                    .to_lowercase();
+
+                if name.contains("original") {
+                    AudioTrackKind::Original
+                } else if name.contains("descriptive") {
+                    AudioTrackKind::Descriptive
+                } else {
+                    AudioTrackKind::Dubbed
+                }
+            }
+        };
+
+        Some(kind)
+    }
+
+    /// Enhanced bitrate format (`1080p Premium`) only playing for Youtube Premium accounts, other accounts get `403`s
+    pub fn requires_premium(&self) -> bool {
+        crate::constants::PREMIUM_ITAGS.contains(&self.itag)
+            || self
+                .quality_label
+                .as_deref()
+                .map(|x| x.to_lowercase().contains("premium"))
+                .unwrap_or(false)
+    }
+
+    /// Stable [`FormatId`] of this format
+    pub fn format_id(&self) -> FormatId {
+        FormatId::from(FormatIdPart {
+            itag: self.itag,
+            drc: self.is_drc,
+            protocol: if self.is_hls {
+                FormatProtocol::Hls
+            } else if self.is_dash_mpd {
+                FormatProtocol::DashMpd
+            } else {
+                FormatProtocol::Https
+            },
+            audio_track: self.audio_track_id.clone(),
+        })
+    }
+
+    /// Request the stream layer sends for this format with `request_options`: URL, custom headers and cookies,
+    /// to hand the download to external tools or to debug `403`s. Chunked downloads add a `Range` header,
+    /// [`RequestOptions::identity_pool`] and proxies are not applied
+    pub fn as_http_request(
+        &self,
+        request_options: &RequestOptions,
+    ) -> Result<hyper::Request<()>, VideoError> {
+        use reqwest::cookie::CookieStore;
+
+        let url = url::Url::parse(&crate::utils::finalize_url(
+            self,
+            &request_options.stream_session,
+        )?)?;
+
+        let mut request = hyper::Request::get(url.as_str())
+            .body(())
+            .expect("IMPOSSIBLE");
+        let headers = request.headers_mut();
+        crate::utils::override_headers(headers, &request_options.headers);
+
+        if let Some(cookies) =
+            crate::utils::cookie_jar(request_options).and_then(|jar| jar.cookies(&url))
+        {
+            headers.insert(reqwest::header::COOKIE, cookies);
+        }
+
+        Ok(request)
+    }
+
+    /// `curl` command line of [`VideoFormat::as_http_request`], quoted for POSIX shells
+    pub fn as_curl_command(&self, request_options: &RequestOptions) -> Result<String, VideoError> {
+        let request = self.as_http_request(request_options)?;
+
+        let quote = |x: &str| format!("'{}'", x.replace('\'', "'\\''"));
+
+        let mut command = format!("curl {}", quote(&request.uri().to_string()));
+        for (name, value) in request.headers() {
+            let header = format!("{name}: {}", String::from_utf8_lossy(value.as_bytes()));
+            command.push_str(&format!(" -H {}", quote(&header)));
+        }
+
+        Ok(command)
+    }
+}
+
+/// Designation of a multi-audio track
+#[derive(Clone, Copy, PartialEq, Eq, Debug, derive_more::Display)]
+pub enum AudioTrackKind {
+    /// Original audio of the video
+    Original,
+    /// Dubbed by the uploader
+    Dubbed,
+    /// Automatically dubbed by Youtube
+    AutoDubbed,
+    /// Audio description for visually impaired
+    Descriptive,
+}
+
+/// Delivery protocol of a [`FormatIdPart`]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum FormatProtocol {
+    /// Progressive or adaptive HTTPS stream
+    #[default]
+    Https,
+    /// HLS manifest, `hls` flag
+    Hls,
+    /// DASH manifest, `dash` flag
+    DashMpd,
+}
+
+/// One format of a [`FormatId`]
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct FormatIdPart {
+    pub itag: u64,
+    /// Audio has dynamic range compression, `drc` flag
+    pub drc: bool,
+    pub protocol: FormatProtocol,
+    /// [`VideoFormat::audio_track_id`] of multi-audio videos, after `@`
+    pub audio_track: Option<String>,
+}
+
+impl FormatIdPart {
+    /// `format` has this itag, protocol, DRC and audio track
+    pub fn matches(&self, format: &VideoFormat) -> bool {
+        format.itag == self.itag && format.format_id().0.first() == Some(self)
+    }
+}
+
+impl std::fmt::Display for FormatIdPart {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.itag)?;
+
+        if self.drc {
+            f.write_str("-drc")?;
+        }
+        match self.protocol {
+            FormatProtocol::Https => {}
+            FormatProtocol::Hls => f.write_str("-hls")?,
+            FormatProtocol::DashMpd => f.write_str("-dash")?,
+        }
+        if let Some(audio_track) = &self.audio_track {
+            write!(f, "@{audio_track}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for FormatIdPart {
+    type Err = VideoError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || VideoError::InvalidFormatId(value.to_string());
+
+        // Audio track ids contain `-` and `.`, so they are split off first
+        let (format, audio_track) = match value.split_once('@') {
+            Some((_, "")) => return Err(invalid()),
+            Some((format, audio_track)) => (format, Some(audio_track.to_string())),
+            None => (value, None),
+        };
+
+        let mut flags = format.split('-');
+        let itag = flags
+            .next()
+            .filter(|x| !x.is_empty() && x.bytes().all(|x| x.is_ascii_digit()))
+            .and_then(|x| x.parse().ok())
+            .ok_or_else(invalid)?;
+
+        let mut part = Self {
+            itag,
+            drc: false,
+            protocol: FormatProtocol::Https,
+            audio_track,
+        };
+
+        for flag in flags {
+            match flag {
+                "drc" if !part.drc => part.drc = true,
+                "hls" if part.protocol == FormatProtocol::Https => {
+                    part.protocol = FormatProtocol::Hls
+                }
+                "dash" if part.protocol == FormatProtocol::Https => {
+                    part.protocol = FormatProtocol::DashMpd
+                }
+                _ => return Err(invalid()),
+            }
+        }
+
+        Ok(part)
+    }
+}
+
+/// Stable, machine-readable id of the formats to download: itag, DRC, protocol and audio track of each format,
+/// joined with `+` for separate video and audio formats (`"251-drc"`, `"140@en.4"`, `"137+140"`).
+/// Serialized as the string, so download decisions can be stored and resolved later with
+/// [`VideoInfo::formats_by_id`]
+///
+/// # Example
+/// ```ignore
+///     let id: FormatId = "251-drc".parse().unwrap();
+///
+///     let video_options = VideoOptions {
+///         filter: id.into(),
+///         ..Default::default()
+///     };
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct FormatId(Vec<FormatIdPart>);
+
+impl FormatId {
+    /// Id of separately downloaded formats, like video and audio to mux. `None` if `parts` is empty
+    pub fn new(parts: Vec<FormatIdPart>) -> Option<Self> {
+        if parts.is_empty() {
+            return None;
+        }
+
+        Some(Self(parts))
+    }
+
+    pub fn parts(&self) -> &[FormatIdPart] {
+        &self.0
+    }
+
+    /// Id of `self` and `other` downloaded together, `"137"` and `"140"` give `"137+140"`
+    pub fn join(mut self, other: FormatId) -> Self {
+        self.0.extend(other.0);
+        self
+    }
+}
+
+impl From<FormatIdPart> for FormatId {
+    fn from(part: FormatIdPart) -> Self {
+        Self(vec![part])
+    }
+}
+
+impl std::fmt::Display for FormatId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (index, part) in self.0.iter().enumerate() {
+            if index > 0 {
+                f.write_str("+")?;
+            }
+            write!(f, "{part}")?;
+        }
+
+        Ok(())
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+impl std::str::FromStr for FormatId {
+    type Err = VideoError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        value
+            .split('+')
+            .map(|x| x.parse())
+            .collect::<Result<Vec<FormatIdPart>, VideoError>>()
+            .map(Self)
+    }
+}
+
+impl Serialize for FormatId {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for FormatId {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Formats of any part of the id, single format ids select exactly that format
+impl From<FormatId> for FormatFilter {
+    fn from(id: FormatId) -> Self {
+        Self::new(move |x| id.0.iter().any(|part| part.matches(x)))
+    }
+}
+
+impl From<FormatId> for VideoSearchOptions {
+    fn from(id: FormatId) -> Self {
+        Self::Custom(id.into())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[non_exhaustive]
 pub struct RangeObject {
     pub start: Option<String>,
     pub end: Option<String>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+impl RangeObject {
+    /// Inclusive `(start, end)` byte offsets, `None` if either is missing
+    pub fn bounds(&self) -> Option<(u64, u64)> {
+        Some((
+            self.start.as_ref()?.parse().ok()?,
+            self.end.as_ref()?.parse().ok()?,
+        ))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[non_exhaustive]
 pub struct ColorInfo {
     pub primaries: String,
     #[serde(rename = "transferCharacteristics")]
@@ -267,7 +1896,8 @@ pub struct ColorInfo {
     pub matrix_coefficients: String,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[non_exhaustive]
 pub struct VideoDetails {
     pub author: Option<Author>,
     pub likes: i32,
@@ -281,22 +1911,34 @@ pub struct VideoDetails {
     pub embed: Embed,
     pub title: String,
     pub description: String,
+    /// Raw `lengthSeconds`, see [`VideoDetails::duration`]
     #[serde(rename = "lengthSeconds")]
     pub length_seconds: String,
+    /// Length of the video, zero for live streams
+    #[serde(with = "duration_secs", default)]
+    pub duration: std::time::Duration,
     #[serde(rename = "ownerProfileUrl")]
     pub owner_profile_url: String,
     #[serde(rename = "externalChannelId")]
     pub external_channel_id: String,
     #[serde(rename = "isFamilySafe")]
     pub is_family_safe: bool,
+    /// Countries the video can be watched in
     #[serde(rename = "availableCountries")]
-    pub available_countries: Vec<String>,
+    pub available_countries: Vec<CountryCode>,
+    /// Whether the video can be played in embedded players
+    #[serde(rename = "isEmbeddable", default)]
+    pub is_embeddable: bool,
     #[serde(rename = "isUnlisted")]
     pub is_unlisted: bool,
     #[serde(rename = "hasYpcMetadata")]
     pub has_ypc_metadata: bool,
+    /// Raw `viewCount`, see [`VideoDetails::views`]
     #[serde(rename = "viewCount")]
     pub view_count: String,
+    /// Total views, or the current viewers of a running live stream
+    #[serde(default)]
+    pub views: ViewCount,
     pub category: String,
     #[serde(rename = "publishDate")]
     pub publish_date: String,
@@ -306,7 +1948,11 @@ pub struct VideoDetails {
     pub upload_date: String,
     #[serde(rename = "videoId")]
     pub video_id: String,
+    /// Tags set by the uploader, from `videoDetails.keywords`
     pub keywords: Vec<String>,
+    /// Hashtags linked above the title, with the leading `#`
+    #[serde(default)]
+    pub hashtags: Vec<String>,
     pub channel_id: String,
     #[serde(rename = "isOwnerViewing")]
     pub is_owner_viewing: bool,
@@ -321,14 +1967,153 @@ pub struct VideoDetails {
     #[serde(rename = "isLiveContent")]
     pub is_live_content: bool,
     pub thumbnails: Vec<Thumbnail>,
+    /// Language code of the original audio track, `None` if the video has no audio track info
+    #[serde(rename = "defaultAudioLanguage", default)]
+    pub default_audio_language: Option<String>,
+    /// Video has original and dubbed/descriptive audio tracks
+    #[serde(rename = "hasMultipleAudioTracks", default)]
+    pub has_multiple_audio_tracks: bool,
+    /// Uploader disclosed a paid promotion, like a product placement or sponsorship
+    #[serde(rename = "hasPaidPromotion", default)]
+    pub has_paid_promotion: bool,
+    /// Linked game, song and topic of the video
+    #[serde(rename = "richMetadata", default)]
+    pub rich_metadata: Vec<RichMetadata>,
+}
+
+impl VideoDetails {
+    /// Whether `country` is in [`VideoDetails::available_countries`]. Details without the list, like the ones of
+    /// [`crate::compat`] imports, count as available everywhere
+    pub fn is_available_in(&self, country: &CountryCode) -> bool {
+        self.available_countries.is_empty() || self.available_countries.contains(country)
+    }
+}
+
+/// View count of [`VideoDetails::views`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ViewCount {
+    /// Total views
+    Views(u64),
+    /// People watching a running live stream now
+    Watching(u64),
+}
+
+impl ViewCount {
+    /// Number of views or viewers
+    pub fn count(&self) -> u64 {
+        match self {
+            Self::Views(count) | Self::Watching(count) => *count,
+        }
+    }
+
+    pub fn is_watching(&self) -> bool {
+        matches!(self, Self::Watching(_))
+    }
+}
+
+impl Default for ViewCount {
+    fn default() -> Self {
+        Self::Views(0)
+    }
+}
+
+/// [`std::time::Duration`] as whole seconds
+mod duration_secs {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        duration: &std::time::Duration,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(duration.as_secs())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<std::time::Duration, D::Error> {
+        u64::deserialize(deserializer).map(std::time::Duration::from_secs)
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// ISO 3166-1 alpha-2 country code, always uppercase
+///
+/// # Example
+/// ```ignore
+///     let country: CountryCode = "de".parse().unwrap();
+///
+///     assert_eq!(country.as_str(), "DE");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, derive_more::Display)]
+#[display(fmt = "{}", _0)]
+pub struct CountryCode(String);
+
+impl CountryCode {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::str::FromStr for CountryCode {
+    type Err = VideoError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if value.len() != 2 || !value.chars().all(|x| x.is_ascii_alphabetic()) {
+            return Err(VideoError::InvalidCountryCode(value.to_string()));
+        }
+
+        Ok(Self(value.to_ascii_uppercase()))
+    }
+}
+
+impl Serialize for CountryCode {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for CountryCode {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Time range of an approximate publish text like `3 weeks ago`, see
+/// [`parse_approximate_date`](crate::parse_approximate_date)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ApproximateDate {
+    pub earliest: std::time::SystemTime,
+    pub latest: std::time::SystemTime,
+}
+
+impl ApproximateDate {
+    pub fn contains(&self, time: std::time::SystemTime) -> bool {
+        self.earliest <= time && time <= self.latest
+    }
+
+    /// Middle of the range, a single time for sorting
+    pub fn midpoint(&self) -> std::time::SystemTime {
+        self.earliest
+            + self
+                .latest
+                .duration_since(self.earliest)
+                .unwrap_or_default()
+                / 2
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[non_exhaustive]
 pub struct RelatedVideo {
     pub id: String,
     pub url: String,
     pub title: String,
     pub published: String,
+    /// [`RelatedVideo::published`] as a time range, resolved when the page was fetched
+    #[serde(rename = "publishedDate", default)]
+    pub published_date: Option<ApproximateDate>,
     pub author: Option<Author>,
     #[serde(rename = "shortViewCountText")]
     pub short_view_count_text: String,
@@ -340,7 +2125,8 @@ pub struct RelatedVideo {
     pub is_live: bool,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[non_exhaustive]
 pub struct Author {
     pub id: String,
     pub name: String,
@@ -352,19 +2138,139 @@ pub struct Author {
     #[serde(rename = "userUrl")]
     pub user_url: String,
     pub thumbnails: Vec<Thumbnail>,
+    /// Any verification badge, see [`Author::badge`] to tell artists apart
     pub verified: bool,
+    pub badge: Badge,
+    /// Exact count if known, else parsed from the abbreviated text
     #[serde(rename = "subscriberCount")]
-    pub subscriber_count: i32,
+    pub subscriber_count: u64,
+    /// Count as shown on the watch page, e.g. `1.2M subscribers`
+    #[serde(rename = "subscriberCountText")]
+    pub subscriber_count_text: String,
+    /// `None` while YouTube only shows an abbreviated count
+    #[serde(rename = "subscriberCountExact")]
+    pub subscriber_count_exact: Option<u64>,
+}
+
+/// Metadata linked below the description of a video
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum RichMetadata {
+    /// Box art of the played game
+    Game {
+        title: String,
+        /// Release year
+        year: Option<u32>,
+        url: Option<String>,
+        thumbnails: Vec<Thumbnail>,
+    },
+    /// Song credited in the music section
+    Music {
+        song: String,
+        artist: Option<String>,
+        album: Option<String>,
+        url: Option<String>,
+    },
+    /// Topic like "Gaming" or "Music"
+    Topic { title: String, url: Option<String> },
+}
+
+/// Product of the merch shelf or the product list of a video
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[non_exhaustive]
+pub struct Product {
+    pub title: String,
+    /// Price as displayed, with the currency, like `"$25.00"`
+    pub price: String,
+    /// Store selling the product
+    pub vendor: Option<String>,
+    /// Link to the product page of the store
+    pub url: String,
+    /// Largest thumbnail
+    pub thumbnail: Option<Thumbnail>,
+}
+
+/// Concert of the ticket shelf shown below music videos
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[non_exhaustive]
+pub struct TicketEvent {
+    /// Date as displayed, like `"Jun 14"`
+    pub date: String,
+    pub venue: String,
+    /// City and country of the venue
+    pub location: Option<String>,
+    /// Ticket seller
+    pub vendor: Option<String>,
+    /// Link to the tickets
+    pub url: String,
+}
+
+/// Poll of an info card or a community post
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[non_exhaustive]
+pub struct Poll {
+    /// Question of card polls, community posts have it in the post text
+    pub question: Option<String>,
+    pub choices: Vec<PollChoice>,
+    #[serde(rename = "totalVotes")]
+    pub total_votes: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[non_exhaustive]
+pub struct PollChoice {
+    pub text: String,
+    /// Votes of the choice, if visible
+    pub votes: Option<u64>,
+    /// Share of the votes in `0.0..=100.0`, if visible
+    pub percentage: Option<f32>,
+}
+
+/// Verification badge of a channel
+#[derive(
+    Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize, derive_more::Display,
+)]
+pub enum Badge {
+    #[default]
+    None,
+    /// Check mark of verified channels
+    Verified,
+    /// Music note of official artist channels
+    VerifiedArtist,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[non_exhaustive]
 pub struct Chapter {
     pub title: String,
     #[serde(rename = "startTime")]
     pub start_time: i32,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[non_exhaustive]
+pub struct CaptionTrack {
+    /// Timedtext URL of the track, without format parameter
+    #[serde(rename = "baseUrl")]
+    pub base_url: String,
+    pub name: String,
+    #[serde(rename = "languageCode")]
+    pub language_code: String,
+    /// `asr` for auto-generated tracks
+    pub kind: Option<String>,
+    #[serde(rename = "isTranslatable")]
+    pub is_translatable: bool,
+}
+
+impl CaptionTrack {
+    /// Track is generated by speech recognition
+    pub fn is_auto_generated(&self) -> bool {
+        self.kind.as_deref() == Some("asr")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[non_exhaustive]
 pub struct StoryBoard {
     #[serde(rename = "templateUrl")]
     pub template_url: String,
@@ -381,14 +2287,82 @@ pub struct StoryBoard {
     pub storyboard_count: i32,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+impl From<rusty_ytdl_core::StoryboardLevel> for StoryBoard {
+    fn from(level: rusty_ytdl_core::StoryboardLevel) -> Self {
+        Self {
+            template_url: level.template_url,
+            thumbnail_width: level.thumbnail_width,
+            thumbnail_height: level.thumbnail_height,
+            thumbnail_count: level.thumbnail_count,
+            interval: level.interval,
+            columns: level.columns,
+            rows: level.rows,
+            storyboard_count: level.storyboard_count,
+        }
+    }
+}
+
+impl StoryBoard {
+    /// Tile of the thumbnail shown at `time`. Levels without interval spread their thumbnails over `video_duration`
+    pub fn tile_at(
+        &self,
+        time: std::time::Duration,
+        video_duration: std::time::Duration,
+    ) -> Option<StoryboardTile> {
+        let count = u64::try_from(self.thumbnail_count)
+            .ok()
+            .filter(|x| *x > 0)?;
+        let columns = u32::try_from(self.columns).ok().filter(|x| *x > 0)?;
+        let rows = u32::try_from(self.rows).ok().filter(|x| *x > 0)?;
+
+        let interval = match u64::try_from(self.interval).unwrap_or(0) {
+            0 => (video_duration.as_millis() as u64 / count).max(1),
+            interval => interval,
+        };
+        let index = (time.as_millis() as u64 / interval).min(count - 1);
+
+        let per_sheet = columns as u64 * rows as u64;
+        let sheet = index / per_sheet;
+        let position = (index % per_sheet) as u32;
+        let width = u32::try_from(self.thumbnail_width).ok()?;
+        let height = u32::try_from(self.thumbnail_height).ok()?;
+
+        Some(StoryboardTile {
+            url: self.template_url.replace("$M", &sheet.to_string()),
+            x: position % columns * width,
+            y: position / columns * height,
+            width,
+            height,
+            start: std::time::Duration::from_millis(index * interval),
+        })
+    }
+}
+
+/// Thumbnail in a storyboard sheet, see [`StoryBoard::tile_at`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoryboardTile {
+    /// URL of the sheet image
+    pub url: String,
+    /// Left edge in the sheet in pixels
+    pub x: u32,
+    /// Top edge in the sheet in pixels
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    /// Time of the thumbnail in the video
+    pub start: std::time::Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[non_exhaustive]
 pub struct Thumbnail {
     pub width: u64,
     pub height: u64,
     pub url: String,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[non_exhaustive]
 pub struct Embed {
     #[serde(rename = "flashSecureUrl")]
     pub flash_secure_url: String,
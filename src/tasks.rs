@@ -0,0 +1,152 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::Poll;
+
+use once_cell::sync::Lazy;
+use tokio::sync::{watch, Notify};
+use tokio::task::JoinHandle;
+
+static SHARED: Lazy<TaskGroup> = Lazy::new(TaskGroup::new);
+
+/// Background tasks of streams and downloads, like chunk prefetchers and playlist download workers.
+/// [`TaskGroup::shutdown`] cancels the running tasks at their next `.await` and [`TaskGroup::join`] waits until
+/// they are gone, so long-running hosts can drain before a hot-reload. Tasks spawned after a shutdown run normally
+///
+/// # Example
+/// ```ignore
+///     let tasks = TaskGroup::new();
+///     let video_options = VideoOptions {
+///         download_options: DownloadOptions {
+///             tasks: Some(tasks.clone()),
+///             ..Default::default()
+///         },
+///         ..Default::default()
+///     };
+///
+///     // Before unloading
+///     tasks.shutdown();
+///     tasks.join().await;
+/// ```
+#[derive(Clone, derivative::Derivative)]
+#[derivative(Debug)]
+pub struct TaskGroup {
+    #[derivative(Debug = "ignore")]
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    running: AtomicUsize,
+    idle: Notify,
+    /// Bumped by every shutdown, tasks running at that time stop
+    shutdown: watch::Sender<u64>,
+}
+
+/// Counts a task as running until it is dropped, also when it is cancelled or panics
+struct Running(Arc<Inner>);
+
+impl Running {
+    fn new(inner: Arc<Inner>) -> Self {
+        inner.running.fetch_add(1, Ordering::SeqCst);
+        Self(inner)
+    }
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        if self.0.running.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.idle.notify_waiters();
+        }
+    }
+}
+
+impl TaskGroup {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                running: AtomicUsize::new(0),
+                idle: Notify::new(),
+                shutdown: watch::channel(0).0,
+            }),
+        }
+    }
+
+    /// Group of every download without [`DownloadOptions::tasks`](crate::DownloadOptions::tasks)
+    pub fn shared() -> Self {
+        SHARED.clone()
+    }
+
+    /// Tasks spawned and not finished yet
+    pub fn running(&self) -> usize {
+        self.inner.running.load(Ordering::SeqCst)
+    }
+
+    /// Cancel every running task.
+    /// Streams whose prefetcher is cancelled fail with [`VideoError::Cancelled`](crate::VideoError::Cancelled)
+    pub fn shutdown(&self) {
+        self.inner.shutdown.send_modify(|x| *x += 1);
+    }
+
+    /// Wait until no task is running
+    pub async fn join(&self) {
+        loop {
+            // Registered before the check, so the last task finishing in between is not missed
+            let idle = self.inner.idle.notified();
+            if self.running() == 0 {
+                return;
+            }
+            idle.await;
+        }
+    }
+
+    /// Spawn `task` on the current runtime, [`None`] if it was cancelled by [`TaskGroup::shutdown`]
+    pub(crate) fn spawn<F>(&self, task: F) -> JoinHandle<Option<F::Output>>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        tokio::spawn(self.track(task))
+    }
+
+    /// [`TaskGroup::spawn`] on `runtime`
+    pub(crate) fn spawn_on<F>(
+        &self,
+        runtime: &tokio::runtime::Runtime,
+        task: F,
+    ) -> JoinHandle<Option<F::Output>>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        runtime.spawn(self.track(task))
+    }
+
+    fn track<F: Future>(&self, task: F) -> impl Future<Output = Option<F::Output>> {
+        let task = crate::correlation::in_current_operation(task);
+        let running = Running::new(self.inner.clone());
+        let mut shutdown = self.inner.shutdown.subscribe();
+
+        async move {
+            let _running = running;
+            let mut task = std::pin::pin!(task);
+            let mut cancelled = std::pin::pin!(shutdown.changed());
+
+            std::future::poll_fn(|cx| {
+                if let Poll::Ready(output) = task.as_mut().poll(cx) {
+                    return Poll::Ready(Some(output));
+                }
+                match cancelled.as_mut().poll(cx) {
+                    Poll::Ready(_) => Poll::Ready(None),
+                    Poll::Pending => Poll::Pending,
+                }
+            })
+            .await
+        }
+    }
+}
+
+impl Default for TaskGroup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,174 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use reqwest::{Request, Response};
+use reqwest_middleware::{Middleware, Next};
+use task_local_extensions::Extensions;
+use tokio::sync::Semaphore;
+
+use crate::constants::{THROTTLED_MODE_DELAY, THROTTLED_MODE_JITTER};
+
+/// Reproducible slow request mode for debugging rate limits. Requests sharing one `ThrottledMode` are sent one
+/// at a time, each after a fixed delay plus a jitter derived from the seed and the request number, so two runs
+/// with the same seed wait the same. Chunk prefetching and parallel playlist downloads are turned off, and with
+/// the `log` feature every request and response is logged at info level
+///
+/// # Example
+/// ```ignore
+///     let video_options = VideoOptions {
+///         request_options: RequestOptions {
+///             throttled_mode: Some(ThrottledMode::new(Duration::from_secs(2)).with_seed(42)),
+///             ..Default::default()
+///         },
+///         ..Default::default()
+///     };
+/// ```
+#[derive(Clone, derivative::Derivative)]
+#[derivative(Debug)]
+pub struct ThrottledMode {
+    delay: Duration,
+    jitter: Duration,
+    seed: u64,
+    #[derivative(Debug = "ignore")]
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    permit: Semaphore,
+    requests: AtomicU64,
+}
+
+impl ThrottledMode {
+    /// Wait `delay` before every request, jitter is [`THROTTLED_MODE_JITTER`] with seed `0`
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            jitter: THROTTLED_MODE_JITTER,
+            seed: 0,
+            inner: Arc::new(Inner {
+                permit: Semaphore::new(1),
+                requests: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Largest jitter added to the delay, `Duration::ZERO` waits exactly the delay
+    pub fn with_jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Seed of the jitter, runs with the same seed wait the same
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Requests sent so far
+    pub fn requests(&self) -> u64 {
+        self.inner.requests.load(Ordering::SeqCst)
+    }
+
+    /// Wait before the request number `index`, counted from `0`
+    pub fn delay_for(&self, index: u64) -> Duration {
+        let jitter = self.jitter.as_millis() as u64;
+        if jitter == 0 {
+            return self.delay;
+        }
+
+        self.delay + Duration::from_millis(splitmix64(self.seed ^ index) % (jitter + 1))
+    }
+}
+
+impl Default for ThrottledMode {
+    fn default() -> Self {
+        Self::new(THROTTLED_MODE_DELAY)
+    }
+}
+
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[async_trait::async_trait]
+impl Middleware for ThrottledMode {
+    #[cfg_attr(not(feature = "log"), allow(clippy::let_and_return))]
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        // Held until the response headers arrive, bodies are read after the next request started
+        let _permit = self.inner.permit.acquire().await;
+
+        let index = self.inner.requests.fetch_add(1, Ordering::SeqCst);
+        let delay = self.delay_for(index);
+        tokio::time::sleep(delay).await;
+
+        #[cfg(feature = "log")]
+        log_request(index, delay, &req);
+
+        let result = next.run(req, extensions).await;
+
+        #[cfg(feature = "log")]
+        log_response(index, &result);
+
+        result
+    }
+}
+
+/// Query strings and cookies are left out as they carry signatures and credentials
+#[cfg(feature = "log")]
+fn log_request(index: u64, delay: Duration, req: &Request) {
+    let headers = req
+        .headers()
+        .iter()
+        .filter(|(name, _)| {
+            *name != reqwest::header::COOKIE && *name != reqwest::header::AUTHORIZATION
+        })
+        .map(|(name, value)| format!("{name}: {}", value.to_str().unwrap_or("<binary>")))
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    log::info!(
+        "[{}] throttled #{index} after {} ms: {} {}{} [{headers}]",
+        crate::correlation::log_label(),
+        delay.as_millis(),
+        req.method(),
+        req.url().host_str().unwrap_or(""),
+        req.url().path(),
+    );
+}
+
+#[cfg(feature = "log")]
+fn log_response(index: u64, result: &reqwest_middleware::Result<Response>) {
+    match result {
+        Ok(response) => {
+            let header = |name: reqwest::header::HeaderName| {
+                response
+                    .headers()
+                    .get(name)
+                    .and_then(|x| x.to_str().ok())
+                    .unwrap_or("-")
+                    .to_string()
+            };
+
+            log::info!(
+                "[{}] throttled #{index}: {} (content-length {}, retry-after {})",
+                crate::correlation::log_label(),
+                response.status(),
+                header(reqwest::header::CONTENT_LENGTH),
+                header(reqwest::header::RETRY_AFTER),
+            );
+        }
+        Err(err) => log::info!(
+            "[{}] throttled #{index} failed: {err}",
+            crate::correlation::log_label()
+        ),
+    }
+}
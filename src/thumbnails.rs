@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+use crate::structs::{StoryBoard, VideoError};
+use crate::Video;
+
+impl Video {
+    /// JPEG of the thumbnail closest to `time`, cut out of the largest storyboard sheet.
+    /// Only the sheet image is downloaded, not the video
+    /// # Example
+    /// ```ignore
+    ///     let jpeg = video.thumbnail_at(Duration::from_secs(42)).await.unwrap();
+    ///
+    ///     std::fs::write("preview.jpg", jpeg).unwrap();
+    /// ```
+    pub async fn thumbnail_at(&self, time: Duration) -> Result<Vec<u8>, VideoError> {
+        let info = self.get_basic_info().await?;
+        let video_duration = info.video_details.duration;
+
+        let tile = largest_storyboard(&info.video_details.storyboards)
+            .and_then(|x| x.tile_at(time, video_duration))
+            .ok_or_else(|| VideoError::ThumbnailError("No storyboard".to_string()))?;
+
+        let sheet = self
+            .get_client()
+            .get(&tile.url)
+            .send()
+            .await?
+            .error_for_status()
+            .map_err(VideoError::Reqwest)?
+            .bytes()
+            .await
+            .map_err(VideoError::Reqwest)?;
+
+        let sheet = image::load_from_memory(&sheet)
+            .map_err(|e| VideoError::ThumbnailError(e.to_string()))?;
+
+        // Last sheets are often shorter than the grid
+        if tile.x >= sheet.width() || tile.y >= sheet.height() {
+            return Err(VideoError::ThumbnailError(format!(
+                "Tile at {}x{} is outside of the {}x{} sheet",
+                tile.x,
+                tile.y,
+                sheet.width(),
+                sheet.height()
+            )));
+        }
+        let width = tile.width.min(sheet.width() - tile.x);
+        let height = tile.height.min(sheet.height() - tile.y);
+
+        let mut jpeg = std::io::Cursor::new(vec![]);
+        sheet
+            .crop_imm(tile.x, tile.y, width, height)
+            .to_rgb8()
+            .write_to(&mut jpeg, image::ImageOutputFormat::Jpeg(90))
+            .map_err(|e| VideoError::ThumbnailError(e.to_string()))?;
+
+        Ok(jpeg.into_inner())
+    }
+}
+
+/// Level with the biggest thumbnails, the most frequent ones of the usual storyboards
+fn largest_storyboard(storyboards: &[StoryBoard]) -> Option<&StoryBoard> {
+    storyboards
+        .iter()
+        .filter(|x| x.thumbnail_count > 0 && x.columns > 0 && x.rows > 0)
+        .max_by_key(|x| x.thumbnail_width as i64 * x.thumbnail_height as i64)
+}
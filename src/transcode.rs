@@ -0,0 +1,293 @@
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+
+use crate::stream::{LiveDownloadReport, Stream};
+use crate::structs::{VideoError, VideoFormat};
+
+/// Converts downloaded bytes chunk by chunk while the download runs (decoders, resamplers, encoders)
+///
+/// # Example
+/// ```ignore
+///     struct Passthrough;
+///
+///     impl Transcoder for Passthrough {
+///         fn transcode(&mut self, chunk: &[u8]) -> Result<Bytes, VideoError> {
+///             Ok(Bytes::copy_from_slice(chunk))
+///         }
+///     }
+/// ```
+pub trait Transcoder: Send {
+    /// Output of the next downloaded chunk, may be empty until enough input is buffered
+    fn transcode(&mut self, chunk: &[u8]) -> Result<Bytes, VideoError>;
+
+    /// Remaining output after the last chunk
+    fn finish(&mut self) -> Result<Bytes, VideoError> {
+        Ok(Bytes::new())
+    }
+
+    /// MIME type of the output, [`None`] if unknown
+    fn content_type(&self) -> Option<&'static str> {
+        None
+    }
+}
+
+/// Creates a [`Transcoder`] for every download of [`DownloadOptions::transcoder`](crate::DownloadOptions::transcoder)
+pub type TranscoderFactory = Arc<dyn Fn(&VideoFormat) -> Box<dyn Transcoder> + Send + Sync>;
+
+/// [`Stream`] yielding the output of a [`Transcoder`] fed with the chunks of another stream
+pub struct TranscodeStream {
+    stream: Box<dyn Stream + Send + Sync>,
+    transcoder: Mutex<Option<Box<dyn Transcoder>>>,
+    content_type: Option<&'static str>,
+}
+
+impl TranscodeStream {
+    pub fn new(stream: Box<dyn Stream + Send + Sync>, transcoder: Box<dyn Transcoder>) -> Self {
+        Self {
+            stream,
+            content_type: transcoder.content_type(),
+            transcoder: Mutex::new(Some(transcoder)),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Stream for TranscodeStream {
+    async fn chunk(&self) -> Result<Option<Bytes>, VideoError> {
+        loop {
+            let chunk = self.stream.chunk().await?;
+
+            let mut transcoder = self.transcoder.lock().unwrap();
+            let output = match (transcoder.as_mut(), chunk) {
+                (None, _) => return Ok(None),
+                (Some(transcoder), Some(chunk)) => transcoder.transcode(&chunk)?,
+                (Some(_), None) => {
+                    let output = transcoder.take().expect("IMPOSSIBLE").finish()?;
+                    return Ok((!output.is_empty()).then_some(output));
+                }
+            };
+
+            // Transcoders may buffer whole chunks, skip empty output instead of ending the stream
+            if !output.is_empty() {
+                return Ok(Some(output));
+            }
+        }
+    }
+
+    fn live_report(&self) -> Option<LiveDownloadReport> {
+        self.stream.live_report()
+    }
+
+    fn content_type(&self) -> Option<&str> {
+        self.content_type
+    }
+}
+
+#[cfg(feature = "decode")]
+pub use pcm::PcmDecoder;
+
+#[cfg(feature = "decode")]
+mod pcm {
+    use std::io::Read;
+    use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+    use std::sync::Mutex;
+    use std::thread::JoinHandle;
+
+    use bytes::Bytes;
+    use symphonia::core::audio::SampleBuffer;
+    use symphonia::core::codecs::DecoderOptions;
+    use symphonia::core::errors::Error as SymphoniaError;
+    use symphonia::core::formats::FormatOptions;
+    use symphonia::core::io::{MediaSourceStream, ReadOnlySource};
+    use symphonia::core::meta::MetadataOptions;
+    use symphonia::core::probe::Hint;
+
+    use super::Transcoder;
+    use crate::structs::{VideoError, VideoFormat};
+
+    /// Blocking reader over the chunks sent to [`PcmDecoder`], ends when the decoder is finished
+    struct ChunkReader {
+        // Media sources must be `Sync`
+        chunks: Mutex<Receiver<Vec<u8>>>,
+        chunk: Vec<u8>,
+        pos: usize,
+    }
+
+    impl Read for ChunkReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            while self.pos >= self.chunk.len() {
+                match self.chunks.lock().unwrap().recv() {
+                    Ok(chunk) => {
+                        self.chunk = chunk;
+                        self.pos = 0;
+                    }
+                    Err(_) => return Ok(0),
+                }
+            }
+
+            let n = buf.len().min(self.chunk.len() - self.pos);
+            buf[..n].copy_from_slice(&self.chunk[self.pos..self.pos + n]);
+            self.pos += n;
+
+            Ok(n)
+        }
+    }
+
+    /// [`Transcoder`] decoding `mp4` AAC formats into interleaved little endian `f32` PCM samples, with the
+    /// sample rate and channels of the format. Decoding runs on its own thread, output follows the download.
+    /// Opus formats are not supported, use [`Video::audio_frames`](crate::Video::audio_frames) for them
+    ///
+    /// # Example
+    /// ```ignore
+    ///     let download_options = DownloadOptions {
+    ///         transcoder: Some(std::sync::Arc::new(|format: &VideoFormat| {
+    ///             Box::new(PcmDecoder::new(format)) as Box<dyn Transcoder>
+    ///         })),
+    ///         ..Default::default()
+    ///     };
+    /// ```
+    pub struct PcmDecoder {
+        input: Option<Sender<Vec<u8>>>,
+        output: Receiver<Result<Vec<u8>, VideoError>>,
+        worker: Option<JoinHandle<()>>,
+    }
+
+    impl PcmDecoder {
+        pub fn new(format: &VideoFormat) -> Self {
+            let (input, chunks) = channel();
+            let (samples, output) = channel();
+
+            let mut hint = Hint::new();
+            if let Some(container) = format.container.as_deref() {
+                hint.with_extension(container);
+            }
+
+            let worker = std::thread::spawn(move || {
+                let reader = ChunkReader {
+                    chunks: Mutex::new(chunks),
+                    chunk: vec![],
+                    pos: 0,
+                };
+
+                if let Err(err) = decode(reader, hint, &samples) {
+                    let _ = samples.send(Err(err));
+                }
+            });
+
+            Self {
+                input: Some(input),
+                output,
+                worker: Some(worker),
+            }
+        }
+
+        fn drain(&mut self, wait: bool) -> Result<Bytes, VideoError> {
+            let mut buf = vec![];
+
+            loop {
+                let samples = if wait {
+                    match self.output.recv() {
+                        Ok(samples) => samples,
+                        Err(_) => break,
+                    }
+                } else {
+                    match self.output.try_recv() {
+                        Ok(samples) => samples,
+                        Err(TryRecvError::Empty | TryRecvError::Disconnected) => break,
+                    }
+                };
+
+                buf.extend_from_slice(&samples?);
+            }
+
+            Ok(buf.into())
+        }
+    }
+
+    impl Transcoder for PcmDecoder {
+        fn transcode(&mut self, chunk: &[u8]) -> Result<Bytes, VideoError> {
+            if let Some(input) = self.input.as_ref() {
+                // Worker is gone after a decode error, which is returned by drain
+                let _ = input.send(chunk.to_vec());
+            }
+
+            self.drain(false)
+        }
+
+        fn finish(&mut self) -> Result<Bytes, VideoError> {
+            self.input = None;
+            let output = self.drain(true);
+
+            if let Some(worker) = self.worker.take() {
+                let _ = worker.join();
+            }
+
+            output
+        }
+    }
+
+    fn decode(
+        reader: ChunkReader,
+        hint: Hint,
+        samples: &Sender<Result<Vec<u8>, VideoError>>,
+    ) -> Result<(), VideoError> {
+        let source =
+            MediaSourceStream::new(Box::new(ReadOnlySource::new(reader)), Default::default());
+
+        let mut format = symphonia::default::get_probe()
+            .format(
+                &hint,
+                source,
+                &FormatOptions::default(),
+                &MetadataOptions::default(),
+            )
+            .map_err(|e| VideoError::TranscodeError(e.to_string()))?
+            .format;
+
+        let track = format
+            .default_track()
+            .ok_or_else(|| VideoError::TranscodeError("No audio track".to_string()))?;
+        let track_id = track.id;
+
+        let mut decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions::default())
+            .map_err(|e| VideoError::TranscodeError(e.to_string()))?;
+
+        loop {
+            let packet = match format.next_packet() {
+                Ok(packet) => packet,
+                Err(SymphoniaError::IoError(e))
+                    if e.kind() == std::io::ErrorKind::UnexpectedEof =>
+                {
+                    return Ok(())
+                }
+                Err(e) => return Err(VideoError::TranscodeError(e.to_string())),
+            };
+
+            if packet.track_id() != track_id {
+                continue;
+            }
+
+            let decoded = match decoder.decode(&packet) {
+                Ok(decoded) => decoded,
+                // Corrupted packets are skipped
+                Err(SymphoniaError::DecodeError(_)) => continue,
+                Err(e) => return Err(VideoError::TranscodeError(e.to_string())),
+            };
+
+            let mut buf = SampleBuffer::<f32>::new(decoded.capacity() as u64, *decoded.spec());
+            buf.copy_interleaved_ref(decoded);
+
+            let bytes = buf
+                .samples()
+                .iter()
+                .flat_map(|x| x.to_le_bytes())
+                .collect::<Vec<u8>>();
+
+            if samples.send(Ok(bytes)).is_err() {
+                return Ok(());
+            }
+        }
+    }
+}
@@ -1,17 +1,26 @@
+use once_cell::sync::Lazy;
 use rand::Rng;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use unicode_segmentation::UnicodeSegmentation;
+use unicode_normalization::UnicodeNormalization;
 use urlencoding::decode;
 
 use crate::constants::{
-    AGE_RESTRICTED_URLS, AUDIO_ENCODING_RANKS, BASE_URL, ESCAPING_SEQUENZES, IPV6_REGEX,
-    PARSE_INT_REGEX, VALID_QUERY_DOMAINS, VIDEO_ENCODING_RANKS,
+    AGE_RESTRICTED_URLS, AUDIO_ENCODING_RANKS, BASE_URL, CONSENT_COOKIES,
+    DEFAULT_INNERTUBE_CLIENT_VERSION, ESCAPING_SEQUENZES, INNERTUBE_CONFIG_OVERRIDE,
+    INNERTUBE_CONFIG_TTL, IPV6_REGEX, PARSE_INT_REGEX, PART_RANGES_SAVE_INTERVAL,
+    VALID_QUERY_DOMAINS, VIDEO_ENCODING_RANKS,
+};
+use crate::info_extras::{
+    get_author, get_chapters, get_dislikes, get_hashtags, get_likes, get_live_viewers,
+    get_metadata_row_urls, get_metadata_rows, get_storyboards,
 };
-use crate::info_extras::{get_author, get_chapters, get_dislikes, get_likes, get_storyboards};
 use crate::structs::{
-    Embed, EscapeSequence, StringUtils, Thumbnail, VideoDetails, VideoError, VideoFormat,
-    VideoOptions, VideoQuality, VideoSearchOptions,
+    ApproximateDate, AudioTrackKind, Badge, Chapter, ChecksumAlgorithm, Config, CountryCode, Embed,
+    EscapeSequence, FormatFilter, FormatSorter, InnertubeClient, InnertubeConfig, ProxyOverride,
+    RequestOptions, RichMetadata, SanitizeProfile, StreamSession, StreamUrlStatus, StringUtils,
+    Thumbnail, VideoDetails, VideoError, VideoFormat, VideoInfo, VideoOptions, VideoQuality,
+    VideoSearchOptions, ViewCount,
 };
 
 #[allow(dead_code)]
@@ -174,6 +183,23 @@ pub fn add_format_meta(format: &mut serde_json::Map<String, serde_json::Value>)
         format.insert("codecs".to_string(), serde_json::json!(null));
     }
 
+    // Fall back to the `xtags=drc%3D1` url tag when `isDrc` is missing
+    if !format.contains_key("isDrc") {
+        let is_drc = ["url", "signatureCipher", "cipher"].iter().any(|key| {
+            format
+                .get(*key)
+                .and_then(|x| x.as_str())
+                .map(|x| {
+                    x.replace("%25", "%")
+                        .replace("%3D", "=")
+                        .replace("%3d", "=")
+                        .contains("drc=1")
+                })
+                .unwrap_or(false)
+        });
+        format.insert("isDrc".to_string(), serde_json::Value::Bool(is_drc));
+    }
+
     if format
         .get("hasVideo")
         .and_then(|x| x.as_bool())
@@ -226,6 +252,43 @@ pub fn add_format_meta(format: &mut serde_json::Map<String, serde_json::Value>)
         format.insert("audioCodec".to_string(), serde_json::json!(null));
     }
 
+    // Flatten multi-audio track info
+    let audio_track = format.remove("audioTrack");
+    let has_audio = format
+        .get("hasAudio")
+        .and_then(|x| x.as_bool())
+        .unwrap_or(false);
+
+    format.insert(
+        "audioTrackId".to_string(),
+        audio_track
+            .as_ref()
+            .and_then(|x| x.get("id"))
+            .cloned()
+            .unwrap_or(serde_json::Value::Null),
+    );
+    format.insert(
+        "audioTrackName".to_string(),
+        audio_track
+            .as_ref()
+            .and_then(|x| x.get("displayName"))
+            .cloned()
+            .unwrap_or(serde_json::Value::Null),
+    );
+    format.insert(
+        "isDefaultAudio".to_string(),
+        serde_json::Value::Bool(
+            audio_track
+                .as_ref()
+                .map(|x| {
+                    x.get("audioIsDefault")
+                        .and_then(|x| x.as_bool())
+                        .unwrap_or(false)
+                })
+                .unwrap_or(has_audio),
+        ),
+    );
+
     let regex_is_live = Regex::new(r"\bsource[/=]yt_live_broadcast\b").unwrap();
     let regex_is_hls = Regex::new(r"/manifest/hls_(variant|playlist)/").unwrap();
     let regex_is_dashmpd = Regex::new(r"/manifest/dash/").unwrap();
@@ -252,18 +315,148 @@ pub fn add_format_meta(format: &mut serde_json::Map<String, serde_json::Value>)
     );
 }
 
-pub fn filter_formats(formats: &mut Vec<VideoFormat>, options: &VideoSearchOptions) {
-    match options {
-        VideoSearchOptions::Audio => {
-            formats.retain(|x| (!x.has_video && x.has_audio) || x.is_live);
-        }
-        VideoSearchOptions::Video => {
-            formats.retain(|x| (x.has_video && !x.has_audio) || x.is_live);
-        }
-        _ => {
-            formats.retain(|x| (x.has_video && x.has_audio) || x.is_live);
+/// Keep only formats of the given audio track language (or the original track),
+/// formats are kept untouched if there is no matching track
+pub fn filter_audio_tracks(formats: &mut Vec<VideoFormat>, language: Option<&str>) {
+    let has_original = formats
+        .iter()
+        .any(|x| x.audio_track_kind() == Some(AudioTrackKind::Original));
+
+    let is_matching = |format: &VideoFormat| match language {
+        Some(language) => format
+            .audio_language()
+            .map(|x| {
+                let x = x.to_lowercase();
+                let language = language.to_lowercase();
+
+                x == language || x.starts_with(&format!("{language}-"))
+            })
+            .unwrap_or(false),
+        // Default track can be a dub depending on request language, prefer the original
+        None if has_original => {
+            format.audio_track_kind().is_none()
+                || format.audio_track_kind() == Some(AudioTrackKind::Original)
         }
+        None => format.is_default_audio,
+    };
+
+    if formats.iter().any(|x| x.has_audio && is_matching(x)) {
+        formats.retain(|x| !x.has_audio || is_matching(x));
+    }
+}
+
+/// URL to request `format` with: the format URL with the parameters of `session` set, replacing the ones with the
+/// same name. HLS manifests are returned as they are
+pub fn finalize_url(format: &VideoFormat, session: &StreamSession) -> Result<String, VideoError> {
+    if format.url.is_empty() {
+        return Err(VideoError::VideoSourceNotFound);
+    }
+
+    if format.is_hls || session.is_empty() {
+        return Ok(format.url.clone());
+    }
+
+    let mut url = url::Url::parse(&format.url)?;
+    let session_pairs = session.query_pairs();
+
+    let query = url
+        .query_pairs()
+        .filter(|(name, _)| !session_pairs.iter().any(|(x, _)| x == name))
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect::<Vec<(String, String)>>();
+
+    url.query_pairs_mut()
+        .clear()
+        .extend_pairs(&query)
+        .extend_pairs(&session_pairs);
+
+    Ok(url.to_string())
+}
+
+/// Expiry time of a media URL from its `expire` parameter
+pub fn stream_url_expiry(url: &url::Url) -> Option<std::time::SystemTime> {
+    url.query_pairs()
+        .find(|(name, _)| name == "expire")
+        .and_then(|(_, value)| value.parse::<u64>().ok())
+        .map(|x| std::time::UNIX_EPOCH + std::time::Duration::from_secs(x))
+}
+
+/// Whether a stored media URL can still be requested. URLs past their `expire` parameter are expired without a
+/// request, others are probed for their first byte with the stream proxy of `request_options`, as URLs are bound
+/// to the IP they were extracted from. Other answers than success and `403`/`404`/`410` are returned as
+/// [`VideoError::FormatRequestFailed`], the URL may work on a retry
+/// # Example
+/// ```ignore
+///     match check_stream_url(&cached.url, None).await? {
+///         StreamUrlStatus::Valid { .. } => {}
+///         StreamUrlStatus::Expired { .. } | StreamUrlStatus::Revoked { .. } => {
+///             cached = video.get_info().await?;
+///         }
+///     }
+/// ```
+pub async fn check_stream_url(
+    url: &str,
+    request_options: Option<&RequestOptions>,
+) -> Result<StreamUrlStatus, VideoError> {
+    let parsed = url::Url::parse(url)?;
+    let expires_at = stream_url_expiry(&parsed);
+
+    if let Some(expired_at) = expires_at.filter(|x| *x <= std::time::SystemTime::now()) {
+        return Ok(StreamUrlStatus::Expired { expired_at });
     }
+
+    let default_request_options = RequestOptions::default();
+    let request_options = request_options.unwrap_or(&default_request_options);
+    let proxy_override = request_options.stream_proxy.as_ref();
+
+    let client = build_client_with_proxy(request_options, proxy_override)?
+        .build()
+        .map_err(VideoError::Reqwest)?;
+    let client = with_request_middlewares(
+        reqwest_middleware::ClientBuilder::new(client),
+        request_options,
+        proxy_override,
+        None,
+    )?
+    .build();
+
+    let response = client
+        .get(parsed)
+        .header(reqwest::header::RANGE, "bytes=0-0")
+        .send()
+        .await?;
+
+    let status = response.status();
+    if status.is_success() || status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+        return Ok(StreamUrlStatus::Valid { expires_at });
+    }
+
+    match status.as_u16() {
+        403 | 404 | 410 => Ok(StreamUrlStatus::Revoked {
+            status: status.as_u16(),
+        }),
+        _ => Err(VideoError::FormatRequestFailed(format!(
+            "stream URL answered with {status}"
+        ))),
+    }
+}
+
+/// Keep one variant of formats served with and without DRC, the DRC one if `prefer_drc`
+pub fn filter_drc_variants(formats: &mut Vec<VideoFormat>, prefer_drc: bool) {
+    let twins = formats
+        .iter()
+        .filter(|x| x.is_drc == prefer_drc)
+        .map(|x| (x.itag, x.audio_track_id.clone()))
+        .collect::<std::collections::HashSet<(u64, Option<String>)>>();
+
+    formats
+        .retain(|x| x.is_drc == prefer_drc || !twins.contains(&(x.itag, x.audio_track_id.clone())));
+}
+
+pub fn filter_formats(formats: &mut Vec<VideoFormat>, options: &VideoSearchOptions) {
+    let filter = FormatFilter::from(options);
+
+    formats.retain(|x| filter.matches(x));
 }
 
 /// Try to get format with [`VideoOptions`] filter
@@ -275,6 +468,12 @@ pub fn choose_format<'a>(
     let mut formats = formats.to_owned();
 
     filter_formats(&mut formats, filter);
+    filter_audio_tracks(&mut formats, options.audio_language.as_deref());
+    filter_drc_variants(&mut formats, options.prefer_drc);
+
+    if !options.request_options.is_authenticated() {
+        formats.retain(|x| !x.requires_premium());
+    }
 
     if formats.iter().any(|x| x.is_hls) {
         formats.retain(|fmt| (fmt.is_hls) || !(fmt.is_live));
@@ -351,140 +550,20 @@ pub fn choose_format<'a>(
     }
 }
 
-pub fn sort_formats_by<F>(a: &VideoFormat, b: &VideoFormat, sort_by: Vec<F>) -> std::cmp::Ordering
-where
-    F: Fn(&VideoFormat) -> i32,
-{
-    let mut res = std::cmp::Ordering::Equal;
-
-    for func in sort_by {
-        res = func(b).cmp(&func(a));
-
-        // Is not equal return order
-        if res != std::cmp::Ordering::Equal {
-            break;
-        }
-    }
-
-    res
-}
+static DEFAULT_FORMAT_SORTER: Lazy<FormatSorter> = Lazy::new(FormatSorter::default);
+static VIDEO_FORMAT_SORTER: Lazy<FormatSorter> = Lazy::new(FormatSorter::video);
+static AUDIO_FORMAT_SORTER: Lazy<FormatSorter> = Lazy::new(FormatSorter::audio);
 
 pub fn sort_formats_by_video(a: &VideoFormat, b: &VideoFormat) -> std::cmp::Ordering {
-    sort_formats_by(
-        a,
-        b,
-        [
-            |form: &VideoFormat| {
-                let quality_label = form.quality_label.clone().unwrap_or("".to_string());
-
-                let quality_label = PARSE_INT_REGEX
-                    .captures(&quality_label)
-                    .and_then(|x| x.get(0))
-                    .map(|x| x.as_str())
-                    .and_then(|x| x.parse::<i32>().ok())
-                    .unwrap_or(0i32);
-
-                quality_label
-            },
-            |form: &VideoFormat| form.bitrate as i32,
-            // getVideoEncodingRank,
-            |form: &VideoFormat| {
-                let index = VIDEO_ENCODING_RANKS
-                    .iter()
-                    .position(|enc| {
-                        form.codecs.is_some()
-                            && form.codecs.clone().unwrap_or("".to_string()).contains(enc)
-                    })
-                    .map(|x| x as i32)
-                    .unwrap_or(-1);
-
-                index
-            },
-        ]
-        .to_vec(),
-    )
+    VIDEO_FORMAT_SORTER.compare(a, b)
 }
 
 pub fn sort_formats_by_audio(a: &VideoFormat, b: &VideoFormat) -> std::cmp::Ordering {
-    sort_formats_by(
-        a,
-        b,
-        [
-            |form: &VideoFormat| form.audio_bitrate.unwrap_or(0) as i32,
-            // getAudioEncodingRank,
-            |form: &VideoFormat| {
-                let index = AUDIO_ENCODING_RANKS
-                    .iter()
-                    .position(|enc| {
-                        form.codecs.is_some()
-                            && form.codecs.clone().unwrap_or("".to_string()).contains(enc)
-                    })
-                    .map(|x| x as i32)
-                    .unwrap_or(-1);
-
-                index
-            },
-        ]
-        .to_vec(),
-    )
+    AUDIO_FORMAT_SORTER.compare(a, b)
 }
 
 pub fn sort_formats(a: &VideoFormat, b: &VideoFormat) -> std::cmp::Ordering {
-    sort_formats_by(
-        a,
-        b,
-        [
-            // Formats with both video and audio are ranked highest.
-            |form: &VideoFormat| form.is_hls as i32,
-            |form: &VideoFormat| form.is_dash_mpd as i32,
-            |form: &VideoFormat| (form.has_video && form.has_audio) as i32,
-            |form: &VideoFormat| form.has_video as i32,
-            |form: &VideoFormat| {
-                (form
-                    .content_length
-                    .clone()
-                    .unwrap_or("0".to_string())
-                    .parse::<u64>()
-                    .unwrap_or(0)
-                    > 0) as i32
-            },
-            |form: &VideoFormat| {
-                let quality_label = form.quality_label.clone().unwrap_or("".to_string());
-
-                let quality_label = PARSE_INT_REGEX
-                    .captures(&quality_label)
-                    .and_then(|x| x.get(0))
-                    .map(|x| x.as_str())
-                    .and_then(|x| x.parse::<i32>().ok())
-                    .unwrap_or(0i32);
-
-                quality_label
-            },
-            |form: &VideoFormat| form.bitrate as i32,
-            |form: &VideoFormat| form.audio_bitrate.unwrap_or(0) as i32,
-            // getVideoEncodingRank,
-            |form: &VideoFormat| {
-                let index = VIDEO_ENCODING_RANKS
-                    .iter()
-                    .position(|enc| form.codecs.clone().unwrap_or("".to_string()).contains(enc))
-                    .map(|x| x as i32)
-                    .unwrap_or(-1);
-
-                index
-            },
-            // getAudioEncodingRank,
-            |form: &VideoFormat| {
-                let index = AUDIO_ENCODING_RANKS
-                    .iter()
-                    .position(|enc| form.codecs.clone().unwrap_or("".to_string()).contains(enc))
-                    .map(|x| x as i32)
-                    .unwrap_or(-1);
-
-                index
-            },
-        ]
-        .to_vec(),
-    )
+    DEFAULT_FORMAT_SORTER.compare(a, b)
 }
 
 pub fn set_download_url(
@@ -522,23 +601,13 @@ pub fn set_download_url(
             }
         }
 
-        let decipher_script = js_sandbox::Script::from_string(decipher_script_string.1.as_str());
-
-        if decipher_script.is_err() {
-            if args.get("url").is_none() {
-                return url.to_string();
-            } else {
-                let args_url = args.get("url").and_then(|x| x.as_str()).unwrap_or("");
-                return args_url.to_string();
-            }
-        }
-
-        let result = decipher_script.unwrap().call(
-            decipher_script_string.0.as_str(),
-            (&args.get("s").and_then(|x| x.as_str()).unwrap_or(""),),
+        let result = call_js(
+            &decipher_script_string.1,
+            &decipher_script_string.0,
+            args.get("s").and_then(|x| x.as_str()).unwrap_or(""),
         );
 
-        if result.is_err() {
+        if result.is_none() {
             if args.get("url").is_none() {
                 return url.to_string();
             } else {
@@ -598,19 +667,13 @@ pub fn set_download_url(
             return url.to_string();
         }
 
-        let n_transform_script =
-            js_sandbox::Script::from_string(n_transform_script_string.1.as_str());
-
-        if n_transform_script.is_err() {
-            return url.to_string();
-        }
-
-        let result = n_transform_script.unwrap().call(
-            n_transform_script_string.0.as_str(),
-            (&components.get("n").and_then(|x| x.as_str()).unwrap_or(""),),
+        let result = call_js(
+            &n_transform_script_string.1,
+            &n_transform_script_string.0,
+            components.get("n").and_then(|x| x.as_str()).unwrap_or(""),
         );
 
-        if result.is_err() {
+        if result.is_none() {
             return url.to_string();
         }
 
@@ -691,76 +754,35 @@ pub fn set_download_url(
     serde_json::json!(return_url.to_string())
 }
 
-/// Excavate video id from URLs or id with Regex
-pub fn get_video_id(url: &str) -> Option<String> {
-    let url_regex = Regex::new(r"^https?://").unwrap();
+/// Call `function` of `script` with a string argument, [`None`] if the script fails
+#[cfg(feature = "extract")]
+fn call_js(script: &str, function: &str, arg: &str) -> Option<String> {
+    let mut script = js_sandbox::Script::from_string(script).ok()?;
 
-    if validate_id(url.to_string()) {
-        Some(url.to_string())
-    } else if url_regex.is_match(url.trim()) {
-        get_url_video_id(url)
-    } else {
-        None
-    }
+    script.call(function, (&arg,)).ok()
 }
 
-pub fn validate_id(id: String) -> bool {
-    let id_regex = Regex::new(r"^[a-zA-Z0-9-_]{11}$").unwrap();
-
-    id_regex.is_match(id.trim())
+/// Player scripts can't run without the `extract` feature, URLs are left as they are
+#[cfg(not(feature = "extract"))]
+fn call_js(_script: &str, _function: &str, _arg: &str) -> Option<String> {
+    None
 }
 
-fn get_url_video_id(url: &str) -> Option<String> {
-    let valid_path_domains =
-        // Regex::new(r"^https?:\\//\\//(youtu\.be\\//|(www\.)?youtube\.com\\//(embed|v|shorts)\\//)")
-        //     .unwrap();
-        Regex::new(r"(?m)(?:^|\W)(?:youtube(?:-nocookie)?\.com/(?:.*[?&]v=|v/|shorts/|e(?:mbed)?/|[^/]+/.+/)|youtu\.be/)([\w-]+)")
-        .unwrap();
-
-    let parsed_result = url::Url::parse(url.trim());
-
-    if parsed_result.is_err() {
-        return None;
-    }
-
-    let parsed = url::Url::parse(url.trim()).unwrap();
-
-    let mut id: Option<String> = None;
-
-    for value in parsed.query_pairs() {
-        if value.0.to_string().as_str() == "v" {
-            id = Some(value.1.to_string());
-        }
-    }
-
-    if valid_path_domains.is_match(url.trim()) && id.is_none() {
-        let captures = valid_path_domains.captures(url.trim());
-        // println!("{:#?}", captures);
-        if let Some(captures_some) = captures {
-            let id_group = captures_some.get(1);
-            if let Some(id_group_some) = id_group {
-                id = Some(id_group_some.as_str().to_string());
-            }
-        }
-    } else if url::Url::parse(url.trim()).unwrap().host_str().is_some()
-        && !VALID_QUERY_DOMAINS
-            .iter()
-            .any(|domain| domain == &parsed.host_str().unwrap_or(""))
-    {
-        return None;
-    }
-
-    if let Some(id_some) = id {
-        id = Some(id_some.substring(0, 11).to_string());
+/// Excavate video id from URLs or id with Regex
+pub fn get_video_id(url: &str) -> Option<String> {
+    get_video_id_with_domains(url, VALID_QUERY_DOMAINS)
+}
 
-        if !validate_id(id.clone().unwrap()) {
-            return None;
-        }
+/// [`get_video_id`] accepting `?v=` URLs of `valid_query_domains`, see [`Config::valid_query_domains`]
+pub fn get_video_id_with_domains<S: AsRef<str>>(
+    url: &str,
+    valid_query_domains: &[S],
+) -> Option<String> {
+    rusty_ytdl_core::parse_video_id(url, valid_query_domains).map(|x| x.to_string())
+}
 
-        id
-    } else {
-        None
-    }
+pub fn validate_id(id: String) -> bool {
+    rusty_ytdl_core::validate_id(&id)
 }
 
 pub fn get_text(obj: &serde_json::Value) -> &serde_json::Value {
@@ -784,7 +806,7 @@ pub fn get_text(obj: &serde_json::Value) -> &serde_json::Value {
 pub fn clean_video_details(
     initial_response: &serde_json::Value,
     player_response: &serde_json::Value,
-    media: serde_json::Value,
+    media: Vec<RichMetadata>,
     id: String,
 ) -> VideoDetails {
     let empty_serde_object = serde_json::json!({});
@@ -808,9 +830,20 @@ pub fn clean_video_details(
         .get("embed")
         .and_then(|x| x.as_object())
         .unwrap_or(&empty_serde_map);
+    let length_seconds = data
+        .get("lengthSeconds")
+        .and_then(|x| x.as_str())
+        .unwrap_or("0")
+        .to_string();
+    let view_count = data
+        .get("viewCount")
+        .and_then(|x| x.as_str())
+        .unwrap_or("0")
+        .to_string();
+
     VideoDetails {
         author: get_author(initial_response, player_response),
-        age_restricted: is_age_restricted(&media),
+        age_restricted: is_age_restricted(initial_response),
 
         likes: get_likes(initial_response),
         dislikes: get_dislikes(initial_response),
@@ -878,11 +911,8 @@ pub fn clean_video_details(
                 .unwrap_or("")
                 .to_string()
         },
-        length_seconds: data
-            .get("lengthSeconds")
-            .and_then(|x| x.as_str())
-            .unwrap_or("0")
-            .to_string(),
+        duration: std::time::Duration::from_secs(length_seconds.parse().unwrap_or(0)),
+        length_seconds,
         owner_profile_url: data
             .get("ownerProfileUrl")
             .and_then(|x| x.as_str())
@@ -902,8 +932,13 @@ pub fn clean_video_details(
             .and_then(|x| x.as_array())
             .unwrap_or(&empty_serde_vec)
             .iter()
-            .map(|x| x.as_str().unwrap_or("").to_string())
-            .collect::<Vec<String>>(),
+            .filter_map(|x| x.as_str().and_then(|x| x.parse().ok()))
+            .collect::<Vec<CountryCode>>(),
+        is_embeddable: player_response
+            .get("playabilityStatus")
+            .and_then(|x| x.get("playableInEmbed"))
+            .and_then(|x| x.as_bool())
+            .unwrap_or(!embed_object.is_empty()),
         is_unlisted: data
             .get("isUnlisted")
             .and_then(|x| x.as_bool())
@@ -912,11 +947,11 @@ pub fn clean_video_details(
             .get("hasYpcMetadata")
             .and_then(|x| x.as_bool())
             .unwrap_or(false),
-        view_count: data
-            .get("viewCount")
-            .and_then(|x| x.as_str())
-            .unwrap_or("0")
-            .to_string(),
+        views: match get_live_viewers(initial_response) {
+            Some(viewers) => ViewCount::Watching(viewers),
+            None => ViewCount::Views(view_count.parse().unwrap_or(0)),
+        },
+        view_count,
         category: data
             .get("category")
             .and_then(|x| x.as_str())
@@ -949,6 +984,7 @@ pub fn clean_video_details(
             .iter()
             .map(|x| x.as_str().unwrap_or("").to_string())
             .collect::<Vec<String>>(),
+        hashtags: get_hashtags(initial_response),
         channel_id: data
             .get("channelId")
             .and_then(|x| x.as_str())
@@ -1018,50 +1054,64 @@ pub fn clean_video_details(
                     .to_string(),
             })
             .collect::<Vec<Thumbnail>>(),
+        default_audio_language: None,
+        has_multiple_audio_tracks: false,
+        has_paid_promotion: has_paid_promotion(initial_response, player_response),
+        rich_metadata: media,
     }
 }
 
-pub fn is_verified(badges: &serde_json::Value) -> bool {
-    badges
-        .as_array()
-        .map(|x| {
-            let verified_index = x
-                .iter()
-                .position(|c| {
-                    let json = serde_json::json!(c);
-                    json["metadataBadgeRenderer"]["tooltip"] == "Verified"
-                })
-                .unwrap_or(usize::MAX);
+/// Paid promotion disclosure, shown as the "Includes paid promotion" overlay of the player
+pub fn has_paid_promotion(
+    initial_response: &serde_json::Value,
+    player_response: &serde_json::Value,
+) -> bool {
+    let overlay = &player_response["paidContentOverlay"];
+    if overlay.get("paidContentOverlayRenderer").is_some()
+        || overlay.get("paidContentOverlayViewModel").is_some()
+    {
+        return true;
+    }
 
-            verified_index < usize::MAX
-        })
-        .unwrap_or(false)
+    [
+        &player_response["videoDetails"]["isPaidPromotion"],
+        &player_response["microformat"]["playerMicroformatRenderer"]["isPaidPromotion"],
+        &initial_response["playerOverlays"]["playerOverlayRenderer"]["isPaidPromotion"],
+    ]
+    .iter()
+    .any(|x| x.as_bool() == Some(true))
 }
 
-pub fn is_age_restricted(media: &serde_json::Value) -> bool {
-    let mut age_restricted = false;
-    if media.is_object() && media.as_object().is_some() {
-        age_restricted = AGE_RESTRICTED_URLS.iter().any(|url| {
-            media
-                .as_object()
-                .map(|x| {
-                    let mut bool_vec: Vec<bool> = vec![];
-
-                    for (_key, value) in x {
-                        if let Some(value_some) = value.as_str() {
-                            bool_vec.push(value_some.contains(url))
-                        } else {
-                            bool_vec.push(false);
-                        }
-                    }
+pub fn is_verified(badges: &serde_json::Value) -> bool {
+    get_badge(badges) != Badge::None
+}
 
-                    bool_vec.iter().any(|v| v == &true)
-                })
-                .unwrap_or(false)
-        })
+/// [`Badge`] of `ownerBadges`, an artist badge wins over a plain verification badge
+pub fn get_badge(badges: &serde_json::Value) -> Badge {
+    let badges = badges.as_array().map(|x| x.as_slice()).unwrap_or_default();
+    let renderers = || badges.iter().map(|x| &x["metadataBadgeRenderer"]);
+
+    if renderers().any(|x| {
+        x["icon"]["iconType"] == "OFFICIAL_ARTIST_BADGE"
+            || x["style"] == "BADGE_STYLE_TYPE_VERIFIED_ARTIST"
+    }) {
+        Badge::VerifiedArtist
+    } else if renderers()
+        .any(|x| x["tooltip"] == "Verified" || x["style"] == "BADGE_STYLE_TYPE_VERIFIED")
+    {
+        Badge::Verified
+    } else {
+        Badge::None
     }
+}
 
-    age_restricted
+/// Age restriction notice linked in the metadata rows below the description
+pub fn is_age_restricted(initial_response: &serde_json::Value) -> bool {
+    get_metadata_rows(initial_response).iter().any(|row| {
+        get_metadata_row_urls(row)
+            .iter()
+            .any(|url| AGE_RESTRICTED_URLS.iter().any(|x| url.contains(x)))
+    })
 }
 
 pub fn is_rental(player_response: &serde_json::Value) -> bool {
@@ -1096,6 +1146,19 @@ pub fn is_not_yet_broadcasted(player_response: &serde_json::Value) -> bool {
         == "LIVE_STREAM_OFFLINE"
 }
 
+/// Scheduled start of an upcoming live stream or premiere in unix seconds
+pub fn get_scheduled_start_time(player_response: &serde_json::Value) -> Option<u64> {
+    player_response
+        .get("playabilityStatus")
+        .and_then(|x| x.get("liveStreamability"))
+        .and_then(|x| x.get("liveStreamabilityRenderer"))
+        .and_then(|x| x.get("offlineSlate"))
+        .and_then(|x| x.get("liveStreamOfflineSlateRenderer"))
+        .and_then(|x| x.get("scheduledStartTime"))
+        .and_then(|x| x.as_str())
+        .and_then(|x| x.parse().ok())
+}
+
 pub fn is_play_error(player_response: &serde_json::Value, statuses: Vec<&str>) -> bool {
     let playability = player_response
         .get("playabilityStatus")
@@ -1122,144 +1185,249 @@ pub fn is_private_video(player_response: &serde_json::Value) -> bool {
     false
 }
 
+/// Playable video without `streamingData`, another client may get the formats.
+/// Errors, private, rented and upcoming videos have no formats for any client
+pub fn lacks_streaming_data(player_response: &serde_json::Value) -> bool {
+    player_response.get("streamingData").is_none()
+        && !is_play_error(player_response, vec!["ERROR"])
+        && !is_private_video(player_response)
+        && !is_rental(player_response)
+        && !is_not_yet_broadcasted(player_response)
+}
+
+/// Player functions by player script URL, shared by every [`Video`](crate::Video) of the process
+static PLAYER_FUNCTIONS_CACHE: Lazy<
+    std::sync::RwLock<std::collections::HashMap<String, PlayerFunctions>>,
+> = Lazy::new(Default::default);
+
+/// Player scripts kept in [`PLAYER_FUNCTIONS_CACHE`], older players are dropped when YouTube rolls out new ones
+const PLAYER_FUNCTIONS_CACHE_SIZE: usize = 8;
+
+/// Decipher and n-transform functions of the player script, fetched once per player and cached for every task
 pub async fn get_functions(
     html5player: impl Into<String>,
     client: &reqwest_middleware::ClientWithMiddleware,
+    config: &Config,
 ) -> Result<Vec<(String, String)>, VideoError> {
-    let mut url = url::Url::parse(BASE_URL).expect("IMPOSSIBLE");
+    Ok(get_player_functions(html5player, client, config)
+        .await?
+        .to_vec())
+}
+
+/// [`PlayerFunctions`] of the player script at `html5player`, fetched once per player and cached for every task
+pub async fn get_player_functions(
+    html5player: impl Into<String>,
+    client: &reqwest_middleware::ClientWithMiddleware,
+    config: &Config,
+) -> Result<PlayerFunctions, VideoError> {
+    let mut url = url::Url::parse(&config.base_url)?;
     url.set_path(&html5player.into());
     url.query_pairs_mut().clear();
 
     let url = url.as_str();
 
-    let response = get_html(client, url, None).await?;
+    if let Some(functions) = PLAYER_FUNCTIONS_CACHE
+        .read()
+        .ok()
+        .and_then(|x| x.get(url).cloned())
+    {
+        return Ok(functions);
+    }
 
-    Ok(extract_functions(response))
-}
+    let response = get_html(client, url, Some(&web_headers())).await?;
+    let functions = PlayerFunctions::extract(&response);
 
-pub fn extract_functions(body: String) -> Vec<(String, String)> {
-    let mut functions: Vec<(String, String)> = vec![];
-
-    // let mut cut_after_js_script =
-    //     js_sandbox::Script::from_string(CUT_AFTER_JS).expect("cut_after_js function error");
-
-    fn extract_manipulations(
-        body: String,
-        caller: &str,
-        // cut_after_js_script: &mut js_sandbox::Script,
-    ) -> String {
-        let function_name = between(caller, r#"a=a.split("");"#, ".");
-        if function_name.is_empty() {
-            return String::new();
+    if let Ok(mut cache) = PLAYER_FUNCTIONS_CACHE.write() {
+        if cache.len() >= PLAYER_FUNCTIONS_CACHE_SIZE {
+            cache.clear();
         }
+        cache.insert(url.to_string(), functions.clone());
+    }
 
-        let function_start = format!(r#"var {function_name}={{"#);
-        let ndx = body.find(function_start.as_str());
+    Ok(functions)
+}
 
-        if ndx.is_none() {
-            return String::new();
-        }
+static PLAYER_ID_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\\?/s\\?/player\\?/([0-9a-zA-Z_-]+)\\?/").unwrap());
 
-        let sub_body = body.slice((ndx.unwrap() + function_start.len() - 1)..);
+/// Path of the current player script from the iframe API, for requests without a watch page
+pub async fn get_current_html5player(
+    client: &reqwest_middleware::ClientWithMiddleware,
+    config: &Config,
+) -> Result<String, VideoError> {
+    let body = get_html(client, config.url("/iframe_api"), Some(&web_headers())).await?;
 
-        // let cut_after_sub_body = cut_after_js_script.call("cutAfterJS", (&sub_body,));
-        // let cut_after_sub_body: String = cut_after_sub_body.unwrap_or(String::from("null"));
+    let player_id = PLAYER_ID_REGEX
+        .captures(&body)
+        .and_then(|x| x.get(1))
+        .ok_or(VideoError::BodyCannotParsed)?
+        .as_str();
 
-        let cut_after_sub_body = cut_after_js(sub_body).unwrap_or(String::from("null"));
+    Ok(format!(
+        "/s/player/{player_id}/player_ias.vflset/en_US/base.js"
+    ))
+}
 
-        let return_formatted_string = format!("var {function_name}={cut_after_sub_body}");
+/// Signature decipher and `n` transform functions of a player script (`base.js`), as `(name, script)` pairs.
+/// `n` transform failures make Youtube throttle downloads, so the extraction is checked against a corpus of
+/// player layouts in `tests/players`
+///
+/// # Example
+/// ```ignore
+///     let functions = PlayerFunctions::extract(&std::fs::read_to_string("base.js").unwrap());
+///
+///     println!("{:?}", functions.transform_n("-lRhZ2ZJk9Pu3QO5"));
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct PlayerFunctions {
+    pub decipher: Option<(String, String)>,
+    pub n_transform: Option<(String, String)>,
+    /// `signatureTimestamp` of the player, sent with innertube player requests so the returned
+    /// URLs are ciphered for this player
+    pub signature_timestamp: Option<u64>,
+}
 
-        return_formatted_string
+impl PlayerFunctions {
+    /// Find the functions in the body of a player script, missing ones are `None`
+    pub fn extract(body: &str) -> Self {
+        Self {
+            decipher: extract_decipher(body),
+            n_transform: extract_n_transform(body),
+            signature_timestamp: SIGNATURE_TIMESTAMP_REGEX
+                .captures(body)
+                .and_then(|x| x.get(1))
+                .and_then(|x| x.as_str().parse().ok()),
+        }
     }
 
-    fn extract_decipher(
-        body: String,
-        functions: &mut Vec<(String, String)>,
-        // cut_after_js_script: &mut js_sandbox::Script,
-    ) {
-        let function_name = between(body.as_str(), r#"a.set("alr","yes");c&&(c="#, "(decodeURIC");
-        // println!("decipher function name: {}", function_name);
-        if !function_name.is_empty() {
-            let function_start = format!("{function_name}=function(a)");
-            let ndx = body.find(function_start.as_str());
+    /// Deciphered `s` parameter of a `signatureCipher`, `None` without the `extract` feature or if the script fails
+    pub fn decipher(&self, signature: &str) -> Option<String> {
+        let (name, script) = self.decipher.as_ref()?;
+        call_js(script, name, signature)
+    }
 
-            if let Some(ndx_some) = ndx {
-                let sub_body = body.slice((ndx_some + function_start.len())..);
+    /// Transformed `n` parameter, `None` without the `extract` feature or if the script fails
+    pub fn transform_n(&self, n: &str) -> Option<String> {
+        let (name, script) = self.n_transform.as_ref()?;
+        call_js(script, name, n)
+    }
 
-                // let cut_after_sub_body = cut_after_js_script.call("cutAfterJS", (&sub_body,));
-                // let cut_after_sub_body: String = cut_after_sub_body.unwrap_or(String::from("{}"));
+    /// Decipher at index 0 and n transform at index 1 for [`set_download_url`], empty if missing
+    pub(crate) fn to_vec(&self) -> Vec<(String, String)> {
+        vec![
+            self.decipher.clone().unwrap_or_default(),
+            self.n_transform.clone().unwrap_or_default(),
+        ]
+    }
+}
 
-                let cut_after_sub_body = cut_after_js(sub_body).unwrap_or(String::from("{}"));
+pub fn extract_functions(body: String) -> Vec<(String, String)> {
+    PlayerFunctions::extract(&body).to_vec()
+}
 
-                let mut function_body = format!("var {function_start}{cut_after_sub_body}");
+/// `signatureTimestamp:19590`, `sts:18764` in older players
+static SIGNATURE_TIMESTAMP_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\b(?:signatureTimestamp|sts)\s*:\s*(\d{5})\b").unwrap());
+
+/// Callers of the decipher function, one per player layout
+static DECIPHER_NAME_REGEXES: Lazy<[Regex; 3]> = Lazy::new(|| {
+    [
+        Regex::new(r#"a\.set\("alr","yes"\);c&&\(c=([a-zA-Z0-9_$]+)\(decodeURIC"#).unwrap(),
+        Regex::new(r#"\b[cs]&&[adf]\.set\([^,]+,encodeURIComponent\(([a-zA-Z0-9_$]+)\("#).unwrap(),
+        Regex::new(r#"\bm=([a-zA-Z0-9_$]{2,})\(decodeURIComponent\(h\.s\)\)"#).unwrap(),
+    ]
+});
+
+/// Caller of the `n` transform function, which can be an element of an array (`Rla[0](b)`)
+static N_TRANSFORM_NAME_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?:\.get\("n"\)\)&&\(b=|b=String\.fromCharCode\(110\),c=a\.get\(b\)\)&&\(c=)([a-zA-Z0-9_$]+)(?:\[(\d+)\])?\([a-zA-Z0-9_$]\)"#,
+    )
+    .unwrap()
+});
 
-                function_body = format!(
-                    "{manipulated_body};{function_body};",
-                    manipulated_body = extract_manipulations(
-                        body.clone(),
-                        function_body.as_str(),
-                        // cut_after_js_script
-                    ),
-                );
+/// Early return of the `n` transform on a player global, which is undefined when the function runs alone
+static N_TRANSFORM_GUARD_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#";\s*if\s*\(\s*typeof\s+[a-zA-Z0-9_$]+\s*===?\s*(?:"undefined"|'undefined')\s*\)\s*return\s+[a-zA-Z0-9_$]+;"#)
+        .unwrap()
+});
 
-                function_body.retain(|c| c != '\n');
+fn extract_decipher(body: &str) -> Option<(String, String)> {
+    let name = DECIPHER_NAME_REGEXES
+        .iter()
+        .find_map(|regex| regex.captures(body))?
+        .get(1)?
+        .as_str();
 
-                functions.push((function_name.to_string(), function_body));
-            }
-        }
-    }
+    let function = find_function(body, name)?;
 
-    fn extract_ncode(
-        body: String,
-        functions: &mut Vec<(String, String)>,
-        // cut_after_js_script: &mut js_sandbox::Script,
-    ) {
-        let mut function_name = between(body.as_str(), r#"&&(b=a.get("n"))&&(b="#, "(b)");
-
-        let left_name = format!(
-            "var {splitted_function_name}=[",
-            splitted_function_name = function_name
-                .split('[')
-                .collect::<Vec<&str>>()
-                .first()
-                .unwrap_or(&"")
-        );
+    // Helper object with the split string manipulations, like `Ly.gV(a,41)`
+    let helper = Regex::new(r#"[a-zA-Z0-9_$]+=[a-zA-Z0-9_$]+\.split\(""\);([a-zA-Z0-9_$]+)\."#)
+        .unwrap()
+        .captures(&function)
+        .and_then(|x| x.get(1))
+        .and_then(|x| find_object(body, x.as_str()))
+        .unwrap_or_default();
 
-        if function_name.contains('[') {
-            function_name = between(body.as_str(), left_name.as_str(), "]");
-        }
+    let mut script = format!("{helper};{function}");
+    script.retain(|c| c != '\n');
 
-        // println!("ncode function name: {}", function_name);
+    Some((name.to_string(), script))
+}
 
-        if !function_name.is_empty() {
-            let function_start = format!("{function_name}=function(a)");
-            let ndx = body.find(function_start.as_str());
+fn extract_n_transform(body: &str) -> Option<(String, String)> {
+    let captures = N_TRANSFORM_NAME_REGEX.captures(body)?;
+    let mut name = captures.get(1)?.as_str();
+
+    if let Some(index) = captures.get(2) {
+        let index: usize = index.as_str().parse().ok()?;
+        let array = Regex::new(&format!(
+            r"(?:^|[^a-zA-Z0-9_$.]){}=\[([^\]]+)\]",
+            regex::escape(name)
+        ))
+        .ok()?
+        .captures(body)?
+        .get(1)?
+        .as_str();
+
+        name = array.split(',').nth(index)?.trim();
+    }
 
-            if let Some(ndx_some) = ndx {
-                let sub_body = body.slice((ndx_some + function_start.len())..);
+    let mut script = N_TRANSFORM_GUARD_REGEX
+        .replace_all(&find_function(body, name)?, ";")
+        .into_owned();
+    script.retain(|c| c != '\n');
 
-                // let cut_after_sub_body = cut_after_js_script.call("cutAfterJS", (&sub_body,));
-                // let cut_after_sub_body: String = cut_after_sub_body.unwrap_or(String::from("{}"));
+    Some((name.to_string(), script))
+}
 
-                let cut_after_sub_body = cut_after_js(sub_body).unwrap_or(String::from("{}"));
+/// `var name=function(a){...};` of a function assigned to `name`
+fn find_function(body: &str, name: &str) -> Option<String> {
+    let start = Regex::new(&format!(
+        r"(?:^|[^a-zA-Z0-9_$.]){}\s*=\s*function\(([a-zA-Z0-9_$,]*)\)",
+        regex::escape(name)
+    ))
+    .ok()?
+    .captures(body)?;
 
-                let mut function_body = format!("var {function_start}{cut_after_sub_body};");
+    let arguments = start.get(1)?.as_str();
+    let function_body = cut_after_js(&body[start.get(0)?.end()..])?;
 
-                function_body.retain(|c| c != '\n');
+    Some(format!("var {name}=function({arguments}){function_body};"))
+}
 
-                functions.push((function_name.to_string(), function_body));
-            }
-        }
-    }
+/// `var name={...}` of an object literal assigned to `name`
+fn find_object(body: &str, name: &str) -> Option<String> {
+    let start = Regex::new(&format!(
+        r"(?:^|[^a-zA-Z0-9_$.])var {}=\{{",
+        regex::escape(name)
+    ))
+    .ok()?
+    .find(body)?;
 
-    extract_decipher(
-        body.clone(),
-        &mut functions, /*&mut cut_after_js_script*/
-    );
-    extract_ncode(body, &mut functions /*&mut cut_after_js_script*/);
+    let object = cut_after_js(&body[start.end() - 1..])?;
 
-    // println!("{:#?} {}", functions, functions.len());
-    functions
+    Some(format!("var {name}={object}"))
 }
 
 pub async fn get_html(
@@ -1267,58 +1435,397 @@ pub async fn get_html(
     url: impl Into<String>,
     headers: Option<&reqwest::header::HeaderMap>,
 ) -> Result<String, VideoError> {
-    let request = if headers.is_some() {
-        client.get(url.into()).headers(headers.unwrap().clone())
-    } else {
-        client.get(url.into())
-    }
-    .send()
-    .await;
+    let url: String = url.into();
+    let mut headers = headers.cloned().unwrap_or_default();
 
-    if request.is_err() {
-        return Err(VideoError::ReqwestMiddleware(request.err().unwrap()));
-    }
+    let (final_url, body) = get_html_response(client, &url, &headers).await?;
+
+    let body = match detect_blocking_page(&final_url, &body) {
+        None => body,
+        Some(BlockingPage::Captcha(captcha_url)) => {
+            return Err(VideoError::BotDetection { captcha_url })
+        }
+        Some(BlockingPage::Consent) => {
+            // Cookieless clients get the consent interstitial in the EU, accept it once with consent cookies
+            if !headers.contains_key(reqwest::header::COOKIE) {
+                headers.insert(
+                    reqwest::header::COOKIE,
+                    reqwest::header::HeaderValue::from_static(CONSENT_COOKIES),
+                );
+            }
 
-    let response_first = request.unwrap().text().await;
+            let (final_url, body) = get_html_response(client, &url, &headers).await?;
+
+            match detect_blocking_page(&final_url, &body) {
+                None => body,
+                Some(BlockingPage::Captcha(captcha_url)) => {
+                    return Err(VideoError::BotDetection { captcha_url })
+                }
+                Some(BlockingPage::Consent) => {
+                    return Err(VideoError::ConsentRequired(final_url.into()))
+                }
+            }
+        }
+    };
 
-    if response_first.is_err() {
-        return Err(VideoError::BodyCannotParsed);
+    if let Some(innertube) = extract_innertube_config(&body) {
+        if let Ok(mut cache) = INNERTUBE_CONFIG_CACHE.write() {
+            *cache = Some((innertube, std::time::Instant::now()));
+        }
     }
 
-    Ok(response_first.unwrap())
+    Ok(body)
 }
 
-/// Try to generate IPv6 with custom valid block
-/// # Example
-/// ```ignore
-/// let ipv6: std::net::IpAddr = get_random_v6_ip("2001:4::/48")?;
-/// ```
-pub fn get_random_v6_ip(ip: impl Into<String>) -> Result<std::net::IpAddr, VideoError> {
-    let ipv6_format: String = ip.into();
+async fn get_html_response(
+    client: &reqwest_middleware::ClientWithMiddleware,
+    url: &str,
+    headers: &reqwest::header::HeaderMap,
+) -> Result<(url::Url, String), VideoError> {
+    let response = client
+        .get(url)
+        .headers(headers.clone())
+        .send()
+        .await
+        .map_err(VideoError::ReqwestMiddleware)?;
+
+    let final_url = response.url().clone();
+    let body = response.text().await.map_err(VideoError::Reqwest)?;
+
+    Ok((final_url, body))
+}
 
-    if !IPV6_REGEX.is_match(&ipv6_format) {
-        return Err(VideoError::InvalidIPv6Format);
-    }
+/// Interstitial served instead of the requested page
+#[derive(Debug, PartialEq, Eq)]
+pub enum BlockingPage {
+    /// Google consent page of cookieless EU clients
+    Consent,
+    /// `google.com/sorry` captcha page with its url
+    Captcha(String),
+}
 
-    let format_attr = ipv6_format.split('/').collect::<Vec<&str>>();
-    let raw_addr = format_attr.first();
-    let raw_mask = format_attr.get(1);
+static CAPTCHA_URL_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"https://(?:www|ipv4|ipv6)\.google\.com/sorry/[^"'\s<>]*"#).unwrap());
 
-    if raw_addr.is_none() || raw_mask.is_none() {
-        return Err(VideoError::InvalidIPv6Format);
-    }
+/// Detect consent and captcha pages from the final url after redirects and the body
+pub fn detect_blocking_page(url: &url::Url, body: &str) -> Option<BlockingPage> {
+    let host = url.host_str().unwrap_or("");
 
-    let raw_addr = raw_addr.unwrap();
-    let raw_mask = raw_mask.unwrap();
+    if host.starts_with("consent.") {
+        return Some(BlockingPage::Consent);
+    }
 
-    let base_10_mask = raw_mask.parse::<u8>();
-    if base_10_mask.is_err() {
-        return Err(VideoError::InvalidIPv6Subnet);
+    if host.contains("google.") && url.path().starts_with("/sorry") {
+        return Some(BlockingPage::Captcha(url.to_string()));
     }
 
-    let mut base_10_mask = base_10_mask.unwrap();
+    if body.contains("id=\"captcha-form\"") {
+        let captcha_url = CAPTCHA_URL_REGEX
+            .find(body)
+            .map(|x| x.as_str().replace("&amp;", "&"))
+            .unwrap_or_else(|| url.to_string());
 
-    if !(24..=128).contains(&base_10_mask) {
+        return Some(BlockingPage::Captcha(captcha_url));
+    }
+
+    if body.contains("action=\"https://consent.youtube.com/")
+        || body.contains("action=\"https://consent.google.com/")
+    {
+        return Some(BlockingPage::Consent);
+    }
+
+    None
+}
+
+static INNERTUBE_API_KEY_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#""(?:INNERTUBE_API_KEY|innertubeApiKey)":"([^"]+)""#).unwrap());
+
+static INNERTUBE_CLIENT_VERSION_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#""(?:INNERTUBE_CONTEXT_CLIENT_VERSION|innertube_context_client_version)":"([^"]+)""#,
+    )
+    .unwrap()
+});
+
+static VISITOR_DATA_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#""VISITOR_DATA":"([^"]+)""#).unwrap());
+
+/// Innertube values discovered by [`get_html`] and the time they were discovered
+static INNERTUBE_CONFIG_CACHE: Lazy<
+    std::sync::RwLock<Option<(InnertubeConfig, std::time::Instant)>>,
+> = Lazy::new(|| std::sync::RwLock::new(None));
+
+/// Read innertube values from the `ytcfg` embedded in `html`, [`None`] if the page has no API key
+pub fn extract_innertube_config(html: &str) -> Option<InnertubeConfig> {
+    let api_key = INNERTUBE_API_KEY_REGEX.captures(html)?.get(1)?.as_str();
+
+    let context = html
+        .split_once(r#""INNERTUBE_CONTEXT":"#)
+        .and_then(|(_, rest)| {
+            serde_json::Deserializer::from_str(rest)
+                .into_iter::<serde_json::Value>()
+                .next()
+                .and_then(|x| x.ok())
+        })
+        .filter(|x| x.is_object())
+        .unwrap_or(serde_json::Value::Null);
+
+    let client_version = INNERTUBE_CLIENT_VERSION_REGEX
+        .captures(html)
+        .and_then(|x| x.get(1))
+        .map(|x| x.as_str().to_string())
+        .or_else(|| {
+            context["client"]["clientVersion"]
+                .as_str()
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| DEFAULT_INNERTUBE_CLIENT_VERSION.to_string());
+
+    let visitor_data = VISITOR_DATA_REGEX
+        .captures(html)
+        .and_then(|x| x.get(1))
+        .map(|x| x.as_str().to_string())
+        .or_else(|| {
+            context["client"]["visitorData"]
+                .as_str()
+                .map(str::to_string)
+        });
+
+    Some(InnertubeConfig {
+        api_key: api_key.to_string(),
+        client_version,
+        visitor_data,
+        context,
+    })
+}
+
+/// Innertube values for API requests. [`INNERTUBE_CONFIG_OVERRIDE`] if set, otherwise the values discovered from
+/// the last page fetched with [`get_html`], refreshed from the home page once older than [`INNERTUBE_CONFIG_TTL`]
+pub async fn innertube_config(
+    client: &reqwest_middleware::ClientWithMiddleware,
+    config: &Config,
+) -> InnertubeConfig {
+    if let Some(innertube) = INNERTUBE_CONFIG_OVERRIDE
+        .read()
+        .ok()
+        .and_then(|x| x.clone())
+    {
+        return innertube;
+    }
+
+    let cached = || INNERTUBE_CONFIG_CACHE.read().ok().and_then(|x| x.clone());
+
+    if let Some((innertube, discovered_at)) = cached() {
+        if discovered_at.elapsed() < INNERTUBE_CONFIG_TTL {
+            return innertube;
+        }
+    }
+
+    // A stale value is still better than the hardcoded default if the home page can't be fetched
+    let _ = get_html(client, config.url("/?hl=en"), Some(&web_headers())).await;
+
+    cached().map(|x| x.0).unwrap_or_default()
+}
+
+/// Create [`reqwest::ClientBuilder`] with [`RequestOptions`] applied
+pub fn build_client(
+    request_options: &RequestOptions,
+) -> Result<reqwest::ClientBuilder, VideoError> {
+    build_client_with_proxy(request_options, None)
+}
+
+/// [`build_client`] with a per-operation proxy, e.g. [`RequestOptions::stream_proxy`]
+pub fn build_client_with_proxy(
+    request_options: &RequestOptions,
+    proxy_override: Option<&ProxyOverride>,
+) -> Result<reqwest::ClientBuilder, VideoError> {
+    let mut client = reqwest::Client::builder();
+
+    if let Some(proxy_override) = proxy_override {
+        client = match proxy_override {
+            ProxyOverride::Direct => client.no_proxy(),
+            ProxyOverride::Proxy(proxy) => client.proxy(proxy.clone()),
+        };
+    } else if request_options.proxy.is_some() {
+        let proxy = request_options.proxy.as_ref().unwrap().clone();
+        client = client.proxy(proxy);
+    } else if let Some(pool) = request_options.proxy_pool.as_ref() {
+        // Pooled connections would keep a tunnel through a demoted proxy
+        client = client.proxy(pool.reqwest_proxy()).pool_max_idle_per_host(0);
+    }
+
+    if request_options.ipv6_block.is_some() {
+        let ipv6 = get_random_v6_ip(request_options.ipv6_block.as_ref().unwrap())?;
+        client = client.local_address(ipv6);
+    }
+
+    if let Some(jar) = cookie_jar(request_options) {
+        client = client.cookie_provider(std::sync::Arc::new(jar));
+    }
+
+    // Also set per request by `CustomHeaders`, so they override the profile headers of page and innertube requests
+    client = client.default_headers(request_options.headers.clone());
+
+    if let Some(resolver) = request_options.dns_resolver.as_ref() {
+        client = client.dns_resolver(std::sync::Arc::new(SharedResolver(resolver.clone())));
+    }
+
+    // Group overrides by host so every pinned address is kept
+    let mut dns_overrides: Vec<(&str, Vec<std::net::SocketAddr>)> = vec![];
+    for (host, addr) in request_options.dns_overrides.iter() {
+        match dns_overrides.iter_mut().find(|(name, _)| name == host) {
+            Some((_, addrs)) => addrs.push(*addr),
+            None => dns_overrides.push((host.as_str(), vec![*addr])),
+        }
+    }
+    for (host, addrs) in dns_overrides {
+        client = client.resolve_to_addrs(host, &addrs);
+    }
+
+    Ok(client)
+}
+
+/// [`HeaderProfile`](crate::HeaderProfile) headers of the web client, sent with youtube.com pages and web innertube requests
+pub(crate) fn web_headers() -> reqwest::header::HeaderMap {
+    InnertubeClient::Web.header_profile().headers()
+}
+
+/// Replace the same-named `headers` with every value of `overrides`
+pub(crate) fn override_headers(
+    headers: &mut reqwest::header::HeaderMap,
+    overrides: &reqwest::header::HeaderMap,
+) {
+    for name in overrides.keys() {
+        headers.remove(name);
+    }
+    for (name, value) in overrides.iter() {
+        headers.append(name, value.clone());
+    }
+}
+
+/// Applies [`RequestOptions::headers`] over the headers set per request
+struct CustomHeaders(reqwest::header::HeaderMap);
+
+#[async_trait::async_trait]
+impl reqwest_middleware::Middleware for CustomHeaders {
+    async fn handle(
+        &self,
+        mut req: reqwest::Request,
+        extensions: &mut task_local_extensions::Extensions,
+        next: reqwest_middleware::Next<'_>,
+    ) -> reqwest_middleware::Result<reqwest::Response> {
+        override_headers(req.headers_mut(), &self.0);
+        next.run(req, extensions).await
+    }
+}
+
+/// [`RequestOptions::cookies`] for `youtube.com`
+pub(crate) fn cookie_jar(request_options: &RequestOptions) -> Option<reqwest::cookie::Jar> {
+    let cookie = request_options.cookies.as_ref()?;
+    let host = "https://youtube.com".parse::<url::Url>().unwrap();
+
+    let jar = reqwest::cookie::Jar::default();
+    jar.add_cookie_str(cookie.as_str(), &host);
+
+    Some(jar)
+}
+
+/// [`reqwest::ClientBuilder::dns_resolver`] needs sized resolver
+struct SharedResolver(std::sync::Arc<dyn reqwest::dns::Resolve>);
+
+impl reqwest::dns::Resolve for SharedResolver {
+    fn resolve(&self, name: hyper::client::connect::dns::Name) -> reqwest::dns::Resolving {
+        self.0.resolve(name)
+    }
+}
+
+/// Try to generate IPv6 with custom valid block
+/// # Example
+/// ```ignore
+/// let ipv6: std::net::IpAddr = get_random_v6_ip("2001:4::/48")?;
+/// ```
+/// Add the middlewares required by `request_options`, e.g. proxy failover of [`RequestOptions::proxy_pool`]
+/// and identity rotation of [`RequestOptions::identity_pool`]. `video_id` pins the identity of sticky rotations
+pub fn with_request_middlewares(
+    mut client: reqwest_middleware::ClientBuilder,
+    request_options: &RequestOptions,
+    proxy_override: Option<&ProxyOverride>,
+    video_id: Option<&str>,
+) -> Result<reqwest_middleware::ClientBuilder, VideoError> {
+    if !request_options.headers.is_empty() {
+        client = client.with(CustomHeaders(request_options.headers.clone()));
+    }
+
+    // Before the log, so its timings leave out the delays
+    if let Some(throttled_mode) = request_options.throttled_mode.as_ref() {
+        client = client.with(throttled_mode.clone());
+    }
+    if let Some(pacer) = request_options.min_request_interval.as_ref() {
+        client = client.with(crate::pacer::PacedHosts::new(
+            pacer.clone(),
+            &request_options.config,
+        ));
+    }
+
+    // Below the retry middleware, so every attempt is logged
+    #[cfg(feature = "log")]
+    {
+        client = client.with(crate::correlation::RequestLog);
+    }
+
+    client = client.with(
+        request_options
+            .circuit_breaker
+            .clone()
+            .unwrap_or_else(crate::CircuitBreaker::shared),
+    );
+
+    match request_options.proxy_pool.as_ref() {
+        Some(pool) if request_options.proxy.is_none() && proxy_override.is_none() => {
+            client = client.with(crate::proxy::ProxyFailover(pool.clone()));
+        }
+        _ => {}
+    }
+
+    // Sends the requests itself, so it must be the last one
+    if let Some(pool) = request_options.identity_pool.as_ref() {
+        client = client.with(crate::identity::IdentityRotation::new(
+            pool,
+            request_options,
+            proxy_override,
+            video_id,
+        )?);
+    }
+
+    Ok(client)
+}
+
+pub fn get_random_v6_ip(ip: impl Into<String>) -> Result<std::net::IpAddr, VideoError> {
+    let ipv6_format: String = ip.into();
+
+    if !IPV6_REGEX.is_match(&ipv6_format) {
+        return Err(VideoError::InvalidIPv6Format);
+    }
+
+    let format_attr = ipv6_format.split('/').collect::<Vec<&str>>();
+    let raw_addr = format_attr.first();
+    let raw_mask = format_attr.get(1);
+
+    if raw_addr.is_none() || raw_mask.is_none() {
+        return Err(VideoError::InvalidIPv6Format);
+    }
+
+    let raw_addr = raw_addr.unwrap();
+    let raw_mask = raw_mask.unwrap();
+
+    let base_10_mask = raw_mask.parse::<u8>();
+    if base_10_mask.is_err() {
+        return Err(VideoError::InvalidIPv6Subnet);
+    }
+
+    let mut base_10_mask = base_10_mask.unwrap();
+
+    if !(24..=128).contains(&base_10_mask) {
         return Err(VideoError::InvalidIPv6Subnet);
     }
 
@@ -1381,51 +1888,108 @@ pub fn make_absolute_url(base: &str, url: &str) -> Result<url::Url, VideoError>
 }
 
 pub fn time_to_ms(duration: &str) -> usize {
-    let mut ms = 0;
-    for (i, curr) in duration.split(':').rev().enumerate() {
-        ms += curr.parse::<usize>().unwrap_or(0) * (u32::pow(60_u32, i as u32) as usize);
-    }
-    ms *= 1000;
-    ms
+    rusty_ytdl_core::time_to_ms(duration)
 }
 
-pub fn parse_abbreviated_number(time_str: &str) -> usize {
-    let replaced_string = time_str.replace(',', ".").replace(' ', "");
-    let string_match_regex = Regex::new(r"([\d,.]+)([MK]?)").unwrap();
-    // let mut return_value = 0usize;
+static RELATIVE_TIME_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(\d+)\s+(second|minute|hour|day|week|month|year)s?\s+ago").unwrap());
 
-    let caps = string_match_regex
-        .captures(replaced_string.as_str())
-        .unwrap();
+static ABSOLUTE_DATE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(Jan|Feb|Mar|Apr|May|Jun|Jul|Aug|Sep|Oct|Nov|Dec)[a-z]*\.?\s+(\d{1,2}),\s+(\d{4})")
+        .unwrap()
+});
+
+/// Parse publish texts like `3 weeks ago`, `Streamed 2 days ago` or `Premiered Jan 5, 2021`.
+/// Relative texts are resolved against `now` with 30 days months and 365 days years, so they are approximate
+pub fn parse_published_time(
+    text: &str,
+    now: std::time::SystemTime,
+) -> Option<std::time::SystemTime> {
+    if let Some(caps) = RELATIVE_TIME_REGEX.captures(text) {
+        let amount = caps[1].parse::<u64>().ok()?;
+        let unit = match &caps[2] {
+            "second" => 1,
+            "minute" => 60,
+            "hour" => 60 * 60,
+            "day" => 24 * 60 * 60,
+            "week" => 7 * 24 * 60 * 60,
+            "month" => 30 * 24 * 60 * 60,
+            _ => 365 * 24 * 60 * 60,
+        };
 
-    let return_value = if caps.len() > 0 {
-        let mut num;
+        return now.checked_sub(std::time::Duration::from_secs(amount.checked_mul(unit)?));
+    }
 
-        match caps.get(1) {
-            Some(regex_match) => num = regex_match.as_str().parse::<f32>().unwrap_or(0f32),
-            None => num = 0f32,
-        }
+    parse_absolute_date(text)
+}
 
-        let multi = match caps.get(2) {
-            Some(regex_match) => regex_match.as_str(),
-            None => "",
+/// Range a publish text like `3 weeks ago` or `Premiered Jan 5, 2021` stands for. YouTube rounds relative
+/// texts down, so `3 weeks ago` is between 4 and 3 weeks before `now`. Months count as 28 to 31 days and years
+/// as 365 to 366 days, absolute dates cover their UTC day
+pub fn parse_approximate_date(text: &str, now: std::time::SystemTime) -> Option<ApproximateDate> {
+    const DAY: u64 = 24 * 60 * 60;
+
+    if let Some(caps) = RELATIVE_TIME_REGEX.captures(text) {
+        let amount = caps[1].parse::<u64>().ok()?;
+        let (shortest, longest) = match &caps[2] {
+            "second" => (1, 1),
+            "minute" => (60, 60),
+            "hour" => (60 * 60, 60 * 60),
+            "day" => (DAY, DAY),
+            "week" => (7 * DAY, 7 * DAY),
+            "month" => (28 * DAY, 31 * DAY),
+            _ => (365 * DAY, 366 * DAY),
+        };
+        let ago = |unit: u64, amount: u64| {
+            amount
+                .checked_mul(unit)
+                .and_then(|x| now.checked_sub(std::time::Duration::from_secs(x)))
         };
 
-        match multi {
-            "M" => num *= 1000000f32,
-            "K" => num *= 1000f32,
-            _ => {
-                // Do Nothing
-            }
-        }
+        return Some(ApproximateDate {
+            earliest: ago(longest, amount.saturating_add(1)).unwrap_or(std::time::UNIX_EPOCH),
+            latest: ago(shortest, amount)?,
+        });
+    }
 
-        num = num.round();
-        num as usize
+    let day = parse_absolute_date(text)?;
+    Some(ApproximateDate {
+        earliest: day,
+        latest: day + std::time::Duration::from_secs(DAY - 1),
+    })
+}
+
+/// Midnight UTC of dates like `Jan 5, 2021`
+fn parse_absolute_date(text: &str) -> Option<std::time::SystemTime> {
+    let caps = ABSOLUTE_DATE_REGEX.captures(text)?;
+    let month = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ]
+    .iter()
+    .position(|x| *x == &caps[1])? as i64
+        + 1;
+    let day = caps[2].parse::<i64>().ok()?;
+    let year = caps[3].parse::<i64>().ok()?;
+
+    // Days since unix epoch of a proleptic gregorian date
+    let (y, m) = if month <= 2 {
+        (year - 1, month + 9)
     } else {
-        0usize
+        (year, month - 3)
     };
+    let era = y.div_euclid(400);
+    let year_of_era = y - era * 400;
+    let day_of_year = (153 * m + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+
+    u64::try_from(days)
+        .ok()
+        .map(|days| std::time::UNIX_EPOCH + std::time::Duration::from_secs(days * 24 * 60 * 60))
+}
 
-    return_value
+pub fn parse_abbreviated_number(time_str: &str) -> usize {
+    rusty_ytdl_core::parse_abbreviated_number(time_str)
 }
 
 pub fn merge(a: &mut serde_json::Value, b: &serde_json::Value) {
@@ -1497,7 +2061,11 @@ pub fn cut_after_js(mixed_json: &str) -> Option<String> {
     // Current open brackets to be closed
     let mut counter = 0;
 
-    let mixed_json_unicode = mixed_json.graphemes(true).collect::<Vec<&str>>();
+    // Chars and not graphemes, `substring` counts chars and a combining mark would shift the cut
+    let mixed_json_unicode = mixed_json
+        .char_indices()
+        .map(|(i, c)| &mixed_json[i..i + c.len_utf8()])
+        .collect::<Vec<&str>>();
     for (i, value) in mixed_json_unicode.iter().enumerate() {
         let value = <&str>::clone(value);
 
@@ -1569,6 +2137,472 @@ pub fn cut_after_js(mixed_json: &str) -> Option<String> {
     None
 }
 
+/// Language code of the original audio track, default track is used if the original is unknown
+pub fn get_default_audio_language(formats: &[VideoFormat]) -> Option<String> {
+    formats
+        .iter()
+        .find(|x| x.audio_track_kind() == Some(AudioTrackKind::Original))
+        .or_else(|| {
+            formats
+                .iter()
+                .find(|x| x.audio_track_id.is_some() && x.is_default_audio)
+        })
+        .and_then(|x| x.audio_language())
+        .map(|x| x.to_string())
+}
+
+/// Formats have more than one distinct audio track
+pub fn has_multiple_audio_tracks(formats: &[VideoFormat]) -> bool {
+    let mut track_ids = formats
+        .iter()
+        .filter_map(|x| x.audio_track_id.as_deref())
+        .collect::<Vec<&str>>();
+
+    track_ids.sort_unstable();
+    track_ids.dedup();
+
+    track_ids.len() > 1
+}
+
+/// Fill output template keys `{id}`, `{title}`, `{author}`, `{itag}`, `{quality}` and `{ext}` with video and format details,
+/// result is sanitized with [`sanitize_filename`]. Unknown keys are kept as is
+/// # Example
+/// ```ignore
+///     let file_name = render_output_template("{author} - {title}.{ext}", &video_info, &format, SanitizeProfile::Windows);
+/// ```
+pub fn render_output_template(
+    template: &str,
+    info: &VideoInfo,
+    format: &VideoFormat,
+    profile: SanitizeProfile,
+) -> String {
+    render_output_template_with(template, info, format, profile, &[])
+}
+
+/// [`render_output_template`] with additional keys
+pub(crate) fn render_output_template_with(
+    template: &str,
+    info: &VideoInfo,
+    format: &VideoFormat,
+    profile: SanitizeProfile,
+    extra: &[(&str, String)],
+) -> String {
+    let details = &info.video_details;
+
+    let ext = format.container.clone().unwrap_or_else(|| {
+        format
+            .mime_type
+            .split(';')
+            .next()
+            .and_then(|x| x.split('/').nth(1))
+            .unwrap_or("mp4")
+            .to_string()
+    });
+
+    let quality = format
+        .quality_label
+        .as_deref()
+        .or(format.audio_quality.as_deref())
+        .unwrap_or("")
+        .to_string();
+
+    let author = details
+        .author
+        .as_ref()
+        .map(|x| x.name.clone())
+        .unwrap_or(details.owner_channel_name.clone());
+
+    let mut values: Vec<(&str, String)> = vec![
+        ("id", details.video_id.clone()),
+        ("title", details.title.clone()),
+        ("author", author),
+        ("itag", format.itag.to_string()),
+        ("quality", quality),
+        ("ext", ext),
+    ];
+    // Later values win, so extra keys can override the built-in ones
+    values.extend_from_slice(extra);
+
+    // Single pass, so values containing `{...}` are not replaced again
+    let mut file_name = String::new();
+    let mut rest = template;
+
+    while let Some(open) = rest.find('{') {
+        file_name.push_str(&rest[..open]);
+        rest = &rest[open..];
+
+        let value = rest.find('}').and_then(|close| {
+            values
+                .iter()
+                .rev()
+                .find(|(key, _)| *key == &rest[1..close])
+                .map(|(_, value)| (close, value))
+        });
+
+        match value {
+            Some((close, value)) => {
+                file_name.push_str(value);
+                rest = &rest[close + 1..];
+            }
+            None => {
+                file_name.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    file_name.push_str(rest);
+
+    sanitize_filename(&file_name, profile)
+}
+
+static DESCRIPTION_CHAPTER_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?:^|\s|\[|\()(?:(\d{1,2}):)?(\d{1,2}):(\d{2})(?:$|\s|\]|\))").unwrap()
+});
+
+/// Chapters of `00:00 Title` / `Title - 1:02:03` lines in a description, for videos without chapter markers.
+/// Like Youtube, the list is empty unless there are at least 3 ascending timestamps starting at `0:00`
+pub fn chapters_from_description(description: &str) -> Vec<Chapter> {
+    let mut chapters: Vec<Chapter> = vec![];
+
+    for line in description.lines() {
+        let caps = match DESCRIPTION_CHAPTER_REGEX.captures(line) {
+            Some(caps) => caps,
+            None => continue,
+        };
+
+        let number = |index: usize| {
+            caps.get(index)
+                .and_then(|x| x.as_str().parse::<i32>().ok())
+                .unwrap_or(0)
+        };
+        let start_time = number(1) * 3600 + number(2) * 60 + number(3);
+
+        let matched = caps.get(0).expect("IMPOSSIBLE");
+        let title = format!("{} {}", &line[..matched.start()], &line[matched.end()..]);
+        let title = title
+            .trim_matches(|c: char| c.is_whitespace() || "-–—:|".contains(c))
+            .to_string();
+
+        if chapters
+            .last()
+            .map_or(start_time != 0, |x| start_time <= x.start_time)
+        {
+            return vec![];
+        }
+
+        chapters.push(Chapter { title, start_time });
+    }
+
+    if chapters.len() < 3 {
+        return vec![];
+    }
+
+    chapters
+}
+
+/// Incremental hasher of [`ChecksumAlgorithm`]
+pub(crate) enum ChecksumHasher {
+    Sha256(sha2::Sha256),
+    Md5(md5::Md5),
+}
+
+impl ChecksumHasher {
+    pub(crate) fn new(algorithm: ChecksumAlgorithm) -> Self {
+        use sha2::Digest;
+
+        match algorithm {
+            ChecksumAlgorithm::Sha256 => Self::Sha256(sha2::Sha256::new()),
+            ChecksumAlgorithm::Md5 => Self::Md5(md5::Md5::new()),
+        }
+    }
+
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        use sha2::Digest;
+
+        match self {
+            Self::Sha256(hasher) => hasher.update(data),
+            Self::Md5(hasher) => hasher.update(data),
+        }
+    }
+
+    /// Lowercase hex digest
+    pub(crate) fn finalize(self) -> String {
+        use sha2::Digest;
+
+        match self {
+            Self::Sha256(hasher) => hex::encode(hasher.finalize()),
+            Self::Md5(hasher) => hex::encode(hasher.finalize()),
+        }
+    }
+}
+
+/// `.part` file of a file download written with positioned writes, so chunks can land at their final offsets in any
+/// order. Written ranges of resumable files are saved next to the file as `<part>.ranges` on allocation, every
+/// [`PART_RANGES_SAVE_INTERVAL`] written bytes and when kept, so a dropped download or a crash loses at most the
+/// bytes written since the last save
+pub(crate) struct PartFile {
+    file: std::fs::File,
+    ranges_path: std::path::PathBuf,
+    resume: bool,
+    /// Sorted and merged `start..end` ranges
+    ranges: std::sync::Mutex<Vec<(u64, u64)>>,
+    /// Bytes written since the ranges were last saved
+    unsaved: std::sync::atomic::AtomicU64,
+}
+
+impl PartFile {
+    /// Open `path`, keeping what was written before if `resume`, otherwise truncated.
+    /// Without a ranges file the existing bytes count as written from the start
+    pub(crate) fn open(path: &std::path::Path, resume: bool) -> Result<Self, VideoError> {
+        let mut ranges_path = path.as_os_str().to_owned();
+        ranges_path.push(".ranges");
+        let ranges_path = std::path::PathBuf::from(ranges_path);
+
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .read(true)
+            .write(true)
+            .truncate(!resume)
+            .open(path)
+            .map_err(|e| VideoError::DownloadError(e.to_string()))?;
+
+        let ranges = if !resume {
+            let _ = std::fs::remove_file(&ranges_path);
+            vec![]
+        } else if let Ok(saved) = std::fs::read_to_string(&ranges_path) {
+            saved
+                .lines()
+                .filter_map(|x| x.split_once('-'))
+                .filter_map(|(start, end)| Some((start.parse().ok()?, end.parse().ok()?)))
+                .collect()
+        } else {
+            let length = file
+                .metadata()
+                .map_err(|e| VideoError::DownloadError(e.to_string()))?
+                .len();
+            vec![(0, length)]
+        };
+
+        let part_file = Self {
+            file,
+            ranges_path,
+            resume,
+            ranges: std::sync::Mutex::new(vec![]),
+            unsaved: std::sync::atomic::AtomicU64::new(0),
+        };
+        for (start, end) in ranges.into_iter().filter(|(start, end)| start < end) {
+            part_file.record(start, end);
+        }
+
+        Ok(part_file)
+    }
+
+    /// Reserve `length` bytes on disk, out of order writes don't fragment the file and a full disk fails up front.
+    /// Ranges are saved right away, a download dropped midway resumes from them instead of the file length
+    pub(crate) fn allocate(&self, length: u64) -> Result<(), VideoError> {
+        fs2::FileExt::allocate(&self.file, length)
+            .map_err(|e| VideoError::DownloadError(e.to_string()))?;
+
+        self.save_ranges()
+    }
+
+    /// Write `buf` at `offset`, callable from many tasks at once
+    pub(crate) fn write_at(&self, offset: u64, buf: &[u8]) -> Result<(), VideoError> {
+        #[cfg(unix)]
+        let result = std::os::unix::fs::FileExt::write_all_at(&self.file, buf, offset);
+
+        #[cfg(windows)]
+        let result = (|| {
+            let mut written = 0;
+            while written < buf.len() {
+                match std::os::windows::fs::FileExt::seek_write(
+                    &self.file,
+                    &buf[written..],
+                    offset + written as u64,
+                )? {
+                    0 => return Err(std::io::ErrorKind::WriteZero.into()),
+                    n => written += n,
+                }
+            }
+            Ok(())
+        })();
+
+        // Seek and write of one task must not interleave with another's
+        #[cfg(not(any(unix, windows)))]
+        let result = {
+            use std::io::{Seek, Write};
+
+            let _ranges = self.ranges.lock().unwrap();
+            (&self.file)
+                .seek(std::io::SeekFrom::Start(offset))
+                .and_then(|_| (&self.file).write_all(buf))
+        };
+
+        result.map_err(|e| VideoError::DownloadError(e.to_string()))?;
+        self.record(offset, offset + buf.len() as u64);
+
+        let length = buf.len() as u64;
+        let unsaved = self
+            .unsaved
+            .fetch_add(length, std::sync::atomic::Ordering::Relaxed)
+            + length;
+        if self.resume && unsaved >= PART_RANGES_SAVE_INTERVAL {
+            self.save_ranges()?;
+        }
+
+        Ok(())
+    }
+
+    fn record(&self, start: u64, end: u64) {
+        let mut ranges = self.ranges.lock().unwrap();
+        ranges.push((start, end));
+        ranges.sort_unstable();
+
+        let mut merged: Vec<(u64, u64)> = Vec::with_capacity(ranges.len());
+        for (start, end) in ranges.drain(..) {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+        *ranges = merged;
+    }
+
+    /// Bytes written from the start of the file without gaps
+    pub(crate) fn contiguous(&self) -> u64 {
+        match self.ranges.lock().unwrap().first() {
+            Some((0, end)) => *end,
+            _ => 0,
+        }
+    }
+
+    /// Save the written ranges for a later resume, only needed if the file is longer than its first range
+    pub(crate) fn keep(&self) -> Result<(), VideoError> {
+        let length = self
+            .file
+            .metadata()
+            .map_err(|e| VideoError::DownloadError(e.to_string()))?
+            .len();
+
+        if length <= self.contiguous() {
+            let _ = std::fs::remove_file(&self.ranges_path);
+            return Ok(());
+        }
+
+        self.save_ranges()
+    }
+
+    /// Ranges stay locked while saved, saves of concurrent writes don't interleave
+    fn save_ranges(&self) -> Result<(), VideoError> {
+        let ranges = self.ranges.lock().unwrap();
+        self.unsaved.store(0, std::sync::atomic::Ordering::Relaxed);
+
+        let saved = ranges
+            .iter()
+            .map(|(start, end)| format!("{start}-{end}\n"))
+            .collect::<String>();
+
+        std::fs::write(&self.ranges_path, saved)
+            .map_err(|e| VideoError::DownloadError(e.to_string()))
+    }
+
+    /// Cut the file at `length`, dropping the unwritten rest of an allocation, and flush it to disk
+    pub(crate) fn finish(&self, length: u64) -> Result<(), VideoError> {
+        let _ = std::fs::remove_file(&self.ranges_path);
+
+        self.file
+            .set_len(length)
+            .and_then(|_| self.file.sync_all())
+            .map_err(|e| VideoError::DownloadError(e.to_string()))
+    }
+}
+
+/// Windows device names which can't be used as file name (with or without extension)
+const RESERVED_FILE_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Maximum file name length in bytes, most filesystems limit a path component to 255 bytes
+const MAX_FILE_NAME_BYTES: usize = 255;
+
+/// Turn video title (or any string) into a safe file name for the given [`SanitizeProfile`]
+/// - Unicode is normalized to NFC (`Strict` transliterates to ASCII where possible)
+/// - Reserved and control characters are replaced with `_`
+/// - Name is truncated to 255 bytes, keeping the extension
+/// # Example
+/// ```ignore
+///     let file_name = sanitize_filename("AC/DC: Live? <1991>.mp4", SanitizeProfile::Windows);
+///
+///     assert_eq!(file_name, "AC_DC_ Live_ _1991_.mp4");
+/// ```
+pub fn sanitize_filename(title: &str, profile: SanitizeProfile) -> String {
+    let normalized: String = match profile {
+        // Decompose and drop combining marks, "é" => "e"
+        SanitizeProfile::Strict => title
+            .nfkd()
+            .filter(|c| !unicode_normalization::char::is_combining_mark(*c))
+            .collect(),
+        _ => title.nfc().collect(),
+    };
+
+    let mut file_name: String = normalized
+        .chars()
+        .map(|c| {
+            let reserved = match profile {
+                SanitizeProfile::Unix => c == '/' || c == '\0',
+                SanitizeProfile::Windows => {
+                    c.is_control()
+                        || matches!(c, '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*')
+                }
+                SanitizeProfile::Strict => {
+                    !(c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+                }
+            };
+
+            if reserved {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+
+    if profile != SanitizeProfile::Unix {
+        // Windows silently drops trailing dots and spaces
+        file_name = file_name.trim_end_matches(['.', ' ']).to_string();
+
+        let stem_len = file_name.split('.').next().unwrap_or("").trim_end().len();
+        if RESERVED_FILE_NAMES
+            .iter()
+            .any(|x| x.eq_ignore_ascii_case(&file_name[..stem_len]))
+        {
+            file_name.insert(stem_len, '_');
+        }
+    }
+
+    if file_name.len() > MAX_FILE_NAME_BYTES {
+        let extension = file_name
+            .rfind('.')
+            .map(|i| file_name[i..].to_string())
+            .filter(|x| x.len() <= 16)
+            .unwrap_or_default();
+
+        let mut end = MAX_FILE_NAME_BYTES - extension.len();
+        while !file_name.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        file_name = format!("{}{}", &file_name[..end], extension);
+    }
+
+    if file_name.is_empty() || file_name == "." || file_name == ".." {
+        return "_".to_string();
+    }
+
+    file_name
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1690,4 +2724,611 @@ mod tests {
         assert!(cut_after_js(r#"{"a": 1,{ "b": 1}"#).is_none());
         println!("[PASSED] test_returns_error_when_missing_closing_bracket");
     }
+
+    #[test]
+    fn test_sanitize_filename() {
+        assert_eq!(
+            sanitize_filename("AC/DC: Live? <1991>.mp4", SanitizeProfile::Windows),
+            "AC_DC_ Live_ _1991_.mp4"
+        );
+        assert_eq!(
+            sanitize_filename("con.mp4", SanitizeProfile::Windows),
+            "con_.mp4"
+        );
+        assert_eq!(
+            sanitize_filename("Trailing... ", SanitizeProfile::Windows),
+            "Trailing"
+        );
+        assert_eq!(
+            sanitize_filename("AC/DC: Live?", SanitizeProfile::Unix),
+            "AC_DC: Live?"
+        );
+        assert_eq!(
+            sanitize_filename("Café Déjà vu.webm", SanitizeProfile::Strict),
+            "Cafe_Deja_vu.webm"
+        );
+        assert_eq!(sanitize_filename("..", SanitizeProfile::Unix), "_");
+
+        let long_name =
+            sanitize_filename(&format!("{}.mp4", "ü".repeat(200)), SanitizeProfile::Unix);
+        assert!(long_name.len() <= 255);
+        assert!(long_name.ends_with("ü.mp4"));
+    }
+
+    #[test]
+    fn test_parse_published_time() {
+        let now = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        let day = std::time::Duration::from_secs(24 * 60 * 60);
+
+        assert_eq!(parse_published_time("3 days ago", now), Some(now - day * 3));
+        assert_eq!(
+            parse_published_time("Streamed 1 year ago", now),
+            Some(now - day * 365)
+        );
+        assert_eq!(
+            parse_published_time("Premiered Mar 1, 2020", now),
+            Some(std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_583_020_800))
+        );
+        assert_eq!(parse_published_time("1.2M views", now), None);
+    }
+
+    #[test]
+    fn test_parse_approximate_date() {
+        let now = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        let day = std::time::Duration::from_secs(24 * 60 * 60);
+
+        let weeks = parse_approximate_date("3 weeks ago", now).unwrap();
+        assert_eq!(weeks.earliest, now - day * 28);
+        assert_eq!(weeks.latest, now - day * 21);
+        assert!(weeks.contains(now - day * 25));
+        assert!(!weeks.contains(now - day * 20));
+
+        let months = parse_approximate_date("Streamed 1 month ago", now).unwrap();
+        assert_eq!(months.earliest, now - day * 62);
+        assert_eq!(months.latest, now - day * 28);
+
+        let premiere = parse_approximate_date("Premiered Mar 1, 2020", now).unwrap();
+        let midnight = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_583_020_800);
+        assert_eq!(premiere.earliest, midnight);
+        assert!(premiere.contains(midnight + day / 2));
+        assert!(!premiere.contains(midnight + day));
+        assert_eq!(
+            premiere.midpoint(),
+            midnight + (day - std::time::Duration::from_secs(1)) / 2
+        );
+
+        assert_eq!(parse_approximate_date("1.2M views", now), None);
+    }
+
+    #[test]
+    fn test_detect_blocking_page() {
+        let watch = "https://www.youtube.com/watch?v=FZ8BxMU3BYc"
+            .parse::<url::Url>()
+            .unwrap();
+
+        assert_eq!(detect_blocking_page(&watch, "<html></html>"), None);
+        assert_eq!(
+            detect_blocking_page(
+                &"https://consent.youtube.com/m?continue=x".parse().unwrap(),
+                ""
+            ),
+            Some(BlockingPage::Consent)
+        );
+        assert_eq!(
+            detect_blocking_page(
+                &watch,
+                r#"<form action="https://consent.youtube.com/save" method="POST">"#
+            ),
+            Some(BlockingPage::Consent)
+        );
+        assert_eq!(
+            detect_blocking_page(
+                &"https://www.google.com/sorry/index?continue=x"
+                    .parse()
+                    .unwrap(),
+                ""
+            ),
+            Some(BlockingPage::Captcha(
+                "https://www.google.com/sorry/index?continue=x".to_string()
+            ))
+        );
+        assert_eq!(
+            detect_blocking_page(
+                &watch,
+                r#"<form id="captcha-form" action="https://www.google.com/sorry/index?a=1&amp;b=2">"#
+            ),
+            Some(BlockingPage::Captcha(
+                "https://www.google.com/sorry/index?a=1&b=2".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_extract_innertube_config() {
+        let html = r#"<script>ytcfg.set({"INNERTUBE_API_KEY":"key123","INNERTUBE_CONTEXT":{"client":{"hl":"de","clientName":"WEB","clientVersion":"2.20240101.00.00","visitorData":"Cgt2aXNpdG9y"},"user":{"lockedSafetyMode":false}},"INNERTUBE_CONTEXT_CLIENT_VERSION":"2.20240101.00.00"});</script>"#;
+
+        let innertube = extract_innertube_config(html).unwrap();
+        assert_eq!(innertube.api_key, "key123");
+        assert_eq!(innertube.client_version, "2.20240101.00.00");
+        assert_eq!(innertube.visitor_data.as_deref(), Some("Cgt2aXNpdG9y"));
+
+        let context = innertube.request_context();
+        assert_eq!(context["client"]["hl"], "en");
+        assert_eq!(context["client"]["visitorData"], "Cgt2aXNpdG9y");
+        assert_eq!(context["user"]["lockedSafetyMode"], false);
+
+        assert!(extract_innertube_config("<html></html>").is_none());
+    }
+
+    #[test]
+    fn test_override_headers() {
+        let mut custom = reqwest::header::HeaderMap::new();
+        custom.append("x-custom", "a".parse().unwrap());
+        custom.append("x-custom", "b".parse().unwrap());
+        custom.insert(reqwest::header::USER_AGENT, "agent".parse().unwrap());
+
+        let mut headers = web_headers();
+        override_headers(&mut headers, &custom);
+
+        assert_eq!(
+            headers.get_all("x-custom").iter().collect::<Vec<_>>(),
+            ["a", "b"]
+        );
+        assert_eq!(
+            headers
+                .get_all(reqwest::header::USER_AGENT)
+                .iter()
+                .collect::<Vec<_>>(),
+            ["agent"]
+        );
+    }
+
+    #[test]
+    fn test_checksum_hasher() {
+        let mut hasher = ChecksumHasher::new(ChecksumAlgorithm::Sha256);
+        hasher.update(b"hello ");
+        hasher.update(b"world");
+        assert_eq!(
+            hasher.finalize(),
+            "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"
+        );
+
+        let mut hasher = ChecksumHasher::new(ChecksumAlgorithm::Md5);
+        hasher.update(b"hello world");
+        assert_eq!(hasher.finalize(), "5eb63bbbe01eeed093cb22bb8f5acdc3");
+    }
+
+    #[test]
+    fn test_part_file() {
+        let path =
+            std::env::temp_dir().join(format!("rusty_ytdl-part-file-{}.part", std::process::id()));
+
+        // Out of order chunks into an allocated file
+        let part_file = PartFile::open(&path, false).unwrap();
+        part_file.allocate(12).unwrap();
+        part_file.write_at(8, b"ijkl").unwrap();
+        part_file.write_at(0, b"abcd").unwrap();
+        assert_eq!(part_file.contiguous(), 4);
+        part_file.keep().unwrap();
+        drop(part_file);
+
+        // Resumed from the first gap, not the allocated length
+        let part_file = PartFile::open(&path, true).unwrap();
+        assert_eq!(part_file.contiguous(), 4);
+        part_file.write_at(4, b"efgh").unwrap();
+        assert_eq!(part_file.contiguous(), 12);
+        part_file.finish(12).unwrap();
+        drop(part_file);
+
+        assert_eq!(std::fs::read(&path).unwrap(), b"abcdefghijkl");
+        assert!(!std::path::Path::new(&format!("{}.ranges", path.display())).exists());
+
+        // Files of appending downloads count as written from the start
+        let part_file = PartFile::open(&path, true).unwrap();
+        assert_eq!(part_file.contiguous(), 12);
+        drop(part_file);
+
+        // Dropped without keep, resumed from the ranges saved while writing
+        let length = PART_RANGES_SAVE_INTERVAL;
+        let part_file = PartFile::open(&path, true).unwrap();
+        part_file.allocate(length + 8).unwrap();
+        part_file.write_at(length + 4, b"wxyz").unwrap();
+        part_file.write_at(0, &vec![0; length as usize]).unwrap();
+        drop(part_file);
+
+        let part_file = PartFile::open(&path, true).unwrap();
+        assert_eq!(part_file.contiguous(), length);
+        part_file.finish(0).unwrap();
+        drop(part_file);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_render_output_template() {
+        let info = crate::compat::ytdlp::from_info_json(&serde_json::json!({
+            "id": "FZ8BxMU3BYc",
+            "title": "{ext}: a/b",
+            "channel": "Channel",
+            "formats": [{ "format_id": "251", "ext": "webm", "acodec": "opus", "vcodec": "none" }],
+        }))
+        .unwrap();
+
+        assert_eq!(
+            render_output_template_with(
+                "{playlist_index} {author} - {title} [{id}] {unknown}.{ext}",
+                &info,
+                &info.formats[0],
+                SanitizeProfile::Windows,
+                &[("playlist_index", "01".to_string())],
+            ),
+            "01 Channel - {ext}_ a_b [FZ8BxMU3BYc] {unknown}.webm"
+        );
+    }
+
+    #[test]
+    fn test_get_scheduled_start_time() {
+        let player_response = serde_json::json!({
+            "playabilityStatus": {
+                "status": "LIVE_STREAM_OFFLINE",
+                "liveStreamability": { "liveStreamabilityRenderer": { "offlineSlate": {
+                    "liveStreamOfflineSlateRenderer": { "scheduledStartTime": "1700000000" }
+                } } }
+            }
+        });
+
+        assert!(is_not_yet_broadcasted(&player_response));
+        assert_eq!(get_scheduled_start_time(&player_response), Some(1700000000));
+        assert_eq!(get_scheduled_start_time(&serde_json::json!({})), None);
+    }
+
+    #[test]
+    fn test_get_extraction_report() {
+        let player_response = serde_json::json!({
+            "playabilityStatus": { "status": "OK" },
+            "videoDetails": { "videoId": "FZ8BxMU3BYc", "title": "Title", "lengthSeconds": "10" },
+            "microformat": { "playerMicroformatRenderer": {} },
+            "streamingData": { "adaptiveFormats": [] },
+        });
+        let initial_response = serde_json::json!({
+            "contents": { "twoColumnWatchNextResults": { "results": { "results": { "contents": [
+                { "videoPrimaryInfoRenderer": {} },
+                { "videoSecondaryInfoRenderer": { "owner": {} } },
+            ] } } } }
+        });
+
+        let report = crate::info_extras::get_extraction_report(&initial_response, &player_response);
+        assert_eq!(
+            report.missing_paths,
+            vec![
+                "initialData.contents.twoColumnWatchNextResults.results.results.contents.*.videoSecondaryInfoRenderer.owner.videoOwnerRenderer",
+                "initialData.contents.twoColumnWatchNextResults.secondaryResults.secondaryResults.results",
+            ]
+        );
+
+        // Extractors without initial data only have the player response checked
+        assert!(crate::info_extras::get_extraction_report(
+            &serde_json::Value::Null,
+            &player_response
+        )
+        .is_clean());
+    }
+
+    #[test]
+    fn test_chapters_from_description() {
+        let chapters = chapters_from_description(
+            "Tracklist:\n00:00 Intro\n3:15 - Second Song\n[1:02:03] Finale (live)\nThanks for watching",
+        );
+        assert_eq!(
+            chapters,
+            vec![
+                Chapter {
+                    title: "Intro".to_string(),
+                    start_time: 0,
+                },
+                Chapter {
+                    title: "Second Song".to_string(),
+                    start_time: 195,
+                },
+                Chapter {
+                    title: "Finale (live)".to_string(),
+                    start_time: 3723,
+                },
+            ]
+        );
+
+        assert!(chapters_from_description("0:00 a\n2:00 b").is_empty());
+        assert!(chapters_from_description("1:00 a\n2:00 b\n3:00 c").is_empty());
+        assert!(chapters_from_description("0:00 a\n2:00 b\n1:00 c").is_empty());
+    }
+
+    #[test]
+    fn test_get_badge() {
+        let verified = serde_json::json!([{"metadataBadgeRenderer": {
+            "icon": {"iconType": "CHECK_CIRCLE_THICK"},
+            "style": "BADGE_STYLE_TYPE_VERIFIED",
+            "tooltip": "Verified"
+        }}]);
+        let artist = serde_json::json!([{"metadataBadgeRenderer": {
+            "icon": {"iconType": "OFFICIAL_ARTIST_BADGE"},
+            "style": "BADGE_STYLE_TYPE_VERIFIED_ARTIST",
+            "tooltip": "Official Artist Channel"
+        }}]);
+
+        assert_eq!(get_badge(&verified), Badge::Verified);
+        assert_eq!(get_badge(&artist), Badge::VerifiedArtist);
+        assert_eq!(get_badge(&serde_json::json!([])), Badge::None);
+        assert!(is_verified(&artist));
+    }
+
+    #[test]
+    fn test_clean_video_details_availability() {
+        let player_response = serde_json::json!({
+            "playabilityStatus": { "playableInEmbed": false },
+            "microformat": { "playerMicroformatRenderer": {
+                "availableCountries": ["DE", "us", "", "XYZ"],
+                "embed": { "iframeUrl": "https://www.youtube.com/embed/abc" },
+            }},
+            "videoDetails": { "isCrawlable": true },
+        });
+
+        let details = clean_video_details(
+            &serde_json::json!({}),
+            &player_response,
+            vec![],
+            "abc".to_string(),
+        );
+        let countries: Vec<&str> = details
+            .available_countries
+            .iter()
+            .map(|x| x.as_str())
+            .collect();
+        assert_eq!(countries, vec!["DE", "US"]);
+        assert!(!details.is_embeddable);
+        assert!(details.is_crawlable);
+        assert!(details.is_available_in(&"us".parse().unwrap()));
+        assert!(!details.is_available_in(&"FR".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_clean_video_details_counts() {
+        let player_response = serde_json::json!({
+            "videoDetails": { "lengthSeconds": "212", "viewCount": "1500" },
+        });
+        let details = clean_video_details(
+            &serde_json::json!({}),
+            &player_response,
+            vec![],
+            "abc".to_string(),
+        );
+        assert_eq!(details.duration, std::time::Duration::from_secs(212));
+        assert_eq!(details.views, ViewCount::Views(1500));
+        assert_eq!(details.view_count, "1500");
+
+        let initial_response = serde_json::json!({
+            "contents": { "twoColumnWatchNextResults": { "results": { "results": { "contents": [
+                { "videoPrimaryInfoRenderer": { "viewCount": { "videoViewCountRenderer": {
+                    "viewCount": { "runs": [{ "text": "1,234" }, { "text": " watching now" }] },
+                    "isLive": true,
+                }}}},
+            ] } } } }
+        });
+        let details = clean_video_details(
+            &initial_response,
+            &player_response,
+            vec![],
+            "abc".to_string(),
+        );
+        assert_eq!(details.views, ViewCount::Watching(1234));
+        assert_eq!(details.view_count, "1500");
+    }
+
+    #[test]
+    fn test_has_paid_promotion() {
+        let overlay = serde_json::json!({
+            "paidContentOverlay": { "paidContentOverlayRenderer": {
+                "text": { "simpleText": "Includes paid promotion" },
+                "durationMs": "10000",
+            }},
+        });
+        let flag = serde_json::json!({ "videoDetails": { "isPaidPromotion": true } });
+        let none = serde_json::json!({ "videoDetails": { "isPaidPromotion": false } });
+
+        assert!(has_paid_promotion(&serde_json::json!({}), &overlay));
+        assert!(has_paid_promotion(&serde_json::json!({}), &flag));
+        assert!(!has_paid_promotion(&serde_json::json!({}), &none));
+    }
+
+    #[test]
+    fn test_get_hashtags() {
+        let initial_response = serde_json::json!({
+            "contents": { "twoColumnWatchNextResults": { "results": { "results": { "contents": [
+                { "videoPrimaryInfoRenderer": { "superTitleLink": { "runs": [
+                    { "text": "#music" },
+                    { "text": " " },
+                    { "text": "#live " },
+                    { "text": "#music" },
+                    { "text": "Berlin" },
+                ]}}},
+                { "videoSecondaryInfoRenderer": {} },
+            ]}}}},
+        });
+
+        assert_eq!(get_hashtags(&initial_response), vec!["#music", "#live"]);
+        assert!(get_hashtags(&serde_json::json!({})).is_empty());
+    }
+
+    #[test]
+    fn test_get_media() {
+        use crate::info_extras::get_media;
+
+        let link = |url: &str| serde_json::json!({ "commandMetadata": { "webCommandMetadata": { "url": url } } });
+        let row = |title: &str, text: &str, url: &str| {
+            serde_json::json!({ "metadataRowRenderer": {
+                "title": { "simpleText": title },
+                "contents": [{ "runs": [{ "text": text, "navigationEndpoint": link(url) }] }],
+            }})
+        };
+        let initial_response = serde_json::json!({
+            "contents": { "twoColumnWatchNextResults": { "results": { "results": { "contents": [
+                { "videoSecondaryInfoRenderer": { "metadataRowContainer": { "metadataRowContainerRenderer": { "rows": [
+                    { "richMetadataRowRenderer": { "contents": [
+                        { "richMetadataRenderer": {
+                            "style": "RICH_METADATA_RENDERER_STYLE_BOX_ART",
+                            "title": { "simpleText": "Minecraft" },
+                            "subtitle": { "simpleText": "2011" },
+                            "callToAction": { "simpleText": "Browse game" },
+                            "thumbnail": { "thumbnails": [{ "url": "https://yt3.ggpht.com/a", "width": 68, "height": 94 }] },
+                            "endpoint": link("/channel/UCQvWX73GQygcwXOTSf_VDVg"),
+                        }},
+                        { "richMetadataRenderer": {
+                            "style": "RICH_METADATA_RENDERER_STYLE_TOPIC",
+                            "title": { "simpleText": "Gaming" },
+                            "endpoint": link("/gaming"),
+                        }},
+                    ]}},
+                    row("Song", "Sweden", "/watch?v=aBkTkxKDduc"),
+                    row("Artist", "C418", "/channel/UC"),
+                    row("Age restriction", "Learn more", "https://support.google.com/youtube/?p=age_restrictions"),
+                ]}}}},
+            ]}}}},
+        });
+
+        assert_eq!(
+            get_media(&initial_response),
+            vec![
+                RichMetadata::Music {
+                    song: "Sweden".to_string(),
+                    artist: Some("C418".to_string()),
+                    album: None,
+                    url: Some("https://www.youtube.com/watch?v=aBkTkxKDduc".to_string()),
+                },
+                RichMetadata::Game {
+                    title: "Minecraft".to_string(),
+                    year: Some(2011),
+                    url: Some(
+                        "https://www.youtube.com/channel/UCQvWX73GQygcwXOTSf_VDVg".to_string()
+                    ),
+                    thumbnails: vec![Thumbnail {
+                        width: 68,
+                        height: 94,
+                        url: "https://yt3.ggpht.com/a".to_string(),
+                    }],
+                },
+                RichMetadata::Topic {
+                    title: "Gaming".to_string(),
+                    url: Some("https://www.youtube.com/gaming".to_string()),
+                },
+            ]
+        );
+        assert!(is_age_restricted(&initial_response));
+        assert!(get_media(&serde_json::json!({})).is_empty());
+        assert!(!is_age_restricted(&serde_json::json!({})));
+    }
+
+    #[test]
+    fn test_get_raw_sections() {
+        let initial_response = serde_json::json!({
+            "contents": { "twoColumnWatchNextResults": { "results": { "results": { "contents": [
+                { "videoPrimaryInfoRenderer": { "title": { "runs": [{ "text": "Title" }] } } },
+                { "videoSecondaryInfoRenderer": { "owner": {} } },
+                { "itemSectionRenderer": { "contents": [
+                    { "merchandiseShelfRenderer": { "items": [] } },
+                ]}},
+                { "itemSectionRenderer": {
+                    "sectionIdentifier": "comments-entry-point",
+                    "contents": [{ "commentsEntryPointHeaderRenderer": { "commentCount": { "simpleText": "12" } } }],
+                }},
+            ]}}}},
+        });
+
+        let sections = crate::info_extras::get_raw_sections(&initial_response);
+        assert_eq!(
+            sections.primary_info.as_deref().unwrap()["title"]["runs"][0]["text"],
+            "Title"
+        );
+        assert!(sections.secondary_info.is_some());
+        assert!(sections.merch_shelf.is_some());
+        assert_eq!(
+            sections.comments_teaser.as_deref().unwrap()["commentCount"]["simpleText"],
+            "12"
+        );
+
+        assert!(crate::info_extras::get_raw_sections(&serde_json::json!({})).is_empty());
+    }
+
+    #[test]
+    fn test_get_products() {
+        let thumbnails = serde_json::json!({ "thumbnails": [
+            { "url": "https://i.ytimg.com/small", "width": 60, "height": 60 },
+            { "url": "https://i.ytimg.com/large", "width": 240, "height": 240 },
+        ]});
+        let initial_response = serde_json::json!({
+            "contents": { "twoColumnWatchNextResults": { "results": { "results": { "contents": [
+                { "itemSectionRenderer": { "contents": [{ "merchandiseShelfRenderer": { "items": [
+                    { "merchandiseItemRenderer": {
+                        "title": "Tour Shirt",
+                        "price": "$25.00",
+                        "vendorName": "Spreadshirt",
+                        "thumbnail": thumbnails,
+                        "buttonCommand": { "commandExecutorCommand": { "commands": [
+                            { "urlEndpoint": { "url": "https://shop.example/shirt" } },
+                        ]}},
+                    }},
+                ]}}]}},
+            ]}}}},
+            "engagementPanels": [{ "engagementPanelSectionListRenderer": { "content": { "productListRenderer": { "contents": [
+                { "productListItemRenderer": {
+                    "title": { "simpleText": "Tour Shirt" },
+                    "price": { "simpleText": "$25.00" },
+                    "onClickCommand": { "urlEndpoint": { "url": "https://shop.example/shirt" } },
+                }},
+                { "productListItemRenderer": {
+                    "title": { "simpleText": "Vinyl" },
+                    "price": { "simpleText": "$30.00" },
+                    "merchantName": { "simpleText": "Store" },
+                    "onClickCommand": { "urlEndpoint": { "url": "https://shop.example/vinyl" } },
+                }},
+            ]}}}}],
+        });
+
+        let products = crate::info_extras::get_products(&initial_response);
+        assert_eq!(products.len(), 2);
+        assert_eq!(products[0].title, "Tour Shirt");
+        assert_eq!(products[0].price, "$25.00");
+        assert_eq!(products[0].vendor.as_deref(), Some("Spreadshirt"));
+        assert_eq!(products[0].url, "https://shop.example/shirt");
+        assert_eq!(products[0].thumbnail.as_ref().unwrap().width, 240);
+        assert_eq!(products[1].vendor.as_deref(), Some("Store"));
+        assert!(products[1].thumbnail.is_none());
+    }
+
+    #[test]
+    fn test_get_ticket_events() {
+        let initial_response = serde_json::json!({
+            "contents": { "twoColumnWatchNextResults": { "results": { "results": { "contents": [
+                { "itemSectionRenderer": { "contents": [{ "ticketShelfRenderer": {
+                    "header": { "simpleText": "Tickets" },
+                    "tickets": [
+                        { "ticketEventRenderer": {
+                            "date": { "simpleText": "Jun 14" },
+                            "venueName": { "simpleText": "Columbiahalle" },
+                            "location": { "simpleText": "Berlin, Germany" },
+                            "vendorName": { "simpleText": "Ticketmaster" },
+                            "navigationEndpoint": { "urlEndpoint": { "url": "https://tickets.example/1" } },
+                        }},
+                        { "ticketEventRenderer": { "venueName": { "simpleText": "No date" } } },
+                    ],
+                }}]}},
+            ]}}}},
+        });
+
+        let events = crate::info_extras::get_ticket_events(&initial_response);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].date, "Jun 14");
+        assert_eq!(events[0].venue, "Columbiahalle");
+        assert_eq!(events[0].location.as_deref(), Some("Berlin, Germany"));
+        assert_eq!(events[0].vendor.as_deref(), Some("Ticketmaster"));
+        assert_eq!(events[0].url, "https://tickets.example/1");
+    }
 }
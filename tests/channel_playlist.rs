@@ -0,0 +1,327 @@
+mod common;
+
+#[tokio::test]
+async fn playlist_metadata() {
+    use rusty_ytdl::search::{Playlist, PlaylistSearchOptions, PlaylistVisibility};
+    use rusty_ytdl::{Config, RequestOptions};
+
+    let initial_data = serde_json::json!({
+        "metadata": {"playlistMetadataRenderer": {"title": "Mixtape", "description": "Songs I like"}},
+        "microformat": {"microformatDataRenderer": {"unlisted": true}},
+        "sidebar": {"playlistSidebarRenderer": {"items": [
+            {"playlistSidebarPrimaryInfoRenderer": {
+                "title": {"runs": [{"text": "Mixtape"}]},
+                "stats": [
+                    {"runs": [{"text": "1,234"}, {"text": " videos"}]},
+                    {"simpleText": "56 views"},
+                    {"runs": [{"text": "Last updated on "}, {"text": "Jan 5, 2024"}]}
+                ]
+            }},
+            {"playlistSidebarSecondaryInfoRenderer": {"videoOwner": {"videoOwnerRenderer": {
+                "title": {"runs": [{"text": "Someone", "navigationEndpoint": {"browseEndpoint": {
+                    "browseId": "UCxxxxxxxxxxxxxxxxxxxxxx", "canonicalBaseUrl": "/@someone"
+                }}}]},
+                "navigationEndpoint": {"browseEndpoint": {"canonicalBaseUrl": "/@someone"}}
+            }}}}
+        ]}}
+    });
+    let page = format!("<html><script>var ytInitialData = {initial_data};</script></html>");
+
+    // Only the playlist page is served, item pagination would fail
+    let address = common::spawn_server(move |request| {
+        if request.line().starts_with("GET /playlist?list=") {
+            common::response("200 OK", &["Content-Type: text/html"], &page)
+        } else {
+            common::response("404 Not Found", &[], "")
+        }
+    })
+    .await;
+
+    let options = PlaylistSearchOptions {
+        request_options: Some(RequestOptions {
+            config: Config {
+                origin: format!("http://{address}"),
+                ..Default::default()
+            },
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+
+    let metadata = Playlist::get_metadata(
+        "https://www.youtube.com/playlist?list=PLwMEL7UNT4o9iMzrvNBXZqXbNPFfT6rVD",
+        Some(&options),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(metadata.id, "PLwMEL7UNT4o9iMzrvNBXZqXbNPFfT6rVD");
+    assert_eq!(metadata.title, "Mixtape");
+    assert_eq!(metadata.description, "Songs I like");
+    assert_eq!(metadata.channel.name, "Someone");
+    assert_eq!(metadata.visibility, PlaylistVisibility::Unlisted);
+    assert_eq!(metadata.video_count, Some(1234));
+    assert_eq!(metadata.last_update.as_deref(), Some("Jan 5, 2024"));
+
+    assert!(
+        Playlist::get_metadata("https://www.youtube.com/watch?v=FZ8BxMU3BYc", None)
+            .await
+            .is_err()
+    );
+}
+
+#[test]
+fn playlist_diff() {
+    use rusty_ytdl::search::{PlaylistEntry, PlaylistSnapshot};
+
+    let snapshot = |ids: &[&str]| PlaylistSnapshot {
+        playlist_id: "PLAYLIST_ID".to_string(),
+        entries: ids
+            .iter()
+            .map(|x| PlaylistEntry {
+                video_id: x.to_string(),
+                title: x.to_uppercase(),
+            })
+            .collect(),
+    };
+
+    let previous = snapshot(&["a", "b", "c", "d", "e"]);
+    assert!(previous.diff(&previous).is_empty());
+
+    // `e` moved to the front, `c` removed, `f` added
+    let current = snapshot(&["e", "a", "b", "d", "f"]);
+    let diff = current.diff(&previous);
+
+    let ids = |changes: &[rusty_ytdl::search::PlaylistChange]| {
+        changes
+            .iter()
+            .map(|x| (x.entry.video_id.clone(), x.previous_index, x.index))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(ids(&diff.added), [("f".to_string(), None, Some(4))]);
+    assert_eq!(ids(&diff.removed), [("c".to_string(), Some(2), None)]);
+    assert_eq!(ids(&diff.reordered), [("e".to_string(), Some(4), Some(0))]);
+
+    let saved = serde_json::to_string(&previous).unwrap();
+    let restored: PlaylistSnapshot = serde_json::from_str(&saved).unwrap();
+    assert_eq!(restored, previous);
+
+    // Second copy of a duplicated video
+    let diff = snapshot(&["a", "b", "a"]).diff(&snapshot(&["a", "b"]));
+    assert_eq!(ids(&diff.added), [("a".to_string(), None, Some(2))]);
+    assert!(diff.removed.is_empty() && diff.reordered.is_empty());
+}
+
+#[tokio::test]
+async fn paginator_state() {
+    use rusty_ytdl::search::Playlist;
+
+    let state = r#"{
+        "id": "PLAYLIST_ID",
+        "name": "Playlist",
+        "url": "https://www.youtube.com/playlist?list=PLAYLIST_ID",
+        "channel": {
+            "id": "UC_CHANNEL_ID",
+            "name": "Channel",
+            "url": "",
+            "icon": [],
+            "verified": false,
+            "subscribers": 0
+        },
+        "thumbnails": [],
+        "views": 42,
+        "last_update": null,
+        "continuation": { "api": "KEY", "token": "TOKEN", "client_version": "2.20230331.00.00" }
+    }"#;
+
+    let playlist = Playlist::resume(state, None).unwrap();
+    assert_eq!(playlist.name, "Playlist");
+    assert_eq!(playlist.views, 42);
+    assert!(playlist.videos.is_empty());
+
+    let saved = playlist.save_state();
+    assert!(saved.contains(r#""token":"TOKEN""#));
+    assert_eq!(Playlist::resume(&saved, None).unwrap(), playlist);
+
+    assert!(Playlist::resume("not a state", None).is_err());
+}
+
+#[tokio::test]
+async fn channel_resolve() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use rusty_ytdl::search::Channel;
+    use rusty_ytdl::{Config, RequestOptions, VideoError};
+
+    let resolved = Arc::new(AtomicUsize::new(0));
+    let counter = resolved.clone();
+    let address = common::spawn_server(move |request| {
+        if !request
+            .line()
+            .starts_with("POST /youtubei/v1/navigation/resolve_url")
+        {
+            return common::response("404 Not Found", &[], "");
+        }
+
+        counter.fetch_add(1, Ordering::SeqCst);
+        if String::from_utf8_lossy(&request.body).contains("https://www.youtube.com/@someone") {
+            common::json_response(&serde_json::json!({
+                "endpoint": {"browseEndpoint": {"browseId": "UCxxxxxxxxxxxxxxxxxxxxxx"}}
+            }))
+        } else {
+            common::response("404 Not Found", &[], "")
+        }
+    })
+    .await;
+
+    let request_options = RequestOptions {
+        config: Config {
+            origin: format!("http://{address}"),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let id = Channel::resolve("@someone", Some(&request_options))
+        .await
+        .unwrap();
+    assert_eq!(id, "UCxxxxxxxxxxxxxxxxxxxxxx");
+
+    // Cached, with the different spelling of the same handle
+    let id = Channel::resolve("https://www.youtube.com/@SomeOne/", Some(&request_options))
+        .await
+        .unwrap();
+    assert_eq!(id, "UCxxxxxxxxxxxxxxxxxxxxxx");
+    assert_eq!(resolved.load(Ordering::SeqCst), 1);
+
+    // Channel ids need no request
+    let id = Channel::resolve("/channel/UCyyyyyyyyyyyyyyyyyyyyyy", Some(&request_options))
+        .await
+        .unwrap();
+    assert_eq!(id, "UCyyyyyyyyyyyyyyyyyyyyyy");
+
+    assert!(matches!(
+        Channel::resolve("/user/nobody", Some(&request_options)).await,
+        Err(VideoError::ChannelNotFound(_))
+    ));
+    assert_eq!(resolved.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn channel_about() {
+    use rusty_ytdl::search::{Channel, ChannelLink};
+    use rusty_ytdl::{Config, RequestOptions};
+
+    // Header with the "more" continuation, then the about panel
+    let header = serde_json::json!({
+        "header": {"pageHeaderRenderer": {"content": {"description": {"commandRuns": [
+            {"onTap": {"innertubeCommand": {"continuationCommand": {"token": "ABOUT_TOKEN"}}}}
+        ]}}}}
+    });
+    let about = serde_json::json!({
+        "onResponseReceivedEndpoints": [{"appendContinuationItemsAction": {"continuationItems": [
+            {"aboutChannelRenderer": {"metadata": {"aboutChannelViewModel": {
+                "description": "Videos about things",
+                "country": "Germany",
+                "joinedDateText": {"content": "Joined Mar 5, 2006"},
+                "viewCountText": "1,234,567 views",
+                "subscriberCountText": "987 subscribers",
+                "links": [
+                    {"channelExternalLinkViewModel": {
+                        "title": {"content": "Shop"},
+                        "link": {"content": "shop.example.com"}
+                    }}
+                ]
+            }}}}
+        ]}}]
+    });
+
+    let address = common::spawn_server(move |request| {
+        let body = String::from_utf8_lossy(&request.body);
+
+        let json = if !request.line().starts_with("POST /youtubei/v1/browse") {
+            None
+        } else if body.contains("ABOUT_TOKEN") {
+            Some(&about)
+        } else if body.contains("UCxxxxxxxxxxxxxxxxxxxxxx") {
+            Some(&header)
+        } else {
+            None
+        };
+
+        match json {
+            Some(json) => common::json_response(json),
+            None => common::response("404 Not Found", &[], ""),
+        }
+    })
+    .await;
+
+    let request_options = RequestOptions {
+        config: Config {
+            origin: format!("http://{address}"),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let channel = Channel {
+        id: "UCxxxxxxxxxxxxxxxxxxxxxx".to_string(),
+        name: "Someone".to_string(),
+        url: String::new(),
+        icon: vec![],
+        verified: false,
+        subscribers: 0,
+    };
+
+    let about = channel.about(Some(&request_options)).await.unwrap();
+    assert_eq!(about.description, "Videos about things");
+    assert_eq!(about.country.as_deref(), Some("Germany"));
+    assert_eq!(about.join_date.as_deref(), Some("Mar 5, 2006"));
+    assert_eq!(about.total_views, Some(1234567));
+    assert_eq!(about.subscriber_count_exact, Some(987));
+    assert_eq!(
+        about.links,
+        [ChannelLink {
+            title: "Shop".to_string(),
+            url: "https://shop.example.com".to_string(),
+        }]
+    );
+}
+
+#[test]
+fn search_filters() {
+    use rusty_ytdl::search::{
+        SearchFeature, SearchFilters, SearchOptions, SearchType, SortBy, UploadDate,
+    };
+
+    // Plain type filter keeps the previous encoding
+    assert_eq!(
+        SearchOptions::default().params().as_deref(),
+        Some("EgIQAQ%253D%253D")
+    );
+
+    assert_eq!(
+        SearchOptions {
+            search_type: SearchType::All,
+            ..Default::default()
+        }
+        .params(),
+        None
+    );
+
+    let options = SearchOptions {
+        filters: SearchFilters {
+            upload_date: Some(UploadDate::ThisWeek),
+            features: vec![SearchFeature::FourK],
+            sort_by: SortBy::UploadDate,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    assert_eq!(
+        options.params().as_deref(),
+        Some("CAISBggDEAFwAQ%253D%253D")
+    );
+}
@@ -0,0 +1,177 @@
+//! Local HTTP servers of the integration tests
+#![allow(dead_code)]
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use rusty_ytdl::stream::NonLiveStreamOptions;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// Request received by a server of [`spawn_server`]
+pub struct Request {
+    pub method: String,
+    /// Path with the query string
+    pub path: String,
+    /// Lowercase names
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(x, _)| x.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// `"GET /path?query"`
+    pub fn line(&self) -> String {
+        format!("{} {}", self.method, self.path)
+    }
+
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_slice(&self.body).unwrap()
+    }
+
+    /// Inclusive start and end of `Range: bytes=<start>-<end>`, `None` end for open ranges
+    pub fn range(&self) -> Option<(usize, Option<usize>)> {
+        let (start, end) = self
+            .header("range")?
+            .strip_prefix("bytes=")?
+            .split_once('-')?;
+        Some((start.parse().ok()?, end.parse().ok()))
+    }
+}
+
+/// Answer every connection with `handler`, returns the address of the server
+pub async fn spawn_server<F>(handler: F) -> SocketAddr
+where
+    F: Fn(Request) -> Vec<u8> + Send + Sync + 'static,
+{
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let handler = Arc::new(handler);
+
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let handler = handler.clone();
+
+            tokio::spawn(async move {
+                if let Some(request) = read_request(&mut socket).await {
+                    let _ = socket.write_all(&handler(request)).await;
+                }
+            });
+        }
+    });
+
+    address
+}
+
+async fn read_request(socket: &mut tokio::net::TcpStream) -> Option<Request> {
+    let mut request = vec![];
+    let mut buf = [0u8; 1024];
+
+    let header_end = loop {
+        if let Some(end) = request.windows(4).position(|x| x == b"\r\n\r\n") {
+            break end + 4;
+        }
+        let n = socket.read(&mut buf).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        request.extend_from_slice(&buf[..n]);
+    };
+
+    let head = String::from_utf8_lossy(&request[..header_end]).to_string();
+    let mut lines = head.lines();
+    let mut line = lines.next()?.split(' ');
+    let (method, path) = (line.next()?.to_string(), line.next()?.to_string());
+    let headers = lines
+        .filter_map(|x| x.split_once(':'))
+        .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+        .collect::<Vec<_>>();
+
+    let length = headers
+        .iter()
+        .find(|(name, _)| name == "content-length")
+        .and_then(|(_, value)| value.parse::<usize>().ok())
+        .unwrap_or(0);
+    while request.len() < header_end + length {
+        let n = socket.read(&mut buf).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        request.extend_from_slice(&buf[..n]);
+    }
+
+    Some(Request {
+        method,
+        path,
+        headers,
+        body: request[header_end..].to_vec(),
+    })
+}
+
+/// `HTTP/1.1 <status>` response with `headers` (`"Name: value"`) and `body`, the connection is closed after it
+pub fn response(status: &str, headers: &[&str], body: impl AsRef<[u8]>) -> Vec<u8> {
+    let body = body.as_ref();
+    let mut response = format!("HTTP/1.1 {status}\r\n");
+    for header in headers {
+        response.push_str(header);
+        response.push_str("\r\n");
+    }
+    response.push_str(&format!(
+        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    ));
+
+    let mut response = response.into_bytes();
+    response.extend_from_slice(body);
+    response
+}
+
+pub fn json_response(json: &serde_json::Value) -> Vec<u8> {
+    response(
+        "200 OK",
+        &["Content-Type: application/json"],
+        json.to_string(),
+    )
+}
+
+/// Bytes of `content` the `Range` of `request` asks for with their `Content-Range`, the whole content without a
+/// range and `416` past the end
+pub fn range_response(content: &[u8], request: &Request, headers: &[&str]) -> Vec<u8> {
+    let (start, end) = match request.range() {
+        Some(range) => range,
+        None => return response("200 OK", headers, content),
+    };
+
+    if start >= content.len() {
+        let content_range = format!("Content-Range: bytes */{}", content.len());
+        return response("416 Range Not Satisfiable", &[&content_range], "");
+    }
+
+    let end = end.unwrap_or(usize::MAX).min(content.len() - 1);
+    let content_range = format!("Content-Range: bytes {start}-{end}/{}", content.len());
+    let headers = [headers, &[content_range.as_str()]].concat();
+
+    response("206 Partial Content", &headers, &content[start..=end])
+}
+
+/// Server answering byte ranges of `content` on every path
+pub async fn spawn_range_server(content: Vec<u8>) -> SocketAddr {
+    spawn_server(move |request| range_response(&content, &request, &[])).await
+}
+
+/// Options of a stream of `link` in 40 byte chunks, without prefetching. The content length is taken from the
+/// first response
+pub fn stream_options(link: String) -> NonLiveStreamOptions {
+    NonLiveStreamOptions {
+        link,
+        dl_chunk_size: 40,
+        end: 40,
+        ..Default::default()
+    }
+}
@@ -0,0 +1,505 @@
+mod common;
+
+#[tokio::test]
+async fn download_preview() {
+    use std::time::Duration;
+
+    use rusty_ytdl::extractor::{ExtractedInfo, Extractor};
+    use rusty_ytdl::{Config, Video, VideoError, VideoOptions, VideoSearchOptions};
+
+    // 16 init bytes, `sidx` box with 2 fragments of 2 seconds, 50 and 60 bytes long
+    let mut content = vec![0u8; 16];
+    content.extend_from_slice(&56u32.to_be_bytes());
+    content.extend_from_slice(b"sidx");
+    content.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 1]);
+    content.extend_from_slice(&1000u32.to_be_bytes());
+    content.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2]);
+    for size in [50u32, 60] {
+        content.extend_from_slice(&size.to_be_bytes());
+        content.extend_from_slice(&2000u32.to_be_bytes());
+        content.extend_from_slice(&[0x90, 0, 0, 0]);
+    }
+    content.extend((content.len()..200).map(|x| x as u8));
+
+    let address = common::spawn_range_server(content.clone()).await;
+
+    struct FixtureExtractor(String);
+
+    #[async_trait::async_trait]
+    impl Extractor for FixtureExtractor {
+        async fn fetch_info(
+            &self,
+            _client: &reqwest_middleware::ClientWithMiddleware,
+            _config: &Config,
+            video_id: &str,
+        ) -> Result<ExtractedInfo, VideoError> {
+            Ok(ExtractedInfo {
+                player_response: serde_json::json!({
+                    "playabilityStatus": { "status": "OK" },
+                    "streamingData": { "formats": [], "adaptiveFormats": [{
+                        "itag": 137,
+                        "url": self.0,
+                        "mimeType": "video/mp4; codecs=\"avc1.640028\"",
+                        "bitrate": 1000,
+                        "width": 1920,
+                        "height": 1080,
+                        "quality": "hd1080",
+                        "qualityLabel": "1080p",
+                        "contentLength": "200",
+                        "approxDurationMs": "4000",
+                        "initRange": { "start": "0", "end": "15" },
+                        "indexRange": { "start": "16", "end": "71" },
+                    }] },
+                    "videoDetails": { "videoId": video_id, "title": "Fixture", "lengthSeconds": "4" },
+                }),
+                ..Default::default()
+            })
+        }
+
+        async fn decipher(
+            &self,
+            _client: &reqwest_middleware::ClientWithMiddleware,
+            _config: &Config,
+            _info: &ExtractedInfo,
+        ) -> Result<Vec<(String, String)>, VideoError> {
+            Ok(vec![])
+        }
+    }
+
+    let video = Video::new_with_options(
+        "FZ8BxMU3BYc",
+        VideoOptions {
+            filter: VideoSearchOptions::Video,
+            extractor: Some(std::sync::Arc::new(FixtureExtractor(format!(
+                "http://{address}/videoplayback"
+            )))),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let path = std::env::temp_dir().join(format!("rusty_ytdl_preview_{}.mp4", std::process::id()));
+
+    // 1 second from 2.5 seconds is in the second fragment only
+    let result = video
+        .download_preview(&path, Duration::from_secs(1), Duration::from_millis(2500))
+        .await
+        .unwrap();
+
+    let preview = std::fs::read(&path).unwrap();
+    assert_eq!(preview, [&content[..16], &content[122..182]].concat());
+    assert_eq!(result.size, preview.len() as u64);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "blocking")]
+#[test]
+fn blocking_progress() {
+    use std::sync::{Arc, Mutex};
+
+    use rusty_ytdl::blocking::Video;
+    use rusty_ytdl::extractor::{ExtractedInfo, Extractor};
+    use rusty_ytdl::{Config, DownloadOptions, VideoError, VideoOptions};
+
+    struct FixtureExtractor(String);
+
+    #[async_trait::async_trait]
+    impl Extractor for FixtureExtractor {
+        async fn fetch_info(
+            &self,
+            _client: &reqwest_middleware::ClientWithMiddleware,
+            _config: &Config,
+            video_id: &str,
+        ) -> Result<ExtractedInfo, VideoError> {
+            Ok(ExtractedInfo {
+                player_response: serde_json::json!({
+                    "playabilityStatus": { "status": "OK" },
+                    "streamingData": { "formats": [], "adaptiveFormats": [{
+                        "itag": 140,
+                        "url": self.0,
+                        "mimeType": "audio/mp4; codecs=\"mp4a.40.2\"",
+                        "bitrate": 128000,
+                        "audioQuality": "AUDIO_QUALITY_MEDIUM",
+                        "contentLength": "200",
+                        "approxDurationMs": "4000",
+                    }] },
+                    "videoDetails": { "videoId": video_id, "title": "Fixture", "lengthSeconds": "4" },
+                }),
+                ..Default::default()
+            })
+        }
+
+        async fn decipher(
+            &self,
+            _client: &reqwest_middleware::ClientWithMiddleware,
+            _config: &Config,
+            _info: &ExtractedInfo,
+        ) -> Result<Vec<(String, String)>, VideoError> {
+            Ok(vec![])
+        }
+    }
+
+    // Server runs on its own runtime, the download blocks this thread
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let content = (0..200).map(|x| x as u8).collect::<Vec<u8>>();
+    let address = runtime.block_on(common::spawn_range_server(content.clone()));
+
+    let video = Video::new_with_options(
+        "FZ8BxMU3BYc",
+        VideoOptions {
+            extractor: Some(Arc::new(FixtureExtractor(format!(
+                "http://{address}/videoplayback"
+            )))),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let threads = Arc::new(Mutex::new(vec![]));
+    let progress_threads = threads.clone();
+    let options = DownloadOptions {
+        on_progress: Some(Arc::new(move |_| {
+            progress_threads
+                .lock()
+                .unwrap()
+                .push(std::thread::current().id());
+        })),
+        ..Default::default()
+    };
+
+    let path = std::env::temp_dir().join(format!("rusty_ytdl_blocking_{}.m4a", std::process::id()));
+    video.download_with_options(&path, options).unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), content);
+
+    let threads = threads.lock().unwrap();
+    assert!(!threads.is_empty());
+    assert!(threads.iter().all(|x| *x == std::thread::current().id()));
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn download_archive() {
+    use rusty_ytdl::archive::{DownloadArchive, TextFileArchive};
+
+    let path = std::env::temp_dir().join(format!("rusty_ytdl_archive_{}.txt", std::process::id()));
+    // Last line without a newline
+    std::fs::write(&path, "youtube FZ8BxMU3BYc\nvimeo 12345").unwrap();
+
+    let archive = TextFileArchive::new(&path).unwrap();
+    assert!(archive.contains("FZ8BxMU3BYc").unwrap());
+    assert!(!archive.contains("12345").unwrap());
+
+    archive.insert("dQw4w9WgXcQ").unwrap();
+    archive.insert("dQw4w9WgXcQ").unwrap();
+    drop(archive);
+
+    let reopened = TextFileArchive::new(&path).unwrap();
+    assert!(reopened.contains("dQw4w9WgXcQ").unwrap());
+    assert!(!reopened.contains("12345").unwrap());
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "youtube FZ8BxMU3BYc\nvimeo 12345\nyoutube dQw4w9WgXcQ\n"
+    );
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn sqlite_archive() {
+    use rusty_ytdl::archive::{DownloadArchive, SqliteArchive};
+
+    let archive = SqliteArchive::new(":memory:").unwrap();
+    assert!(!archive.contains("dQw4w9WgXcQ").unwrap());
+
+    archive.insert("dQw4w9WgXcQ").unwrap();
+    archive.insert("dQw4w9WgXcQ").unwrap();
+    assert!(archive.contains("dQw4w9WgXcQ").unwrap());
+    assert!(!archive.contains("FZ8BxMU3BYc").unwrap());
+}
+
+#[tokio::test]
+async fn playlist_download() {
+    use std::collections::HashSet;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use rusty_ytdl::archive::DownloadArchive;
+    use rusty_ytdl::extractor::{ExtractedInfo, Extractor};
+    use rusty_ytdl::search::{
+        Playlist, PlaylistDownloadOptions, PlaylistProgress, PlaylistSearchOptions,
+    };
+    use rusty_ytdl::{Config, RequestOptions, ThrottledMode, VideoError, VideoOptions};
+
+    /// Fails every video after a while, records the most videos it had open at the same time
+    #[derive(Default)]
+    struct SlowExtractor {
+        open: AtomicUsize,
+        most_open: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl Extractor for SlowExtractor {
+        async fn fetch_info(
+            &self,
+            _client: &reqwest_middleware::ClientWithMiddleware,
+            _config: &Config,
+            _video_id: &str,
+        ) -> Result<ExtractedInfo, VideoError> {
+            let open = self.open.fetch_add(1, Ordering::SeqCst) + 1;
+            self.most_open.fetch_max(open, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            self.open.fetch_sub(1, Ordering::SeqCst);
+
+            Ok(ExtractedInfo {
+                player_response: serde_json::json!({ "playabilityStatus": { "status": "ERROR" } }),
+                ..Default::default()
+            })
+        }
+
+        async fn decipher(
+            &self,
+            _client: &reqwest_middleware::ClientWithMiddleware,
+            _config: &Config,
+            _info: &ExtractedInfo,
+        ) -> Result<Vec<(String, String)>, VideoError> {
+            Ok(vec![])
+        }
+    }
+
+    /// Archive with the first video, its lookup of the last one fails
+    struct FixtureArchive(HashSet<String>);
+
+    impl DownloadArchive for FixtureArchive {
+        fn contains(&self, video_id: &str) -> Result<bool, VideoError> {
+            if video_id == "eeeeeeeeeee" {
+                return Err(VideoError::ArchiveError("unreadable".to_string()));
+            }
+            Ok(self.0.contains(video_id))
+        }
+
+        fn insert(&self, _video_id: &str) -> Result<(), VideoError> {
+            unreachable!("no video is downloaded")
+        }
+    }
+
+    let ids = [
+        "aaaaaaaaaaa",
+        "bbbbbbbbbbb",
+        "ccccccccccc",
+        "ddddddddddd",
+        "eeeeeeeeeee",
+    ];
+    let initial_data = serde_json::json!({
+        "contents": {"twoColumnBrowseResultsRenderer": {"tabs": [{"tabRenderer": {"content": {
+            "sectionListRenderer": {"contents": [{"itemSectionRenderer": {"contents": [{
+                "playlistVideoListRenderer": {"contents": ids.iter().map(|id| serde_json::json!({
+                    "playlistVideoRenderer": {
+                        "videoId": id,
+                        "title": {"runs": [{"text": id}]},
+                        "shortBylineText": {"runs": [{"text": "Someone"}]},
+                    }
+                })).collect::<Vec<_>>()}
+            }]}}]}
+        }}}]}},
+        "sidebar": {"playlistSidebarRenderer": {"items": [
+            {"playlistSidebarPrimaryInfoRenderer": {"title": {"runs": [{"text": "Mixtape"}]}}},
+            {"playlistSidebarSecondaryInfoRenderer": {}}
+        ]}}
+    });
+    let page = format!("<html><script>var ytInitialData = {initial_data};</script></html>");
+
+    let address = common::spawn_server(move |request| {
+        if request.line().starts_with("GET /playlist?list=") {
+            common::response("200 OK", &["Content-Type: text/html"], &page)
+        } else {
+            common::response("404 Not Found", &[], "")
+        }
+    })
+    .await;
+
+    let request_options = RequestOptions {
+        config: Config {
+            origin: format!("http://{address}"),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let playlist = Playlist::get(
+        "https://www.youtube.com/playlist?list=PLwMEL7UNT4o9iMzrvNBXZqXbNPFfT6rVD",
+        Some(&PlaylistSearchOptions {
+            limit: ids.len() as u64,
+            request_options: Some(request_options.clone()),
+            ..Default::default()
+        }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(playlist.videos.len(), ids.len());
+
+    let dir = std::env::temp_dir().join(format!("rusty_ytdl_playlist_{}", std::process::id()));
+
+    for (throttled_mode, most_open) in [(None, 3), (Some(ThrottledMode::default()), 1)] {
+        let extractor = Arc::new(SlowExtractor::default());
+        let last_progress = Arc::new(Mutex::new(None::<PlaylistProgress>));
+        let progress = last_progress.clone();
+
+        let options = PlaylistDownloadOptions {
+            video_options: VideoOptions {
+                extractor: Some(extractor.clone()),
+                request_options: RequestOptions {
+                    throttled_mode,
+                    ..request_options.clone()
+                },
+                ..Default::default()
+            },
+            archive: Some(Arc::new(FixtureArchive(
+                [ids[0].to_string()].into_iter().collect(),
+            ))),
+            on_progress: Some(Arc::new(move |x| {
+                *progress.lock().unwrap() = Some(x.clone());
+            })),
+            ..Default::default()
+        };
+
+        // Archived video is skipped, the others fail without stopping the rest
+        let report = playlist.download_all(&dir, &options, 3).await.unwrap();
+        assert!(report.downloaded.is_empty());
+        assert_eq!(report.skipped, [ids[0]]);
+
+        assert_eq!(report.failed.len(), 4);
+        for (id, err) in &report.failed {
+            match id.as_str() {
+                "eeeeeeeeeee" => assert!(matches!(err, VideoError::ArchiveError(_)), "{err:?}"),
+                _ => assert!(matches!(err, VideoError::VideoNotFound), "{err:?}"),
+            }
+        }
+
+        assert_eq!(extractor.most_open.load(Ordering::SeqCst), most_open);
+
+        let last_progress = last_progress.lock().unwrap().clone().unwrap();
+        assert_eq!(
+            (
+                last_progress.total,
+                last_progress.completed,
+                last_progress.skipped,
+                last_progress.failed
+            ),
+            (5, 0, 1, 4)
+        );
+    }
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[cfg(feature = "object-store")]
+#[tokio::test]
+async fn object_store_sink() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    use bytes::Bytes;
+    use object_store::{memory::InMemory, path::Path, ObjectStore};
+    use rusty_ytdl::storage::{ObjectStoreSink, StorageSink};
+    use rusty_ytdl::stream::{NonLiveStream, NonLiveStreamOptions, Stream};
+    use rusty_ytdl::VideoError;
+
+    let content = (0..200).map(|x| x as u8).collect::<Vec<u8>>();
+    let address = common::spawn_range_server(content.clone()).await;
+
+    let store = Arc::new(InMemory::new());
+
+    // Every chunk ends up in one object
+    let stream = NonLiveStream::new(NonLiveStreamOptions {
+        content_length: content.len() as u64,
+        ..common::stream_options(format!("http://{address}/video"))
+    })
+    .unwrap();
+    let sink = ObjectStoreSink::new(store.clone(), "videos/FZ8BxMU3BYc.mp4");
+    assert_eq!(sink.write_stream(&stream).await.unwrap(), 200);
+
+    let uploaded = store
+        .get(&Path::from("videos/FZ8BxMU3BYc.mp4"))
+        .await
+        .unwrap()
+        .bytes()
+        .await
+        .unwrap();
+    assert_eq!(uploaded, content);
+
+    /// First chunk, then an error
+    #[derive(Default)]
+    struct FailingStream(AtomicBool);
+
+    #[async_trait::async_trait]
+    impl Stream for FailingStream {
+        async fn chunk(&self) -> Result<Option<Bytes>, VideoError> {
+            if self.0.swap(true, Ordering::SeqCst) {
+                return Err(VideoError::DownloadError("connection reset".to_string()));
+            }
+            Ok(Some(Bytes::from_static(b"abc")))
+        }
+    }
+
+    // Upload is aborted, nothing is left at the path
+    let sink = ObjectStoreSink::new(store.clone(), "videos/failed.mp4");
+    let result = sink.write_stream(&FailingStream::default()).await;
+    assert!(
+        matches!(result, Err(VideoError::DownloadError(_))),
+        "{result:?}"
+    );
+    assert!(matches!(
+        store.head(&Path::from("videos/failed.mp4")).await,
+        Err(object_store::Error::NotFound { .. })
+    ));
+}
+
+#[cfg(feature = "aria2")]
+#[test]
+fn aria2_args() {
+    use rusty_ytdl::external::{Aria2Options, ExternalDownloader};
+    use rusty_ytdl::{RequestOptions, VideoFormat};
+
+    let downloader = ExternalDownloader::Aria2(Aria2Options {
+        connections: 4,
+        args: vec!["--all-proxy=http://127.0.0.1:8080".to_string()],
+        ..Default::default()
+    });
+    let request_options = RequestOptions {
+        cookies: Some("SID=abc".to_string()),
+        ..Default::default()
+    };
+
+    let mut format = VideoFormat::default();
+    format.url = "https://youtube.com/videoplayback?itag=18".to_string();
+
+    let args = downloader
+        .args(
+            &format,
+            &request_options,
+            std::path::Path::new("videos/a b.mp4"),
+        )
+        .unwrap();
+
+    assert!(args.contains(&"--dir=videos".to_string()), "{args:?}");
+    assert!(args.contains(&"--out=a b.mp4".to_string()), "{args:?}");
+    assert!(args.contains(&"--split=4".to_string()), "{args:?}");
+    assert!(
+        args.contains(&"--header=cookie: SID=abc".to_string()),
+        "{args:?}"
+    );
+    assert!(
+        args.iter().any(|x| x.starts_with("--user-agent=")),
+        "{args:?}"
+    );
+    assert_eq!(args[args.len() - 2], "--all-proxy=http://127.0.0.1:8080");
+    assert_eq!(args.last().unwrap(), &format.url);
+
+    format.is_hls = true;
+    assert!(downloader
+        .args(&format, &request_options, std::path::Path::new("a.mp4"))
+        .is_err());
+}
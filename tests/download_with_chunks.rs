@@ -1,5 +1,3 @@
-use rusty_ytdl;
-
 #[tokio::test]
 async fn download_with_chunks() {
     use rusty_ytdl::{Video, VideoOptions, VideoQuality, VideoSearchOptions};
@@ -0,0 +1,1390 @@
+mod common;
+
+#[test]
+fn data_model() {
+    use rusty_ytdl::{VideoDetails, VideoFormat, ViewCount};
+    use std::time::Duration;
+
+    let mut format = VideoFormat::default();
+    format.itag = 251;
+    format.content_length = Some("1024".to_string());
+    format.approx_duration_ms = Some("1500".to_string());
+    format.audio_sample_rate = Some("48000".to_string());
+
+    assert_eq!(format.content_length_bytes(), Some(1024));
+    assert_eq!(format.duration(), Some(Duration::from_millis(1500)));
+    assert_eq!(format.sample_rate(), Some(48000));
+    assert!(VideoFormat::default().content_length_bytes().is_none());
+
+    // Typed values survive a JSON round trip next to the raw strings
+    let mut details = VideoDetails::default();
+    details.length_seconds = "90".to_string();
+    details.duration = Duration::from_secs(90);
+    details.view_count = "12345".to_string();
+    details.views = ViewCount::Watching(321);
+
+    let json = serde_json::to_value(&details).unwrap();
+    assert_eq!(json["duration"], 90);
+    assert_eq!(json["views"], serde_json::json!({ "watching": 321 }));
+
+    let details: VideoDetails = serde_json::from_value(json).unwrap();
+    assert_eq!(details.duration, Duration::from_secs(90));
+    assert_eq!(details.views, ViewCount::Watching(321));
+    assert_eq!(details.views.count(), 321);
+    assert!(details.views.is_watching());
+    assert!(!ViewCount::default().is_watching());
+}
+
+#[tokio::test]
+async fn custom_extractor() {
+    use rusty_ytdl::extractor::{ExtractedInfo, Extractor};
+    use rusty_ytdl::{Config, Video, VideoError, VideoOptions};
+
+    struct FixtureExtractor;
+
+    #[async_trait::async_trait]
+    impl Extractor for FixtureExtractor {
+        async fn fetch_info(
+            &self,
+            _client: &reqwest_middleware::ClientWithMiddleware,
+            _config: &Config,
+            video_id: &str,
+        ) -> Result<ExtractedInfo, VideoError> {
+            Ok(ExtractedInfo {
+                player_response: serde_json::json!({
+                    "playabilityStatus": { "status": "OK" },
+                    "streamingData": { "formats": [], "adaptiveFormats": [] },
+                    "videoDetails": { "videoId": video_id, "title": "Fixture", "lengthSeconds": "10" },
+                }),
+                ..Default::default()
+            })
+        }
+
+        async fn decipher(
+            &self,
+            _client: &reqwest_middleware::ClientWithMiddleware,
+            _config: &Config,
+            _info: &ExtractedInfo,
+        ) -> Result<Vec<(String, String)>, VideoError> {
+            Ok(vec![])
+        }
+    }
+
+    let video = Video::new_with_options(
+        "FZ8BxMU3BYc",
+        VideoOptions {
+            extractor: Some(std::sync::Arc::new(FixtureExtractor)),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let info = video.get_basic_info().await.unwrap();
+    assert_eq!(info.video_details.title, "Fixture");
+    assert!(info.formats.is_empty());
+}
+
+#[tokio::test]
+async fn extractor_error_variant() {
+    use rusty_ytdl::extractor::{ExtractedInfo, Extractor};
+    use rusty_ytdl::{current_request_id, Config, Video, VideoError, VideoOptions};
+
+    struct MissingExtractor(std::sync::Mutex<Option<rusty_ytdl::RequestId>>);
+
+    #[async_trait::async_trait]
+    impl Extractor for MissingExtractor {
+        async fn fetch_info(
+            &self,
+            _client: &reqwest_middleware::ClientWithMiddleware,
+            _config: &Config,
+            _video_id: &str,
+        ) -> Result<ExtractedInfo, VideoError> {
+            *self.0.lock().unwrap() = current_request_id();
+
+            Ok(ExtractedInfo {
+                player_response: serde_json::json!({ "playabilityStatus": { "status": "ERROR" } }),
+                ..Default::default()
+            })
+        }
+
+        async fn decipher(
+            &self,
+            _client: &reqwest_middleware::ClientWithMiddleware,
+            _config: &Config,
+            _info: &ExtractedInfo,
+        ) -> Result<Vec<(String, String)>, VideoError> {
+            Ok(vec![])
+        }
+    }
+
+    let extractor = std::sync::Arc::new(MissingExtractor(Default::default()));
+    let video = Video::new_with_options(
+        "FZ8BxMU3BYc",
+        VideoOptions {
+            extractor: Some(extractor.clone()),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(video.last_error_context(), None);
+
+    // Variant and message stay the same, the id of the operation is kept on the video
+    let err = video.get_info().await.unwrap_err();
+    assert!(matches!(err, VideoError::VideoNotFound));
+    assert_eq!(err.to_string(), VideoError::VideoNotFound.to_string());
+
+    let request_id = video.last_error_context().unwrap().request_id;
+    assert!(request_id.is_some());
+    assert_eq!(request_id, *extractor.0.lock().unwrap());
+}
+
+#[test]
+fn client_fallback() {
+    use rusty_ytdl::extractor::{Extractor, InnertubeExtractor, WebExtractor};
+    use rusty_ytdl::{ClientFallback, HeaderProfile, InnertubeClient, InnertubeConfig, VideoInfo};
+
+    let fallback = ClientFallback::default();
+    assert_eq!(
+        fallback.clients,
+        vec![
+            InnertubeClient::Android,
+            InnertubeClient::Ios,
+            InnertubeClient::Tv
+        ]
+    );
+    assert!(fallback.on_missing_streaming_data && fallback.on_forbidden_format);
+
+    let extractor = InnertubeExtractor::new(InnertubeClient::Ios);
+    let body = extractor.player_request(&InnertubeConfig::default(), "FZ8BxMU3BYc", None);
+    assert_eq!(body["context"]["client"]["clientName"], "IOS");
+    assert_eq!(
+        body["context"]["client"]["clientVersion"],
+        InnertubeClient::Ios.version()
+    );
+    assert_eq!(extractor.client(), Some(InnertubeClient::Ios));
+    assert_eq!(WebExtractor.client(), Some(InnertubeClient::Web));
+    assert_eq!(InnertubeClient::Tv.header_profile(), HeaderProfile::Tv);
+
+    let info: VideoInfo = serde_json::from_value(serde_json::json!({
+        "dashManifestUrl": null,
+        "hlsManifestUrl": null,
+        "formats": [],
+        "relatedVideos": [],
+        "videoDetails": serde_json::to_value(rusty_ytdl::VideoDetails::default()).unwrap(),
+        "extractionClient": "Android",
+    }))
+    .unwrap();
+    assert_eq!(info.extraction_client, Some(InnertubeClient::Android));
+}
+
+#[tokio::test]
+async fn client_headers() {
+    use std::sync::{Arc, Mutex};
+
+    use rusty_ytdl::constants::{ANDROID_USER_AGENT, DESKTOP_USER_AGENT};
+    use rusty_ytdl::extractor::{Extractor, InnertubeExtractor};
+    use rusty_ytdl::{Config, InnertubeClient};
+
+    let requests = Arc::new(Mutex::new(vec![]));
+    let received = requests.clone();
+    let address = common::spawn_server(move |request| {
+        let response = match request.line().as_str() {
+            "GET /iframe_api" => common::response("200 OK", &[], r"\/s\/player\/abcdef12\/"),
+            x if x.starts_with("POST /youtubei/v1/player") => common::json_response(
+                &serde_json::json!({ "playabilityStatus": { "status": "OK" } }),
+            ),
+            _ => common::response("200 OK", &[], ""),
+        };
+        received.lock().unwrap().push(request);
+        response
+    })
+    .await;
+
+    let config = Config {
+        base_url: format!("http://{address}"),
+        origin: format!("http://{address}"),
+        ..Default::default()
+    };
+    let client = reqwest_middleware::ClientBuilder::new(rusty_ytdl::reqwest::Client::new()).build();
+
+    InnertubeExtractor::new(InnertubeClient::Android)
+        .fetch_info(&client, &config, "FZ8BxMU3BYc")
+        .await
+        .unwrap();
+
+    // Pages are fetched as the web client, the player as the client of the extractor
+    let requests = requests.lock().unwrap();
+    let iframe_api = requests.iter().find(|x| x.path == "/iframe_api").unwrap();
+    assert_eq!(iframe_api.header("user-agent"), Some(DESKTOP_USER_AGENT));
+    assert_eq!(iframe_api.header("x-youtube-client-name"), Some("1"));
+
+    let player = requests
+        .iter()
+        .find(|x| x.path.starts_with("/youtubei/v1/player"))
+        .unwrap();
+    assert_eq!(player.header("user-agent"), Some(ANDROID_USER_AGENT));
+    assert_eq!(player.header("x-youtube-client-name"), Some("3"));
+}
+
+#[test]
+fn signature_timestamp() {
+    use rusty_ytdl::extractor::InnertubeExtractor;
+    use rusty_ytdl::{InnertubeConfig, PlayerFunctions};
+
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/players");
+    let sts = |player: &str| {
+        PlayerFunctions::extract(&std::fs::read_to_string(dir.join(player)).unwrap())
+            .signature_timestamp
+    };
+
+    assert_eq!(sts("2021-alr.js"), Some(18764));
+    assert_eq!(sts("2023-fromcharcode.js"), Some(19590));
+    assert_eq!(
+        PlayerFunctions::extract("var a={sts:123};").signature_timestamp,
+        None
+    );
+
+    let body = InnertubeExtractor::default().player_request(
+        &InnertubeConfig::default(),
+        "FZ8BxMU3BYc",
+        Some(19590),
+    );
+    assert_eq!(body["videoId"], "FZ8BxMU3BYc");
+    assert_eq!(
+        body["playbackContext"]["contentPlaybackContext"]["signatureTimestamp"],
+        19590
+    );
+    assert_eq!(body["context"]["client"]["clientName"], "WEB");
+
+    let body = InnertubeExtractor::default().player_request(
+        &InnertubeConfig::default(),
+        "FZ8BxMU3BYc",
+        None,
+    );
+    assert!(body["playbackContext"]["contentPlaybackContext"]
+        .get("signatureTimestamp")
+        .is_none());
+}
+
+/// Reduced player scripts in `tests/players` with the decipher and `n` call sites of past Youtube players.
+/// Expected outputs were recorded by running the fixtures in V8 (node)
+#[cfg(feature = "extract")]
+#[test]
+fn player_corpus() {
+    use rusty_ytdl::PlayerFunctions;
+
+    #[derive(serde::Deserialize)]
+    struct Fixture {
+        player: String,
+        decipher: String,
+        n_transform: String,
+        signature_timestamp: u64,
+        signatures: Vec<(String, String)>,
+        n: Vec<(String, String)>,
+    }
+
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/players");
+    let corpus: Vec<Fixture> =
+        serde_json::from_str(&std::fs::read_to_string(dir.join("corpus.json")).unwrap()).unwrap();
+
+    for fixture in corpus {
+        let body = std::fs::read_to_string(dir.join(&fixture.player)).unwrap();
+        let functions = PlayerFunctions::extract(&body);
+
+        let name = |x: &Option<(String, String)>| x.as_ref().map(|x| x.0.clone());
+        assert_eq!(
+            name(&functions.decipher),
+            Some(fixture.decipher),
+            "{}",
+            fixture.player
+        );
+        assert_eq!(
+            name(&functions.n_transform),
+            Some(fixture.n_transform),
+            "{}",
+            fixture.player
+        );
+        assert_eq!(
+            functions.signature_timestamp,
+            Some(fixture.signature_timestamp)
+        );
+
+        for (signature, expected) in fixture.signatures {
+            assert_eq!(
+                functions.decipher(&signature).as_deref(),
+                Some(expected.as_str()),
+                "{} signature {signature}",
+                fixture.player
+            );
+        }
+        for (n, expected) in fixture.n {
+            assert_eq!(
+                functions.transform_n(&n).as_deref(),
+                Some(expected.as_str()),
+                "{} n {n}",
+                fixture.player
+            );
+        }
+    }
+}
+
+/// Extraction on mutated `tests/players` fixtures must not panic. Set `RUSTY_YTDL_PLAYER_FUZZ` to the number of
+/// mutations per fixture for a longer run and `RUSTY_YTDL_PLAYER_FUZZ_SEED` to replay a failure
+#[test]
+fn player_fuzz() {
+    use rand::{Rng, SeedableRng};
+    use rusty_ytdl::PlayerFunctions;
+
+    let iterations: usize = std::env::var("RUSTY_YTDL_PLAYER_FUZZ")
+        .ok()
+        .and_then(|x| x.parse().ok())
+        .unwrap_or(64);
+    let seed: u64 = std::env::var("RUSTY_YTDL_PLAYER_FUZZ_SEED")
+        .ok()
+        .and_then(|x| x.parse().ok())
+        .unwrap_or_else(rand::random);
+
+    // Fragments breaking the scanners: quotes, brackets, regex slashes, multi-byte and combining chars
+    let fragments = [
+        "\"",
+        "'",
+        "`",
+        "{",
+        "}",
+        "[",
+        "]",
+        "(",
+        ")",
+        "/",
+        "\\",
+        ";",
+        "é",
+        "e\u{301}",
+        "👍",
+        "=function(a)",
+        "a.set(\"alr\",\"yes\");c&&(c=",
+        ".get(\"n\"))&&(b=",
+        "[0](b)",
+        "var ",
+    ];
+
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/players");
+    let mut players = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|x| x.unwrap().path())
+        .filter(|x| x.extension().map(|x| x == "js").unwrap_or(false))
+        .collect::<Vec<_>>();
+    players.sort();
+    assert!(!players.is_empty());
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+
+    for player in players {
+        let body = std::fs::read_to_string(&player).unwrap();
+        let functions = PlayerFunctions::extract(&body);
+        assert!(
+            functions.decipher.is_some() && functions.n_transform.is_some(),
+            "{}",
+            player.display()
+        );
+
+        let chars = body.chars().collect::<Vec<char>>();
+
+        for iteration in 0..iterations {
+            let mut mutated = chars.clone();
+            for _ in 0..rng.gen_range(1..=4) {
+                let at = rng.gen_range(0..=mutated.len());
+                match rng.gen_range(0..3) {
+                    0 => mutated.truncate(at),
+                    1 => {
+                        let end = (at + rng.gen_range(1..64)).min(mutated.len());
+                        mutated.drain(at..end);
+                    }
+                    _ => {
+                        let fragment = fragments[rng.gen_range(0..fragments.len())];
+                        mutated.splice(at..at, fragment.chars());
+                    }
+                }
+            }
+            let mutated = mutated.into_iter().collect::<String>();
+
+            let result = std::panic::catch_unwind(|| PlayerFunctions::extract(&mutated));
+            assert!(
+                result.is_ok(),
+                "{} panicked, RUSTY_YTDL_PLAYER_FUZZ_SEED={seed} iteration {iteration}",
+                player.display()
+            );
+        }
+    }
+}
+
+#[test]
+fn runtime_config() {
+    use rusty_ytdl::{Config, RequestOptions, Video, VideoOptions};
+
+    let config = Config::default();
+    assert_eq!(
+        config.watch_url("FZ8BxMU3BYc"),
+        "https://www.youtube.com/watch?v=FZ8BxMU3BYc"
+    );
+    assert_eq!(config.url("/results"), "https://www.youtube.com/results");
+
+    let options = VideoOptions {
+        request_options: RequestOptions {
+            config: Config {
+                base_url: "https://yt.example.com/watch?v=".to_string(),
+                origin: "https://yt.example.com/".to_string(),
+                valid_query_domains: vec!["yt.example.com".to_string()],
+            },
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let video = Video::new_with_options(
+        "https://yt.example.com/watch?v=FZ8BxMU3BYc",
+        options.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        video.get_video_url(),
+        "https://yt.example.com/watch?v=FZ8BxMU3BYc"
+    );
+    assert_eq!(
+        options.request_options.config.url("/results"),
+        "https://yt.example.com/results"
+    );
+
+    // Only the configured hosts are accepted
+    assert!(Video::new("https://yt.example.com/watch?v=FZ8BxMU3BYc").is_err());
+    assert!(
+        Video::new_with_options("https://www.youtube.com/watch?v=FZ8BxMU3BYc", options).is_err()
+    );
+}
+
+#[test]
+fn error_source_chain() {
+    use rusty_ytdl::VideoError;
+    use std::error::Error;
+
+    let err = rusty_ytdl::compat::ytdlp::from_info_json_str("{").unwrap_err();
+    match &err {
+        VideoError::Json(json) => assert!(json.is_eof()),
+        _ => panic!("{err:?}"),
+    }
+
+    let inner = VideoError::FFmpegNotFound("ffmpeg".to_string());
+    let err = VideoError::PostProcessError("mux".to_string(), Box::new(inner));
+    assert_eq!(
+        err.source().unwrap().to_string(),
+        "ffmpeg not found: ffmpeg"
+    );
+    assert!(err.help().unwrap().contains("FFmpegLocator"));
+
+    let err = VideoError::BotDetection {
+        captcha_url: "https://www.google.com/sorry/index".to_string(),
+    };
+    assert!(err.help().unwrap().contains("cookies"));
+    assert!(VideoError::FormatNotFound.help().is_none());
+}
+
+#[test]
+fn thread_safety() {
+    use rusty_ytdl::{IdentityPool, ProxyPool, RequestOptions, Video, VideoInfo, VideoOptions};
+
+    fn assert_send_sync<T: Send + Sync + 'static>() {}
+
+    assert_send_sync::<Video>();
+    assert_send_sync::<VideoOptions>();
+    assert_send_sync::<RequestOptions>();
+    assert_send_sync::<VideoInfo>();
+    assert_send_sync::<ProxyPool>();
+    assert_send_sync::<IdentityPool>();
+    assert_send_sync::<rusty_ytdl::extractor::WebExtractor>();
+
+    #[cfg(feature = "search")]
+    {
+        assert_send_sync::<rusty_ytdl::search::YouTube>();
+        assert_send_sync::<rusty_ytdl::search::Playlist>();
+    }
+
+    #[cfg(feature = "blocking")]
+    assert_send_sync::<rusty_ytdl::blocking::Video>();
+
+    // Clones share the options instead of copying them per task
+    let video = Video::new("FZ8BxMU3BYc").unwrap();
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let video = video.clone();
+            std::thread::spawn(move || video.get_video_id())
+        })
+        .collect();
+
+    for handle in handles {
+        assert_eq!(handle.join().unwrap(), "FZ8BxMU3BYc");
+    }
+}
+
+#[tokio::test]
+async fn choose_audio_track() {
+    use rusty_ytdl::{choose_format, VideoFormat, VideoOptions, VideoQuality, VideoSearchOptions};
+
+    let format = |itag: u64, audio_track: serde_json::Value| -> VideoFormat {
+        serde_json::from_value(serde_json::json!({
+            "itag": itag,
+            "mimeType": "audio/webm; codecs=\"opus\"",
+            "bitrate": 1000,
+            "audioBitrate": 160,
+            "url": "",
+            "hasVideo": false,
+            "hasAudio": true,
+            "isLive": false,
+            "isHLS": false,
+            "isDashMPD": false,
+            "audioTrackId": audio_track.get("id"),
+            "audioTrackName": audio_track.get("displayName"),
+            "isDefaultAudio": audio_track.get("audioIsDefault"),
+        }))
+        .unwrap()
+    };
+
+    let formats = vec![
+        format(
+            251,
+            serde_json::json!({ "id": "es-419.3", "displayName": "Spanish (Latin America)", "audioIsDefault": true }),
+        ),
+        format(
+            251,
+            serde_json::json!({ "id": "en-US.4", "displayName": "English (United States) original", "audioIsDefault": false }),
+        ),
+        format(
+            251,
+            serde_json::json!({ "id": "fr-FR.3", "displayName": "French (France)", "audioIsDefault": false }),
+        ),
+    ];
+
+    let choose = |audio_language: Option<&str>| {
+        let options = VideoOptions {
+            quality: VideoQuality::HighestAudio,
+            filter: VideoSearchOptions::Audio,
+            audio_language: audio_language.map(|x| x.to_string()),
+            ..Default::default()
+        };
+
+        choose_format(&formats, &options).unwrap().audio_track_id
+    };
+
+    // Default track is a dub, original is preferred
+    assert_eq!(choose(None).as_deref(), Some("en-US.4"));
+    assert_eq!(choose(Some("es")).as_deref(), Some("es-419.3"));
+    assert_eq!(choose(Some("fr-FR")).as_deref(), Some("fr-FR.3"));
+    // Unknown language falls back to every track
+    assert!(choose(Some("de")).is_some());
+}
+
+#[test]
+fn choose_drc_variant() {
+    use rusty_ytdl::{choose_format, FormatSorter, VideoFormat, VideoOptions, VideoQuality};
+
+    let format = |itag: u64, audio_bitrate: u64, is_drc: bool| -> VideoFormat {
+        serde_json::from_value(serde_json::json!({
+            "itag": itag,
+            "mimeType": "audio/webm; codecs=\"opus\"",
+            "bitrate": 1000,
+            "audioBitrate": audio_bitrate,
+            "url": "",
+            "hasVideo": false,
+            "hasAudio": true,
+            "isLive": false,
+            "isHLS": false,
+            "isDashMPD": false,
+            "isDrc": is_drc,
+        }))
+        .unwrap()
+    };
+
+    let formats = vec![
+        format(251, 160, true),
+        format(251, 160, false),
+        format(250, 64, true),
+        format(250, 64, false),
+        format(249, 48, true),
+    ];
+
+    let choose = |quality: VideoQuality, prefer_drc: bool| {
+        let options = VideoOptions {
+            quality,
+            prefer_drc,
+            ..Default::default()
+        };
+        let format = choose_format(&formats, &options).unwrap();
+        (format.itag, format.is_drc)
+    };
+
+    assert_eq!(choose(VideoQuality::HighestAudio, false), (251, false));
+    assert_eq!(choose(VideoQuality::HighestAudio, true), (251, true));
+    // Only served with DRC
+    assert_eq!(choose(VideoQuality::LowestAudio, false), (249, true));
+
+    let mut sorted = formats.clone();
+    FormatSorter::audio().sort(&mut sorted);
+    assert!(!sorted[0].is_drc && sorted[1].is_drc);
+}
+
+#[test]
+fn exclude_premium_formats() {
+    use rusty_ytdl::{
+        choose_format, RequestOptions, VideoFormat, VideoOptions, VideoSearchOptions,
+    };
+
+    let format = |itag: u64, quality_label: &str, bitrate: u64| -> VideoFormat {
+        serde_json::from_value(serde_json::json!({
+            "itag": itag,
+            "mimeType": "video/webm; codecs=\"vp9\"",
+            "bitrate": bitrate,
+            "qualityLabel": quality_label,
+            "url": "",
+            "hasVideo": true,
+            "hasAudio": false,
+            "isLive": false,
+            "isHLS": false,
+            "isDashMPD": false,
+        }))
+        .unwrap()
+    };
+
+    let formats = vec![
+        format(248, "1080p", 2_000_000),
+        format(356, "1080p Premium", 6_000_000),
+    ];
+    assert!(!formats[0].requires_premium());
+    assert!(formats[1].requires_premium());
+
+    let choose = |cookies: Option<&str>| {
+        let options = VideoOptions {
+            filter: VideoSearchOptions::Video,
+            request_options: RequestOptions {
+                cookies: cookies.map(|x| x.to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        choose_format(&formats, &options).unwrap().itag
+    };
+
+    assert_eq!(choose(None), 248);
+    assert_eq!(choose(Some("SOCS=CAI; CONSENT=YES+cb")), 248);
+    assert_eq!(choose(Some("SOCS=CAI; SAPISID=abc/def")), 356);
+}
+
+#[test]
+fn country_code() {
+    use rusty_ytdl::{CountryCode, VideoDetails};
+
+    let country: CountryCode = "de".parse().unwrap();
+    assert_eq!(country.to_string(), "DE");
+    assert!("D".parse::<CountryCode>().is_err());
+    assert!("D1".parse::<CountryCode>().is_err());
+
+    let mut value = serde_json::to_value(VideoDetails::default()).unwrap();
+    assert!(VideoDetails::default().is_available_in(&country));
+
+    value["availableCountries"] = serde_json::json!(["US", "fr"]);
+    value["isEmbeddable"] = serde_json::json!(true);
+    let details: VideoDetails = serde_json::from_value(value.clone()).unwrap();
+    assert!(details.is_embeddable);
+    assert!(details.is_available_in(&"FR".parse().unwrap()));
+    assert!(!details.is_available_in(&country));
+
+    value["availableCountries"] = serde_json::json!(["USA"]);
+    assert!(serde_json::from_value::<VideoDetails>(value).is_err());
+}
+
+#[test]
+fn community_post_poll() {
+    use rusty_ytdl::parse_poll;
+
+    let post = serde_json::json!({ "backstagePostRenderer": {
+        "contentText": { "runs": [{ "text": "Next video?" }] },
+        "backstageAttachment": { "pollRenderer": {
+            "choices": [
+                { "text": { "runs": [{ "text": "Tutorial" }] }, "voteRatio": 0.75, "votePercentage": { "simpleText": "75%" } },
+                { "text": { "runs": [{ "text": "Vlog" }] }, "votePercentage": { "simpleText": "25%" } },
+                { "image": {} },
+            ],
+            "totalVotes": { "simpleText": "1.2K votes" },
+        }},
+    }});
+
+    let poll = parse_poll(&post).unwrap();
+    assert_eq!(poll.question, None);
+    assert_eq!(poll.total_votes, Some(1200));
+    assert_eq!(poll.choices.len(), 2);
+    assert_eq!(poll.choices[0].text, "Tutorial");
+    assert_eq!(poll.choices[0].percentage, Some(75.0));
+    assert_eq!(poll.choices[1].percentage, Some(25.0));
+    assert_eq!(poll.choices[1].votes, None);
+
+    let card = serde_json::json!({ "cardRenderer": { "content": { "pollRenderer": {
+        "question": { "simpleText": "Favourite level?" },
+        "choices": [{ "text": { "simpleText": "1-1" }, "numVotes": "42" }],
+    }}}});
+    let poll = parse_poll(&card).unwrap();
+    assert_eq!(poll.question.as_deref(), Some("Favourite level?"));
+    assert_eq!(poll.choices[0].votes, Some(42));
+    assert_eq!(poll.total_votes, None);
+
+    assert!(parse_poll(&serde_json::json!({ "backstagePostRenderer": {} })).is_none());
+}
+
+#[tokio::test]
+async fn format_filter() {
+    use rusty_ytdl::{choose_format, FormatFilter, VideoFormat, VideoOptions, VideoQuality};
+
+    let format = |itag: u64, mime_type: &str, has_video: bool, has_audio: bool| -> VideoFormat {
+        let codecs = mime_type.split('"').nth(1).unwrap_or("");
+        serde_json::from_value(serde_json::json!({
+            "itag": itag,
+            "mimeType": mime_type,
+            "bitrate": itag * 1000,
+            "url": "",
+            "hasVideo": has_video,
+            "hasAudio": has_audio,
+            "isLive": false,
+            "isHLS": false,
+            "isDashMPD": false,
+            "container": mime_type.split(';').next().unwrap().split('/').nth(1),
+            "codecs": codecs,
+        }))
+        .unwrap()
+    };
+
+    let formats = vec![
+        format(
+            18,
+            r#"video/mp4; codecs="avc1.42001E, mp4a.40.2""#,
+            true,
+            true,
+        ),
+        format(140, r#"audio/mp4; codecs="mp4a.40.2""#, false, true),
+        format(251, r#"audio/webm; codecs="opus""#, false, true),
+        format(248, r#"video/webm; codecs="vp9""#, true, false),
+    ];
+
+    let choose = |filter: FormatFilter| {
+        let options = VideoOptions {
+            quality: VideoQuality::Highest,
+            filter: filter.into(),
+            ..Default::default()
+        };
+
+        choose_format(&formats, &options).map(|x| x.itag).ok()
+    };
+
+    assert_eq!(choose(FormatFilter::audio()), Some(251));
+    assert_eq!(
+        choose(FormatFilter::audio().and(FormatFilter::container("mp4"))),
+        Some(140)
+    );
+    assert_eq!(choose(FormatFilter::codec("VP9")), Some(248));
+    assert_eq!(
+        choose(FormatFilter::has_audio().and(!FormatFilter::itag(251))),
+        Some(18)
+    );
+    assert_eq!(
+        choose(FormatFilter::itag(1).or(FormatFilter::itag(140))),
+        Some(140)
+    );
+    assert_eq!(choose(FormatFilter::new(|x| x.bitrate > 1_000_000)), None);
+}
+
+#[tokio::test]
+async fn format_sorter() {
+    use rusty_ytdl::{FormatSortKey, FormatSorter, VideoFormat};
+
+    let format = |itag: u64, value: serde_json::Value| -> VideoFormat {
+        let mut base = serde_json::json!({
+            "itag": itag,
+            "mimeType": "video/mp4",
+            "bitrate": 1000,
+            "url": "",
+            "hasVideo": true,
+            "hasAudio": false,
+            "isLive": false,
+            "isHLS": false,
+            "isDashMPD": false,
+        });
+        base.as_object_mut()
+            .unwrap()
+            .extend(value.as_object().unwrap().clone());
+        serde_json::from_value(base).unwrap()
+    };
+
+    let formats = vec![
+        format(
+            251,
+            serde_json::json!({ "hasVideo": false, "hasAudio": true, "codecs": "opus", "audioBitrate": 160 }),
+        ),
+        format(
+            136,
+            serde_json::json!({ "qualityLabel": "720p", "fps": 30, "codecs": "avc1.4d401f" }),
+        ),
+        format(
+            298,
+            serde_json::json!({ "qualityLabel": "720p60", "fps": 60, "codecs": "avc1.4d4020" }),
+        ),
+        format(
+            247,
+            serde_json::json!({ "qualityLabel": "720p", "fps": 30, "codecs": "vp9" }),
+        ),
+        format(
+            18,
+            serde_json::json!({ "qualityLabel": "360p", "hasAudio": true, "codecs": "avc1.42001E, mp4a.40.2" }),
+        ),
+        format(
+            140,
+            serde_json::json!({ "hasVideo": false, "hasAudio": true, "codecs": "mp4a.40.2", "audioBitrate": 128 }),
+        ),
+    ];
+
+    let itags = |sorter: &FormatSorter, formats: &[VideoFormat]| {
+        let mut formats = formats.to_vec();
+        sorter.sort(&mut formats);
+        formats.iter().map(|x| x.itag).collect::<Vec<u64>>()
+    };
+
+    // Audio & video first, then by resolution, fps and codec preference, equal formats by itag
+    assert_eq!(
+        itags(&FormatSorter::default(), &formats),
+        vec![18, 298, 247, 136, 251, 140]
+    );
+
+    // Order does not depend on input order
+    let mut reversed = formats.clone();
+    reversed.reverse();
+    assert_eq!(
+        itags(&FormatSorter::default(), &reversed),
+        itags(&FormatSorter::default(), &formats)
+    );
+
+    let mut sorter = FormatSorter::new(vec![FormatSortKey::Resolution, FormatSortKey::VideoCodec]);
+    sorter.video_codecs = vec!["avc1".to_string(), "vp9".to_string()];
+    assert_eq!(itags(&sorter, &formats), vec![136, 298, 247, 18, 140, 251]);
+
+    assert_eq!(
+        itags(&FormatSorter::audio(), &formats),
+        vec![251, 140, 18, 136, 247, 298]
+    );
+}
+
+#[test]
+fn format_id_round_trip() {
+    use rusty_ytdl::{FormatFilter, FormatId, FormatProtocol, VideoFormat, VideoInfo};
+
+    for id in [
+        "251",
+        "251-drc",
+        "96-hls",
+        "140-drc-dash@es-419.3",
+        "137+140@en.4",
+    ] {
+        let parsed: FormatId = id.parse().unwrap();
+        assert_eq!(parsed.to_string(), id);
+
+        let json = serde_json::to_string(&parsed).unwrap();
+        assert_eq!(json, format!("\"{id}\""));
+        assert_eq!(serde_json::from_str::<FormatId>(&json).unwrap(), parsed);
+    }
+
+    for id in ["", "abc", "251-foo", "251-hls-dash", "251@", "137+"] {
+        assert!(id.parse::<FormatId>().is_err(), "{id}");
+    }
+
+    let mut plain = VideoFormat::default();
+    plain.itag = 251;
+    plain.url = "https://example.com/videoplayback?itag=251".to_string();
+
+    let mut drc = VideoFormat::default();
+    drc.itag = 251;
+    drc.is_drc = true;
+
+    let mut video = VideoFormat::default();
+    video.itag = 137;
+
+    assert_eq!(drc.format_id().to_string(), "251-drc");
+    assert_eq!(drc.format_id().parts()[0].protocol, FormatProtocol::Https);
+
+    let filter = FormatFilter::from("251-drc".parse::<FormatId>().unwrap());
+    assert!(filter.matches(&drc));
+    assert!(!filter.matches(&plain));
+
+    let mut info = VideoInfo::default();
+    info.formats = vec![plain, drc, video];
+
+    let id = info.formats[2]
+        .format_id()
+        .join(info.formats[1].format_id());
+    assert_eq!(id.to_string(), "137+251-drc");
+
+    let formats = info.formats_by_id(&id).unwrap();
+    assert_eq!(formats.len(), 2);
+    assert_eq!(formats[0].itag, 137);
+    assert!(formats[1].is_drc);
+
+    assert!(info.formats_by_id(&"140".parse().unwrap()).is_err());
+}
+
+#[test]
+fn size_estimate() {
+    use rusty_ytdl::{FormatFilter, SizeSource, VideoFormat, VideoInfo};
+
+    let format = |itag: u64, has_video: bool, has_audio: bool| {
+        let mut format = VideoFormat::default();
+        format.itag = itag;
+        format.has_video = has_video;
+        format.has_audio = has_audio;
+        format
+    };
+
+    let mut video = format(137, true, false);
+    video.content_length = Some("50000000".to_string());
+    video.bitrate = 4_000_000;
+
+    // 128 kbps for the 200 seconds of the video
+    let mut audio = format(140, false, true);
+    audio.average_bitrate = Some(128_000);
+    audio.bitrate = 130_000;
+
+    let mut muxed = format(18, true, true);
+    muxed.approx_duration_ms = Some("100000".to_string());
+    muxed.bitrate = 800_000;
+
+    let mut info = VideoInfo::default();
+    info.video_details.duration = std::time::Duration::from_secs(200);
+    info.formats = vec![video, audio, muxed, format(22, true, true)];
+
+    let estimate = info.estimate_download_size(&FormatFilter::any());
+    let sizes: Vec<(u64, Option<u64>, SizeSource)> = estimate
+        .formats
+        .iter()
+        .map(|x| (x.itag, x.bytes, x.source))
+        .collect();
+    assert_eq!(
+        sizes,
+        vec![
+            (137, Some(50_000_000), SizeSource::ContentLength),
+            (140, Some(3_200_000), SizeSource::Bitrate),
+            (18, Some(10_000_000), SizeSource::Bitrate),
+            (22, None, SizeSource::Unknown),
+        ]
+    );
+
+    let combined = estimate.combined.unwrap();
+    assert_eq!(combined.itag, 137);
+    assert_eq!(combined.bytes, Some(53_200_000));
+    assert_eq!(combined.source, SizeSource::Bitrate);
+
+    let audio = info.estimate_download_size(&FormatFilter::audio());
+    assert_eq!(audio.best.unwrap().itag, 140);
+    assert!(audio.combined.is_none());
+}
+
+#[test]
+fn storyboard_tile() {
+    use rusty_ytdl::StoryBoard;
+    use std::time::Duration;
+
+    let mut storyboard = StoryBoard::default();
+    storyboard.template_url =
+        "https://i.ytimg.com/sb/FZ8BxMU3BYc/storyboard3_L2/M$M.jpg".to_string();
+    storyboard.thumbnail_width = 160;
+    storyboard.thumbnail_height = 90;
+    storyboard.thumbnail_count = 120;
+    storyboard.interval = 2000;
+    storyboard.columns = 5;
+    storyboard.rows = 5;
+
+    let duration = Duration::from_secs(240);
+
+    // Thumbnail 36: second sheet, third row, second column
+    let tile = storyboard
+        .tile_at(Duration::from_millis(73_500), duration)
+        .unwrap();
+    assert_eq!(
+        tile.url,
+        "https://i.ytimg.com/sb/FZ8BxMU3BYc/storyboard3_L2/M1.jpg"
+    );
+    assert_eq!(
+        (tile.x, tile.y, tile.width, tile.height),
+        (160, 180, 160, 90)
+    );
+    assert_eq!(tile.start, Duration::from_secs(72));
+
+    // Past the end, the last thumbnail
+    let tile = storyboard
+        .tile_at(Duration::from_secs(600), duration)
+        .unwrap();
+    assert_eq!(
+        tile.url,
+        "https://i.ytimg.com/sb/FZ8BxMU3BYc/storyboard3_L2/M4.jpg"
+    );
+    assert_eq!((tile.x, tile.y), (640, 270));
+
+    // Level without interval spreads thumbnails over the video
+    storyboard.interval = 0;
+    storyboard.thumbnail_count = 100;
+    let tile = storyboard
+        .tile_at(Duration::from_secs(120), duration)
+        .unwrap();
+    assert_eq!(tile.start, Duration::from_millis(50 * 2400));
+
+    storyboard.columns = 0;
+    assert!(storyboard.tile_at(Duration::ZERO, duration).is_none());
+}
+
+#[test]
+fn curl_export() {
+    use rusty_ytdl::{RequestOptions, VideoFormat};
+
+    let mut headers = rusty_ytdl::reqwest::header::HeaderMap::new();
+    headers.insert("x-test", "it's".parse().unwrap());
+    let request_options = RequestOptions {
+        cookies: Some("SID=abc".to_string()),
+        headers,
+        ..Default::default()
+    };
+
+    let mut format = VideoFormat::default();
+    format.url = "https://rr1---sn-abc.googlevideo.com/videoplayback?itag=18&sig=x".to_string();
+
+    let request = format.as_http_request(&request_options).unwrap();
+    assert_eq!(request.method(), "GET");
+    assert_eq!(request.uri().to_string(), format.url);
+    // Media requests get the custom headers, no header profile
+    assert_eq!(request.headers()["x-test"], "it's");
+    assert!(request.headers().get("user-agent").is_none());
+    // Cookies are only sent to youtube.com
+    assert!(request.headers().get("cookie").is_none());
+
+    let curl = format.as_curl_command(&request_options).unwrap();
+    assert!(
+        curl.starts_with(&format!("curl '{}'", format.url)),
+        "{curl}"
+    );
+    assert!(curl.contains(r#" -H 'x-test: it'\''s'"#), "{curl}");
+
+    format.url = "https://youtube.com/api/timedtext?v=FZ8BxMU3BYc".to_string();
+    let request = format.as_http_request(&request_options).unwrap();
+    assert_eq!(request.headers()["cookie"], "SID=abc");
+
+    format.url = String::new();
+    assert!(format.as_http_request(&request_options).is_err());
+}
+
+#[test]
+fn info_json() {
+    use rusty_ytdl::{VideoFormat, VideoInfo, ViewCount};
+
+    let mut format = VideoFormat::default();
+    format.itag = 251;
+    format.url = "https://rr1.googlevideo.com/videoplayback?itag=251".to_string();
+    format.mime_type =
+        serde_json::from_value(serde_json::json!("audio/webm; codecs=\"opus\"")).unwrap();
+    format.has_audio = true;
+    format.bitrate = 130500;
+    format.audio_sample_rate = Some("48000".to_string());
+    format.content_length = Some("3456789".to_string());
+    format.approx_duration_ms = Some("212001".to_string());
+
+    let mut video_info = VideoInfo::default();
+    video_info.formats = vec![format];
+
+    let details = &mut video_info.video_details;
+    details.video_id = "FZ8BxMU3BYc".to_string();
+    details.title = "Title".to_string();
+    details.channel_id = "UC123".to_string();
+    details.owner_channel_name = "Channel".to_string();
+    details.upload_date = "2023-04-01".to_string();
+    // Typed values are exported, not the raw strings
+    details.duration = std::time::Duration::from_secs(212);
+    details.length_seconds = "unknown".to_string();
+    details.views = ViewCount::Views(1000);
+    details.chapters =
+        serde_json::from_value(serde_json::json!([{ "title": "Intro", "startTime": 0 }])).unwrap();
+
+    let info = video_info.to_info_json();
+
+    assert_eq!(
+        info["schema_version"],
+        rusty_ytdl::constants::INFO_JSON_SCHEMA_VERSION
+    );
+    assert_eq!(info["id"], "FZ8BxMU3BYc");
+    assert_eq!(info["duration"], 212);
+    assert_eq!(info["view_count"], 1000);
+    assert_eq!(info["upload_date"], "2023-04-01");
+    assert_eq!(
+        info["channel"],
+        serde_json::json!({ "id": "UC123", "name": "Channel", "url": "" })
+    );
+    assert_eq!(
+        info["chapters"],
+        serde_json::json!([{ "title": "Intro", "start_time": 0 }])
+    );
+
+    // Missing values are null, numbers stay numbers
+    let format = &info["formats"][0];
+    assert_eq!(format["itag"], 251);
+    assert_eq!(format["mime_type"], "audio/webm; codecs=\"opus\"");
+    assert_eq!(format["audio_sample_rate"], 48000);
+    assert_eq!(format["content_length"], 3456789);
+    assert_eq!(format["duration_ms"], 212001);
+    assert_eq!(format["width"], serde_json::Value::Null);
+    assert_eq!(format["fps"], serde_json::Value::Null);
+}
+
+#[tokio::test]
+async fn ytdlp_info_json_round_trip() {
+    use rusty_ytdl::compat::ytdlp::{from_info_json, to_info_json};
+
+    let info_json = serde_json::json!({
+        "id": "FZ8BxMU3BYc",
+        "title": "Title",
+        "duration": 212.0,
+        "view_count": 1000,
+        "channel": "Channel",
+        "channel_id": "UC123",
+        "upload_date": "20230401",
+        "formats": [
+            {
+                "format_id": "sb0",
+                "url": "https://i.ytimg.com/sb/FZ8BxMU3BYc/storyboard3_L0/default.jpg",
+                "protocol": "mhtml",
+                "vcodec": "none",
+                "acodec": "none"
+            },
+            {
+                "format_id": "251",
+                "format_note": "medium",
+                "url": "https://rr1.googlevideo.com/videoplayback?itag=251",
+                "ext": "webm",
+                "protocol": "https",
+                "vcodec": "none",
+                "acodec": "opus",
+                "tbr": 130.5,
+                "asr": 48000,
+                "audio_channels": 2,
+                "filesize": 3456789
+            },
+            {
+                "format_id": "137",
+                "format_note": "1080p",
+                "url": "https://rr1.googlevideo.com/videoplayback?itag=137",
+                "ext": "mp4",
+                "protocol": "https",
+                "vcodec": "avc1.640028",
+                "acodec": "none",
+                "width": 1920,
+                "height": 1080,
+                "fps": 30
+            }
+        ]
+    });
+
+    let video_info = from_info_json(&info_json).unwrap();
+
+    assert_eq!(video_info.video_details.video_id, "FZ8BxMU3BYc");
+    assert_eq!(video_info.video_details.length_seconds, "212");
+    assert_eq!(video_info.video_details.upload_date, "2023-04-01");
+    assert_eq!(video_info.formats.len(), 2);
+
+    let audio = &video_info.formats[0];
+    assert_eq!(audio.itag, 251);
+    assert_eq!(audio.mime_type, r#"audio/webm; codecs="opus""#);
+    assert!(audio.has_audio && !audio.has_video);
+    assert_eq!(
+        audio.audio_quality,
+        Some("AUDIO_QUALITY_MEDIUM".to_string())
+    );
+
+    let exported = to_info_json(&video_info);
+
+    assert_eq!(exported["id"], "FZ8BxMU3BYc");
+    assert_eq!(exported["upload_date"], "20230401");
+    // Same values as the crate's own info JSON
+    assert_eq!(exported["duration"], 212);
+    assert_eq!(exported["view_count"], 1000);
+    assert_eq!(exported["channel_id"], "UC123");
+    assert_eq!(exported["formats"][0]["filesize"], 3456789);
+    assert_eq!(exported["formats"][0]["format_id"], "251");
+    assert_eq!(exported["formats"][0]["format_note"], "medium");
+    assert_eq!(exported["formats"][1]["vcodec"], "avc1.640028");
+    assert_eq!(exported["formats"][1]["acodec"], "none");
+
+    // 8 bytes which are not a YYYYMMDD date are kept as they are
+    let video_info = from_info_json(&serde_json::json!({
+        "id": "FZ8BxMU3BYc",
+        "upload_date": "2023年4",
+    }))
+    .unwrap();
+    assert_eq!(video_info.video_details.upload_date, "2023年4");
+}
+
+#[cfg(feature = "captions")]
+#[test]
+fn transcript_search() {
+    use rusty_ytdl::captions::Transcript;
+    use std::time::Duration;
+
+    let json3 = r#"{
+        "events": [
+            {"tStartMs": 0, "dDurationMs": 5000, "id": 1, "wpWinPosId": 1, "wsWinStyleId": 1},
+            {"tStartMs": 1200, "dDurationMs": 2000, "segs": [{"utf8": "We're no strangers"}, {"utf8": " to love"}]},
+            {"tStartMs": 3200, "dDurationMs": 1800, "segs": [{"utf8": "You know the rules\nand so do I"}]},
+            {"tStartMs": 5000, "dDurationMs": 100, "segs": [{"utf8": "\n"}]},
+            {"tStartMs": 9000, "dDurationMs": 2500, "segs": [{"utf8": "Never gonna give you up"}]},
+            {"tStartMs": 11500, "dDurationMs": 2500, "segs": [{"utf8": "Never gonna"}]},
+            {"tStartMs": 14000, "dDurationMs": 2500, "segs": [{"utf8": "let you down"}]}
+        ]
+    }"#;
+
+    let transcript = Transcript::from_json3("en", json3).unwrap();
+    assert_eq!(transcript.segments.len(), 5);
+    assert_eq!(
+        transcript.segments[1].text,
+        "You know the rules and so do I"
+    );
+
+    let starts = |query: &str| {
+        transcript
+            .search(query)
+            .iter()
+            .map(|x| x.start)
+            .collect::<Vec<Duration>>()
+    };
+
+    assert_eq!(starts("RULES"), vec![Duration::from_millis(3200)]);
+    assert_eq!(
+        starts("never gonna"),
+        vec![Duration::from_millis(9000), Duration::from_millis(11500)]
+    );
+    // Phrase split over two segments
+    assert_eq!(starts("gonna let you"), vec![Duration::from_millis(11500)]);
+    assert!(starts("desert you").is_empty());
+    assert!(starts("  ").is_empty());
+}
+
+#[tokio::test]
+async fn availability_check() {
+    use rusty_ytdl::search::{Availability, YouTube};
+    use rusty_ytdl::{Config, RequestOptions};
+
+    let playability = |id: &str| match id {
+        "aaaaaaaaaaa" => serde_json::json!({ "status": "OK" }),
+        "bbbbbbbbbbb" => {
+            serde_json::json!({ "status": "LOGIN_REQUIRED", "reason": "This video is private" })
+        }
+        "ccccccccccc" => serde_json::json!({ "status": "ERROR", "reason": "Video unavailable" }),
+        "ddddddddddd" => {
+            serde_json::json!({ "status": "LOGIN_REQUIRED", "reason": "Sign in to confirm your age" })
+        }
+        "eeeeeeeeeee" => serde_json::json!({
+            "status": "UNPLAYABLE",
+            "errorScreen": { "playerErrorMessageRenderer": {
+                "reason": { "simpleText": "Video unavailable" },
+                "subreason": { "runs": [{ "text": "The uploader has not made this video available in your country" }] },
+            }},
+        }),
+        _ => {
+            serde_json::json!({ "status": "UNPLAYABLE", "reason": "Join this channel to get access" })
+        }
+    };
+
+    // Only the deleted video is unknown to oEmbed
+    let address = common::spawn_server(move |request| {
+        if request.line().starts_with("POST /youtubei/v1/player") {
+            common::json_response(&serde_json::json!({
+                "playabilityStatus": playability(request.json()["videoId"].as_str().unwrap()),
+            }))
+        } else if request.path.starts_with("/oembed") && request.path.contains("ccccccccccc") {
+            common::response("404 Not Found", &[], "")
+        } else {
+            common::response("200 OK", &[], "{}")
+        }
+    })
+    .await;
+
+    let youtube = YouTube::new_with_options(&RequestOptions {
+        config: Config {
+            origin: format!("http://{address}"),
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+    .unwrap();
+
+    let report = youtube
+        .check_availability(&[
+            "aaaaaaaaaaa",
+            "https://www.youtube.com/watch?v=bbbbbbbbbbb",
+            "ccccccccccc",
+            "ddddddddddd",
+            "eeeeeeeeeee",
+            "fffffffffff",
+            "not a video",
+        ])
+        .await;
+
+    let statuses: Vec<(&str, Availability)> = report
+        .videos
+        .iter()
+        .map(|x| (x.id.as_str(), x.status))
+        .collect();
+    assert_eq!(
+        statuses,
+        [
+            ("aaaaaaaaaaa", Availability::Public),
+            ("bbbbbbbbbbb", Availability::Private),
+            ("ccccccccccc", Availability::Deleted),
+            ("ddddddddddd", Availability::AgeRestricted),
+            ("eeeeeeeeeee", Availability::GeoBlocked),
+            ("fffffffffff", Availability::Unknown),
+            ("not a video", Availability::Invalid),
+        ]
+    );
+    assert_eq!(
+        report.videos[4].reason.as_deref(),
+        Some("Video unavailable The uploader has not made this video available in your country")
+    );
+    assert_eq!(report.unavailable().count(), 6);
+    assert_eq!(report.count(Availability::Public), 1);
+}
+
+#[test]
+fn diagnose_report() {
+    use rusty_ytdl::{DiagnosticCheck, DiagnosticReport, DiagnosticStep, DiagnosticVerdict};
+    use std::time::Duration;
+
+    let report = DiagnosticReport {
+        video_id: "FZ8BxMU3BYc".to_string(),
+        steps: vec![
+            DiagnosticStep {
+                check: DiagnosticCheck::Reachability,
+                passed: true,
+                detail: "204 No Content".to_string(),
+                latency: Duration::from_millis(42),
+            },
+            DiagnosticStep {
+                check: DiagnosticCheck::WatchPage,
+                passed: false,
+                detail: "LOGIN_REQUIRED: Sign in to confirm you're not a bot".to_string(),
+                latency: Duration::from_millis(310),
+            },
+        ],
+        verdict: DiagnosticVerdict::Blocked,
+    };
+
+    let printed = report.to_string();
+    assert!(printed.contains("[ok] reachability (42 ms): 204 No Content"));
+    assert!(printed.contains("[failed] watch page (310 ms): LOGIN_REQUIRED"));
+    assert!(printed.ends_with("verdict: blocked"));
+}
@@ -0,0 +1,9 @@
+var _yt_player={};(function(g){var window=this;var Ab="Lecture en cours…",Bb="été 👍";
+var kB={sts:18764,cver:"2.20210721"};
+var Ly={Ew:function(a,b){a.splice(0,b)},Nl:function(a){a.reverse()},gV:function(a,b){var c=a[0];a[0]=a[b%a.length];a[b%a.length]=c}};
+Wy=function(a){a=a.split("");Ly.gV(a,41);Ly.Nl(a,23);Ly.Ew(a,2);Ly.gV(a,9);return a.join("")};
+var aWma=function(a){return a};
+Wma=function(a){var b=a.split(""),c=[function(d,e){d.push(e)},1152657693,-364146245,"/",function(d){d.reverse()},function(d,e){e=(e%d.length+d.length)%d.length;d.splice(-e).reverse().forEach(function(f){d.unshift(f)})},b,"{}"];c[5](c[6],c[1]);c[4](c[6]);c[5](c[6],c[2]);return b.join("")};
+g.ZB=function(a,b,c){c&&(c=Wy(decodeURIComponent(c)));a.set("alr","yes");c&&(c=Wy(decodeURIComponent(c)),a.set(b,encodeURIComponent(c)));var d;(b=a.get("n"))&&(b=Wma(b),a.set("n",b))};
+g.nc=function(a){return a.z.get("n")};
+})(_yt_player);
@@ -0,0 +1,9 @@
+var _yt_player={};(function(g){var window=this;var Ab='it\'s "quoted" /not a regex/';
+g.IW=function(a){return{signatureTimestamp:19123,referer:a.referer}};
+var Oqa=/[?&]n=([^&]+)/,Pqa=function(a){return a.replace(/\}/g,"")};
+var bY={Dq:function(a,b){a.splice(0,b)},Ji:function(a){a.reverse()},Rj:function(a,b){var c=a[0];a[0]=a[b%a.length];a[b%a.length]=c}};
+cY=function(a){a=a.split("");bY.Ji(a,47);bY.Rj(a,17);bY.Dq(a,3);bY.Ji(a,36);return a.join("")};
+$ma=function(a){var b=a.split(""),c=[-1297232478,function(d,e){e=(e%d.length+d.length)%d.length;var f=d[0];d[0]=d[e];d[e]=f},"}",b,function(d){for(var e=d.length;e;)d.push(d.splice(--e,1)[0])},'"{',function(d,e){d.push(e)}];c[1](c[3],c[0]);c[4](c[3]);c[1](c[3],7);return b.join("")};
+var Rla=[$ma];
+g.Vx=function(a,b,c){c&&d.set(b,encodeURIComponent(cY(decodeURIComponent(c))));var e;a.D&&(b=a.get("n"))&&(b=Rla[0](b),a.set("n",b),Rla.length||$ma(""))};
+})(_yt_player);
@@ -0,0 +1,9 @@
+var _yt_player={};(function(g){var window=this;var Tva=[],Za="é שלום";
+var nF={playerType:"html5",signatureTimestamp:19590};
+var Hx={w7:function(a,b){var c=a[0];a[0]=a[b%a.length];a[b%a.length]=c},Q4:function(a,b){a.splice(0,b)},z2:function(a){a.reverse()}};
+Ska=function(a){a=a.split("");Hx.z2(a,12);Hx.w7(a,63);Hx.Q4(a,1);Hx.w7(a,28);Hx.z2(a,44);return a.join("")};
+Hxa=function(a){var b=a.split(""),c=[function(d,e){e=(e%d.length+d.length)%d.length;d.splice(0,1,d.splice(e,1,d[0])[0])},b,-979901195,function(d){d.reverse()},'`${x}`',1044517143];if(typeof Tva==="undefined")return a;c[0](c[1],c[2]);c[3](c[1]);c[0](c[1],c[5]);return b.join("")};
+var Wx=function(){},Nqa=[Wx,Hxa];
+g.h3=function(h){var m=Ska(decodeURIComponent(h.s));h.set(h.sp,m)};
+g.Aq=function(a){var b,c;(b=String.fromCharCode(110),c=a.get(b))&&(c=Nqa[1](c),a.set(b,c))};
+})(_yt_player);
@@ -0,0 +1,318 @@
+#[cfg(all(feature = "ffmpeg", unix))]
+#[tokio::test]
+async fn ffmpeg_locator() {
+    use rusty_ytdl::ffmpeg::FFmpegLocator;
+    use rusty_ytdl::VideoError;
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = std::env::temp_dir().join(format!("rusty_ytdl-ffmpeg-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("ffmpeg");
+
+    std::fs::write(
+        &path,
+        r#"#!/bin/sh
+case "$2" in
+  -version) echo "ffmpeg version 6.0-static https://johnvansickle.com/ffmpeg/ Copyright (c) 2000-2023" ;;
+  -muxers) printf 'File formats:\n D. = Demuxing supported\n .E = Muxing supported\n --\n  E matroska        Matroska\n  E mp4             MP4 (MPEG-4 Part 14)\n' ;;
+  -encoders) printf 'Encoders:\n V..... = Video\n ------\n S..... srt                  SubRip subtitle\n A....D aac                  AAC (Advanced Audio Coding)\n' ;;
+esac
+"#,
+    )
+    .unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    #[allow(clippy::needless_update)]
+    let mut locator = FFmpegLocator {
+        path: Some(path.clone()),
+        ..Default::default()
+    };
+    let ffmpeg = locator.locate().await.unwrap();
+
+    assert_eq!(ffmpeg.version, "6.0-static");
+    assert!(ffmpeg.require_muxer("matroska").is_ok());
+    assert!(ffmpeg.require_encoder("srt").is_ok());
+    assert!(matches!(
+        ffmpeg.require_muxer("webm"),
+        Err(VideoError::FFmpegUnsupported(_, _))
+    ));
+    assert!(!ffmpeg.encoders.contains("------"));
+
+    locator.path = Some(dir.join("missing"));
+    assert!(matches!(
+        locator.locate().await,
+        Err(VideoError::FFmpegNotFound(_))
+    ));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn post_process() {
+    use rusty_ytdl::postprocess::{
+        MoveTo, PostProcessChain, PostProcessContext, PostProcessStatus, PostProcessor,
+    };
+    use rusty_ytdl::{DownloadResult, Video, VideoError, VideoFormat, VideoInfo};
+    use std::sync::{Arc, Mutex};
+
+    struct Rename;
+
+    #[async_trait::async_trait]
+    impl PostProcessor for Rename {
+        fn name(&self) -> &str {
+            "rename"
+        }
+
+        async fn process(&self, context: &mut PostProcessContext<'_>) -> Result<(), VideoError> {
+            let path = context.path.with_extension("txt");
+            std::fs::rename(&context.path, &path).unwrap();
+            context.path = path;
+            context.report(0.5);
+            Ok(())
+        }
+    }
+
+    struct Fail;
+
+    #[async_trait::async_trait]
+    impl PostProcessor for Fail {
+        fn name(&self) -> &str {
+            "fail"
+        }
+
+        async fn process(&self, _context: &mut PostProcessContext<'_>) -> Result<(), VideoError> {
+            Err(VideoError::DownloadError("broken".to_string()))
+        }
+    }
+
+    let dir = std::env::temp_dir().join(format!("rusty_ytdl-postprocess-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("video.bin");
+    std::fs::write(&path, b"data").unwrap();
+
+    let events = Arc::new(Mutex::new(vec![]));
+    let recorded = events.clone();
+    let chain = PostProcessChain::new()
+        .then(Rename)
+        .then(Fail)
+        .then(MoveTo::new(dir.join("library")))
+        .skip("fail")
+        .on_progress(Arc::new(move |event| {
+            recorded
+                .lock()
+                .unwrap()
+                .push((event.step.clone(), event.status))
+        }));
+    assert_eq!(chain.names(), vec!["rename", "fail", "move"]);
+
+    let video = Video::new("FZ8BxMU3BYc").unwrap();
+    let info = VideoInfo::default();
+    let format = VideoFormat::default();
+
+    let mut result = DownloadResult::default();
+    result.path = path;
+
+    let result = chain.run(&video, &info, &format, result).await.unwrap();
+    assert_eq!(result.path, dir.join("library").join("video.txt"));
+    assert_eq!(std::fs::read(&result.path).unwrap(), b"data");
+    assert_eq!(result.size, 4);
+    assert_eq!(
+        *events.lock().unwrap(),
+        vec![
+            ("rename".to_string(), PostProcessStatus::Started),
+            ("rename".to_string(), PostProcessStatus::Progress(0.5)),
+            ("rename".to_string(), PostProcessStatus::Finished),
+            ("fail".to_string(), PostProcessStatus::Skipped),
+            ("move".to_string(), PostProcessStatus::Started),
+            ("move".to_string(), PostProcessStatus::Finished),
+        ]
+    );
+
+    let failing = PostProcessChain::new().then(Fail);
+    let err = failing
+        .run(&video, &info, &format, result)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, VideoError::PostProcessError(step, _) if step == "fail"));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[cfg(all(feature = "ffmpeg", unix))]
+#[tokio::test]
+async fn normalize_loudness() {
+    use rusty_ytdl::ffmpeg::{FFmpegLocator, LoudnessMode, NormalizeLoudness};
+    use rusty_ytdl::postprocess::PostProcessChain;
+    use rusty_ytdl::{DownloadResult, Video, VideoFormat, VideoInfo};
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = std::env::temp_dir().join(format!("rusty_ytdl-loudness-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let ffmpeg = dir.join("ffmpeg");
+
+    // Fake ffmpeg printing a loudnorm measurement, other runs write their arguments into the output file
+    std::fs::write(
+        &ffmpeg,
+        r#"#!/bin/sh
+case "$2" in
+  -version) echo "ffmpeg version 6.0"; exit 0 ;;
+  -muxers|-encoders) printf ' --\n  E aac   AAC\n'; exit 0 ;;
+esac
+for arg; do last="$arg"; done
+if [ "$last" = "-" ]; then
+  printf '[Parsed_loudnorm_0 @ 0x1] \n{\n\t"input_i" : "-23.50",\n\t"input_tp" : "-3.20",\n\t"input_lra" : "5.10",\n\t"input_thresh" : "-34.00",\n\t"target_offset" : "0.30"\n}\n' >&2
+else
+  printf '%s\n' "$@" > "$last"
+fi
+"#,
+    )
+    .unwrap();
+    std::fs::set_permissions(&ffmpeg, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let video = Video::new("FZ8BxMU3BYc").unwrap();
+    let info = VideoInfo::default();
+    let format = VideoFormat::default();
+
+    for mode in [LoudnessMode::ReplayGain, LoudnessMode::EbuR128] {
+        let path = dir.join("audio.m4a");
+        std::fs::write(&path, b"audio").unwrap();
+
+        #[allow(clippy::needless_update)]
+        let chain = PostProcessChain::new().then(NormalizeLoudness {
+            mode,
+            ffmpeg: FFmpegLocator {
+                path: Some(ffmpeg.clone()),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        let mut result = DownloadResult::default();
+        result.path = path.clone();
+        let result = chain.run(&video, &info, &format, result).await.unwrap();
+        assert_eq!(result.path, path);
+
+        let args = std::fs::read_to_string(&result.path).unwrap();
+        match mode {
+            LoudnessMode::ReplayGain => {
+                assert!(args.contains("REPLAYGAIN_TRACK_GAIN=5.50 dB"), "{args}");
+                assert!(args.contains("REPLAYGAIN_TRACK_PEAK=0.691831"), "{args}");
+                assert!(args.contains("use_metadata_tags"), "{args}");
+            }
+            LoudnessMode::EbuR128 => {
+                assert!(
+                    args.contains("measured_I=-23.50:measured_TP=-3.20:measured_LRA=5.10"),
+                    "{args}"
+                );
+                assert!(args.contains("\naac\n"), "{args}");
+            }
+        }
+    }
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[cfg(all(feature = "ffmpeg", unix))]
+#[tokio::test]
+async fn split_chapters() {
+    use rusty_ytdl::ffmpeg::{FFmpegLocator, SplitChapters};
+    use rusty_ytdl::postprocess::{MoveTo, PostProcessChain};
+    use rusty_ytdl::{DownloadResult, Video, VideoFormat, VideoInfo};
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = std::env::temp_dir().join(format!("rusty_ytdl-chapters-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let ffmpeg = dir.join("ffmpeg");
+
+    // Fake ffmpeg writing its arguments into the output file
+    std::fs::write(
+        &ffmpeg,
+        r#"#!/bin/sh
+case "$2" in
+  -version) echo "ffmpeg version 6.0"; exit 0 ;;
+  -muxers|-encoders) exit 0 ;;
+esac
+for arg; do last="$arg"; done
+echo "$@" > "$last"
+"#,
+    )
+    .unwrap();
+    std::fs::set_permissions(&ffmpeg, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let mut info = VideoInfo::default();
+    info.video_details.title = "Album".to_string();
+    info.video_details.description = "0:00 Intro\n1:30 Song / Live\n4:00 Outro".to_string();
+
+    let path = dir.join("album.m4a");
+    std::fs::write(&path, b"audio").unwrap();
+    let mut result = DownloadResult::default();
+    result.path = path.clone();
+
+    #[allow(clippy::needless_update)]
+    let chain = PostProcessChain::new()
+        .then(SplitChapters {
+            output_template: "{title} {chapter_number} {chapter}.{ext}".to_string(),
+            remove_original: true,
+            ffmpeg: FFmpegLocator {
+                path: Some(ffmpeg),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .then(MoveTo::new(dir.join("library")));
+
+    let video = Video::new("FZ8BxMU3BYc").unwrap();
+    let result = chain
+        .run(&video, &info, &VideoFormat::default(), result)
+        .await
+        .unwrap();
+
+    let names: Vec<String> = result
+        .parts
+        .iter()
+        .map(|x| x.file_name().unwrap().to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            "Album 01 Intro.m4a",
+            "Album 02 Song _ Live.m4a",
+            "Album 03 Outro.m4a"
+        ]
+    );
+    assert_eq!(result.path, result.parts[0]);
+    assert!(!path.exists());
+
+    let second = std::fs::read_to_string(&result.parts[1]).unwrap();
+    assert!(second.contains("-ss 90 -i"), "{second}");
+    assert!(second.contains("-t 150 "), "{second}");
+    let last = std::fs::read_to_string(&result.parts[2]).unwrap();
+    assert!(!last.contains("-t "), "{last}");
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn nfo_sidecar() {
+    use rusty_ytdl::compat::ytdlp::from_info_json;
+    use rusty_ytdl::SidecarFormat;
+
+    let video_info = from_info_json(&serde_json::json!({
+        "id": "FZ8BxMU3BYc",
+        "title": "Tom & Jerry <Remastered>",
+        "channel": "Channel",
+        "channel_id": "UC123",
+        "upload_date": "20230401",
+        "duration": 600,
+        "tags": ["cartoon"],
+    }))
+    .unwrap();
+
+    let nfo = video_info.to_sidecar_string(SidecarFormat::Nfo);
+
+    assert!(nfo.contains("<title>Tom &amp; Jerry &lt;Remastered&gt;</title>"));
+    assert!(nfo.contains("<director>Channel</director>"));
+    assert!(nfo.contains("<year>2023</year>"));
+    assert!(nfo.contains("<runtime>10</runtime>"));
+    assert!(nfo.contains("<tag>cartoon</tag>"));
+    assert!(nfo.contains("<uniqueid type=\"youtube\" default=\"true\">FZ8BxMU3BYc</uniqueid>"));
+}
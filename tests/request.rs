@@ -0,0 +1,165 @@
+#[test]
+fn proxy_pool() {
+    use rusty_ytdl::ProxyPool;
+
+    let pool = ProxyPool::new(["http://10.0.0.1:8080", "http://10.0.0.2:8080"]).unwrap();
+    let first = pool.current().unwrap();
+    assert_eq!(first.as_str(), "http://10.0.0.1:8080/");
+
+    // Failover to the next healthy proxy
+    pool.demote(&first);
+    let second = pool.current().unwrap();
+    assert_eq!(second.as_str(), "http://10.0.0.2:8080/");
+    assert_eq!(pool.healthy(), vec![second.clone()]);
+
+    // Every proxy demoted, the one recovering first is used
+    pool.demote(&second);
+    assert!(pool.healthy().is_empty());
+    assert_eq!(pool.current().unwrap(), first);
+
+    pool.restore(&second);
+    assert_eq!(pool.current().unwrap(), second);
+
+    // Zero cooldown never keeps a proxy demoted
+    let pool = pool.with_cooldown(std::time::Duration::ZERO);
+    pool.demote(&first);
+    assert_eq!(pool.healthy().len(), 2);
+
+    assert!(ProxyPool::new(["not a url"]).is_err());
+}
+
+#[test]
+fn identity_pool() {
+    use rusty_ytdl::{Identity, IdentityPool, RotationStrategy};
+
+    let identities = vec![Identity::default(); 3];
+
+    let pool = IdentityPool::new(identities.clone(), RotationStrategy::PerRequest);
+    let selected = (0..4).map(|_| pool.select(None)).collect::<Vec<usize>>();
+    assert_eq!(selected, vec![0, 1, 2, 0]);
+
+    // Identity only changes after a rate limit
+    let pool = IdentityPool::new(identities.clone(), RotationStrategy::OnRateLimit);
+    assert_eq!(pool.select(None), 0);
+    assert_eq!(pool.select(None), 0);
+    pool.report_rate_limited(0);
+    assert_eq!(pool.select(None), 1);
+    // Stale report of an identity which is not used anymore
+    pool.report_rate_limited(0);
+    assert_eq!(pool.select(None), 1);
+
+    let pool = IdentityPool::new(identities, RotationStrategy::StickyPerVideo);
+    let pinned = pool.select(Some("FZ8BxMU3BYc"));
+    for _ in 0..3 {
+        assert_eq!(pool.select(Some("FZ8BxMU3BYc")), pinned);
+    }
+}
+
+#[tokio::test]
+async fn request_pacer() {
+    use rusty_ytdl::RequestPacer;
+    use std::time::{Duration, Instant};
+
+    let pacer = RequestPacer::new(Duration::from_millis(50)).with_jitter(Duration::from_millis(20));
+
+    // Clones share the schedule, concurrent callers are queued
+    let started = Instant::now();
+    let waits = (0..4).map(|_| {
+        let pacer = pacer.clone();
+        tokio::spawn(async move {
+            pacer.wait().await;
+            started.elapsed()
+        })
+    });
+
+    let mut starts = vec![];
+    for wait in waits.collect::<Vec<_>>() {
+        starts.push(wait.await.unwrap());
+    }
+    starts.sort();
+
+    assert!(starts[0] < Duration::from_millis(50));
+    for pair in starts.windows(2) {
+        assert!(pair[1] - pair[0] >= Duration::from_millis(45));
+    }
+    assert!(starts[3] >= Duration::from_millis(150));
+}
+
+#[tokio::test]
+async fn throttled_mode() {
+    use rusty_ytdl::ThrottledMode;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    // Same seed, same waits
+    let first = ThrottledMode::new(Duration::from_millis(100))
+        .with_jitter(Duration::from_millis(50))
+        .with_seed(7);
+    let second = ThrottledMode::new(Duration::from_millis(100))
+        .with_jitter(Duration::from_millis(50))
+        .with_seed(7);
+    for index in 0..20 {
+        let delay = first.delay_for(index);
+        assert_eq!(delay, second.delay_for(index));
+        assert!(delay >= Duration::from_millis(100) && delay <= Duration::from_millis(150));
+    }
+
+    // Server records the most requests it had open at the same time
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let open = Arc::new(AtomicUsize::new(0));
+    let most_open = Arc::new(AtomicUsize::new(0));
+    let (open_server, most_open_server) = (open.clone(), most_open.clone());
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let (open, most_open) = (open_server.clone(), most_open_server.clone());
+
+            tokio::spawn(async move {
+                let mut request = vec![];
+                let mut buf = [0u8; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    if n == 0 {
+                        return;
+                    }
+                    request.extend_from_slice(&buf[..n]);
+                }
+
+                let now_open = open.fetch_add(1, Ordering::SeqCst) + 1;
+                most_open.fetch_max(now_open, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(30)).await;
+                open.fetch_sub(1, Ordering::SeqCst);
+
+                socket
+                    .write_all(
+                        b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok",
+                    )
+                    .await
+                    .unwrap();
+            });
+        }
+    });
+
+    let throttled_mode = ThrottledMode::new(Duration::from_millis(20)).with_jitter(Duration::ZERO);
+    let client = reqwest_middleware::ClientBuilder::new(rusty_ytdl::reqwest::Client::new())
+        .with(throttled_mode.clone())
+        .build();
+
+    let started = std::time::Instant::now();
+    let url = format!("http://{address}/");
+    let requests = (0..3).map(|_| {
+        let client = client.clone();
+        let url = url.clone();
+        tokio::spawn(async move { client.get(&url).send().await.unwrap().status() })
+    });
+    for request in requests.collect::<Vec<_>>() {
+        assert!(request.await.unwrap().is_success());
+    }
+
+    assert_eq!(throttled_mode.requests(), 3);
+    assert_eq!(most_open.load(Ordering::SeqCst), 1);
+    assert!(started.elapsed() >= Duration::from_millis(150));
+}
@@ -0,0 +1,889 @@
+mod common;
+
+#[tokio::test]
+async fn stream_seek() {
+    use rusty_ytdl::stream::{NonLiveStream, NonLiveStreamOptions, Stream};
+
+    // 16 init bytes, `sidx` box with 2 fragments of 2 seconds, 50 and 60 bytes long
+    let mut content = vec![0u8; 16];
+    content.extend_from_slice(&56u32.to_be_bytes());
+    content.extend_from_slice(b"sidx");
+    content.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 1]);
+    content.extend_from_slice(&1000u32.to_be_bytes());
+    content.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2]);
+    for size in [50u32, 60] {
+        content.extend_from_slice(&size.to_be_bytes());
+        content.extend_from_slice(&2000u32.to_be_bytes());
+        content.extend_from_slice(&[0x90, 0, 0, 0]);
+    }
+    content.extend((content.len()..200).map(|x| x as u8));
+
+    let address = common::spawn_range_server(content.clone()).await;
+
+    let stream = NonLiveStream::new(NonLiveStreamOptions {
+        content_length: content.len() as u64,
+        init_range: Some((0, 15)),
+        index_range: Some((16, 71)),
+        content_type: Some("video/mp4".to_string()),
+        ..common::stream_options(format!("http://{address}/video"))
+    })
+    .unwrap();
+    assert_eq!(stream.content_type(), Some("video/mp4"));
+
+    let first = stream.chunk().await.unwrap().unwrap();
+    assert_eq!(&first[..], &content[..=40]);
+
+    // Reposition on a byte offset
+    stream.seek(150).await.unwrap();
+    let mut downloaded = vec![];
+    while let Some(chunk) = stream.chunk().await.unwrap() {
+        downloaded.extend_from_slice(&chunk);
+    }
+    assert_eq!(downloaded, &content[150..]);
+
+    // 3 seconds are in the second fragment
+    let offset = stream
+        .seek_time(std::time::Duration::from_secs(3))
+        .await
+        .unwrap();
+    assert_eq!(offset, 72 + 50);
+    assert_eq!(stream.chunk().await.unwrap().unwrap()[0], content[122]);
+
+    assert!(stream.seek(500).await.is_err());
+}
+
+#[tokio::test]
+async fn stream_sniff() {
+    use rusty_ytdl::stream::{ContainerType, NonLiveStream, NonLiveStreamOptions, Stream};
+
+    // EBML header served as `video/mp4`
+    let mut content = vec![0x1A, 0x45, 0xDF, 0xA3];
+    content.extend((4..100).map(|x| x as u8));
+
+    let server_content = content.clone();
+    let address = common::spawn_server(move |request| {
+        common::range_response(&server_content, &request, &["Content-Type: video/mp4"])
+    })
+    .await;
+
+    let stream = NonLiveStream::new(NonLiveStreamOptions {
+        prefetch_chunks: 1,
+        content_type: Some("video/mp4; codecs=\"avc1.4d401f\"".to_string()),
+        ..common::stream_options(format!("http://{address}/video"))
+    })
+    .unwrap();
+
+    assert_eq!(stream.sniffed_container(), None);
+
+    let mut received = vec![];
+    while let Some(chunk) = stream.chunk().await.unwrap() {
+        received.extend_from_slice(&chunk);
+    }
+    assert_eq!(received, content);
+    // Ended prefetching stream keeps returning `None`
+    assert!(stream.chunk().await.unwrap().is_none());
+
+    assert_eq!(stream.response_content_type(), Some("video/mp4"));
+    assert_eq!(stream.sniffed_container(), Some(ContainerType::WebM));
+
+    let mut ts = vec![0u8; 376];
+    ts[0] = 0x47;
+    ts[188] = 0x47;
+    assert_eq!(ContainerType::sniff(&ts), Some(ContainerType::Ts));
+    assert_eq!(
+        ContainerType::sniff(b"\0\0\0\x18ftypdash"),
+        Some(ContainerType::Mp4)
+    );
+    assert_eq!(ContainerType::sniff(&content[10..]), None);
+}
+
+#[tokio::test]
+async fn task_shutdown() {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use rusty_ytdl::stream::{NonLiveStream, NonLiveStreamOptions, Stream};
+    use rusty_ytdl::{TaskGroup, VideoError};
+
+    // Server never answers, the prefetcher waits forever
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let mut sockets = vec![];
+        loop {
+            let (socket, _) = listener.accept().await.unwrap();
+            sockets.push(socket);
+        }
+    });
+
+    let tasks = TaskGroup::new();
+    let stream = Arc::new(
+        NonLiveStream::new(NonLiveStreamOptions {
+            content_length: 100,
+            prefetch_chunks: 2,
+            tasks: Some(tasks.clone()),
+            ..common::stream_options(format!("http://{address}/video"))
+        })
+        .unwrap(),
+    );
+
+    let chunk = tokio::spawn({
+        let stream = stream.clone();
+        async move { stream.chunk().await }
+    });
+
+    while tasks.running() == 0 {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    tasks.shutdown();
+    tokio::time::timeout(Duration::from_secs(5), tasks.join())
+        .await
+        .unwrap();
+    assert_eq!(tasks.running(), 0);
+
+    let result = chunk.await.unwrap();
+    assert!(matches!(result, Err(VideoError::Cancelled)), "{result:?}");
+}
+
+#[tokio::test]
+async fn unknown_content_length() {
+    use rusty_ytdl::stream::{NonLiveStream, NonLiveStreamOptions, Stream};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    let content: Vec<u8> = (0..100).collect();
+    let requests = Arc::new(Mutex::new(vec![]));
+
+    let server_content = content.clone();
+    let server_requests = requests.clone();
+    let address = common::spawn_server(move |request| {
+        server_requests
+            .lock()
+            .unwrap()
+            .push(request.header("range").map(|x| x.to_string()));
+        common::range_response(&server_content, &request, &[])
+    })
+    .await;
+
+    let totals = Arc::new(AtomicUsize::new(0));
+    let progress_totals = totals.clone();
+    let stream = NonLiveStream::new(NonLiveStreamOptions {
+        on_progress: Some(Arc::new(move |progress| {
+            if progress.total == Some(100) {
+                progress_totals.fetch_add(1, Ordering::Relaxed);
+            }
+        })),
+        ..common::stream_options(format!("http://{address}/video"))
+    })
+    .unwrap();
+
+    // No request before the first chunk
+    assert!(requests.lock().unwrap().is_empty());
+
+    let first = stream.chunk().await.unwrap().unwrap();
+    assert_eq!(first.len(), 41);
+    assert_eq!(Stream::content_length(&stream), 100);
+
+    let mut received = first.to_vec();
+    while let Some(chunk) = stream.chunk().await.unwrap() {
+        received.extend_from_slice(&chunk);
+    }
+    assert_eq!(received, content);
+
+    // First range is the first request, the last one stops at the learned length
+    assert_eq!(
+        *requests.lock().unwrap(),
+        [
+            Some("bytes=0-40".to_string()),
+            Some("bytes=41-80".to_string()),
+            Some("bytes=81-".to_string()),
+        ]
+    );
+    assert_eq!(totals.load(Ordering::Relaxed), 3);
+}
+
+#[tokio::test]
+async fn range_not_satisfiable() {
+    use rusty_ytdl::stream::{NonLiveStream, NonLiveStreamOptions, Stream};
+
+    let content = (0..100u8).collect::<Vec<u8>>();
+
+    // Serve at most 30 bytes to simulate short bodies
+    let server_content = content.clone();
+    let address = common::spawn_server(move |request| {
+        let (start, end) = request.range().unwrap();
+        if start >= server_content.len() {
+            return common::response("416 Range Not Satisfiable", &[], "");
+        }
+
+        let end = end
+            .unwrap_or(usize::MAX)
+            .min(server_content.len() - 1)
+            .min(start + 29);
+        common::response("206 Partial Content", &[], &server_content[start..=end])
+    })
+    .await;
+
+    // Content length is larger than the real content
+    let stream = NonLiveStream::new(NonLiveStreamOptions {
+        content_length: 150,
+        dl_chunk_size: 49,
+        end: 49,
+        ..common::stream_options(format!("http://{address}/video"))
+    })
+    .unwrap();
+
+    let mut downloaded = vec![];
+    while let Some(chunk) = stream.chunk().await.unwrap() {
+        downloaded.extend_from_slice(&chunk);
+    }
+
+    assert_eq!(downloaded, content);
+}
+
+#[tokio::test]
+async fn forbidden_format() {
+    use rusty_ytdl::stream::{NonLiveStream, NonLiveStreamOptions, Stream};
+    use rusty_ytdl::VideoError;
+
+    let address =
+        common::spawn_server(|_| common::response("403 Forbidden", &[], "Forbidden")).await;
+
+    let stream = NonLiveStream::new(NonLiveStreamOptions {
+        content_length: 100,
+        ..common::stream_options(format!("http://{address}/video"))
+    })
+    .unwrap();
+
+    // Error pages are not returned as content, so format fallback can skip the format
+    let result = stream.chunk().await;
+    assert!(
+        matches!(result, Err(VideoError::FormatRequestFailed(_))),
+        "{result:?}"
+    );
+}
+
+#[tokio::test]
+async fn fallback_urls() {
+    use rusty_ytdl::stream::{NonLiveStream, NonLiveStreamOptions, Stream};
+
+    // Primary host fails, the `fvip` mirror answers
+    let address = common::spawn_server(|request| {
+        if request.header("host").unwrap().starts_with("rr2---sn-bbb") {
+            common::response("206 Partial Content", &[], "video")
+        } else {
+            common::response("500 Internal Server Error", &[], "")
+        }
+    })
+    .await;
+
+    let client = rusty_ytdl::reqwest::Client::builder()
+        .resolve("rr1---sn-aaa.media.test", address)
+        .resolve("rr2---sn-bbb.media.test", address)
+        .build()
+        .unwrap();
+
+    let stream = NonLiveStream::new(NonLiveStreamOptions {
+        client: Some(reqwest_middleware::ClientBuilder::new(client).build()),
+        content_length: 5,
+        dl_chunk_size: 1024,
+        end: 1024,
+        ..common::stream_options(format!(
+            "http://rr1---sn-aaa.media.test:{}/videoplayback?mn=sn-aaa%2Csn-bbb&fvip=2",
+            address.port()
+        ))
+    })
+    .unwrap();
+
+    assert_eq!(stream.chunk().await.unwrap().unwrap().as_ref(), b"video");
+    assert!(stream.chunk().await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn circuit_breaker() {
+    use rusty_ytdl::CircuitBreaker;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    // `sn-aaa` always fails, `sn-bbb` answers
+    let failing_hits = Arc::new(AtomicUsize::new(0));
+    let hits = failing_hits.clone();
+    let address = common::spawn_server(move |request| {
+        if request.header("host").unwrap().starts_with("rr1---sn-aaa") {
+            hits.fetch_add(1, Ordering::SeqCst);
+            common::response("503 Service Unavailable", &[], "")
+        } else {
+            common::response("200 OK", &[], "ok")
+        }
+    })
+    .await;
+
+    let client = rusty_ytdl::reqwest::Client::builder()
+        .resolve("rr1---sn-aaa.media.test", address)
+        .resolve("rr1---sn-bbb.media.test", address)
+        .build()
+        .unwrap();
+    let breaker = CircuitBreaker::new(3);
+    let client = reqwest_middleware::ClientBuilder::new(client)
+        .with(breaker.clone())
+        .build();
+
+    let url = format!(
+        "http://rr1---sn-aaa.media.test:{}/videoplayback?mn=sn-aaa%2Csn-bbb",
+        address.port()
+    );
+
+    // Failures are returned until the circuit opens, then the mirror answers
+    for _ in 0..2 {
+        let response = client.get(&url).send().await.unwrap();
+        assert_eq!(response.status(), 503);
+    }
+    let response = client.get(&url).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert!(breaker.is_open("rr1---sn-aaa.media.test"));
+
+    // Open hosts are skipped
+    let response = client.get(&url).send().await.unwrap();
+    assert_eq!(response.text().await.unwrap(), "ok");
+    assert_eq!(failing_hits.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn live_report() {
+    use rusty_ytdl::stream::{LiveGap, LiveStream, LiveStreamOptions, Stream};
+    use std::time::Duration;
+
+    // Segment 11 is never available
+    let address = common::spawn_server(|request| match request.path.as_str() {
+        "/playlist.m3u8" => common::response(
+            "200 OK",
+            &[],
+            "#EXTM3U\n#EXT-X-VERSION:3\n#EXT-X-TARGETDURATION:2\n#EXT-X-MEDIA-SEQUENCE:10\n\
+            #EXTINF:2.0,\nseg10.ts\n#EXTINF:2.0,\nseg11.ts\n#EXTINF:2.0,\nseg12.ts\n#EXT-X-ENDLIST\n",
+        ),
+        "/seg11.ts" => common::response("404 Not Found", &[], ""),
+        path => common::response("200 OK", &[], path),
+    })
+    .await;
+
+    let stream = LiveStream::new(LiveStreamOptions {
+        stream_url: format!("http://{address}/playlist.m3u8"),
+        ..Default::default()
+    })
+    .unwrap();
+
+    let mut content = vec![];
+    while let Some(chunk) = stream.chunk().await.unwrap() {
+        content.extend_from_slice(&chunk);
+    }
+
+    assert_eq!(content, b"/seg10.ts/seg12.ts");
+
+    let report = stream.live_report().unwrap();
+    assert_eq!(report.segments_total, 3);
+    assert_eq!(report.segments_missed, 1);
+    assert_eq!(
+        report.gaps,
+        vec![LiveGap {
+            discontinuity_sequence: 0,
+            first_sequence: 11,
+            last_sequence: 11,
+            duration: Duration::from_secs(2),
+        }]
+    );
+}
+
+#[tokio::test]
+async fn progressive_reader() {
+    use rusty_ytdl::stream::{NonLiveStream, NonLiveStreamOptions, Stream};
+    use tokio::io::AsyncReadExt;
+
+    fn mp4_box(kind: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut mp4_box = ((data.len() + 8) as u32).to_be_bytes().to_vec();
+        mp4_box.extend_from_slice(kind);
+        mp4_box.extend_from_slice(data);
+        mp4_box
+    }
+
+    let ftyp = mp4_box(b"ftyp", b"isom\0\0\0\0");
+    let mdat = mp4_box(b"mdat", &(0..100).collect::<Vec<u8>>());
+    let moov = |chunk_offset: u32| {
+        let mut stco = vec![0, 0, 0, 0, 0, 0, 0, 1];
+        stco.extend_from_slice(&chunk_offset.to_be_bytes());
+
+        ["stbl", "minf", "mdia", "trak", "moov"]
+            .iter()
+            .fold(mp4_box(b"stco", &stco), |x, kind| {
+                mp4_box(kind.as_bytes(), &x)
+            })
+    };
+
+    // Tail moov pointing at the first byte of the mdat data
+    let content = [ftyp.clone(), mdat.clone(), moov(ftyp.len() as u32 + 8)].concat();
+
+    let address = common::spawn_range_server(content.clone()).await;
+
+    let stream: Box<dyn Stream + Send + Sync> = Box::new(
+        NonLiveStream::new(NonLiveStreamOptions {
+            content_length: content.len() as u64,
+            dl_chunk_size: 16,
+            end: 16,
+            ..common::stream_options(format!("http://{address}/video"))
+        })
+        .unwrap(),
+    );
+
+    let mut reader = stream.into_progressive_reader();
+    let mut output = vec![];
+    reader.read_to_end(&mut output).await.unwrap();
+
+    let moov = moov((ftyp.len() + moov(0).len()) as u32 + 8);
+    assert_eq!(output, [ftyp, moov, mdat].concat());
+}
+
+#[cfg(feature = "serve")]
+#[tokio::test]
+async fn serve_range() {
+    use hyper::body::HttpBody;
+    use hyper::header::{HeaderValue, ACCEPT_RANGES, CONTENT_RANGE};
+    use rusty_ytdl::serve::serve_stream;
+    use rusty_ytdl::stream::{NonLiveStream, NonLiveStreamOptions};
+
+    let content = (0..200).map(|x| x as u8).collect::<Vec<u8>>();
+
+    let address = common::spawn_range_server(content.clone()).await;
+
+    let new_stream = |content_length: usize| {
+        NonLiveStream::new(NonLiveStreamOptions {
+            content_length: content_length as u64,
+            ..common::stream_options(format!("http://{address}/video"))
+        })
+        .map(|x| Box::new(x) as Box<dyn rusty_ytdl::stream::Stream + Send + Sync>)
+        .unwrap()
+    };
+
+    // Unknown length is learned from the first chunk, which is part of a range from the start
+    for (content_length, range, expected) in [
+        (content.len(), "bytes=50-119", 50..120),
+        (0, "bytes=50-119", 50..120),
+        (0, "bytes=0-59", 0..60),
+    ] {
+        let range = HeaderValue::from_static(range);
+        let mut response = serve_stream(new_stream(content_length), "video/mp4", Some(&range))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), 206);
+        assert_eq!(response.headers()[ACCEPT_RANGES], "bytes");
+        assert_eq!(
+            response.headers()[CONTENT_RANGE],
+            format!("bytes {}-{}/200", expected.start, expected.end - 1)
+        );
+
+        let mut body = vec![];
+        while let Some(chunk) = response.body_mut().data().await {
+            body.extend_from_slice(&chunk.unwrap());
+        }
+        assert_eq!(body, content[expected]);
+    }
+
+    let range = HeaderValue::from_static("bytes=200-");
+    let response = serve_stream(new_stream(content.len()), "video/mp4", Some(&range))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 416);
+    assert_eq!(response.headers()[CONTENT_RANGE], "bytes */200");
+}
+
+#[test]
+fn finalize_stream_url() {
+    use rusty_ytdl::{finalize_url, StreamSession, VideoFormat};
+
+    let mut format = VideoFormat::default();
+    format.url =
+        "https://rr1---sn-abc.googlevideo.com/videoplayback?itag=251&c=ANDROID&n=abc".to_string();
+
+    assert_eq!(
+        finalize_url(&format, &StreamSession::default()).unwrap(),
+        format.url
+    );
+
+    let session = StreamSession {
+        po_token: Some("MnQ=".to_string()),
+        client_name: Some("WEB".to_string()),
+        client_version: Some("2.20240726.00.00".to_string()),
+        params: vec![
+            ("ctt".to_string(), "token".to_string()),
+            ("cver".to_string(), "2.20241001.00.00".to_string()),
+        ],
+    };
+    let url = url::Url::parse(&finalize_url(&format, &session).unwrap()).unwrap();
+    let query = url
+        .query_pairs()
+        .into_owned()
+        .collect::<Vec<(String, String)>>();
+    let get = |name: &str| {
+        query
+            .iter()
+            .filter(|(x, _)| x == name)
+            .map(|(_, value)| value.as_str())
+            .collect::<Vec<&str>>()
+    };
+
+    assert_eq!(get("itag"), ["251"]);
+    assert_eq!(get("n"), ["abc"]);
+    assert_eq!(get("pot"), ["MnQ="]);
+    assert_eq!(get("c"), ["WEB"]);
+    assert_eq!(get("cver"), ["2.20241001.00.00"]);
+    assert_eq!(get("ctt"), ["token"]);
+
+    format.is_hls = true;
+    assert_eq!(finalize_url(&format, &session).unwrap(), format.url);
+
+    format.url.clear();
+    assert!(finalize_url(&format, &session).is_err());
+}
+
+#[tokio::test]
+async fn stream_url_check() {
+    use rusty_ytdl::{check_stream_url, RequestOptions, StreamUrlStatus, VideoError};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    let address = common::spawn_server(|request| {
+        if request.path.starts_with("/valid") {
+            assert_eq!(request.header("range"), Some("bytes=0-0"));
+            common::response("206 Partial Content", &["Content-Range: bytes 0-0/10"], "0")
+        } else if request.path.starts_with("/headers") {
+            // Custom headers only, media hosts get no header profile
+            assert_eq!(request.header("x-test"), Some("1"));
+            assert_eq!(request.header("x-youtube-client-name"), None);
+            common::response("206 Partial Content", &["Content-Range: bytes 0-0/10"], "0")
+        } else if request.path.starts_with("/revoked") {
+            common::response("403 Forbidden", &[], "")
+        } else {
+            common::response("429 Too Many Requests", &[], "")
+        }
+    })
+    .await;
+
+    let expire = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        + 3600;
+
+    assert_eq!(
+        check_stream_url(&format!("http://{address}/valid?expire={expire}"), None)
+            .await
+            .unwrap(),
+        StreamUrlStatus::Valid {
+            expires_at: Some(UNIX_EPOCH + Duration::from_secs(expire)),
+        }
+    );
+
+    let mut headers = rusty_ytdl::reqwest::header::HeaderMap::new();
+    headers.insert("x-test", "1".parse().unwrap());
+    assert!(matches!(
+        check_stream_url(
+            &format!("http://{address}/headers"),
+            Some(&RequestOptions {
+                headers,
+                ..Default::default()
+            })
+        )
+        .await
+        .unwrap(),
+        StreamUrlStatus::Valid { .. }
+    ));
+
+    // Past expiry, no request is sent
+    assert_eq!(
+        check_stream_url(&format!("http://{address}/missing?expire=1000"), None)
+            .await
+            .unwrap(),
+        StreamUrlStatus::Expired {
+            expired_at: UNIX_EPOCH + Duration::from_secs(1000),
+        }
+    );
+
+    assert_eq!(
+        check_stream_url(&format!("http://{address}/revoked?expire={expire}"), None)
+            .await
+            .unwrap(),
+        StreamUrlStatus::Revoked { status: 403 }
+    );
+
+    assert!(matches!(
+        check_stream_url(&format!("http://{address}/busy"), None).await,
+        Err(VideoError::FormatRequestFailed(_))
+    ));
+}
+
+#[tokio::test]
+async fn bandwidth_budget() {
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    use rusty_ytdl::stream::BandwidthBudget;
+
+    let budget = Arc::new(BandwidthBudget::new(100_000));
+
+    let first = budget.join();
+    let second = budget.join();
+    assert_eq!(budget.active(), 2);
+    assert_eq!(budget.share(), 50_000);
+
+    let started = Instant::now();
+    first.consume(10_000).await;
+    assert!(started.elapsed() >= Duration::from_millis(190));
+
+    // The remaining download gets the whole budget
+    drop(second);
+    assert_eq!(budget.share(), 100_000);
+
+    let started = Instant::now();
+    first.consume(10_000).await;
+    let elapsed = started.elapsed();
+    assert!(elapsed >= Duration::from_millis(90) && elapsed < Duration::from_millis(190));
+
+    drop(first);
+    assert_eq!(budget.active(), 0);
+}
+
+#[tokio::test]
+async fn transcode_stream() {
+    use bytes::Bytes;
+    use rusty_ytdl::stream::Stream;
+    use rusty_ytdl::transcode::{TranscodeStream, Transcoder};
+    use rusty_ytdl::VideoError;
+    use std::sync::Mutex;
+
+    struct ChunkStream(Mutex<Vec<&'static [u8]>>);
+
+    #[async_trait::async_trait]
+    impl Stream for ChunkStream {
+        async fn chunk(&self) -> Result<Option<Bytes>, VideoError> {
+            let mut chunks = self.0.lock().unwrap();
+            Ok((!chunks.is_empty()).then(|| Bytes::from_static(chunks.remove(0))))
+        }
+    }
+
+    // Emits whole 4 byte frames, the rest is flushed by finish
+    #[derive(Default)]
+    struct Frames(Vec<u8>);
+
+    impl Transcoder for Frames {
+        fn transcode(&mut self, chunk: &[u8]) -> Result<Bytes, VideoError> {
+            self.0.extend_from_slice(chunk);
+            let frames = self.0.len() / 4 * 4;
+            Ok(self.0.drain(..frames).collect::<Vec<u8>>().into())
+        }
+
+        fn finish(&mut self) -> Result<Bytes, VideoError> {
+            Ok(std::mem::take(&mut self.0).into())
+        }
+    }
+
+    let chunks = vec![&b"ab"[..], b"cdefg", b"h", b"ij"];
+    let stream = TranscodeStream::new(
+        Box::new(ChunkStream(Mutex::new(chunks))),
+        Box::<Frames>::default(),
+    );
+
+    let mut output = vec![];
+    while let Some(chunk) = stream.chunk().await.unwrap() {
+        output.push(chunk);
+    }
+
+    assert_eq!(output, vec![&b"abcd"[..], b"efgh", b"ij"]);
+}
+
+#[tokio::test]
+async fn audio_frames() {
+    use rusty_ytdl::stream::{AudioFrames, Stream};
+    use rusty_ytdl::VideoError;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    struct ChunkStream(Mutex<Vec<Vec<u8>>>);
+
+    #[async_trait::async_trait]
+    impl Stream for ChunkStream {
+        async fn chunk(&self) -> Result<Option<bytes::Bytes>, VideoError> {
+            let mut chunks = self.0.lock().unwrap();
+            Ok((!chunks.is_empty()).then(|| chunks.remove(0).into()))
+        }
+    }
+
+    fn element(id: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut element = id.to_vec();
+        element.push(0x80 | data.len() as u8);
+        element.extend_from_slice(data);
+        element
+    }
+
+    let unknown_size = [0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF];
+
+    let mut webm = element(&[0x1A, 0x45, 0xDF, 0xA3], &element(&[0x42, 0x82], b"webm"));
+    webm.extend_from_slice(&[0x18, 0x53, 0x80, 0x67]);
+    webm.extend_from_slice(&unknown_size);
+    webm.extend(element(
+        &[0x15, 0x49, 0xA9, 0x66],
+        &element(&[0x2A, 0xD7, 0xB1], &[0x0F, 0x42, 0x40]),
+    ));
+    let track_entry = [
+        element(&[0xD7], &[1]),
+        element(&[0x86], b"A_OPUS"),
+        element(&[0x63, 0xA2], b"OpusHead"),
+    ]
+    .concat();
+    webm.extend(element(
+        &[0x16, 0x54, 0xAE, 0x6B],
+        &element(&[0xAE], &track_entry),
+    ));
+    webm.extend_from_slice(&[0x1F, 0x43, 0xB6, 0x75]);
+    webm.extend_from_slice(&unknown_size);
+    webm.extend(element(&[0xE7], &[0x03, 0xE8]));
+    webm.extend(element(&[0xA3], &[0x81, 0x00, 0x00, 0x80, 0xFC, 1, 2, 3]));
+    webm.extend(element(&[0xA3], &[0x82, 0x00, 0x00, 0x80, 0xFC, 9]));
+    // Block group with two Xiph laced frames of 3 and 2 bytes
+    webm.extend(element(
+        &[0xA0],
+        &element(
+            &[0xA1],
+            &[0x81, 0x00, 0x14, 0x02, 0x01, 0x03, 0xFC, 4, 5, 0xFC, 6],
+        ),
+    ));
+
+    let chunks = webm.chunks(5).map(|x| x.to_vec()).collect();
+    let mut frames = AudioFrames::new(Box::new(ChunkStream(Mutex::new(chunks))));
+
+    let mut packets = vec![];
+    while let Some(packet) = frames.next().await.unwrap() {
+        packets.push((packet.timestamp, packet.data.to_vec()));
+    }
+
+    assert_eq!(frames.opus_head().unwrap().as_ref(), b"OpusHead");
+    assert_eq!(
+        packets,
+        vec![
+            (Duration::from_millis(1000), vec![0xFC, 1, 2, 3]),
+            (Duration::from_millis(1020), vec![0xFC, 4, 5]),
+            (Duration::from_millis(1040), vec![0xFC, 6]),
+        ]
+    );
+}
+
+#[cfg(feature = "remux")]
+#[test]
+fn remux_ts() {
+    use rusty_ytdl::remux::TsRemuxer;
+
+    fn packets(pid: u16, payload: &[u8]) -> Vec<u8> {
+        let mut ts = vec![];
+
+        for (index, chunk) in payload.chunks(184).enumerate() {
+            let mut packet = vec![
+                0x47,
+                ((index == 0) as u8) << 6 | (pid >> 8) as u8,
+                pid as u8,
+            ];
+
+            if chunk.len() == 184 {
+                packet.push(0x10);
+            } else {
+                // Adaptation field stuffing
+                let stuffing = 183 - chunk.len();
+                packet.extend_from_slice(&[0x30, stuffing as u8]);
+                if stuffing > 0 {
+                    packet.push(0);
+                    packet.extend(std::iter::repeat(0xFF).take(stuffing - 1));
+                }
+            }
+
+            packet.extend_from_slice(chunk);
+            ts.extend(packet);
+        }
+
+        ts
+    }
+
+    fn timestamp(prefix: u8, ts: u64) -> [u8; 5] {
+        [
+            prefix << 4 | (((ts >> 30) & 0x07) as u8) << 1 | 1,
+            (ts >> 22) as u8,
+            (((ts >> 15) & 0x7F) as u8) << 1 | 1,
+            (ts >> 7) as u8,
+            ((ts & 0x7F) as u8) << 1 | 1,
+        ]
+    }
+
+    fn pes(stream_id: u8, pts: u64, payload: &[u8]) -> Vec<u8> {
+        let mut pes = vec![0, 0, 1, stream_id, 0, 0, 0x80, 0x80, 5];
+        pes.extend(timestamp(0x02, pts));
+        pes.extend_from_slice(payload);
+        pes
+    }
+
+    let sps = [0x67, 0x42, 0x00, 0x1E, 0xDA, 0x05, 0x07, 0xE4];
+    let pps = [0x68, 0xCE, 0x38, 0x80];
+    let idr = [0x65, 0x88, 0x84, 0x00, 0x10];
+
+    let mut keyframe = vec![0, 0, 0, 1, 0x09, 0xF0, 0, 0, 0, 1];
+    keyframe.extend_from_slice(&sps);
+    keyframe.extend_from_slice(&[0, 0, 0, 1]);
+    keyframe.extend_from_slice(&pps);
+    keyframe.extend_from_slice(&[0, 0, 0, 1]);
+    keyframe.extend_from_slice(&idr);
+
+    // 2 AAC LC frames, 44.1kHz stereo
+    let adts = [
+        0xFF, 0xF1, 0x50, 0x80, 0x01, 0x7F, 0xFC, 0x21, 0x10, 0x04, 0x60,
+    ];
+    let audio = [adts, adts].concat();
+
+    let pat = [
+        0, 0x00, 0xB0, 13, 0, 1, 0xC1, 0, 0, 0, 1, 0xF0, 0x00, 0, 0, 0, 0,
+    ];
+    let pmt = [
+        0, 0x02, 0xB0, 23, 0, 1, 0xC1, 0, 0, 0xE1, 0x00, 0xF0, 0x00, 0x1B, 0xE1, 0x00, 0xF0, 0x00,
+        0x0F, 0xE1, 0x01, 0xF0, 0x00, 0, 0, 0, 0,
+    ];
+
+    let ts = [
+        packets(0, &pat),
+        packets(0x1000, &pmt),
+        packets(0x100, &pes(0xE0, 90000, &keyframe)),
+        packets(0x101, &pes(0xC0, 90000, &audio)),
+        packets(0x100, &pes(0xE0, 93000, &[0, 0, 0, 1, 0x41, 0x9A, 0x02])),
+        packets(0x101, &pes(0xC0, 94000, &audio)),
+        packets(0x100, &pes(0xE0, 96000, &[0, 0, 0, 1, 0x65, 0x88, 0x01])),
+    ]
+    .concat();
+
+    let mut remuxer = TsRemuxer::new();
+    let mut mp4 = vec![];
+    for chunk in ts.chunks(100) {
+        mp4.extend_from_slice(&remuxer.push(chunk).unwrap());
+    }
+    mp4.extend_from_slice(&remuxer.finish().unwrap());
+
+    let mut boxes = vec![];
+    let mut pos = 0;
+    while pos < mp4.len() {
+        let size = u32::from_be_bytes(mp4[pos..pos + 4].try_into().unwrap()) as usize;
+        boxes.push(String::from_utf8_lossy(&mp4[pos + 4..pos + 8]).to_string());
+        pos += size;
+    }
+    assert_eq!(boxes, ["ftyp", "moov", "moof", "mdat", "moof", "mdat"]);
+
+    // Sample entry, `avc1` is also a brand of `ftyp`
+    let avc1 = mp4.windows(4).rposition(|x| x == b"avc1").unwrap();
+    assert_eq!(mp4[avc1 + 28..avc1 + 32], [0x01, 0x40, 0x00, 0xF0]);
+
+    let mut sample = (idr.len() as u32).to_be_bytes().to_vec();
+    sample.extend_from_slice(&idr);
+    assert!(mp4.windows(sample.len()).any(|x| x == sample));
+}